@@ -0,0 +1,148 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use crypto::{digest::Digest, sha2::Sha256};
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, File};
+use tracing::debug;
+
+use crate::{
+    get_http_client,
+    transfer::{self, file_sha256},
+};
+
+/// An output file of a job run which was pushed to comet.
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Default)]
+pub struct ArtifactRef {
+    /// file name of the output
+    pub name: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(data);
+    hasher.result_str()
+}
+
+/// Artifacts are addressed by their lowercase sha256, anything else is rejected
+/// so a hash can never escape the cache directory.
+pub fn is_sha256(v: &str) -> bool {
+    v.len() == 64 && v.bytes().all(|c| matches!(c, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Converts the websocket address of comet to its http address.
+pub fn comet_http_addr(addr: &str) -> String {
+    if let Some(v) = addr.strip_prefix("wss://") {
+        format!("https://{v}")
+    } else if let Some(v) = addr.strip_prefix("ws://") {
+        format!("http://{v}")
+    } else if addr.contains("://") {
+        addr.to_string()
+    } else {
+        format!("http://{addr}")
+    }
+}
+
+/// Local directory of artifacts keyed by sha256, used by comet and agent.
+#[derive(Clone, Debug)]
+pub struct ArtifactCache {
+    dir: PathBuf,
+}
+
+impl ArtifactCache {
+    pub fn new(dir: &str) -> Result<Self> {
+        let dir = shellexpand::full(dir)?.to_string();
+        Ok(Self {
+            dir: PathBuf::from(dir),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn path(&self, sha256: &str) -> Result<PathBuf> {
+        if !is_sha256(sha256) {
+            anyhow::bail!("invalid artifact hash {sha256}");
+        }
+        Ok(self.dir.join(&sha256[..2]).join(sha256))
+    }
+
+    pub async fn contains(&self, sha256: &str) -> Result<bool> {
+        Ok(fs::try_exists(self.path(sha256)?).await?)
+    }
+
+    pub async fn read(&self, sha256: &str) -> Result<Vec<u8>> {
+        Ok(fs::read(self.path(sha256)?).await?)
+    }
+
+    /// Moves a received file into place after verifying its checksum.
+    pub async fn import(&self, sha256: &str, src: &Path) -> Result<PathBuf> {
+        let path = self.path(sha256)?;
+        let (actual, _) = file_sha256(src).await?;
+        if actual != sha256 {
+            anyhow::bail!("artifact checksum mismatch, expected {sha256}, got {actual}");
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        fs::rename(src, &path).await?;
+        Ok(path)
+    }
+
+    /// Returns the path of a cached artifact, downloading it from comet on a miss.
+    pub async fn fetch(&self, comet_addr: &str, secret: &str, sha256: &str) -> Result<PathBuf> {
+        let path = self.path(sha256)?;
+        if fs::try_exists(&path).await? {
+            return Ok(path);
+        }
+        debug!("fetch artifact {sha256} from {comet_addr}");
+        transfer::download(&get_http_client(), comet_addr, secret, sha256, &path).await?;
+        Ok(path)
+    }
+}
+
+/// Pushes a file to comet unless comet already has the same content.
+pub async fn publish(comet_addr: &str, secret: &str, file: &Path) -> Result<ArtifactRef> {
+    let name = file
+        .file_name()
+        .and_then(|v| v.to_str())
+        .ok_or(anyhow!("invalid artifact file {}", file.display()))?
+        .to_string();
+    let mut reader = File::open(file)
+        .await
+        .with_context(|| format!("failed read {}", file.display()))?;
+    let progress = transfer::upload(
+        &get_http_client(),
+        comet_addr,
+        secret,
+        &transfer::new_transfer_id(),
+        &mut reader,
+    )
+    .await?;
+
+    Ok(ArtifactRef {
+        name,
+        sha256: progress.sha256.unwrap_or_default(),
+        size: progress.total,
+    })
+}
+
+#[test]
+fn test_artifact_hash() {
+    let sha256 = sha256_hex(b"hello world");
+    assert_eq!(
+        sha256,
+        "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+    );
+    assert!(is_sha256(&sha256));
+    assert!(!is_sha256("../../etc/passwd"));
+    assert!(!is_sha256(&sha256.to_uppercase()));
+    assert_eq!(
+        comet_http_addr("ws://127.0.0.1:3000"),
+        "http://127.0.0.1:3000"
+    );
+    assert_eq!(comet_http_addr("127.0.0.1:3000"), "http://127.0.0.1:3000");
+}
@@ -4,7 +4,11 @@ pub mod msg;
 pub mod protocol;
 // pub mod server;
 
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Context, Ok, Result};
 use serde_json::Value;
@@ -17,7 +21,7 @@ use tokio::{
 };
 use tracing::info;
 
-use crate::bridge::msg::Msg;
+use crate::{bridge::msg::Msg, metrics};
 
 use self::msg::{MsgKind, MsgReqKind, MsgState};
 
@@ -53,7 +57,22 @@ impl Bridge {
         self.server_clients.lock().await.remove(&key);
     }
 
+    /// Number of connected clients.
+    pub async fn client_count(&self) -> usize {
+        self.server_clients.lock().await.len()
+    }
+
+    /// Messages queued for the client and not yet written out.
+    pub async fn queued(&self, key: &str) -> usize {
+        self.server_clients
+            .lock()
+            .await
+            .get(key)
+            .map_or(0, |v| v.max_capacity() - v.capacity())
+    }
+
     pub async fn send_msg(&self, key: &str, data: MsgReqKind) -> Result<Value> {
+        let kind = metrics::request_kind(&data);
         let msg = Msg {
             id: 0,
             data: MsgKind::Request(data),
@@ -65,10 +84,25 @@ impl Bridge {
             None => return Err(anyhow::anyhow!("not found client {}", key)),
         }
 
-        let resp = timeout(Duration::from_secs(90), rx.recv())
-            .await
-            .context("receive message timeout")?
-            .context("failed receives the next value for the receiver.")?;
+        let started = Instant::now();
+        let resp = match timeout(Duration::from_secs(90), rx.recv()).await {
+            std::result::Result::Ok(v) => v,
+            Err(e) => {
+                metrics::BRIDGE_REQUEST_TIMEOUTS
+                    .with_label_values(&[kind])
+                    .inc();
+                return Err(e).context("receive message timeout");
+            }
+        }
+        .context("failed receives the next value for the receiver.")?;
+
+        let result = match resp {
+            MsgState::Completed(_) => "ok",
+            MsgState::Err(_) => "error",
+        };
+        metrics::BRIDGE_REQUEST_DURATION
+            .with_label_values(&[kind, result])
+            .observe(started.elapsed().as_secs_f64());
 
         return match resp {
             MsgState::Completed(v) => Ok(v),
@@ -12,6 +12,7 @@ use futures_util::{
 
 use moka::future::Cache;
 use poem::web::websocket::{Message as PMessage, WebSocketStream as PWebSocketStream};
+use rustc_serialize::hex::ToHex;
 
 use serde_json::{json, Value};
 use tokio::{
@@ -24,7 +25,7 @@ use tokio_tungstenite::{
     tungstenite::{ClientRequestBuilder, Message},
     MaybeTlsStream, WebSocketStream,
 };
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 use crate::{
     get_endpoint,
@@ -51,6 +52,7 @@ pub struct WsClient<W, R> {
     msg_box: Cache<u64, TransactionMsg>,
     bridge: Option<Bridge>,
     receiver: Option<Receiver<(Msg, Option<Sender<MsgState>>)>>,
+    protocol: Protocol,
 }
 
 impl<W, R> WsClient<W, R> {
@@ -74,6 +76,7 @@ impl<W, R> WsClient<W, R> {
             ws_writer: None,
             ws_reader: None,
             receiver: Some(receiver),
+            protocol: Protocol::legacy(),
         }
     }
 
@@ -166,12 +169,13 @@ impl WsClient<SplitSink<PWebSocketStream, PMessage>, SplitStream<PWebSocketStrea
         let mut receiver = self.receiver.take().unwrap();
         let mut ws_writer = self.ws_writer.take().unwrap();
         let msg_box = self.msg_box.clone();
+        let protocol = self.protocol;
 
         tokio::spawn(async move {
             let id_count = AtomicU64::new(1);
             while let Some(mut v) = receiver.recv().await {
                 let buf = if let MsgKind::Response(_) = v.0.data {
-                    Protocol::pack_response(v.0)
+                    protocol.pack_response(v.0)
                 } else {
                     v.0.id = id_count.fetch_add(1, Ordering::Relaxed);
 
@@ -179,7 +183,7 @@ impl WsClient<SplitSink<PWebSocketStream, PMessage>, SplitStream<PWebSocketStrea
                         let tran = TransactionMsg::new(tx.clone(), v.0.id);
                         msg_box.insert(v.0.id, tran).await;
                     }
-                    Protocol::pack_request(v.0)
+                    protocol.pack_request(v.0)
                 };
 
                 ws_writer
@@ -219,10 +223,19 @@ impl WsClient<SplitSink<PWebSocketStream, PMessage>, SplitStream<PWebSocketStrea
                         self.is_initialized.replace(v.is_initialized);
                         self.ws_reader.replace(ws_reader);
 
+                        // the auth response itself always uses the legacy frame,
+                        // agents without an offer keep getting the plain "ok"
+                        let resp = match v.protocol {
+                            Some(ref offer) => {
+                                self.protocol = Protocol::negotiate(Some(offer));
+                                json!(self.protocol)
+                            }
+                            None => json!("ok"),
+                        };
                         let _ = ws_writer
-                            .send(PMessage::Binary(Protocol::pack_response(Msg {
+                            .send(PMessage::Binary(Protocol::legacy().pack_response(Msg {
                                 id: 0,
-                                data: MsgKind::Response(json!("ok")),
+                                data: MsgKind::Response(resp),
                             })))
                             .await?;
 
@@ -365,6 +378,13 @@ impl
                 .with_header("X-Ssh-User", ssh_opt.user.clone())
                 .with_header("X-Ssh-Password", ssh_opt.password.clone())
                 .with_header("X-Ssh-Port", ssh_opt.port.to_string());
+            // private keys span multiple lines, hex keeps them valid as a header value
+            if let Some(ref private_key) = ssh_opt.private_key {
+                req = req.with_header("X-Ssh-Private-Key", private_key.as_bytes().to_hex());
+            }
+            if let Some(ref passphrase) = ssh_opt.passphrase {
+                req = req.with_header("X-Ssh-Key-Passphrase", passphrase.clone());
+            }
         }
 
         let (ws_stream, _b) = timeout(Duration::from_secs(5), connect_async(req))
@@ -379,6 +399,9 @@ impl
             .await?;
 
         info!("success auth got response {auth_resp}");
+        // comets without negotiation answer "ok" and keep the legacy frames
+        self.protocol = serde_json::from_value(auth_resp).unwrap_or_default();
+        debug!("negotiated bridge protocol {:?}", self.protocol);
 
         self.start_processing_to_server_msg();
         Ok(self)
@@ -388,19 +411,20 @@ impl
         let mut receiver = self.receiver.take().unwrap();
         let mut ws_writer = self.ws_writer.take().unwrap();
         let msg_box = self.msg_box.clone();
+        let protocol = self.protocol;
 
         tokio::spawn(async move {
             let id_count = AtomicU64::new(1);
             while let Some(mut v) = receiver.recv().await {
                 let buf = if let MsgKind::Response(_) = v.0.data {
-                    Protocol::pack_response(v.0)
+                    protocol.pack_response(v.0)
                 } else {
                     v.0.id = id_count.fetch_add(1, Ordering::Relaxed);
                     if let Some(tx) = v.1 {
                         let tran = TransactionMsg::new(tx.clone(), v.0.id);
                         msg_box.insert(v.0.id, tran).await;
                     }
-                    Protocol::pack_request(v.0)
+                    protocol.pack_request(v.0)
                 };
 
                 let ret = timeout(
@@ -430,12 +454,13 @@ impl
 
         let _ = timeout(
             Duration::from_secs(5),
-            ws_writer.send(Message::Binary(Protocol::pack_request(Msg {
+            ws_writer.send(Message::Binary(Protocol::legacy().pack_request(Msg {
                 id: 0,
                 data: MsgKind::Request(MsgReqKind::Auth(AuthParams {
                     is_initialized,
                     agent_ip: self.local_ip.unwrap().to_string(),
                     secret,
+                    protocol: Some(Protocol::offer()),
                 })),
             }))),
         )
@@ -7,10 +7,17 @@ use serde_json::Value;
 use tokio::sync::mpsc::Sender;
 
 use crate::{
+    artifact::ArtifactRef,
+    bridge::protocol::ProtocolOffer,
     comet::handler::SecretHeader,
-    scheduler::types::{
-        BaseJob, BundleOutput, JobAction, RunStatus, RuntimeAction, ScheduleStatus, ScheduleType,
+    scheduler::{
+        timer::{BlackoutWindow, TimerCalendar, TimerSchedule},
+        types::{
+            BaseJob, BundleOutput, HealthCheck, JobAction, ProbeStatus, RestartPolicy, RunStatus,
+            RuntimeAction, ScheduleStatus, ScheduleType,
+        },
     },
+    telemetry::TraceContext,
 };
 
 pub enum MsgState {
@@ -34,6 +41,10 @@ impl TransactionMsg {
 pub struct SftpReadDirParams {
     pub user: String,
     pub password: String,
+    #[serde(default)]
+    pub private_key: Option<String>,
+    #[serde(default)]
+    pub passphrase: Option<String>,
     pub ip: String,
     pub port: u16,
     pub dir: Option<String>,
@@ -45,8 +56,17 @@ pub struct SftpUploadParams {
     pub port: u16,
     pub user: String,
     pub password: String,
+    #[serde(default)]
+    pub private_key: Option<String>,
+    #[serde(default)]
+    pub passphrase: Option<String>,
     pub filepath: String,
+    /// inlined content, only sent by consoles without chunked transfer
+    #[serde(default)]
     pub data: Vec<u8>,
+    /// artifact uploaded to comet beforehand, fetched by the agent in chunks
+    #[serde(default)]
+    pub sha256: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
@@ -55,7 +75,15 @@ pub struct SftpDownloadParams {
     pub port: u16,
     pub user: String,
     pub password: String,
+    #[serde(default)]
+    pub private_key: Option<String>,
+    #[serde(default)]
+    pub passphrase: Option<String>,
     pub filepath: String,
+    /// the agent uploads the file to comet under this id instead of returning
+    /// its content, the console polls the id for progress
+    #[serde(default)]
+    pub transfer_id: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
@@ -64,6 +92,10 @@ pub struct SftpRemoveParams {
     pub port: u16,
     pub user: String,
     pub password: String,
+    #[serde(default)]
+    pub private_key: Option<String>,
+    #[serde(default)]
+    pub passphrase: Option<String>,
     pub remove_type: String,
     pub filepath: String,
 }
@@ -102,11 +134,40 @@ pub struct DispatchJobParams {
     #[serde(default)]
     pub run_id: String,
     pub fields: Option<HashMap<String, serde_json::Value>>,
+    /// kept for agents that predate `timer`
     pub timer_expr: Option<String>,
+    #[serde(default)]
+    pub timer: Option<TimerSchedule>,
+    /// calendars the timer is bound to
+    #[serde(default)]
+    pub calendars: Option<Vec<TimerCalendar>>,
+    /// blackout windows of the target instance, ticks inside one are skipped
+    #[serde(default)]
+    pub blackouts: Option<Vec<BlackoutWindow>>,
     pub restart_interval: Option<Duration>,
+    #[serde(default)]
+    pub restart_policy: Option<RestartPolicy>,
+    #[serde(default)]
+    pub health_check: Option<HealthCheck>,
     pub is_sync: bool,
     pub created_user: String,
     pub action: JobAction,
+    /// W3C trace context of the dispatch
+    #[serde(default)]
+    pub trace_context: Option<TraceContext>,
+}
+
+impl DispatchJobParams {
+    /// The schedule of a timer, falling back to the bare expression of older
+    /// consoles.
+    pub fn timer_schedule(&self) -> Option<TimerSchedule> {
+        self.timer.clone().or_else(|| {
+            self.timer_expr
+                .as_deref()
+                .filter(|v| !v.is_empty())
+                .map(TimerSchedule::legacy)
+        })
+    }
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
@@ -157,6 +218,24 @@ pub struct UpdateJobParams {
     pub prev_time: Option<DateTime<Local>>,
     pub next_time: Option<DateTime<Local>>,
     pub is_timeout: bool,
+    /// restarts of a supervised job since supervising started
+    #[serde(default)]
+    pub restart_count: Option<u32>,
+    /// exit status or error of the last crash of a supervised job
+    #[serde(default)]
+    pub crash_reason: Option<String>,
+    /// probe results of a supervised job
+    #[serde(default)]
+    pub probe_status: Option<ProbeStatus>,
+    /// output files of the run pushed to comet
+    #[serde(default)]
+    pub artifacts: Option<Vec<ArtifactRef>>,
+    /// set on a timer tick that was skipped instead of run
+    #[serde(default)]
+    pub suppressed_by: Option<String>,
+    /// W3C trace context of the run
+    #[serde(default)]
+    pub trace_context: Option<TraceContext>,
 }
 
 impl UpdateJobParams {
@@ -209,6 +288,10 @@ pub struct AuthParams {
     pub agent_ip: String,
     pub secret: String,
     pub is_initialized: bool,
+    /// frame formats the agent understands, absent for agents which only
+    /// speak the legacy json frames
+    #[serde(default)]
+    pub protocol: Option<ProtocolOffer>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
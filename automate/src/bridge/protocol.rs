@@ -1,47 +1,214 @@
+use std::io::Read;
+
 use anyhow::{anyhow, Result};
 use bytes::{BufMut, BytesMut};
+use serde::{Deserialize, Serialize};
 
 use super::msg::Msg;
 
-pub struct Protocol {}
+/// Encoding of a message. bincode is not offered because messages carry
+/// `serde_json::Value` which only self-describing formats can decode.
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    #[default]
+    Json,
+    Msgpack,
+}
+
+impl Codec {
+    fn id(&self) -> u8 {
+        match self {
+            Codec::Json => 0,
+            Codec::Msgpack => 1,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(Codec::Json),
+            1 => Ok(Codec::Msgpack),
+            v => Err(anyhow!("unknown codec {v}")),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Zstd,
+}
+
+/// Sent by the agent in `AuthParams`, in order of preference.
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Default)]
+pub struct ProtocolOffer {
+    pub version: u8,
+    pub codecs: Vec<Codec>,
+    pub compressions: Vec<Compression>,
+}
+
+/// Framing of bridge messages.
+///
+/// Version 1 frames are a one-byte marker followed by json, they are what
+/// agents and comets without negotiation speak. Version 2 frames are
+///
+/// ```text
+/// | marker | version | codec | flags | payload |
+/// ```
+///
+/// where bit 0 of flags marks a zstd compressed payload. Both versions are
+/// always accepted when unpacking, the negotiated options only decide how
+/// outgoing messages are packed.
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Copy)]
+pub struct Protocol {
+    pub version: u8,
+    pub codec: Codec,
+    pub compression: Compression,
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        Self::legacy()
+    }
+}
 
 impl Protocol {
     const REQ_MARK: u8 = 0;
     const RESP_MARK: u8 = 1;
+    const LEGACY_VERSION: u8 = 1;
+    const VERSION: u8 = 2;
+    const FLAG_ZSTD: u8 = 1;
+    /// payloads smaller than this are sent uncompressed
+    const COMPRESS_THRESHOLD: usize = 4096;
+    const ZSTD_LEVEL: i32 = 3;
+
+    pub fn legacy() -> Self {
+        Self {
+            version: Self::LEGACY_VERSION,
+            codec: Codec::Json,
+            compression: Compression::None,
+        }
+    }
 
-    pub fn is_response(data: &Vec<u8>) -> bool {
+    /// What this side supports, offered by the agent when it connects.
+    pub fn offer() -> ProtocolOffer {
+        ProtocolOffer {
+            version: Self::VERSION,
+            codecs: vec![Codec::Msgpack, Codec::Json],
+            compressions: vec![Compression::Zstd, Compression::None],
+        }
+    }
+
+    /// Picks the first codec and compression of the offer which this side
+    /// supports, agents without an offer keep the legacy frames.
+    pub fn negotiate(offer: Option<&ProtocolOffer>) -> Self {
+        let Some(offer) = offer.filter(|v| v.version >= Self::VERSION) else {
+            return Self::legacy();
+        };
+        let supported = Self::offer();
+        Self {
+            version: Self::VERSION,
+            codec: offer
+                .codecs
+                .iter()
+                .find(|v| supported.codecs.contains(v))
+                .copied()
+                .unwrap_or_default(),
+            compression: offer
+                .compressions
+                .iter()
+                .find(|v| supported.compressions.contains(v))
+                .copied()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn is_response(data: &[u8]) -> bool {
         data[0] == Self::RESP_MARK
     }
 
-    pub fn pack_request(data: Msg) -> Vec<u8> {
-        let mut b = BytesMut::new();
-        b.put_u8(Self::REQ_MARK);
-        b.extend(serde_json::to_vec(&data).unwrap());
-        b.to_vec()
+    pub fn pack_request(&self, data: Msg) -> Vec<u8> {
+        self.pack(Self::REQ_MARK, &data)
     }
 
     pub fn unpack_request(data: Vec<u8>) -> Result<Msg> {
         if data[0] != Self::REQ_MARK {
             return Err(anyhow!("invalid request msg format"));
         }
-        let val = &data[1..];
-        Ok(serde_json::from_slice::<Msg>(val)?)
+        Self::unpack(&data)
     }
 
-    pub fn pack_response(data: Msg) -> Vec<u8> {
-        let mut b = BytesMut::new();
-        b.put_u8(Self::RESP_MARK);
-        let data = serde_json::to_vec(&data).unwrap();
-        b.extend(data);
-        b.to_vec()
+    pub fn pack_response(&self, data: Msg) -> Vec<u8> {
+        self.pack(Self::RESP_MARK, &data)
     }
 
     pub fn unpack_response(data: Vec<u8>) -> Result<Msg> {
         if data[0] != Self::RESP_MARK {
             return Err(anyhow!("invalid response msg format"));
         }
-        let val = &data[1..];
-        Ok(serde_json::from_slice::<Msg>(val)?)
+        Self::unpack(&data)
+    }
+
+    fn pack(&self, mark: u8, data: &Msg) -> Vec<u8> {
+        let mut b = BytesMut::new();
+        b.put_u8(mark);
+        if self.version < Self::VERSION {
+            b.extend(serde_json::to_vec(data).unwrap());
+            return b.to_vec();
+        }
+
+        let payload = match self.codec {
+            Codec::Json => serde_json::to_vec(data).unwrap(),
+            // named fields keep messages decodable when either side adds fields
+            Codec::Msgpack => rmp_serde::to_vec_named(data).unwrap(),
+        };
+        let compressed = match self.compression {
+            Compression::Zstd if payload.len() >= Self::COMPRESS_THRESHOLD => {
+                zstd::encode_all(payload.as_slice(), Self::ZSTD_LEVEL).ok()
+            }
+            _ => None,
+        };
+
+        b.put_u8(Self::VERSION);
+        b.put_u8(self.codec.id());
+        match compressed {
+            Some(v) => {
+                b.put_u8(Self::FLAG_ZSTD);
+                b.extend(v);
+            }
+            None => {
+                b.put_u8(0);
+                b.extend(payload);
+            }
+        }
+        b.to_vec()
+    }
+
+    fn unpack(data: &[u8]) -> Result<Msg> {
+        // a json payload starts with '{', so the version byte tells them apart
+        if data.get(1) != Some(&Self::VERSION) {
+            return Ok(serde_json::from_slice::<Msg>(&data[1..])?);
+        }
+        if data.len() < 4 {
+            return Err(anyhow!("invalid msg frame"));
+        }
+
+        let codec = Codec::from_id(data[2])?;
+        let flags = data[3];
+        let payload = if flags & Self::FLAG_ZSTD != 0 {
+            let mut buf = Vec::new();
+            zstd::Decoder::new(&data[4..])?.read_to_end(&mut buf)?;
+            buf
+        } else {
+            data[4..].to_vec()
+        };
+
+        Ok(match codec {
+            Codec::Json => serde_json::from_slice::<Msg>(&payload)?,
+            Codec::Msgpack => rmp_serde::from_slice::<Msg>(&payload)?,
+        })
     }
 }
 
@@ -56,7 +223,7 @@ fn pack_request() {
         )),
     };
 
-    let data = Protocol::pack_request(old.clone());
+    let data = Protocol::legacy().pack_request(old.clone());
 
     match Protocol::unpack_request(data) {
         Ok(new) => {
@@ -65,3 +232,35 @@ fn pack_request() {
         Err(_) => todo!(),
     }
 }
+
+#[test]
+fn pack_negotiated() {
+    use crate::bridge::msg::MsgKind;
+    use serde_json::json;
+
+    assert_eq!(Protocol::negotiate(None), Protocol::legacy());
+    let protocol = Protocol::negotiate(Some(&ProtocolOffer {
+        version: 2,
+        codecs: vec![Codec::Msgpack],
+        compressions: vec![Compression::Zstd],
+    }));
+    assert_eq!(protocol.codec, Codec::Msgpack);
+    assert_eq!(protocol.compression, Compression::Zstd);
+
+    let msg = Msg {
+        id: 7,
+        data: MsgKind::Response(json!({"stdout": "x".repeat(10000), "code": 20000})),
+    };
+    let data = protocol.pack_response(msg.clone());
+    assert!(data.len() < 1000);
+    assert_eq!(Protocol::unpack_response(data).unwrap(), msg);
+
+    // small messages are not compressed
+    let msg = Msg {
+        id: 8,
+        data: MsgKind::Response(json!("ok")),
+    };
+    let data = protocol.pack_response(msg.clone());
+    assert_eq!(data[3], 0);
+    assert_eq!(Protocol::unpack_response(data).unwrap(), msg);
+}
@@ -1,20 +1,26 @@
-use std::pin::Pin;
+use std::{pin::Pin, sync::Arc};
 
 use anyhow::Result;
+use async_trait::async_trait;
 use futures::Future;
-use local_ip_address::local_ip;
-use redis::{
-    from_redis_value,
-    streams::{StreamMaxlen, StreamReadOptions, StreamReadReply},
-    AsyncCommands, Client,
-};
 use redis_macros::{FromRedisValue, ToRedisArgs};
 use serde::{Deserialize, Serialize};
 
-use tracing::{debug, error, info, warn};
-
 use crate::bridge::msg::{AgentOfflineParams, AgentOnlineParams, HeartbeatParams, UpdateJobParams};
 
+pub mod jetstream;
+pub mod memory;
+pub mod redis_stream;
+
+pub use jetstream::JetStreamBus;
+pub use memory::MemoryBus;
+pub use redis_stream::RedisBus;
+
+pub const JOB_TOPIC: &str = "jiascheduler:job:event";
+pub const CONSUMER_GROUP: &str = "jiascheduler-group";
+/// max number of events kept by the backend before old entries are trimmed
+pub const MAX_LEN: usize = 5000;
+
 #[derive(Debug, Serialize, Deserialize, FromRedisValue, ToRedisArgs)]
 pub enum Msg {
     UpdateJob(UpdateJobParams),
@@ -23,150 +29,69 @@ pub enum Msg {
     AgentOffline(AgentOfflineParams),
 }
 
-#[derive(Clone)]
-pub struct Bus {
-    pub redis_client: Client,
-}
+pub type MsgHandler<'a> = dyn FnMut(String, Msg) -> Pin<Box<dyn Future<Output = Result<()>> + Send>>
+    + Send
+    + Sync
+    + 'a;
 
-impl Bus {
-    pub const JOB_TOPIC: &'static str = "jiascheduler:job:event";
-    pub const CONSUMER_GROUP: &'static str = "jiascheduler-group";
+pub type SharedBus = Arc<dyn Bus>;
 
-    pub fn new(redis_client: Client) -> Self {
-        Self { redis_client }
-    }
+/// Event bus between comet and console.
+///
+/// Every message is delivered to one consumer of the [`CONSUMER_GROUP`],
+/// so several consoles can share the load of a single topic.
+#[async_trait]
+pub trait Bus: Send + Sync {
+    async fn send_msg<'a>(&self, items: &'a [(&'a str, Msg)]) -> Result<String>;
+
+    /// Block and feed every received message to `cb` until the backend fails.
+    async fn recv(&self, cb: &mut MsgHandler<'_>) -> Result<String>;
 
-    pub async fn update_job(&self, msg: UpdateJobParams) -> Result<String> {
+    async fn update_job(&self, msg: UpdateJobParams) -> Result<String> {
         self.send_msg(&[("event", Msg::UpdateJob(msg))]).await
     }
 
-    pub async fn heartbeat(&self, msg: HeartbeatParams) -> Result<String> {
+    async fn heartbeat(&self, msg: HeartbeatParams) -> Result<String> {
         self.send_msg(&[("event", Msg::Heartbeat(msg))]).await
     }
 
-    pub async fn agent_online(&self, msg: AgentOnlineParams) -> Result<String> {
+    async fn agent_online(&self, msg: AgentOnlineParams) -> Result<String> {
         self.send_msg(&[("event", Msg::AgentOnline(msg))]).await
     }
 
-    pub async fn agent_offline(&self, msg: AgentOfflineParams) -> Result<String> {
+    async fn agent_offline(&self, msg: AgentOfflineParams) -> Result<String> {
         self.send_msg(&[("event", Msg::AgentOffline(msg))]).await
     }
+}
 
-    pub async fn send_msg<'a>(&self, items: &'a [(&'a str, Msg)]) -> Result<String> {
-        let mut conn = self.redis_client.get_multiplexed_async_connection().await?;
-
-        let v: String = conn.xadd(Self::JOB_TOPIC, "*", items).await?;
-        Ok(v)
-    }
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BusKind {
+    /// Redis Streams, shared by every console and comet
+    #[default]
+    Redis,
+    /// In-process channel, only usable when console and comet run in one process
+    Memory,
+    /// NATS JetStream
+    Nats,
+}
 
-    pub async fn recv(
-        &self,
-        mut cb: impl Sync
-            + Send
-            + FnMut(String, Msg) -> Pin<Box<dyn Future<Output = Result<()>> + Send>>,
-    ) -> Result<String> {
-        let mut conn = self.redis_client.get_multiplexed_async_connection().await?;
-
-        let ret: String = conn
-            .xgroup_create_mkstream(Self::JOB_TOPIC, Self::CONSUMER_GROUP, "$")
-            .await
-            .map_or_else(
-                |e| {
-                    warn!("failed create stream group - {}", e);
-                    "".to_string()
-                },
-                |v| v,
-            );
-
-        info!("create stream group {}", ret);
-
-        let opts = StreamReadOptions::default()
-            .group(Self::CONSUMER_GROUP, local_ip()?.to_string())
-            .block(100)
-            .count(100);
-
-        loop {
-            let ret: StreamReadReply = conn
-                .xread_options(&[Self::JOB_TOPIC], &[">"], &opts)
-                .await?;
-
-            match conn
-                .xtrim::<_, u64>(Self::JOB_TOPIC, StreamMaxlen::Equals(5000))
-                .await
-            {
-                Ok(n) => debug!("trim stream {} {n} entries", Self::JOB_TOPIC),
-                Err(e) => error!("failed to trim stream - {e}"),
-            };
-
-            for stream_key in ret.keys {
-                let msg_key = stream_key.key;
-
-                for stream_id in stream_key.ids {
-                    for (k, v) in stream_id.map {
-                        let ret = match from_redis_value::<Msg>(&v) {
-                            Ok(msg) => cb(k, msg).await,
-                            Err(e) => {
-                                error!("failed to parse redis val - {e}");
-                                Ok(())
-                            }
-                        };
-
-                        if let Err(e) = ret {
-                            error!("failed to handle msg - {e}");
-                        }
-
-                        let _: i32 = conn
-                            .xack(
-                                msg_key.clone(),
-                                Self::CONSUMER_GROUP,
-                                &[stream_id.id.clone()],
-                            )
-                            .await
-                            .map_or_else(
-                                |v| {
-                                    error!("faile to exec xack - {}", v);
-                                    0
-                                },
-                                |v| v,
-                            );
-                    }
-                }
-            }
-        }
-    }
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BusOptions {
+    #[serde(default)]
+    pub kind: BusKind,
+    /// NATS server address, eg: "nats://127.0.0.1:4222"
+    #[serde(default)]
+    pub nats_url: String,
 }
 
-#[tokio::test]
-async fn test_bus() {
-    let redis_client =
-        redis::Client::open("redis://:wang@127.0.0.1").expect("failed connect to redis");
-    let bus = Bus::new(redis_client);
-    bus.send_msg(&[(
-        "event",
-        Msg::UpdateJob(UpdateJobParams {
-            exit_code: Some(1),
-            ..Default::default()
-        }),
-    )])
-    .await
-    .unwrap();
-
-    bus.send_msg(&[(
-        "event",
-        Msg::UpdateJob(UpdateJobParams {
-            exit_code: Some(2),
-            ..Default::default()
-        }),
-    )])
-    .await
-    .unwrap();
-
-    bus.recv(|key, val| {
-        Box::pin(async move {
-            println!("key:{key} val:{}", serde_json::to_string(&val).unwrap());
-            Ok(())
-        })
-    })
-    .await
-    .unwrap();
+impl BusOptions {
+    pub async fn connect(&self, redis_client: ::redis::Client) -> Result<SharedBus> {
+        let bus: SharedBus = match self.kind {
+            BusKind::Redis => Arc::new(RedisBus::new(redis_client)),
+            BusKind::Memory => Arc::new(MemoryBus::shared()),
+            BusKind::Nats => Arc::new(JetStreamBus::connect(&self.nats_url).await?),
+        };
+        Ok(bus)
+    }
 }
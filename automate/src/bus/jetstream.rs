@@ -0,0 +1,115 @@
+use anyhow::{Result, anyhow};
+use async_nats::{
+    HeaderMap,
+    jetstream::{self, Context, consumer::pull, stream},
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use tracing::{error, info};
+
+use super::{Bus, CONSUMER_GROUP, MAX_LEN, Msg, MsgHandler};
+use crate::metrics;
+
+/// Bus backed by a NATS JetStream stream with a durable pull consumer
+#[derive(Clone)]
+pub struct JetStreamBus {
+    js: Context,
+}
+
+impl JetStreamBus {
+    pub const STREAM_NAME: &'static str = "jiascheduler-job-event";
+    /// NATS subjects cannot contain `:`, so [`super::JOB_TOPIC`] is mapped to a dotted subject
+    pub const SUBJECT: &'static str = "jiascheduler.job.event";
+    const KEY_HEADER: &'static str = "Jiascheduler-Key";
+
+    pub async fn connect(nats_url: &str) -> Result<Self> {
+        if nats_url.is_empty() {
+            anyhow::bail!("nats_url is required for nats bus");
+        }
+        let client = async_nats::connect(nats_url).await?;
+        let js = jetstream::new(client);
+
+        js.get_or_create_stream(stream::Config {
+            name: Self::STREAM_NAME.to_string(),
+            subjects: vec![Self::SUBJECT.to_string()],
+            max_messages: MAX_LEN as i64,
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| anyhow!("failed to create jetstream stream - {e}"))?;
+
+        Ok(Self { js })
+    }
+}
+
+#[async_trait]
+impl Bus for JetStreamBus {
+    async fn send_msg<'a>(&self, items: &'a [(&'a str, Msg)]) -> Result<String> {
+        let mut id = String::new();
+        for (k, msg) in items {
+            let mut headers = HeaderMap::new();
+            headers.insert(Self::KEY_HEADER, *k);
+            let ack = self
+                .js
+                .publish_with_headers(Self::SUBJECT, headers, serde_json::to_vec(msg)?.into())
+                .await?
+                .await?;
+            id = ack.sequence.to_string();
+        }
+        Ok(id)
+    }
+
+    async fn recv(&self, cb: &mut MsgHandler<'_>) -> Result<String> {
+        let stream = self
+            .js
+            .get_stream(Self::STREAM_NAME)
+            .await
+            .map_err(|e| anyhow!("failed to get jetstream stream - {e}"))?;
+
+        let consumer = stream
+            .get_or_create_consumer(
+                CONSUMER_GROUP,
+                pull::Config {
+                    durable_name: Some(CONSUMER_GROUP.to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| anyhow!("failed to create jetstream consumer - {e}"))?;
+
+        info!("create jetstream consumer {CONSUMER_GROUP}");
+
+        let mut messages = consumer.messages().await?;
+        while let Some(message) = messages.next().await {
+            let message = message?;
+            if let Ok(info) = message.info() {
+                metrics::observe_bus_lag(
+                    (info.published.unix_timestamp_nanos() / 1_000_000) as i64,
+                );
+            }
+            let key = message
+                .headers
+                .as_ref()
+                .and_then(|h| h.get(Self::KEY_HEADER))
+                .map_or("event".to_string(), |v| v.to_string());
+
+            let ret = match serde_json::from_slice::<Msg>(&message.payload) {
+                Ok(msg) => cb(key, msg).await,
+                Err(e) => {
+                    error!("failed to parse jetstream msg - {e}");
+                    Ok(())
+                }
+            };
+
+            if let Err(e) = ret {
+                error!("failed to handle msg - {e}");
+            }
+
+            if let Err(e) = message.ack().await {
+                error!("failed to ack jetstream msg - {e}");
+            }
+        }
+
+        anyhow::bail!("jetstream consumer closed")
+    }
+}
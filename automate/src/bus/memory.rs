@@ -0,0 +1,119 @@
+use std::{
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use tokio::sync::{
+    Mutex,
+    mpsc::{self, Receiver, Sender},
+};
+use tracing::error;
+
+use super::{Bus, MAX_LEN, Msg, MsgHandler};
+
+static SHARED_BUS: OnceLock<MemoryBus> = OnceLock::new();
+
+/// Bus backed by an in-process channel.
+///
+/// Console and comet must live in the same process, like the all-in-one
+/// `jiascheduler` binary, and pick up the same instance through [`MemoryBus::shared`].
+#[derive(Clone)]
+pub struct MemoryBus {
+    tx: Sender<(String, String)>,
+    rx: Arc<Mutex<Receiver<(String, String)>>>,
+    seq: Arc<AtomicU64>,
+}
+
+impl Default for MemoryBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryBus {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel(MAX_LEN);
+        Self {
+            tx,
+            rx: Arc::new(Mutex::new(rx)),
+            seq: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// process wide instance
+    pub fn shared() -> Self {
+        SHARED_BUS.get_or_init(Self::new).clone()
+    }
+
+    fn next_id(&self) -> String {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |v| v.as_millis());
+        format!("{millis}-{}", self.seq.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+#[async_trait]
+impl Bus for MemoryBus {
+    async fn send_msg<'a>(&self, items: &'a [(&'a str, Msg)]) -> Result<String> {
+        for (k, msg) in items {
+            self.tx
+                .try_send((k.to_string(), serde_json::to_string(msg)?))
+                .map_err(|e| anyhow!("failed to send msg to memory bus - {e}"))?;
+        }
+        Ok(self.next_id())
+    }
+
+    async fn recv(&self, cb: &mut MsgHandler<'_>) -> Result<String> {
+        let mut rx = self.rx.lock().await;
+        while let Some((k, v)) = rx.recv().await {
+            let ret = match serde_json::from_str::<Msg>(&v) {
+                Ok(msg) => cb(k, msg).await,
+                Err(e) => {
+                    error!("failed to parse memory bus msg - {e}");
+                    Ok(())
+                }
+            };
+
+            if let Err(e) = ret {
+                error!("failed to handle msg - {e}");
+            }
+        }
+        anyhow::bail!("memory bus closed")
+    }
+}
+
+#[tokio::test]
+async fn test_memory_bus() {
+    use crate::bridge::msg::UpdateJobParams;
+
+    let bus = MemoryBus::new();
+    for exit_code in 1..=2 {
+        bus.update_job(UpdateJobParams {
+            exit_code: Some(exit_code),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    }
+
+    let mut got = vec![];
+    let _ = tokio::time::timeout(
+        std::time::Duration::from_millis(100),
+        bus.recv(&mut |key, val| {
+            assert_eq!(key, "event");
+            if let Msg::UpdateJob(v) = val {
+                got.push(v.exit_code);
+            }
+            Box::pin(async { Ok(()) })
+        }),
+    )
+    .await;
+
+    assert_eq!(got, vec![Some(1), Some(2)]);
+}
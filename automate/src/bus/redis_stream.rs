@@ -0,0 +1,138 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use local_ip_address::local_ip;
+use redis::{
+    AsyncCommands, Client, from_redis_value,
+    streams::{StreamMaxlen, StreamReadOptions, StreamReadReply},
+};
+
+use tracing::{debug, error, info, warn};
+
+use super::{Bus, CONSUMER_GROUP, JOB_TOPIC, MAX_LEN, Msg, MsgHandler};
+use crate::metrics;
+
+/// Bus backed by Redis Streams `XADD`/`XREADGROUP`
+#[derive(Clone)]
+pub struct RedisBus {
+    pub redis_client: Client,
+}
+
+impl RedisBus {
+    pub fn new(redis_client: Client) -> Self {
+        Self { redis_client }
+    }
+}
+
+#[async_trait]
+impl Bus for RedisBus {
+    async fn send_msg<'a>(&self, items: &'a [(&'a str, Msg)]) -> Result<String> {
+        let mut conn = self.redis_client.get_multiplexed_async_connection().await?;
+
+        let v: String = conn.xadd(JOB_TOPIC, "*", items).await?;
+        Ok(v)
+    }
+
+    async fn recv(&self, cb: &mut MsgHandler<'_>) -> Result<String> {
+        let mut conn = self.redis_client.get_multiplexed_async_connection().await?;
+
+        let ret: String = conn
+            .xgroup_create_mkstream(JOB_TOPIC, CONSUMER_GROUP, "$")
+            .await
+            .unwrap_or_else(|e| {
+                warn!("failed create stream group - {}", e);
+                "".to_string()
+            });
+
+        info!("create stream group {}", ret);
+
+        let opts = StreamReadOptions::default()
+            .group(CONSUMER_GROUP, local_ip()?.to_string())
+            .block(100)
+            .count(100);
+
+        loop {
+            let ret: StreamReadReply = conn.xread_options(&[JOB_TOPIC], &[">"], &opts).await?;
+
+            match conn
+                .xtrim::<_, u64>(JOB_TOPIC, StreamMaxlen::Equals(MAX_LEN))
+                .await
+            {
+                Ok(n) => debug!("trim stream {} {n} entries", JOB_TOPIC),
+                Err(e) => error!("failed to trim stream - {e}"),
+            };
+
+            for stream_key in ret.keys {
+                let msg_key = stream_key.key;
+
+                for stream_id in stream_key.ids {
+                    // entry ids start with the millisecond they were added at
+                    if let Some(ms) = stream_id.id.split('-').next().and_then(|v| v.parse().ok()) {
+                        metrics::observe_bus_lag(ms);
+                    }
+                    for (k, v) in stream_id.map {
+                        let ret = match from_redis_value::<Msg>(&v) {
+                            Ok(msg) => cb(k, msg).await,
+                            Err(e) => {
+                                error!("failed to parse redis val - {e}");
+                                Ok(())
+                            }
+                        };
+
+                        if let Err(e) = ret {
+                            error!("failed to handle msg - {e}");
+                        }
+
+                        let _: i32 = conn
+                            .xack(
+                                msg_key.clone(),
+                                CONSUMER_GROUP,
+                                std::slice::from_ref(&stream_id.id),
+                            )
+                            .await
+                            .unwrap_or_else(|v| {
+                                error!("faile to exec xack - {}", v);
+                                0
+                            });
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_bus() {
+    use crate::bridge::msg::UpdateJobParams;
+
+    let redis_client =
+        redis::Client::open("redis://:wang@127.0.0.1").expect("failed connect to redis");
+    let bus = RedisBus::new(redis_client);
+    bus.send_msg(&[(
+        "event",
+        Msg::UpdateJob(UpdateJobParams {
+            exit_code: Some(1),
+            ..Default::default()
+        }),
+    )])
+    .await
+    .unwrap();
+
+    bus.send_msg(&[(
+        "event",
+        Msg::UpdateJob(UpdateJobParams {
+            exit_code: Some(2),
+            ..Default::default()
+        }),
+    )])
+    .await
+    .unwrap();
+
+    bus.recv(&mut |key, val| {
+        Box::pin(async move {
+            println!("key:{key} val:{}", serde_json::to_string(&val).unwrap());
+            Ok(())
+        })
+    })
+    .await
+    .unwrap();
+}
@@ -9,10 +9,11 @@ use poem::{
 };
 use serde_json::{json, Value};
 use tokio::sync::{mpsc::Sender, oneshot::Sender as OneSender, Mutex};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, info_span};
 use types::SshLoginParams;
 
 use crate::{
+    artifact::ArtifactCache,
     bridge::{
         msg::{
             AgentOfflineParams, AgentOnlineParams, HeartbeatParams, Msg, MsgReqKind, MsgState,
@@ -20,7 +21,9 @@ use crate::{
         },
         Bridge,
     },
-    get_endpoint,
+    bus::{BusOptions, SharedBus},
+    get_endpoint, telemetry,
+    transfer::TransferStore,
 };
 
 use anyhow::Result;
@@ -42,10 +45,10 @@ pub struct Comet {
 }
 
 impl Comet {
-    pub fn new(redis_client: redis::Client, port: u16, secret: String) -> Self {
+    pub fn new(redis_client: redis::Client, bus: SharedBus, port: u16, secret: String) -> Self {
         Self {
             bridge: Bridge::new(),
-            logic: Logic::new(redis_client),
+            logic: Logic::new(redis_client).with_bus(bus),
             ssh_ws_streams: Arc::new(Mutex::new(HashMap::new())),
             port,
             secret,
@@ -125,7 +128,18 @@ impl Comet {
         }
     }
 
-    pub async fn dispatch(&self, req: types::DispatchJobRequest) -> Result<Value> {
+    pub async fn dispatch(&self, mut req: types::DispatchJobRequest) -> Result<Value> {
+        let params = &mut req.dispatch_params;
+        let span = info_span!(
+            target: telemetry::TARGET,
+            "comet.dispatch",
+            eid = %params.base_job.eid,
+            action = %params.action,
+            agent_ip = %req.agent_ip,
+        );
+        telemetry::set_parent(&span, params.trace_context.as_ref());
+        params.trace_context = telemetry::forward(&span, params.trace_context.as_ref());
+
         let val = self.logic.dispath(req).await?;
         let ret = self.bridge.send_msg(&val.0, val.1).await?;
         Ok(ret)
@@ -165,7 +179,16 @@ impl Comet {
         Ok(v)
     }
 
-    pub async fn update_job(&self, req: UpdateJobParams) -> Result<Value> {
+    pub async fn update_job(&self, mut req: UpdateJobParams) -> Result<Value> {
+        let span = info_span!(
+            target: telemetry::TARGET,
+            "comet.update_job",
+            eid = %req.base_job.eid,
+            run_id = %req.run_id,
+        );
+        telemetry::set_parent(&span, req.trace_context.as_ref());
+        req.trace_context = telemetry::forward(&span, req.trace_context.as_ref());
+
         let ret = self.logic.update_job(req).await?;
         Ok(ret)
     }
@@ -193,6 +216,13 @@ pub struct CometOptions {
     pub redis_url: String,
     pub bind_addr: String,
     pub secret: String,
+    pub bus: BusOptions,
+    /// directory where artifacts pushed by console and agents are cached
+    pub artifact_dir: String,
+    /// serve Prometheus metrics on /metrics
+    pub metrics: bool,
+    /// bearer token scrapers have to send, no auth when not set
+    pub metrics_token: Option<String>,
 }
 
 pub async fn run(opts: CometOptions, signal: Option<OneSender<()>>) -> Result<()> {
@@ -202,7 +232,14 @@ pub async fn run(opts: CometOptions, signal: Option<OneSender<()>>) -> Result<()
         .parse::<SocketAddr>()
         .context("failed parse bind address")?
         .port();
-    let comet = Comet::new(redis_client, port, opts.secret.clone());
+    let bus = opts
+        .bus
+        .connect(redis_client.clone())
+        .await
+        .context("failed connect to bus")?;
+    let comet = Comet::new(redis_client, bus, port, opts.secret.clone());
+    let artifacts = ArtifactCache::new(&opts.artifact_dir).context("invalid artifact dir")?;
+    let transfers = TransferStore::new(artifacts.clone());
     let app = Route::new()
         .at(
             "/dispatch",
@@ -226,6 +263,36 @@ pub async fn run(opts: CometOptions, signal: Option<OneSender<()>>) -> Result<()
                 .with(bearer_auth(&opts.secret))
                 .data(comet.clone())),
         )
+        .at(
+            "/artifact/:sha256",
+            get(handler::get_artifact
+                .with(bearer_auth(&opts.secret))
+                .data(artifacts.clone()))
+            .head(
+                handler::head_artifact
+                    .with(bearer_auth(&opts.secret))
+                    .data(artifacts),
+            )
+        )
+        .at(
+            "/transfer/:transfer_id",
+            get(handler::get_transfer
+                .with(bearer_auth(&opts.secret))
+                .data(transfers.clone()))
+            .patch(
+                handler::put_transfer_chunk
+                    .with(bearer_auth(&opts.secret))
+                    .data(transfers.clone()),
+            ),
+        )
+        .at(
+            "/transfer/:transfer_id/complete",
+            post(
+                handler::complete_transfer
+                    .with(bearer_auth(&opts.secret))
+                    .data(transfers),
+            ),
+        )
         .at(
             "/evt/:namespace",
             get(handler::ws
@@ -268,6 +335,17 @@ pub async fn run(opts: CometOptions, signal: Option<OneSender<()>>) -> Result<()
                 .with(bearer_auth(&opts.secret))
                 .data(comet.clone()),
         );
+    let app = if opts.metrics {
+        let token = opts.metrics_token.unwrap_or_default();
+        app.at(
+            "/metrics",
+            get(handler::metrics
+                .with_if(!token.is_empty(), bearer_auth(&token))
+                .data(comet.clone())),
+        )
+    } else {
+        app
+    };
     if let Some(tx) = signal {
         tx.send(()).expect("failed send signal");
     }
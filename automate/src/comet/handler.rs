@@ -1,4 +1,8 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    io::{ErrorKind, SeekFrom},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use anyhow::{anyhow, Result};
 
@@ -10,7 +14,7 @@ use futures_util::{
 
 use poem::{
     handler,
-    http::StatusCode,
+    http::{header, StatusCode},
     web::{
         websocket::{Message, WebSocket, WebSocketStream},
         Data,
@@ -18,18 +22,20 @@ use poem::{
         Path,
         Query, // RemoteAddr,
     },
-    FromRequest, IntoResponse, Request, RequestBody, Response, Result as PoemResult,
+    Body, FromRequest, IntoResponse, Request, RequestBody, Response, Result as PoemResult,
 };
 
+use rustc_serialize::hex::FromHex;
 use serde::{Deserialize, Serialize};
 use tokio::{
     fs::{self, create_dir_all, File},
-    io::AsyncWriteExt,
+    io::{AsyncSeekExt, AsyncWriteExt},
     sync::RwLock,
 };
 use tracing::error;
 
 use crate::{
+    artifact::ArtifactCache,
     bridge::client::WsClient,
     comet::{
         types::{self, SshLoginParams},
@@ -37,6 +43,7 @@ use crate::{
     },
     return_response,
     scheduler::types::{SshConnectionOption, UploadFile},
+    transfer::{parse_range_start, TransferStore},
 };
 
 pub mod middleware {
@@ -115,6 +122,15 @@ impl<'a> FromRequest<'a> for SecretHeader {
         let ssh_password = header
             .get("X-Ssh-Password")
             .and_then(|value| value.to_str().ok());
+        let ssh_private_key = header
+            .get("X-Ssh-Private-Key")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.from_hex().ok())
+            .map(|value| String::from_utf8_lossy(&value).to_string());
+        let ssh_key_passphrase = header
+            .get("X-Ssh-Key-Passphrase")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
         let ssh_port = header.get("x-ssh-port").and_then(|value| {
             value
                 .to_str()
@@ -136,13 +152,13 @@ impl<'a> FromRequest<'a> for SecretHeader {
             },
         };
 
-        if let (Some(u), Some(p), Some(port)) = (ssh_user, ssh_password, ssh_port) {
-            assign.ssh_connection_params = Some(SshConnectionOption {
-                user: u.to_string(),
-                password: p.to_string(),
-                port,
-            });
-        }
+        assign.ssh_connection_params = SshConnectionOption::build(
+            ssh_user.map(|v| v.to_string()),
+            ssh_password.map(|v| v.to_string()),
+            ssh_private_key,
+            ssh_key_passphrase,
+            ssh_port,
+        );
 
         Ok(assign)
     }
@@ -220,6 +236,7 @@ async fn upload_file(file: Option<UploadFile>) -> Result<Option<UploadFile>> {
     Ok(Some(UploadFile {
         filename: file.filename,
         data: None,
+        sha256: None,
     }))
 }
 
@@ -253,6 +270,144 @@ pub async fn get_file(Path(filename): Path<String>) -> impl IntoResponse {
     .body(data)
 }
 
+#[handler]
+pub async fn get_artifact(
+    req: &Request,
+    Path(sha256): Path<String>,
+    artifacts: Data<&ArtifactCache>,
+) -> impl IntoResponse {
+    let resp = Response::builder();
+    let path = match artifacts.path(&sha256) {
+        Ok(v) => v,
+        Err(e) => return resp.status(StatusCode::BAD_REQUEST).body(e.to_string()),
+    };
+
+    let mut file = match File::open(path).await {
+        Ok(v) => v,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return resp.status(StatusCode::NOT_FOUND).body("not found")
+        }
+        Err(e) => {
+            return resp
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(e.to_string())
+        }
+    };
+    let size = match file.metadata().await {
+        Ok(v) => v.len(),
+        Err(e) => {
+            return resp
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(e.to_string())
+        }
+    };
+
+    // a range request resumes an interrupted download
+    let start = req
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_range_start);
+    let resp = resp.content_type("application/octet-stream");
+    match start {
+        Some(start) if start >= size => resp
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{size}"))
+            .finish(),
+        Some(start) => {
+            if let Err(e) = file.seek(SeekFrom::Start(start)).await {
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(e.to_string());
+            }
+            resp.status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {start}-{}/{size}", size - 1),
+                )
+                .body(Body::from_async_read(file))
+        }
+        None => resp.body(Body::from_async_read(file)),
+    }
+}
+
+#[handler]
+pub async fn head_artifact(
+    Path(sha256): Path<String>,
+    artifacts: Data<&ArtifactCache>,
+) -> StatusCode {
+    match artifacts.contains(&sha256).await {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::BAD_REQUEST,
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TransferChunkQuery {
+    pub offset: u64,
+    pub total: u64,
+}
+
+#[derive(Deserialize)]
+pub struct TransferCompleteQuery {
+    pub sha256: String,
+    pub total: u64,
+}
+
+#[handler]
+pub async fn get_transfer(
+    Path(transfer_id): Path<String>,
+    transfers: Data<&TransferStore>,
+) -> Response {
+    match transfers.progress(&transfer_id).await {
+        Ok(Some(v)) => Json(v).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Receives a chunk of an upload, the body is the raw bytes of the chunk.
+#[handler]
+pub async fn put_transfer_chunk(
+    Path(transfer_id): Path<String>,
+    Query(query): Query<TransferChunkQuery>,
+    transfers: Data<&TransferStore>,
+    data: Vec<u8>,
+) -> Response {
+    match transfers
+        .append(&transfer_id, query.offset, query.total, &data)
+        .await
+    {
+        Ok(Ok(v)) => Json(v).into_response(),
+        Ok(Err(v)) => (StatusCode::CONFLICT, Json(v)).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+#[handler]
+pub async fn complete_transfer(
+    Path(transfer_id): Path<String>,
+    Query(query): Query<TransferCompleteQuery>,
+    transfers: Data<&TransferStore>,
+) -> Response {
+    match transfers
+        .complete(&transfer_id, &query.sha256, query.total)
+        .await
+    {
+        Ok(v) => (StatusCode::CREATED, Json(v)).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Prometheus metrics, guarded by the metrics token instead of the comet
+/// secret so that scrapers never hold the latter.
+#[handler]
+pub async fn metrics(comet: Data<&Comet>) -> Response {
+    crate::metrics::COMET_CONNECTED_AGENTS.set(comet.bridge.client_count().await as i64);
+    crate::metrics::response()
+}
+
 #[handler]
 pub async fn dispatch(
     comet: Data<&Comet>,
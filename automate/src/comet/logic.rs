@@ -1,10 +1,10 @@
-use std::net::IpAddr;
+use std::{net::IpAddr, sync::Arc};
 
 use crate::{
     bridge::msg::{
         AgentOfflineParams, AgentOnlineParams, HeartbeatParams, MsgReqKind, UpdateJobParams,
     },
-    bus::Bus,
+    bus::{RedisBus, SharedBus},
     get_endpoint, LinkPair,
 };
 use anyhow::{Ok, Result};
@@ -19,7 +19,7 @@ use super::types::{self};
 pub struct Logic {
     pub redis_client: redis::Client,
     local_ip: IpAddr,
-    bus: Bus,
+    bus: SharedBus,
 }
 
 impl Logic {
@@ -27,10 +27,16 @@ impl Logic {
         Self {
             local_ip: local_ip().expect("failed get local ip"),
             redis_client: redis.clone(),
-            bus: Bus::new(redis),
+            bus: Arc::new(RedisBus::new(redis)),
         }
     }
 
+    /// replace the default redis streams bus
+    pub fn with_bus(mut self, bus: SharedBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
     pub fn get_agent_key(&self, ip: impl Into<String>, mac_addr: impl Into<String>) -> String {
         get_endpoint(ip, mac_addr)
     }
@@ -97,6 +97,10 @@ pub struct SshLoginParams {
     pub namespace: String,
     pub user: String,
     pub password: String,
+    #[serde(default)]
+    pub private_key: Option<String>,
+    #[serde(default)]
+    pub passphrase: Option<String>,
     pub port: u16,
     pub ip: String,
     pub mac_addr: String,
@@ -3,10 +3,14 @@ use local_ip_address::local_ip;
 use std::net::IpAddr;
 use std::sync::{Mutex, OnceLock};
 
+pub mod artifact;
 pub mod bridge;
 pub mod comet;
+pub mod metrics;
 pub mod scheduler;
 pub mod ssh;
+pub mod telemetry;
+pub mod transfer;
 pub use bridge::msg::DispatchJobParams;
 pub use comet::logic::Logic;
 pub use comet::types::{
@@ -14,8 +18,12 @@ pub use comet::types::{
     SftpUploadRequest,
 };
 use reqwest::Client;
+pub use scheduler::timer::{BlackoutWindow, CalendarMode, TimerCalendar, TimerSchedule};
 pub use scheduler::types::BaseJob;
 pub use scheduler::types::JobAction;
+pub use scheduler::types::{
+    ContainerOptions, HealthCheck, Probe, ProbeAction, PullPolicy, RestartMode, RestartPolicy,
+};
 
 pub mod bus;
 
@@ -0,0 +1,178 @@
+//! Prometheus metrics of comet, the agent and the event bus. They live in the
+//! default registry, so the bundled binary exposes everything on one endpoint.
+
+use std::sync::LazyLock;
+
+use poem::{IntoResponse, Response, http::header};
+use prometheus::{
+    GaugeVec, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+    exponential_buckets, register_gauge_vec, register_histogram_vec, register_int_counter,
+    register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+};
+use tracing::error;
+
+use crate::{bridge::msg::MsgReqKind, bus::JOB_TOPIC};
+
+/// Time until the other end of the bridge answered a request.
+pub static BRIDGE_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "jiascheduler_bridge_request_duration_seconds",
+        "Time until the other end of the bridge answered a request",
+        &["kind", "result"],
+        exponential_buckets(0.005, 4.0, 9).unwrap()
+    )
+    .unwrap()
+});
+
+pub static BRIDGE_REQUEST_TIMEOUTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "jiascheduler_bridge_request_timeouts_total",
+        "Bridge requests left unanswered",
+        &["kind"]
+    )
+    .unwrap()
+});
+
+/// Set on every scrape of comet.
+pub static COMET_CONNECTED_AGENTS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "jiascheduler_comet_connected_agents",
+        "Agents connected to this comet"
+    )
+    .unwrap()
+});
+
+/// Age of the latest event taken from the bus by the console.
+pub static BUS_LAG: LazyLock<GaugeVec> = LazyLock::new(|| {
+    register_gauge_vec!(
+        "jiascheduler_bus_lag_seconds",
+        "Age of the latest event received from the bus",
+        &["topic"]
+    )
+    .unwrap()
+});
+
+pub static AGENT_RUNNING_JOBS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "jiascheduler_agent_running_jobs",
+        "Jobs running on this agent",
+        &["schedule_type"]
+    )
+    .unwrap()
+});
+
+pub static AGENT_JOB_EXITS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "jiascheduler_agent_job_exits_total",
+        "Finished runs by exit code, runs that failed to start count as 99",
+        &["schedule_type", "exit_code"]
+    )
+    .unwrap()
+});
+
+pub static AGENT_JOB_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "jiascheduler_agent_job_duration_seconds",
+        "Wall time of finished runs",
+        &["schedule_type"],
+        exponential_buckets(0.1, 4.0, 10).unwrap()
+    )
+    .unwrap()
+});
+
+pub static AGENT_SUPERVISOR_RESTARTS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "jiascheduler_agent_supervisor_restarts_total",
+        "Restarts of supervised jobs"
+    )
+    .unwrap()
+});
+
+/// Set on every scrape of the agent.
+pub static AGENT_OUTBOX_DEPTH: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "jiascheduler_agent_outbox_depth",
+        "Messages waiting to be written to comet"
+    )
+    .unwrap()
+});
+
+/// Records the age of an event published at `published_ms`, a unix timestamp
+/// in milliseconds.
+pub fn observe_bus_lag(published_ms: i64) {
+    let lag = (chrono::Utc::now().timestamp_millis() - published_ms).max(0);
+    BUS_LAG
+        .with_label_values(&[JOB_TOPIC])
+        .set(lag as f64 / 1000.0);
+}
+
+/// Keeps a job counted in [`AGENT_RUNNING_JOBS`] until dropped.
+pub struct RunningJobGuard(IntGauge);
+
+impl RunningJobGuard {
+    pub fn new(schedule_type: &str) -> Self {
+        let gauge = AGENT_RUNNING_JOBS.with_label_values(&[schedule_type]);
+        gauge.inc();
+        Self(gauge)
+    }
+}
+
+impl Drop for RunningJobGuard {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+/// Counts a finished run that took `secs`, `exit_code` is none when the
+/// agent could not tell.
+pub fn observe_job_exit(schedule_type: &str, exit_code: Option<i32>, secs: f64) {
+    let exit_code = exit_code.map_or("unknown".to_string(), |v| v.to_string());
+    AGENT_JOB_EXITS
+        .with_label_values(&[schedule_type, &exit_code])
+        .inc();
+    AGENT_JOB_DURATION
+        .with_label_values(&[schedule_type])
+        .observe(secs);
+}
+
+/// Label of a bridge request.
+pub fn request_kind(req: &MsgReqKind) -> &'static str {
+    match req {
+        MsgReqKind::DispatchJobRequest(_) => "dispatch_job",
+        MsgReqKind::RuntimeActionRequest(_) => "runtime_action",
+        MsgReqKind::PullJobRequest(_) => "pull_job",
+        MsgReqKind::SftpReadDirRequest(_) => "sftp_read_dir",
+        MsgReqKind::SftpUploadRequest(_) => "sftp_upload",
+        MsgReqKind::SftpDownloadRequest(_) => "sftp_download",
+        MsgReqKind::SftpRemoveRequest(_) => "sftp_remove",
+        MsgReqKind::Auth(_) => "auth",
+        MsgReqKind::UpdateJobRequest(_) => "update_job",
+        MsgReqKind::HeartbeatRequest(_) => "heartbeat",
+    }
+}
+
+/// Everything registered so far in the text exposition format.
+pub fn render() -> String {
+    TextEncoder::new()
+        .encode_to_string(&prometheus::gather())
+        .unwrap_or_else(|e| {
+            error!("failed encode metrics - {e}");
+            String::new()
+        })
+}
+
+/// Serves [`render`] as a `/metrics` response.
+pub fn response() -> Response {
+    render()
+        .with_header(header::CONTENT_TYPE, "text/plain; version=0.0.4")
+        .into_response()
+}
+
+#[test]
+fn test_running_job_guard() {
+    let guard = RunningJobGuard::new("flow");
+    assert_eq!(AGENT_RUNNING_JOBS.with_label_values(&["flow"]).get(), 1);
+    assert!(render().contains("jiascheduler_agent_running_jobs{schedule_type=\"flow\"} 1"));
+    drop(guard);
+    assert_eq!(AGENT_RUNNING_JOBS.with_label_values(&["flow"]).get(), 0);
+}
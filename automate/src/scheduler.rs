@@ -1,7 +1,9 @@
 mod cmd;
 pub(self) mod executor;
 pub(self) mod file;
+mod probe;
 pub mod scheduler;
+pub mod timer;
 pub mod types;
 
 pub use scheduler::*;
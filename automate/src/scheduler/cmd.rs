@@ -5,10 +5,12 @@ use bytes::BufMut;
 
 use tokio::{
     io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt},
-    process::Command,
+    process::{Child, Command},
     sync::mpsc::{Receiver, UnboundedSender},
 };
-use tracing::{error, info};
+use tracing::{debug, error, info};
+
+use super::types::Stopped;
 
 async fn read_to_end<A: AsyncRead + Unpin>(
     io: &mut Option<A>,
@@ -39,6 +41,8 @@ async fn read_to_end<A: AsyncRead + Unpin>(
 pub struct Cmd<'a> {
     inner: Command,
     timeout: Option<Duration>,
+    stop_signal: String,
+    grace_period: Option<Duration>,
     read_code_from_stdin: (bool, &'a str),
 }
 
@@ -48,6 +52,8 @@ impl<'a> Cmd<'a> {
             inner: Command::new(program),
             read_code_from_stdin: (false, ""),
             timeout: None,
+            stop_signal: "SIGTERM".to_string(),
+            grace_period: None,
         }
     }
 
@@ -65,6 +71,15 @@ impl<'a> Cmd<'a> {
         self
     }
 
+    /// send `signal` on timeout or kill and wait `grace_period` seconds before SIGKILL
+    pub fn stop_signal(&mut self, signal: &str, grace_period: u64) -> &mut Self {
+        if !signal.is_empty() {
+            self.stop_signal = signal.to_string();
+        }
+        self.grace_period = Some(Duration::from_secs(grace_period));
+        self
+    }
+
     #[cfg(unix)]
     pub fn work_user(&mut self, user: &str) -> Result<&mut Self> {
         let u = users::get_user_by_name(user).ok_or(anyhow!("invalid system user {user}"))?;
@@ -94,11 +109,57 @@ impl<'a> Cmd<'a> {
         Ok(())
     }
 
+    #[cfg(windows)]
+    pub fn signalpg(_pid: u32, _signal: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// send a signal like "SIGTERM" or "TERM" to the process group
+    #[cfg(unix)]
+    pub fn signalpg(pid: u32, signal: &str) -> Result<()> {
+        use nix::sys::signal::Signal;
+
+        let signal: Signal = if signal.starts_with("SIG") {
+            signal.parse()
+        } else {
+            format!("SIG{signal}").parse()
+        }
+        .map_err(|_| anyhow!("invalid stop signal {signal}"))?;
+        let pid = nix::unistd::Pid::from_raw(pid as i32);
+        nix::sys::signal::killpg(pid, signal)?;
+        Ok(())
+    }
+
+    /// stop the process group gracefully and SIGKILL it once the grace period ran out,
+    /// return true when it had to be force killed
+    async fn terminate(&self, child: &mut Child, pid: u32) -> Result<bool> {
+        let mut force_killed = true;
+        if let Some(grace_period) = self.grace_period.filter(|v| !v.is_zero()) {
+            match Self::signalpg(pid, &self.stop_signal) {
+                Ok(_) => {
+                    force_killed = tokio::time::timeout(grace_period, child.wait())
+                        .await
+                        .is_err();
+                }
+                Err(e) => error!("failed to send {} - {e}", self.stop_signal),
+            }
+        }
+
+        // also clean up whatever the job left in its process group
+        if let Err(e) = Self::killpg(pid) {
+            debug!("failed to kill process group {pid} - {e}");
+        }
+        if force_killed {
+            child.kill().await?;
+        }
+        Ok(force_killed)
+    }
+
     pub async fn wait_with_output(
         &mut self,
         tx: UnboundedSender<String>,
         mut kill_signal_rx: Receiver<()>,
-    ) -> Result<Output> {
+    ) -> Result<(Output, Option<Stopped>)> {
         // kill process group See https://github.com/rust-lang/rust/issues/115241
         #[cfg(unix)]
         let mut child = self.inner.process_group(0).spawn()?;
@@ -126,24 +187,30 @@ impl<'a> Cmd<'a> {
         tokio::pin!(sleep);
 
         let pid = child.id().unwrap();
-        tokio::select! {
+        let is_timeout = tokio::select! {
             _ = &mut sleep =>  {
                 info!("timeout kill");
-                child.kill().await?;
-                Self::killpg(pid)?;
-
+                Some(true)
             },
             _ = kill_signal_rx.recv() => {
                 info!("manual kill");
-                child.kill().await?;
-                Self::killpg(pid)?;
+                Some(false)
             },
             ret = child.wait() =>{
                 ret?;
+                None
             },
 
         };
 
+        let stopped = match is_timeout {
+            Some(is_timeout) => Some(Stopped {
+                is_timeout,
+                force_killed: self.terminate(&mut child, pid).await?,
+            }),
+            None => None,
+        };
+
         let (status, stdout, stderr) =
             futures_util::future::try_join3(child.wait(), stdout_fut, stderr_fut).await?;
 
@@ -151,10 +218,42 @@ impl<'a> Cmd<'a> {
         drop(stdout_pipe);
         drop(stderr_pipe);
 
-        Ok(Output {
-            status,
-            stderr,
-            stdout,
-        })
+        Ok((
+            Output {
+                status,
+                stderr,
+                stdout,
+            },
+            stopped,
+        ))
     }
 }
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_graceful_stop() {
+    use std::process::Stdio;
+
+    let run = |code: &'static str| async move {
+        let mut cmd = Cmd::new("bash");
+        cmd.stop_signal("TERM", 2);
+        cmd.get_ref()
+            .args(["-c", code])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let (kill_tx, kill_rx) = tokio::sync::mpsc::channel(1);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            kill_tx.send(()).await.unwrap();
+        });
+        cmd.wait_with_output(tx, kill_rx).await.unwrap().1.unwrap()
+    };
+
+    let stopped = run("trap 'exit 0' TERM; sleep 10 & wait").await;
+    assert!(!stopped.force_killed);
+    assert!(!stopped.is_timeout);
+
+    let stopped = run("trap '' TERM; sleep 10").await;
+    assert!(stopped.force_killed);
+}
@@ -8,6 +8,7 @@ use std::sync::Arc;
 use std::{
     collections::HashMap,
     process::{Output, Stdio},
+    time::Duration,
 };
 use tokio::sync::mpsc::Receiver;
 
@@ -16,7 +17,10 @@ use tracing::error;
 
 use crate::scheduler::cmd::Cmd;
 
-use super::types::{BaseJob, BundleOutput};
+use nanoid::nanoid;
+use tokio::process::Command;
+
+use super::types::{BaseJob, BundleOutput, ContainerOptions, Stopped};
 
 #[derive(Default)]
 pub struct ExecutorBuilder {
@@ -81,27 +85,36 @@ impl Executor {
         ExecutorBuilder::new()
     }
 
+    /// adds an environment variable after the executor was built
+    pub fn env(mut self, k: String, v: String) -> Self {
+        self.env.insert(k, v);
+        self
+    }
+
     pub fn get_log_file_path(&self) -> PathBuf {
         PathBuf::from(&self.output_dir).join(format!("{}.log", self.job.eid))
     }
 
-    pub async fn run(&self, mut ctx: Ctx) -> Result<BundleOutput> {
+    /// run the job, the second value is set when it was stopped by timeout or kill
+    pub async fn run(&self, mut ctx: Ctx) -> Result<(BundleOutput, Option<Stopped>)> {
         if self.job.bundle_script.is_none() {
-            let output = self
+            let (output, stopped) = self
                 .exec(
                     ctx,
                     self.job.cmd_name.clone(),
                     self.job.args.clone(),
                     self.job.code.clone(),
+                    self.job.container.as_ref(),
                 )
                 .await?;
 
-            return Ok(BundleOutput::Output(output));
+            return Ok((BundleOutput::Output(output), stopped));
         }
 
         let kill_signal_tx: Arc<Mutex<Vec<mpsc::Sender<()>>>> = Arc::new(Mutex::new(vec![]));
         let kill_signal_tx_clone = kill_signal_tx.clone();
         let mut outputs = HashMap::new();
+        let mut stopped = None;
 
         let handler = tokio::spawn(async move {
             match ctx.kill_signal_rx.recv().await {
@@ -121,54 +134,147 @@ impl Executor {
         for v in self.job.bundle_script.clone().unwrap().clone().into_iter() {
             let (tx, kill_signal_rx) = mpsc::channel::<()>(1);
             kill_signal_tx.lock().await.push(tx);
-            let output = self
+            let (output, v_stopped) = self
                 .exec(
                     Ctx { kill_signal_rx },
                     v.cmd_name.clone(),
                     v.args.clone(),
                     v.code.clone(),
+                    v.container.as_ref(),
                 )
                 .await?;
             outputs.insert(v.eid, output);
+            stopped = v_stopped.or(stopped);
         }
 
         handler.abort();
-        return Ok(BundleOutput::Bundle(outputs));
+        Ok((BundleOutput::Bundle(outputs), stopped))
+    }
+
+    /// the command of a job, run as its user in its work dir, or through
+    /// the container runtime when it runs in a container named `name`
+    fn command<'c>(
+        &self,
+        cmd_name: String,
+        args: Vec<String>,
+        container: Option<(&ContainerOptions, &str)>,
+        interactive: bool,
+    ) -> Result<Cmd<'c>> {
+        let (cmd_name, args) = match container {
+            Some((opts, name)) => {
+                let mut run_args = opts.run_args(
+                    name,
+                    self.job.work_dir.as_deref(),
+                    self.job.work_user.as_deref(),
+                    &self.env,
+                    interactive,
+                );
+                run_args.push(cmd_name);
+                run_args.extend(args);
+                (opts.runtime().to_string(), run_args)
+            }
+            None => (cmd_name, args),
+        };
+
+        let mut cmd = Cmd::new(cmd_name);
+        // inside a container they are passed to the runtime instead
+        if container.is_none() {
+            if let Some(ref work_dir) = self.job.work_dir {
+                cmd.work_dir(work_dir);
+            }
+
+            if let Some(ref work_user) = self.job.work_user {
+                cmd.work_user(work_user)?;
+            }
+        }
+
+        // the container runtime passes them on by name
+        let container_env = container.map(|(opts, _)| opts.env.iter());
+        for (key, val) in container_env.into_iter().flatten().chain(self.env.iter()) {
+            cmd.get_ref().env(key, val);
+        }
+
+        cmd.get_ref().args(&args);
+        Ok(cmd)
+    }
+
+    /// run the command of an exec probe the way the job runs, a container job
+    /// is probed in another container of its image
+    pub async fn probe(&self, command: &str, args: &[String], timeout: Duration) -> Result<()> {
+        let container = self
+            .job
+            .container
+            .as_ref()
+            .map(|v| (v, format!("jiascheduler-probe-{}", nanoid!())));
+        let mut cmd = self.command(
+            command.to_string(),
+            args.to_vec(),
+            container
+                .as_ref()
+                .map(|(opts, name)| (*opts, name.as_str())),
+            false,
+        )?;
+        cmd.timeout(timeout.as_secs().max(1));
+        cmd.get_ref()
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        let (tx, _) = mpsc::unbounded_channel::<String>();
+        let (_kill_signal_tx, kill_signal_rx) = mpsc::channel::<()>(1);
+        let ret = cmd.wait_with_output(tx, kill_signal_rx).await;
+
+        if let Some((opts, name)) = container
+            && !ret.as_ref().is_ok_and(|v| v.1.is_none())
+        {
+            Self::remove_container(opts.runtime(), &name).await;
+        }
+        let (output, stopped) = ret?;
+        if stopped.is_some() {
+            anyhow::bail!("probe command timed out after {timeout:?}");
+        }
+        if !output.status.success() {
+            anyhow::bail!("probe command {}", output.status);
+        }
+        Ok(())
     }
 
     async fn exec(
         &self,
         ctx: Ctx,
         cmd_name: String,
-        args: Vec<String>,
+        mut args: Vec<String>,
         code: String,
-    ) -> Result<Output> {
-        let mut cmd = Cmd::new(cmd_name);
-        let mut args = args;
+        container: Option<&ContainerOptions>,
+    ) -> Result<(Output, Option<Stopped>)> {
+        let container = container.map(|v| (v, format!("jiascheduler-{}", nanoid!())));
+        if !self.job.read_code_from_stdin {
+            args.push(code.clone());
+        }
+        let mut cmd = self.command(
+            cmd_name,
+            args,
+            container
+                .as_ref()
+                .map(|(opts, name)| (*opts, name.as_str())),
+            self.job.read_code_from_stdin,
+        )?;
         if self.job.read_code_from_stdin {
             cmd = cmd.read_code_from_stdin(&code);
             cmd.get_ref().stdin(Stdio::piped());
-        } else {
-            args.push(code.clone());
-        }
-
-        if let Some(ref work_dir) = self.job.work_dir {
-            cmd.work_dir(work_dir);
         }
 
-        if let Some(ref work_user) = self.job.work_user {
-            cmd.work_user(work_user)?;
-        }
         if self.job.timeout > 0 {
             cmd.timeout(self.job.timeout);
         }
 
-        for (key, val) in self.env.iter() {
-            cmd.get_ref().env(key, val);
+        if let Some(grace_period) = self.job.stop_grace_period {
+            cmd.stop_signal(
+                self.job.stop_signal.as_deref().unwrap_or_default(),
+                grace_period,
+            );
         }
 
-        cmd.get_ref().args(&args);
-
         let (tx, mut rx) = mpsc::unbounded_channel::<String>();
 
         let filepath = self.get_log_file_path();
@@ -197,15 +303,35 @@ impl Executor {
         cmd.get_ref().stdout(Stdio::piped());
         cmd.get_ref().stderr(Stdio::piped());
 
-        let output = cmd.wait_with_output(tx, ctx.kill_signal_rx).await?;
+        let ret = cmd.wait_with_output(tx, ctx.kill_signal_rx).await;
 
-        Ok(output)
+        // killing the cli does not stop the container
+        if let Some((opts, name)) = container
+            && !ret.as_ref().is_ok_and(|v| v.1.is_none())
+        {
+            Self::remove_container(opts.runtime(), &name).await;
+        }
+        ret
+    }
+
+    async fn remove_container(runtime: &str, name: &str) {
+        match Command::new(runtime)
+            .args(["rm", "-f", name])
+            .output()
+            .await
+        {
+            Ok(v) if !v.status.success() => error!(
+                "failed to remove container {name} - {}",
+                String::from_utf8_lossy(&v.stderr)
+            ),
+            Err(e) => error!("failed to remove container {name} - {e}"),
+            _ => {}
+        }
     }
 }
 
 #[tokio::test]
 async fn test_command_exec() {
-    use nanoid::nanoid;
     use std::time::Duration;
     use tokio::time::sleep;
     use tracing::info;
@@ -228,6 +354,10 @@ async fn test_command_exec() {
             work_user: None,
             max_retry: None,
             max_parallel: None,
+            stop_signal: None,
+            stop_grace_period: Some(1),
+            container: None,
+            publish_artifacts: None,
         })
         .build();
 
@@ -238,10 +368,11 @@ async fn test_command_exec() {
         kill_signal_tx.send(()).await.unwrap();
         info!("end manual kill");
     });
-    let output = c.run(Ctx { kill_signal_rx }).await.unwrap();
+    let (output, stopped) = c.run(Ctx { kill_signal_rx }).await.unwrap();
 
     println!("stdout: {:?}", output.get_stdout());
     println!("stderr: {:?}", output.get_stderr());
     println!("exit_status: {:?}", output.get_exit_status());
-    println!("exit_code: {:?}", output.get_exit_code())
+    println!("exit_code: {:?}", output.get_exit_code());
+    println!("stopped: {:?}", stopped)
 }
@@ -1,37 +1,39 @@
-// use crate::get_http_client;
+use std::path::Path;
 
 use super::types::UploadFile;
-use anyhow::Result;
-use tokio::{
-    fs::{create_dir_all, File},
-    io::AsyncWriteExt,
-};
+use crate::artifact::ArtifactCache;
+use anyhow::{Result, anyhow};
+use tokio::fs::{self, create_dir_all};
 
 const UPLOAD_DIR: &str = "/tmp/jiascheduler-agent";
 
-pub async fn try_download_file(_host: String, file: Option<UploadFile>) -> Result<()> {
+/// Places the upload file of a job into the upload dir, an artifact is
+/// fetched from comet only when it is missing from the local cache.
+pub async fn try_download_file(
+    comet_addr: Option<String>,
+    comet_secret: &str,
+    artifacts: &ArtifactCache,
+    file: Option<UploadFile>,
+) -> Result<()> {
     let file = match file {
         Some(v) => v,
         None => return Ok(()),
     };
 
-    let data = if let Some(data) = file.data {
-        data
-    } else {
-        return Ok(());
-    };
-
-    // let client = get_http_client();
-    // let data = client
-    //     .get(format!("http://{}/file/get/{}", host, file.filename))
-    //     .send()
-    //     .await?
-    //     .bytes()
-    //     .await?;
-
+    let filename = Path::new(&file.filename)
+        .file_name()
+        .ok_or(anyhow!("invalid upload filename {}", file.filename))?;
     create_dir_all(UPLOAD_DIR).await?;
-    let target_file = format!("{}/{}", UPLOAD_DIR, file.filename);
-    let mut tmp_file = File::create(target_file.clone()).await?;
-    tmp_file.write_all(&data).await?;
+    let target_file = Path::new(UPLOAD_DIR).join(filename);
+
+    match (file.data, file.sha256) {
+        (Some(data), _) => fs::write(target_file, data).await?,
+        (None, Some(sha256)) => {
+            let comet_addr = comet_addr.ok_or(anyhow!("comet is not connected"))?;
+            let cached = artifacts.fetch(&comet_addr, comet_secret, &sha256).await?;
+            fs::copy(cached, target_file).await?;
+        }
+        (None, None) => {}
+    }
     Ok(())
 }
@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use futures::Future;
+use tokio::{net::TcpStream, time::sleep};
+
+use super::{
+    executor::Executor,
+    types::{Probe, ProbeAction, ProbeResult},
+};
+use crate::get_http_client;
+
+impl ProbeAction {
+    async fn check(&self, executor: &Executor, timeout: Duration) -> Result<()> {
+        match self {
+            ProbeAction::Exec { command, args } => {
+                executor.probe(command, args, timeout).await?;
+            }
+            ProbeAction::Tcp { host, port } => {
+                TcpStream::connect((host.as_str(), *port)).await?;
+            }
+            ProbeAction::Http { url } => {
+                let status = get_http_client().get(url).send().await?.status();
+                if status.is_client_error() || status.is_server_error() {
+                    anyhow::bail!("probe http status {status}");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Probe {
+    /// Check once, an exec probe runs like the job of `executor`.
+    pub async fn check(&self, executor: &Executor) -> Result<()> {
+        let timeout = Duration::from_secs(self.timeout.max(1));
+        if let ProbeAction::Exec { .. } = self.action {
+            // the executor stops the command, and its container, on timeout
+            return self.action.check(executor, timeout).await;
+        }
+        tokio::time::timeout(timeout, self.action.check(executor, timeout))
+            .await
+            .map_err(|_| anyhow!("probe timed out after {timeout:?}"))?
+    }
+
+    /// Check forever and call `report` whenever the probe turns healthy or unhealthy.
+    ///
+    /// The probe starts healthy when `healthy` is true, a liveness probe should
+    /// pass true and stop once the returned result is unhealthy.
+    pub async fn watch<F, Fut>(&self, executor: &Executor, healthy: bool, mut report: F)
+    where
+        F: FnMut(ProbeResult) -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        let mut ret = ProbeResult {
+            healthy,
+            ..Default::default()
+        };
+        sleep(Duration::from_secs(self.initial_delay)).await;
+
+        loop {
+            let prev = ret.healthy;
+            match self.check(executor).await {
+                Ok(_) => {
+                    ret.healthy = true;
+                    ret.failures = 0;
+                    ret.message.clear();
+                }
+                Err(e) => {
+                    ret.failures += 1;
+                    ret.message = e.to_string();
+                    if ret.failures >= self.failure_threshold.max(1) {
+                        ret.healthy = false;
+                    }
+                }
+            }
+
+            if (prev != ret.healthy || ret.failures == 1) && !report(ret.clone()).await {
+                return;
+            }
+
+            sleep(Duration::from_secs(self.interval.max(1))).await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_tcp_probe() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let probe = Probe {
+        action: ProbeAction::Tcp {
+            host: "127.0.0.1".to_string(),
+            port: listener.local_addr().unwrap().port(),
+        },
+        initial_delay: 0,
+        interval: 1,
+        timeout: 1,
+        failure_threshold: 1,
+    };
+    let executor = Executor::builder().build();
+    assert!(probe.check(&executor).await.is_ok());
+
+    drop(listener);
+    assert!(probe.check(&executor).await.is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_exec_probe_runs_as_work_user() {
+    use super::types::BaseJob;
+
+    let dir = std::env::temp_dir().canonicalize().unwrap();
+    let check = |work_user: &str, expected_user: &str| {
+        let executor = Executor::builder()
+            .job(BaseJob {
+                work_user: Some(work_user.to_string()),
+                work_dir: Some(dir.to_string_lossy().to_string()),
+                ..Default::default()
+            })
+            .build();
+        let probe = Probe {
+            action: ProbeAction::Exec {
+                command: "sh".to_string(),
+                args: vec![
+                    "-c".to_string(),
+                    format!(
+                        "[ \"$(id -un)\" = {expected_user} ] && [ \"$(pwd -P)\" = {} ]",
+                        dir.display()
+                    ),
+                ],
+            },
+            initial_delay: 0,
+            interval: 1,
+            timeout: 5,
+            failure_threshold: 1,
+        };
+        async move { probe.check(&executor).await }
+    };
+
+    let user = users::get_current_username().unwrap();
+    let user = user.to_string_lossy();
+    assert!(check(&user, &user).await.is_ok());
+    assert!(check("no-such-user-of-jiascheduler", &user).await.is_err());
+    // only root can switch to another user
+    if users::get_current_uid() == 0 {
+        assert!(check("nobody", "nobody").await.is_ok());
+        assert!(check("nobody", &user).await.is_err());
+    }
+}
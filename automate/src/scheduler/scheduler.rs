@@ -1,26 +1,30 @@
 use std::{
     collections::HashMap,
+    path::PathBuf,
     sync::{Arc, atomic},
     time::Duration,
 };
 
 use anyhow::{Context, Result, anyhow};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, Utc};
 use futures::{SinkExt, StreamExt};
 use nanoid::nanoid;
 
 use crate::{
+    artifact::{self, ArtifactCache, ArtifactRef},
     bridge::msg::{
         BundleOutputParams, RuntimeActionParams, SftpDownloadParams, SftpReadDirParams,
         SftpRemoveParams, SftpUploadParams, UpdateJobParams,
     },
     comet::types::SshLoginParams,
-    get_comet_addr, get_local_ip, get_mac_address, run_id,
+    get_comet_addr, get_http_client, get_local_ip, get_mac_address, metrics, run_id,
     scheduler::types::JobAction,
     set_comet_addr,
-    ssh::{self, ConnectParams, Session},
+    ssh::{self, ConnectParams, Session, SshAuth},
+    telemetry, transfer,
 };
 use futures_util::stream::{SplitSink, SplitStream};
+use poem::{Route, Server, endpoint::make, get, listener::TcpListener};
 
 use serde_json::{Value, json};
 use tokio::{
@@ -30,22 +34,22 @@ use tokio::{
         Mutex,
         mpsc::{Receiver, Sender, UnboundedSender, channel, unbounded_channel},
     },
-    task,
+    task::{self, AbortHandle},
     time::{sleep, timeout},
 };
-use tokio_cron_scheduler::{Job, JobScheduler};
 use tokio_tungstenite::{
     MaybeTlsStream, WebSocketStream, connect_async,
     tungstenite::{ClientRequestBuilder, Message},
 };
-use tracing::{debug, error, info};
-use uuid::Uuid;
+use tracing::{debug, error, info, info_span};
 
 use super::{
     executor::Ctx,
     file::try_download_file,
+    timer::{BlackoutWindow, TimerSchedule},
     types::{
-        self, AssignUserOption, BundleOutput, RuntimeAction, ScheduleType, SshConnectionOption,
+        self, AssignUserOption, BaseJob, BundleOutput, RestartState, RuntimeAction, ScheduleType,
+        SshConnectionOption,
     },
 };
 
@@ -67,15 +71,22 @@ pub struct RunningJobContext {
     daemon_kill_senders: Vec<(String, Sender<()>)>,
 }
 
+/// The task firing a timer, the id tells a replaced timer from its successor.
+struct TimerHandle {
+    id: String,
+    abort: AbortHandle,
+}
+
 #[derive(Clone)]
 pub struct React {
-    sched: JobScheduler,
     bridge: Bridge,
     output_dir: String,
     namespace: String,
     local_ip: String,
     client_key: String,
-    schedule_uuid_mapping: Arc<Mutex<HashMap<String, Uuid>>>,
+    comet_secret: String,
+    artifacts: ArtifactCache,
+    timers: Arc<Mutex<HashMap<String, TimerHandle>>>,
     supervisor_jobs: Arc<Mutex<HashMap<String, UnboundedSender<SupervisorSignal>>>>,
     running_job_contexts: Arc<Mutex<HashMap<String, RunningJobContext>>>,
 }
@@ -92,11 +103,14 @@ impl React {
         local_ip: String,
         client_key: String,
         output_dir: String,
+        comet_secret: String,
+        artifacts: ArtifactCache,
     ) -> Self {
         Self {
-            sched: JobScheduler::new().await.unwrap(),
             output_dir,
-            schedule_uuid_mapping: Arc::new(Mutex::new(HashMap::new())),
+            comet_secret,
+            artifacts,
+            timers: Arc::new(Mutex::new(HashMap::new())),
             running_job_contexts: Arc::new(Mutex::new(HashMap::new())),
             supervisor_jobs: Arc::new(Mutex::new(HashMap::new())),
             bridge,
@@ -115,31 +129,65 @@ impl React {
         self.bridge.send_msg(&self.client_key, data).await
     }
 
-    async fn add_job_schedule(&mut self, eid: String, job: Job) -> Result<Option<DateTime<Local>>> {
-        self.remove_job_schedule(eid.as_str()).await?;
-
-        let mut locked_map = self.schedule_uuid_mapping.lock().await;
-        if locked_map.get(&eid).is_some() {
-            anyhow::bail!("{eid} is existed");
+    /// Pushes the output files of a finished run to comet, a file that cannot be
+    /// published is logged and skipped.
+    async fn publish_artifacts(&self, base_job: &BaseJob) -> Option<Vec<ArtifactRef>> {
+        let files = base_job
+            .publish_artifacts
+            .as_ref()
+            .filter(|v| !v.is_empty())?;
+        let comet_addr = get_comet_addr()?;
+
+        let mut artifacts = Vec::new();
+        for file in files {
+            let mut path = PathBuf::from(file);
+            if let (true, Some(dir)) = (path.is_relative(), base_job.work_dir.as_ref()) {
+                path = PathBuf::from(dir).join(path);
+            }
+            match artifact::publish(&comet_addr, &self.comet_secret, &path).await {
+                Ok(v) => artifacts.push(v),
+                Err(e) => error!("failed publish artifact {} - {e}", path.display()),
+            }
         }
+        Some(artifacts)
+    }
 
-        let uuid = self.sched.add(job).await?;
-
-        let next_time = self.sched.next_tick_for_job(uuid).await?.map(|v| v.into());
-
-        locked_map.insert(eid, uuid);
-        Ok(next_time)
+    /// Replaces the timer of the job, returns the id its task must hold to
+    /// remove itself once exhausted.
+    async fn add_job_schedule(&mut self, eid: String, abort: AbortHandle) -> String {
+        let id = nanoid!();
+        let mut locked_map = self.timers.lock().await;
+        if let Some(prev) = locked_map.insert(
+            eid,
+            TimerHandle {
+                id: id.clone(),
+                abort,
+            },
+        ) {
+            prev.abort.abort();
+        }
+        id
     }
 
     async fn remove_job_schedule(&mut self, eid: &str) -> Result<()> {
-        let mut locked_map = self.schedule_uuid_mapping.lock().await;
-        if let Some(uuid) = locked_map.get(eid) {
-            self.sched.remove(uuid).await?;
-            locked_map.remove(eid);
+        let mut locked_map = self.timers.lock().await;
+        if let Some(timer) = locked_map.remove(eid) {
+            timer.abort.abort();
         }
         Ok(())
     }
 
+    /// Drops the entry of a timer that will not fire again, unless it was
+    /// replaced meanwhile.
+    async fn finish_job_schedule(&mut self, eid: &str, id: &str) -> bool {
+        let mut locked_map = self.timers.lock().await;
+        if locked_map.get(eid).is_some_and(|v| v.id == id) {
+            locked_map.remove(eid);
+            return true;
+        }
+        false
+    }
+
     async fn can_execute(&mut self, params: &DispatchJobParams) -> Result<()> {
         let eid = params.base_job.eid.clone();
         let mut locked_map = self.running_job_contexts.lock().await;
@@ -296,11 +344,6 @@ impl React {
         }
     }
 
-    async fn start(&mut self) -> Result<()> {
-        self.sched.start().await?;
-        Ok(())
-    }
-
     async fn update_supervising(
         &mut self,
         eid: String,
@@ -324,6 +367,14 @@ impl React {
         self.supervisor_jobs.lock().await.contains_key(eid)
     }
 
+    /// forget a supervisor that exited by itself, unless it was already replaced
+    async fn remove_supervising(&mut self, eid: &String, tx: &UnboundedSender<SupervisorSignal>) {
+        let mut jobs = self.supervisor_jobs.lock().await;
+        if jobs.get(eid).is_some_and(|v| v.same_channel(tx)) {
+            jobs.remove(eid);
+        }
+    }
+
     async fn stop_supervising(&mut self, eid: &String) -> Result<()> {
         let mut jobs = self.supervisor_jobs.lock().await;
         let val = jobs.remove(eid);
@@ -341,6 +392,7 @@ pub struct Scheduler<T> {
     comet_secret: String,
     mac_addr: String,
     output_dir: String,
+    artifact_dir: String,
     is_initialized: bool,
     client: Option<T>,
     pub namespace: String,
@@ -362,6 +414,7 @@ impl
         comet_addr: Vec<String>,
         comet_secret: String,
         output_dir: String,
+        artifact_dir: String,
         ssh_connection_option: Option<SshConnectionOption>,
         assign_user_option: Option<AssignUserOption>,
     ) -> Self {
@@ -369,6 +422,7 @@ impl
             comet_addr,
             comet_secret,
             output_dir,
+            artifact_dir,
             client: None,
             mac_addr: get_mac_address().expect("failed get mac address"),
             is_initialized: false,
@@ -383,6 +437,28 @@ impl
         get_endpoint(get_local_ip().to_string(), self.mac_addr.clone())
     }
 
+    /// Serves Prometheus metrics on `bind` in the background.
+    pub fn serve_metrics(&self, bind: String) {
+        let bridge = self.bridge.clone();
+        let client_key = self.client_key();
+        let app = Route::new().at(
+            "/metrics",
+            get(make(move |_| {
+                let bridge = bridge.clone();
+                let client_key = client_key.clone();
+                async move {
+                    metrics::AGENT_OUTBOX_DEPTH.set(bridge.queued(&client_key).await as i64);
+                    metrics::response()
+                }
+            })),
+        );
+        tokio::spawn(async move {
+            if let Err(e) = Server::new(TcpListener::bind(bind)).run(app).await {
+                error!("failed serve metrics - {e}");
+            }
+        });
+    }
+
     pub fn get_comet_addr(&mut self) -> String {
         if let Some(v) = self.comet_addr.pop() {
             self.comet_addr.push(v.clone());
@@ -458,7 +534,11 @@ impl
         tokio::spawn(async move {
             let sess = match Session::connect(ConnectParams {
                 user: login_params.user,
-                password: login_params.password,
+                auth: SshAuth::build(
+                    login_params.password,
+                    login_params.private_key,
+                    login_params.passphrase,
+                ),
                 addrs: (local_ip, login_params.port),
             })
             .await
@@ -539,7 +619,7 @@ impl
     }
 
     async fn exec_job(
-        e: Executor,
+        mut e: Executor,
         react: React,
         schedule_type: Option<ScheduleType>,
         kill_signal_rx: Receiver<()>,
@@ -551,6 +631,33 @@ impl
         let schedule_id = job_params.schedule_id.clone();
         let base_job = job_params.base_job.clone();
         let instance_id = job_params.instance_id.to_owned().unwrap();
+        let metric_label = schedule_type.clone().unwrap_or_default().to_string();
+        let _running = metrics::RunningJobGuard::new(&metric_label);
+        let elapsed = || (Local::now() - start_time).num_milliseconds() as f64 / 1000.0;
+
+        // a timer tick is a run of its own rather than part of the dispatch
+        // that started the timer
+        let span = info_span!(
+            target: telemetry::TARGET,
+            "agent.exec_job",
+            eid = %base_job.eid,
+            run_id = %job_params.run_id,
+            schedule_type = %metric_label,
+            exit_code = tracing::field::Empty,
+        );
+        if schedule_type != Some(ScheduleType::Timer) {
+            telemetry::set_parent(&span, job_params.trace_context.as_ref());
+        }
+        let trace_context = telemetry::forward(
+            &span,
+            job_params
+                .trace_context
+                .as_ref()
+                .filter(|_| schedule_type != Some(ScheduleType::Timer)),
+        );
+        for (k, v) in telemetry::job_env(trace_context.as_ref()) {
+            e = e.env(k, v);
+        }
 
         let _ = react
             .send_update_job_msg(UpdateJobParams {
@@ -564,15 +671,17 @@ impl
                 schedule_type: schedule_type.clone(),
                 created_user: job_params.created_user.clone(),
                 run_id: job_params.run_id.clone(),
+                trace_context: trace_context.clone(),
                 start_time: Some(start_time.clone()),
                 instance_id: instance_id.clone(),
                 ..Default::default()
             })
             .await?;
 
-        let output = match e.run(Ctx { kill_signal_rx }).await {
+        let (output, stopped) = match e.run(Ctx { kill_signal_rx }).await {
             Ok(v) => v,
             Err(e) => {
+                metrics::observe_job_exit(&metric_label, Some(99), elapsed());
                 let bundle_output = if base_job.bundle_script.is_none() {
                     None
                 } else {
@@ -596,20 +705,30 @@ impl
                         created_user: job_params.created_user.clone(),
                         bundle_output,
                         run_id: job_params.run_id.clone(),
+                        trace_context: trace_context.clone(),
                         ..Default::default()
                     })
                     .await?;
                 return Err(e);
             }
         };
+        metrics::observe_job_exit(&metric_label, output.get_exit_code(), elapsed());
+        if let Some(code) = output.get_exit_code() {
+            span.record("exit_code", code);
+        }
+
+        let artifacts = react.publish_artifacts(&base_job).await;
 
         let _ = react
             .send_update_job_msg(UpdateJobParams {
                 base_job: base_job.to_pure_job(),
                 run_status: Some(types::RunStatus::Stop),
                 schedule_id: schedule_id.clone(),
-                exit_status: output.get_exit_status(),
+                exit_status: output
+                    .get_exit_status()
+                    .map(|v| stopped.map_or(v.clone(), |s| s.describe(&v))),
                 exit_code: output.get_exit_code(),
+                is_timeout: stopped.is_some_and(|v| v.is_timeout),
                 instance_id: instance_id.clone(),
                 bind_namespace: react.namespace.clone(),
                 bind_ip: react.local_ip.clone(),
@@ -621,6 +740,8 @@ impl
                 created_user: job_params.created_user.clone(),
                 bundle_output: BundleOutputParams::parse(&output),
                 run_id: job_params.run_id.clone(),
+                trace_context: trace_context.clone(),
+                artifacts,
                 ..Default::default()
             })
             .await?;
@@ -629,71 +750,33 @@ impl
     }
 
     async fn start_timer(dispatch_params: DispatchJobParams, mut react: React) -> Result<Value> {
-        let timer_expr = dispatch_params.timer_expr.clone().unwrap_or_default();
-        let base_job = dispatch_params.base_job.clone();
-        let pure_job = base_job.to_pure_job();
+        let schedule = dispatch_params
+            .timer_schedule()
+            .ok_or(anyhow!("missing timer schedule"))?;
+        schedule.validate()?;
+        let calendars = dispatch_params.calendars.clone().unwrap_or_default();
+        let first_time = schedule
+            .next_allowed(Utc::now(), &calendars)?
+            .ok_or(anyhow!("timer never fires"))?;
+
+        let pure_job = dispatch_params.base_job.to_pure_job();
         let euid = dispatch_params.base_job.eid.clone();
-        let react_clone = react.clone();
         let created_user = dispatch_params.created_user.clone();
         let schedule_id = dispatch_params.schedule_id.clone();
         let instance_id = dispatch_params.instance_id.to_owned().unwrap();
 
-        let job: Job = Job::new_cron_job_async_tz(
-            timer_expr.as_str(),
-            Local,
-            move |job_id, mut job_scheduler| {
-                let base_job = base_job.clone();
-                let (kill_signal_tx, kill_signal_rx) = channel::<()>(1);
-                let mut react_clone = react_clone.clone();
-                let mut dispatch_params = dispatch_params.clone();
-                dispatch_params.run_id = run_id!();
-
-                Box::pin(async move {
-                    sleep(Duration::from_millis(10)).await;
-
-                    if let Err(e) = react_clone.can_execute(&dispatch_params).await {
-                        error!("ignore execute job - {e}");
-                        return;
-                    }
-
-                    let next_time = job_scheduler
-                        .next_tick_for_job(job_id)
-                        .await
-                        .unwrap()
-                        .map(|v| v.into());
-
-                    let prev_time = Some(Local::now().into());
-
-                    let e = Executor::builder()
-                        .job(base_job.clone())
-                        .output_dir(react_clone.output_dir.clone())
-                        .disable_write_log(true)
-                        .build();
-
-                    react_clone
-                        .set_execute_context(&dispatch_params, kill_signal_tx)
-                        .await;
-
-                    if let Err(e) = Self::exec_job(
-                        e,
-                        react_clone.clone(),
-                        Some(ScheduleType::Timer),
-                        kill_signal_rx,
-                        prev_time,
-                        next_time,
-                        &dispatch_params,
-                    )
-                    .await
-                    {
-                        error!("failed exec {} - detail: {e}", base_job.eid);
-                    }
-                    react_clone.end_execute(&dispatch_params).await;
-                })
-            },
-        )
-        .map_err(|v| anyhow!("failed parse timer expr {} - {}", timer_expr, v))?;
-
-        let next_time = react.add_job_schedule(euid, job).await?;
+        let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<String>();
+        let handle = tokio::spawn(Self::run_timer(
+            schedule,
+            first_time,
+            dispatch_params,
+            react.clone(),
+            ready_rx,
+        ));
+        let timer_id = react
+            .add_job_schedule(euid.clone(), handle.abort_handle())
+            .await;
+        let _ = ready_tx.send(timer_id);
 
         let _ = react
             .send_update_job_msg(UpdateJobParams {
@@ -706,7 +789,7 @@ impl
                 exit_code: None,
                 stdout: None,
                 stderr: None,
-                next_time,
+                next_time: Some(first_time.with_timezone(&Local)),
                 bind_namespace: react.namespace.clone(),
                 bind_ip: react.local_ip.clone(),
                 schedule_type: Some(ScheduleType::Timer),
@@ -719,6 +802,163 @@ impl
         Ok(json!(null))
     }
 
+    /// Sleeps until each fire time and runs the job in its own task, so a
+    /// slow run never delays the next one. Sleeps are capped so that a clock
+    /// change is noticed.
+    async fn run_timer(
+        schedule: TimerSchedule,
+        first_time: DateTime<Utc>,
+        dispatch_params: DispatchJobParams,
+        mut react: React,
+        ready_rx: tokio::sync::oneshot::Receiver<String>,
+    ) {
+        let Ok(timer_id) = ready_rx.await else {
+            return;
+        };
+        let eid = dispatch_params.base_job.eid.clone();
+        let calendars = dispatch_params.calendars.clone().unwrap_or_default();
+        let blackouts = dispatch_params.blackouts.clone().unwrap_or_default();
+        let mut fire_time = first_time;
+
+        loop {
+            while let Ok(wait) = (fire_time - Utc::now()).to_std() {
+                sleep(wait.min(Duration::from_secs(60))).await;
+            }
+
+            let now = Utc::now();
+            let next_time = match schedule.next_allowed(fire_time, &calendars) {
+                // skip the runs missed while the agent was suspended
+                Ok(Some(v)) if v <= now => schedule.next_allowed(now, &calendars),
+                v => v,
+            }
+            .unwrap_or_else(|e| {
+                error!("failed compute next time of {eid} - {e}");
+                None
+            });
+
+            match BlackoutWindow::find_active(&blackouts, fire_time) {
+                Some(window) => {
+                    info!(
+                        "skip tick of {eid}, blackout window {} in effect",
+                        window.name
+                    );
+                    tokio::spawn(Self::suppress_timer(
+                        dispatch_params.clone(),
+                        react.clone(),
+                        window.name.clone(),
+                        next_time,
+                    ));
+                }
+                None => {
+                    tokio::spawn(Self::fire_timer(
+                        dispatch_params.clone(),
+                        react.clone(),
+                        next_time,
+                    ));
+                }
+            }
+
+            match next_time {
+                Some(v) => fire_time = v,
+                None => break,
+            }
+        }
+
+        if !react.finish_job_schedule(&eid, &timer_id).await {
+            return;
+        }
+        let _ = react
+            .send_update_job_msg(UpdateJobParams {
+                base_job: dispatch_params.base_job.to_pure_job(),
+                schedule_status: Some(types::ScheduleStatus::Unscheduled),
+                schedule_id: dispatch_params.schedule_id.clone(),
+                instance_id: dispatch_params.instance_id.clone().unwrap_or_default(),
+                bind_namespace: react.namespace.clone(),
+                bind_ip: react.local_ip.clone(),
+                schedule_type: Some(ScheduleType::Timer),
+                created_user: dispatch_params.created_user.clone(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| error!("failed report finished timer {eid} - {e}"));
+    }
+
+    /// Reports a tick skipped by a blackout window so it shows in the history.
+    async fn suppress_timer(
+        dispatch_params: DispatchJobParams,
+        react: React,
+        window: String,
+        next_time: Option<DateTime<Utc>>,
+    ) {
+        let now = Local::now();
+        let ret = react
+            .send_update_job_msg(UpdateJobParams {
+                base_job: dispatch_params.base_job.to_pure_job(),
+                schedule_id: dispatch_params.schedule_id.clone(),
+                instance_id: dispatch_params.instance_id.clone().unwrap_or_default(),
+                bind_namespace: react.namespace.clone(),
+                bind_ip: react.local_ip.clone(),
+                schedule_type: Some(ScheduleType::Timer),
+                created_user: dispatch_params.created_user.clone(),
+                run_id: run_id!(),
+                start_time: Some(now),
+                end_time: Some(now),
+                prev_time: Some(now),
+                next_time: next_time.map(|v| v.with_timezone(&Local)),
+                suppressed_by: Some(format!("blackout window {window}")),
+                ..Default::default()
+            })
+            .await;
+        if let Err(e) = ret {
+            error!(
+                "failed report suppressed tick of {} - {e}",
+                dispatch_params.base_job.eid
+            );
+        }
+    }
+
+    async fn fire_timer(
+        mut dispatch_params: DispatchJobParams,
+        mut react: React,
+        next_time: Option<DateTime<Utc>>,
+    ) {
+        let base_job = dispatch_params.base_job.clone();
+        let (kill_signal_tx, kill_signal_rx) = channel::<()>(1);
+        dispatch_params.run_id = run_id!();
+
+        if let Err(e) = react.can_execute(&dispatch_params).await {
+            error!("ignore execute job - {e}");
+            return;
+        }
+
+        let prev_time = Some(Local::now());
+
+        let e = Executor::builder()
+            .job(base_job.clone())
+            .output_dir(react.output_dir.clone())
+            .disable_write_log(true)
+            .build();
+
+        react
+            .set_execute_context(&dispatch_params, kill_signal_tx)
+            .await;
+
+        if let Err(e) = Self::exec_job(
+            e,
+            react.clone(),
+            Some(ScheduleType::Timer),
+            kill_signal_rx,
+            prev_time,
+            next_time.map(|v| v.with_timezone(&Local)),
+            &dispatch_params,
+        )
+        .await
+        {
+            error!("failed exec {} - detail: {e}", base_job.eid);
+        }
+        react.end_execute(&dispatch_params).await;
+    }
+
     async fn stop_timer(dispatch_params: DispatchJobParams, mut react: React) -> Result<Value> {
         let instance_id = dispatch_params.instance_id.to_owned().unwrap();
         react
@@ -774,12 +1014,13 @@ impl
                 schedule_type: Some(ScheduleType::Daemon),
                 created_user: dispatch_params.created_user.clone(),
                 start_time: None,
+                restart_count: Some(0),
                 ..Default::default()
             })
             .await?;
 
         if !react
-            .update_supervising(eid.clone(), dispatch_params.clone(), tx)
+            .update_supervising(eid.clone(), dispatch_params.clone(), tx.clone())
             .await
         {
             return Ok(json!(null));
@@ -787,54 +1028,205 @@ impl
 
         tokio::spawn(async move {
             let mut dispatch_params = dispatch_params;
-            'main: loop {
-                let ret = Scheduler::wait_exec(dispatch_params.clone(), react.clone()).await;
-                if let Err(e) = ret {
-                    error!("supervising: failed exec job - {e}");
+            let mut restart_state = RestartState::default();
+            loop {
+                let exec = Scheduler::wait_exec(dispatch_params.clone(), react.clone());
+                tokio::pin!(exec);
+                let (ret, probe_failure) = select! {
+                    ret = &mut exec => (Some(ret), None),
+                    reason = Self::watch_health(&dispatch_params, &react) => (None, Some(reason)),
+                };
+                let ret = match ret {
+                    Some(v) => v,
+                    None => {
+                        error!("supervising: {eid} is unhealthy, kill it");
+                        react.kill_job(&eid, ScheduleType::Daemon).await;
+                        exec.await
+                    }
+                };
+
+                let (exit_code, exit_reason) = match ret {
+                    Ok(output) => (
+                        output.get_exit_code(),
+                        output.get_exit_status().unwrap_or_default(),
+                    ),
+                    Err(e) => {
+                        error!("supervising: failed exec job - {e}");
+                        (None, e.to_string())
+                    }
+                };
+                // a killed unhealthy process always counts as a crash
+                let (exit_code, exit_reason) = match probe_failure {
+                    Some(reason) => (None, reason),
+                    None => (exit_code, exit_reason),
+                };
+
+                // the job may have been killed by stop_supervising
+                while let Ok(v) = rx.try_recv() {
+                    if !Self::handle_supervisor_signal(v, &mut dispatch_params) {
+                        return;
+                    }
                 }
-                let dur =
-                    dispatch_params
-                        .restart_interval
-                        .clone()
-                        .map_or(Duration::from_secs(1), |v| {
-                            if v.as_secs() > 0 {
-                                v
-                            } else {
-                                Duration::from_secs(1)
-                            }
-                        });
-                let sleep_time = sleep(dur.to_owned());
 
+                let policy = dispatch_params.restart_policy.clone().unwrap_or_default();
+                let interval = dispatch_params
+                    .restart_interval
+                    .filter(|v| v.as_secs() > 0)
+                    .unwrap_or(Duration::from_secs(1));
+
+                let (schedule_status, delay) = if !policy.should_restart(exit_code) {
+                    info!("supervising: {eid} exited with {exit_code:?}, stop restarting");
+                    (types::ScheduleStatus::Unsupervised, None)
+                } else if let Some(n) = restart_state.record(&policy) {
+                    metrics::AGENT_SUPERVISOR_RESTARTS.inc();
+                    (
+                        types::ScheduleStatus::Supervising,
+                        Some(policy.backoff(interval, n)),
+                    )
+                } else {
+                    error!("supervising: {eid} restarted too many times, mark as fatal");
+                    (types::ScheduleStatus::Fatal, None)
+                };
+
+                let crashed = exit_code.is_none_or(|v| !policy.expected_exit_codes.contains(&v));
+                let _ = react
+                    .send_update_job_msg(UpdateJobParams {
+                        base_job: dispatch_params.base_job.to_pure_job(),
+                        schedule_status: Some(schedule_status),
+                        schedule_id: dispatch_params.schedule_id.clone(),
+                        instance_id: dispatch_params.instance_id.clone().unwrap_or_default(),
+                        bind_namespace: react.namespace.clone(),
+                        bind_ip: react.local_ip.clone(),
+                        schedule_type: Some(ScheduleType::Daemon),
+                        created_user: dispatch_params.created_user.clone(),
+                        restart_count: Some(restart_state.count),
+                        crash_reason: Some(exit_reason).filter(|_| crashed),
+                        ..Default::default()
+                    })
+                    .await
+                    .map_err(|e| error!("supervising: failed update restart status - {e}"));
+
+                let Some(delay) = delay else {
+                    react.remove_supervising(&eid, &tx).await;
+                    return;
+                };
+
+                let sleep_time = sleep(delay);
                 tokio::pin!(sleep_time);
 
                 loop {
                     select! {
                         _ = &mut sleep_time => {
-                            info!("supervising: sleep, waiting restart");
+                            info!("supervising: sleep {delay:?}, waiting restart");
                             break;
                         },
                         Some(v) = rx.recv() => {
-                            match v {
-                                SupervisorSignal::UpdateOptions(opts) => {
-                                     dispatch_params = opts.clone();
-                                    info!("supervising: update options {:?}", opts);
-                                },
-                                SupervisorSignal::Exit => {
-                                    info!("supervising: exited");
-                                    return;
-                                }
+                            if !Self::handle_supervisor_signal(v, &mut dispatch_params) {
+                                return;
                             }
-
                         },
                     }
                 }
-
-                continue 'main;
             }
         });
         Ok(json!(null))
     }
 
+    /// run the probes of a supervised job, return the reason once liveness failed
+    async fn watch_health(dispatch_params: &DispatchJobParams, react: &React) -> String {
+        let health_check = dispatch_params.health_check.clone().unwrap_or_default();
+        let status = &Mutex::new(types::ProbeStatus::default());
+        // exec probes run as the user, in the dir or container of the job
+        let executor = &Executor::builder()
+            .job(dispatch_params.base_job.clone())
+            .disable_write_log(true)
+            .build();
+
+        let liveness = async {
+            let Some(probe) = &health_check.liveness else {
+                return std::future::pending().await;
+            };
+            let mut reason = String::new();
+            probe
+                .watch(executor, true, |ret| {
+                    let healthy = ret.healthy;
+                    reason.clone_from(&ret.message);
+                    async move {
+                        let probe_status = {
+                            let mut v = status.lock().await;
+                            v.liveness = Some(ret);
+                            v.clone()
+                        };
+                        Self::send_probe_status(dispatch_params, react, probe_status).await;
+                        healthy
+                    }
+                })
+                .await;
+            format!("liveness probe failed - {reason}")
+        };
+
+        let readiness = async {
+            if let Some(probe) = &health_check.readiness {
+                probe
+                    .watch(executor, false, |ret| async move {
+                        let probe_status = {
+                            let mut v = status.lock().await;
+                            v.readiness = Some(ret);
+                            v.clone()
+                        };
+                        Self::send_probe_status(dispatch_params, react, probe_status).await;
+                        true
+                    })
+                    .await;
+            }
+            std::future::pending::<()>().await
+        };
+
+        select! {
+            reason = liveness => reason,
+            _ = readiness => unreachable!(),
+        }
+    }
+
+    async fn send_probe_status(
+        dispatch_params: &DispatchJobParams,
+        react: &React,
+        probe_status: types::ProbeStatus,
+    ) {
+        let _ = react
+            .send_update_job_msg(UpdateJobParams {
+                base_job: dispatch_params.base_job.to_pure_job(),
+                schedule_id: dispatch_params.schedule_id.clone(),
+                instance_id: dispatch_params.instance_id.clone().unwrap_or_default(),
+                bind_namespace: react.namespace.clone(),
+                bind_ip: react.local_ip.clone(),
+                schedule_type: Some(ScheduleType::Daemon),
+                created_user: dispatch_params.created_user.clone(),
+                probe_status: Some(probe_status),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| error!("supervising: failed update probe status - {e}"));
+    }
+
+    /// apply a signal to a supervising job, return false once it should exit
+    fn handle_supervisor_signal(
+        signal: SupervisorSignal,
+        dispatch_params: &mut DispatchJobParams,
+    ) -> bool {
+        match signal {
+            SupervisorSignal::UpdateOptions(opts) => {
+                info!("supervising: update options {:?}", opts);
+                *dispatch_params = opts;
+                true
+            }
+            SupervisorSignal::Exit => {
+                info!("supervising: exited");
+                false
+            }
+        }
+    }
+
     async fn stop_supervising(
         dispatch_params: DispatchJobParams,
         mut react: React,
@@ -879,7 +1271,10 @@ impl
         Scheduler::start_supervising(dispatch_params.clone(), react).await
     }
 
-    async fn wait_exec(dispatch_params: DispatchJobParams, mut react: React) -> Result<()> {
+    async fn wait_exec(
+        dispatch_params: DispatchJobParams,
+        mut react: React,
+    ) -> Result<BundleOutput> {
         let mut base_job = dispatch_params.base_job.clone();
         let (kill_signal_tx, kill_signal_rx) = channel::<()>(1);
 
@@ -902,7 +1297,7 @@ impl
             .disable_write_log(true)
             .build();
 
-        Self::exec_job(
+        let output = Self::exec_job(
             e,
             react.clone(),
             Some(schedule_type),
@@ -914,7 +1309,7 @@ impl
         .await?;
         react.end_execute(&dispatch_params).await;
 
-        Ok(())
+        Ok(output)
     }
 
     async fn exec(dispatch_params: DispatchJobParams, mut react: React) -> Result<Value> {
@@ -992,7 +1387,20 @@ impl
         Ok(json!(null))
     }
 
-    pub async fn dispatch_job(dispatch_params: DispatchJobParams, react: React) -> Result<Value> {
+    pub async fn dispatch_job(
+        mut dispatch_params: DispatchJobParams,
+        react: React,
+    ) -> Result<Value> {
+        let span = info_span!(
+            target: telemetry::TARGET,
+            "agent.dispatch_job",
+            eid = %dispatch_params.base_job.eid,
+            action = %dispatch_params.action,
+        );
+        telemetry::set_parent(&span, dispatch_params.trace_context.as_ref());
+        dispatch_params.trace_context =
+            telemetry::forward(&span, dispatch_params.trace_context.as_ref());
+
         let mut base_job = dispatch_params.base_job.clone();
         let upload_file = base_job.upload_file.take();
 
@@ -1000,9 +1408,13 @@ impl
             dispatch_params.action,
             JobAction::StartTimer | JobAction::Exec
         ) {
-            if let Some(comet_addr) = get_comet_addr() {
-                try_download_file(comet_addr, upload_file).await?;
-            }
+            try_download_file(
+                get_comet_addr(),
+                &react.comet_secret,
+                &react.artifacts,
+                upload_file,
+            )
+            .await?;
         }
 
         match dispatch_params.action {
@@ -1038,7 +1450,7 @@ impl
             &req.ip,
             req.port,
             &req.user,
-            &req.password,
+            SshAuth::build(req.password, req.private_key, req.passphrase),
             req.dir.filter(|v| v != "").as_deref(),
         )
         .await?;
@@ -1046,24 +1458,66 @@ impl
         Ok(ret)
     }
 
-    pub async fn sftp_upload(req: SftpUploadParams) -> Result<Value> {
-        let ret = ssh::upload(
-            &req.ip,
-            req.port,
-            &req.user,
-            &req.password,
-            &req.filepath,
-            req.data,
-        )
-        .await?;
-        let ret = serde_json::to_value(ret)?;
-        Ok(ret)
+    pub async fn sftp_upload(req: SftpUploadParams, react: React) -> Result<Value> {
+        let auth = SshAuth::build(req.password, req.private_key, req.passphrase);
+        match req.sha256 {
+            Some(sha256) => {
+                let comet_addr = get_comet_addr().ok_or(anyhow!("comet is not connected"))?;
+                let path = react
+                    .artifacts
+                    .fetch(&comet_addr, &react.comet_secret, &sha256)
+                    .await?;
+                let mut file = tokio::fs::File::open(path).await?;
+                ssh::upload(&req.ip, req.port, &req.user, auth, &req.filepath, &mut file).await?
+            }
+            None => {
+                ssh::upload(
+                    &req.ip,
+                    req.port,
+                    &req.user,
+                    auth,
+                    &req.filepath,
+                    &mut req.data.as_slice(),
+                )
+                .await?
+            }
+        }
+        Ok(Value::Null)
     }
 
-    pub async fn sftp_download(req: SftpDownloadParams) -> Result<Value> {
-        let ret = ssh::download(&req.ip, req.port, &req.user, &req.password, &req.filepath).await?;
-        let ret = serde_json::to_value(ret)?;
-        Ok(ret)
+    pub async fn sftp_download(req: SftpDownloadParams, react: React) -> Result<Value> {
+        let auth = SshAuth::build(req.password, req.private_key, req.passphrase);
+        let Some(transfer_id) = req.transfer_id else {
+            let ret = ssh::download(&req.ip, req.port, &req.user, auth, &req.filepath).await?;
+            return Ok(serde_json::to_value(ret)?);
+        };
+        if !transfer::is_transfer_id(&transfer_id) {
+            anyhow::bail!("invalid transfer id {transfer_id}");
+        }
+
+        let comet_addr = get_comet_addr().ok_or(anyhow!("comet is not connected"))?;
+        tokio::fs::create_dir_all(react.artifacts.dir()).await?;
+        let tmp_path = react
+            .artifacts
+            .dir()
+            .join(format!("{transfer_id}.download"));
+
+        let ret = async {
+            ssh::download_to(&req.ip, req.port, &req.user, auth, &req.filepath, &tmp_path).await?;
+            let mut file = tokio::fs::File::open(&tmp_path).await?;
+            transfer::upload(
+                &get_http_client(),
+                &comet_addr,
+                &react.comet_secret,
+                &transfer_id,
+                &mut file,
+            )
+            .await
+        }
+        .await;
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+
+        Ok(serde_json::to_value(ret?)?)
     }
 
     pub async fn sftp_remove(req: SftpRemoveParams) -> Result<Value> {
@@ -1071,7 +1525,7 @@ impl
             &req.ip,
             req.port,
             &req.user,
-            &req.password,
+            SshAuth::build(req.password, req.private_key, req.passphrase),
             &req.remove_type,
             &req.filepath,
         )
@@ -1085,9 +1539,9 @@ impl
             MsgReqKind::DispatchJobRequest(v) => Self::dispatch_job(v, react.clone()).await,
             MsgReqKind::RuntimeActionRequest(v) => Self::runtime_action(v, react.clone()).await,
             MsgReqKind::SftpReadDirRequest(v) => Self::sftp_read_dir(v).await,
-            MsgReqKind::SftpUploadRequest(v) => Self::sftp_upload(v).await,
+            MsgReqKind::SftpUploadRequest(v) => Self::sftp_upload(v, react.clone()).await,
             MsgReqKind::SftpRemoveRequest(v) => Self::sftp_remove(v).await,
-            MsgReqKind::SftpDownloadRequest(v) => Self::sftp_download(v).await,
+            MsgReqKind::SftpDownloadRequest(v) => Self::sftp_download(v, react.clone()).await,
             MsgReqKind::PullJobRequest(_) => todo!(),
             MsgReqKind::HeartbeatRequest(_) => todo!(),
             _ => todo!(),
@@ -1159,18 +1613,13 @@ impl
             get_local_ip().to_string(),
             self.client_key(),
             self.output_dir.clone(),
+            self.comet_secret.clone(),
+            ArtifactCache::new(&self.artifact_dir)?,
         )
         .await;
-        let mut react_clone: React = react.clone();
 
         self.ssh_poll().await;
 
-        tokio::spawn(async move {
-            react_clone
-                .start()
-                .await
-                .expect("failed start cron scheduler");
-        });
         self.heartbeat().await;
         loop {
             self.recv(react.clone()).await;
@@ -0,0 +1,434 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use croner::{Cron, errors::CronError};
+use serde::{Deserialize, Serialize};
+
+/// Candidates examined before a cron expression is considered to never fire.
+const MAX_CRON_ATTEMPTS: usize = 1000;
+/// Days skipped by calendars before a timer is considered to never fire.
+const MAX_CALENDAR_SKIPS: usize = 5000;
+
+/// When a timer fires.
+///
+/// Cron expressions take five or six fields, seconds first when there are six,
+/// and support `L`, `W` and `#`. When both day of month and day of week are
+/// restricted a day must match both. They are evaluated on the wall clock of
+/// the time zone, the agent's local one when none is given.
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TimerSchedule {
+    Cron {
+        expr: String,
+        /// IANA name such as Europe/Berlin
+        #[serde(default)]
+        timezone: Option<String>,
+    },
+    Interval {
+        every_secs: u64,
+    },
+    /// fires once
+    At {
+        time: DateTime<Utc>,
+    },
+}
+
+impl TimerSchedule {
+    /// A schedule sent by a console that only knows the bare expression.
+    pub fn legacy(expr: &str) -> Self {
+        Self::Cron {
+            expr: expr.to_string(),
+            timezone: None,
+        }
+    }
+
+    /// The expression older agents understand, they only run cron timers in
+    /// their local time zone.
+    pub fn legacy_expr(&self) -> Option<String> {
+        match self {
+            Self::Cron {
+                expr,
+                timezone: None,
+            } => Some(expr.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Rejects schedules that cannot be parsed or will never fire.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::Interval { every_secs: 0 } => anyhow::bail!("interval must be at least 1 second"),
+            Self::At { time } if *time <= Utc::now() => {
+                anyhow::bail!("run time {time} is in the past")
+            }
+            Self::Cron { expr, .. } => {
+                if self.next_after(Utc::now())?.is_none() {
+                    anyhow::bail!("timer expr {expr} never fires");
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The first time the timer fires strictly after the given time, none
+    /// once a one-shot timer has passed.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Result<Option<DateTime<Utc>>> {
+        match self {
+            Self::Cron { expr, timezone } => {
+                let cron = parse_cron(expr)?;
+                match timezone.as_deref().filter(|v| !v.is_empty()) {
+                    Some(name) => next_cron(&cron, &parse_timezone(name)?, after),
+                    None => next_cron(&cron, &chrono::Local, after),
+                }
+            }
+            Self::Interval { every_secs } => {
+                Ok(Some(after + Duration::seconds((*every_secs).max(1) as i64)))
+            }
+            Self::At { time } => Ok(Some(*time).filter(|v| *v > after)),
+        }
+    }
+
+    /// Like [`Self::next_after`], skipping the days the calendars rule out.
+    pub fn next_allowed(
+        &self,
+        after: DateTime<Utc>,
+        calendars: &[TimerCalendar],
+    ) -> Result<Option<DateTime<Utc>>> {
+        let mut cursor = after;
+        for _ in 0..MAX_CALENDAR_SKIPS {
+            let Some(next) = self.next_after(cursor)? else {
+                return Ok(None);
+            };
+            let date = self.local_date(next)?;
+            if TimerCalendar::allows(calendars, date) {
+                return Ok(Some(next));
+            }
+
+            // jump close to the end of the day, an hour short in case of a DST change
+            let day_end = date
+                .succ_opt()
+                .and_then(|v| v.and_hms_opt(0, 0, 0))
+                .ok_or(anyhow!("date out of range"))?;
+            let skip = (day_end - self.local_naive(next)?).num_seconds() - 3601;
+            cursor = match self {
+                Self::Interval { every_secs } => {
+                    let every = (*every_secs).max(1) as i64;
+                    next + Duration::seconds(skip.max(0) / every * every)
+                }
+                _ => next + Duration::seconds(skip.max(0)),
+            };
+        }
+        Ok(None)
+    }
+
+    fn local_naive(&self, time: DateTime<Utc>) -> Result<chrono::NaiveDateTime> {
+        match self {
+            Self::Cron {
+                timezone: Some(name),
+                ..
+            } if !name.is_empty() => Ok(time.with_timezone(&parse_timezone(name)?).naive_local()),
+            _ => Ok(time.with_timezone(&chrono::Local).naive_local()),
+        }
+    }
+
+    fn local_date(&self, time: DateTime<Utc>) -> Result<NaiveDate> {
+        Ok(self.local_naive(time)?.date())
+    }
+
+    /// Up to `count` fire times after the given time.
+    pub fn upcoming(
+        &self,
+        after: DateTime<Utc>,
+        count: usize,
+        calendars: &[TimerCalendar],
+    ) -> Result<Vec<DateTime<Utc>>> {
+        let mut times = Vec::with_capacity(count);
+        let mut cursor = after;
+        while times.len() < count {
+            match self.next_allowed(cursor, calendars)? {
+                Some(v) => {
+                    times.push(v);
+                    cursor = v;
+                }
+                None => break,
+            }
+        }
+        Ok(times)
+    }
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarMode {
+    /// only run on the listed dates
+    Include,
+    /// never run on the listed dates
+    #[default]
+    Exclude,
+}
+
+/// Dates a timer may or may not run on, read in the timer's time zone.
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Default)]
+pub struct TimerCalendar {
+    pub name: String,
+    pub mode: CalendarMode,
+    pub dates: BTreeSet<NaiveDate>,
+}
+
+impl TimerCalendar {
+    /// A date is allowed when it is in none of the exclude calendars and, if
+    /// there are include calendars, in at least one of them.
+    pub fn allows(calendars: &[TimerCalendar], date: NaiveDate) -> bool {
+        let mut included = None;
+        for calendar in calendars {
+            let listed = calendar.dates.contains(&date);
+            match calendar.mode {
+                CalendarMode::Exclude if listed => return false,
+                CalendarMode::Exclude => {}
+                CalendarMode::Include => included = Some(included.unwrap_or(false) || listed),
+            }
+        }
+        included.unwrap_or(true)
+    }
+}
+
+/// A recurring period in which nothing may run, such as a change freeze.
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Default)]
+pub struct BlackoutWindow {
+    pub name: String,
+    /// cron expression of the window start
+    pub start_expr: String,
+    pub duration_secs: u64,
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+impl BlackoutWindow {
+    fn start_schedule(&self) -> TimerSchedule {
+        TimerSchedule::Cron {
+            expr: self.start_expr.clone(),
+            timezone: self.timezone.clone(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.duration_secs == 0 {
+            anyhow::bail!("blackout window {} has no duration", self.name);
+        }
+        self.start_schedule().validate()
+    }
+
+    /// Whether the given time falls inside a window, that is whether the
+    /// window started within the duration before it.
+    pub fn is_active(&self, at: DateTime<Utc>) -> Result<bool> {
+        let since = at - Duration::seconds(self.duration_secs as i64);
+        Ok(self
+            .start_schedule()
+            .next_after(since)?
+            .is_some_and(|v| v <= at))
+    }
+
+    /// The first window active at the given time.
+    pub fn find_active(windows: &[BlackoutWindow], at: DateTime<Utc>) -> Option<&BlackoutWindow> {
+        windows.iter().find(|v| v.is_active(at).unwrap_or_default())
+    }
+}
+
+fn parse_cron(expr: &str) -> Result<Cron> {
+    Cron::new(expr)
+        .with_seconds_optional()
+        .with_dom_and_dow()
+        .parse()
+        .map_err(|e| anyhow!("failed parse timer expr {expr} - {e}"))
+}
+
+fn parse_timezone(name: &str) -> Result<Tz> {
+    name.parse::<Tz>()
+        .map_err(|_| anyhow!("unknown time zone {name}"))
+}
+
+/// Croner only works on unambiguous times, so candidates are searched on the
+/// naive wall clock and mapped onto the time zone here. A time skipped by a
+/// DST change fires when the clock resumes, a repeated one fires on its first
+/// pass only.
+fn next_cron<T: TimeZone>(
+    cron: &Cron,
+    tz: &T,
+    after: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>> {
+    let mut cursor = after.with_timezone(tz).naive_local();
+    for _ in 0..MAX_CRON_ATTEMPTS {
+        let candidate = match cron.find_next_occurrence(&Utc.from_utc_datetime(&cursor), false) {
+            Ok(v) => v.naive_utc(),
+            Err(CronError::TimeSearchLimitExceeded) => return Ok(None),
+            Err(e) => anyhow::bail!("failed find next time - {e}"),
+        };
+
+        let mut wall = candidate;
+        let instant = loop {
+            match tz.from_local_datetime(&wall) {
+                LocalResult::Single(v) => break v,
+                LocalResult::Ambiguous(earliest, _) => break earliest,
+                LocalResult::None if wall - candidate < Duration::days(1) => {
+                    wall += Duration::minutes(1);
+                }
+                LocalResult::None => anyhow::bail!("cannot resolve local time {candidate}"),
+            }
+        };
+
+        let instant = instant.with_timezone(&Utc);
+        if instant > after {
+            return Ok(Some(instant));
+        }
+        cursor = candidate;
+    }
+    Ok(None)
+}
+
+#[test]
+fn test_timer_schedule() {
+    let ny = |v: &str| {
+        chrono::NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M:%S")
+            .unwrap()
+            .and_local_timezone(chrono_tz::America::New_York)
+            .earliest()
+            .unwrap()
+            .with_timezone(&Utc)
+    };
+    let cron = |expr: &str| TimerSchedule::Cron {
+        expr: expr.to_string(),
+        timezone: Some("America/New_York".to_string()),
+    };
+
+    // 02:30 does not exist on the spring forward day
+    let next = cron("0 30 2 * * *")
+        .next_after(ny("2024-03-09 12:00:00"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(next.to_rfc3339(), "2024-03-10T07:00:00+00:00");
+
+    // 01:30 happens twice on the fall back day, the timer fires once
+    let first = cron("0 30 1 * * *")
+        .next_after(ny("2024-11-02 12:00:00"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.to_rfc3339(), "2024-11-03T05:30:00+00:00");
+    let second = cron("0 30 1 * * *").next_after(first).unwrap().unwrap();
+    assert_eq!(second.to_rfc3339(), "2024-11-04T06:30:00+00:00");
+
+    let last_day = cron("0 0 L * *")
+        .next_after(ny("2024-02-10 00:00:00"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(last_day, ny("2024-02-29 00:00:00"));
+
+    let second_monday = cron("0 9 * * MON#2")
+        .next_after(ny("2024-06-01 00:00:00"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(second_monday, ny("2024-06-10 09:00:00"));
+
+    assert!(cron("0 0 30 2 *").validate().is_err());
+    assert!(cron("61 * * * *").validate().is_err());
+    assert!(
+        TimerSchedule::Cron {
+            expr: "* * * * *".to_string(),
+            timezone: Some("Mars/Olympus".to_string()),
+        }
+        .validate()
+        .is_err()
+    );
+
+    let now = Utc::now();
+    let interval = TimerSchedule::Interval { every_secs: 90 };
+    assert_eq!(
+        interval.next_after(now).unwrap(),
+        Some(now + Duration::seconds(90))
+    );
+    assert!(
+        TimerSchedule::Interval { every_secs: 0 }
+            .validate()
+            .is_err()
+    );
+
+    let at = TimerSchedule::At {
+        time: now + Duration::hours(1),
+    };
+    assert!(at.validate().is_ok());
+    assert_eq!(at.next_after(now).unwrap(), Some(now + Duration::hours(1)));
+    assert_eq!(at.next_after(now + Duration::hours(1)).unwrap(), None);
+    assert_eq!(at.upcoming(now, 5, &[]).unwrap().len(), 1);
+    assert_eq!(
+        interval.upcoming(now, 3, &[]).unwrap().last(),
+        Some(&(now + Duration::seconds(270)))
+    );
+
+    let legacy: TimerSchedule =
+        serde_json::from_str(r#"{"kind":"cron","expr":"0 * * * * *"}"#).unwrap();
+    assert_eq!(legacy, TimerSchedule::legacy("0 * * * * *"));
+    assert_eq!(legacy.legacy_expr().as_deref(), Some("0 * * * * *"));
+    assert_eq!(at.legacy_expr(), None);
+}
+
+#[test]
+fn test_calendar_and_blackout() {
+    let date = |v: &str| NaiveDate::parse_from_str(v, "%Y-%m-%d").unwrap();
+    let utc = |v: &str| DateTime::parse_from_rfc3339(v).unwrap().with_timezone(&Utc);
+
+    let holidays = TimerCalendar {
+        name: "holidays".to_string(),
+        mode: CalendarMode::Exclude,
+        dates: [date("2024-12-25"), date("2024-12-26")].into(),
+    };
+    let weekdays = TimerSchedule::Cron {
+        expr: "0 0 9 * * MON-FRI".to_string(),
+        timezone: Some("Europe/Berlin".to_string()),
+    };
+    let next = weekdays
+        .next_allowed(utc("2024-12-24T12:00:00Z"), std::slice::from_ref(&holidays))
+        .unwrap()
+        .unwrap();
+    assert_eq!(next, utc("2024-12-27T08:00:00Z"));
+
+    let every_minute = TimerSchedule::Cron {
+        expr: "* * * * *".to_string(),
+        timezone: Some("Europe/Berlin".to_string()),
+    };
+    let next = every_minute
+        .next_allowed(utc("2024-12-24T22:59:30Z"), std::slice::from_ref(&holidays))
+        .unwrap()
+        .unwrap();
+    assert_eq!(next, utc("2024-12-26T23:00:00Z"));
+
+    let month_end = TimerCalendar {
+        name: "closing".to_string(),
+        mode: CalendarMode::Include,
+        dates: [date("2024-12-31")].into(),
+    };
+    assert!(TimerCalendar::allows(
+        std::slice::from_ref(&month_end),
+        date("2024-12-31")
+    ));
+    assert!(!TimerCalendar::allows(
+        &[month_end, holidays],
+        date("2024-12-25")
+    ));
+    assert!(TimerCalendar::allows(&[], date("2024-12-25")));
+
+    // friday 22:00 to saturday 02:00
+    let freeze = BlackoutWindow {
+        name: "freeze".to_string(),
+        start_expr: "0 22 * * FRI".to_string(),
+        duration_secs: 4 * 3600,
+        timezone: Some("UTC".to_string()),
+    };
+    assert!(freeze.validate().is_ok());
+    assert!(freeze.is_active(utc("2024-12-27T22:00:00Z")).unwrap());
+    assert!(freeze.is_active(utc("2024-12-28T01:59:00Z")).unwrap());
+    assert!(!freeze.is_active(utc("2024-12-28T02:00:00Z")).unwrap());
+    assert!(!freeze.is_active(utc("2024-12-27T21:59:59Z")).unwrap());
+    assert!(BlackoutWindow::find_active(&[freeze], utc("2024-12-27T23:00:00Z")).is_some());
+}
@@ -1,4 +1,9 @@
-use std::{collections::HashMap, fmt, process::Output};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    process::Output,
+    time::{Duration, Instant},
+};
 
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
@@ -94,6 +99,8 @@ pub enum ScheduleStatus {
     Unsupervised,
     Scheduling,
     Unscheduled,
+    /// supervised job crashed too often and was given up
+    Fatal,
 }
 
 impl fmt::Display for ScheduleStatus {
@@ -104,10 +111,181 @@ impl fmt::Display for ScheduleStatus {
             ScheduleStatus::Unscheduled => write!(f, "unscheduled"),
             ScheduleStatus::Supervising => write!(f, "supervising"),
             ScheduleStatus::Unsupervised => write!(f, "unsupervised"),
+            ScheduleStatus::Fatal => write!(f, "fatal"),
         }
     }
 }
 
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartMode {
+    #[default]
+    Always,
+    OnFailure,
+    Never,
+}
+
+impl TryFrom<&str> for RestartMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mode = match value {
+            "always" => RestartMode::Always,
+            "on_failure" => RestartMode::OnFailure,
+            "never" => RestartMode::Never,
+            _ => return Err(anyhow!("invalid restart mode {value}")),
+        };
+        Ok(mode)
+    }
+}
+
+/// supervisord style restart policy of a supervised job
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+#[serde(default)]
+pub struct RestartPolicy {
+    pub mode: RestartMode,
+    /// exit codes treated as a normal exit by [`RestartMode::OnFailure`]
+    pub expected_exit_codes: Vec<i32>,
+    /// upper bound of the exponential backoff in seconds, 0 keeps a flat restart interval
+    pub max_backoff: u64,
+    /// max restarts within `restart_window` before the job turns fatal, 0 means unlimited
+    pub max_restarts: u32,
+    /// window in seconds used to count restarts
+    pub restart_window: u64,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            mode: RestartMode::Always,
+            expected_exit_codes: vec![0],
+            max_backoff: 0,
+            max_restarts: 0,
+            restart_window: 60,
+        }
+    }
+}
+
+impl RestartPolicy {
+    pub fn should_restart(&self, exit_code: Option<i32>) -> bool {
+        match self.mode {
+            RestartMode::Always => true,
+            RestartMode::Never => false,
+            RestartMode::OnFailure => {
+                exit_code.is_none_or(|v| !self.expected_exit_codes.contains(&v))
+            }
+        }
+    }
+
+    /// delay before the nth restart in the window, doubling `interval` up to `max_backoff`
+    pub fn backoff(&self, interval: Duration, n: u32) -> Duration {
+        if self.max_backoff == 0 {
+            return interval;
+        }
+        let max = Duration::from_secs(self.max_backoff).max(interval);
+        interval
+            .saturating_mul(2u32.saturating_pow(n.saturating_sub(1)))
+            .min(max)
+    }
+}
+
+/// restart history of a supervised job
+#[derive(Debug, Default)]
+pub struct RestartState {
+    /// restarts since supervising started
+    pub count: u32,
+    history: VecDeque<Instant>,
+}
+
+impl RestartState {
+    /// record a restart and return the number of restarts within the window,
+    /// or None once `max_restarts` is exceeded
+    pub fn record(&mut self, policy: &RestartPolicy) -> Option<u32> {
+        let now = Instant::now();
+        let window = Duration::from_secs(policy.restart_window);
+        while self
+            .history
+            .front()
+            .is_some_and(|v| now.duration_since(*v) > window)
+        {
+            self.history.pop_front();
+        }
+
+        if policy.max_restarts > 0 && self.history.len() >= policy.max_restarts as usize {
+            return None;
+        }
+
+        self.history.push_back(now);
+        self.count += 1;
+        Some(self.history.len() as u32)
+    }
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProbeAction {
+    /// healthy when the command exits with 0
+    Exec { command: String, args: Vec<String> },
+    /// healthy when the port accepts a connection
+    Tcp { host: String, port: u16 },
+    /// healthy when a GET returns a 2xx or 3xx status
+    Http { url: String },
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+pub struct Probe {
+    pub action: ProbeAction,
+    /// seconds to wait after the process started before the first check
+    #[serde(default)]
+    pub initial_delay: u64,
+    /// seconds between two checks
+    #[serde(default = "Probe::default_interval")]
+    pub interval: u64,
+    /// seconds before a check is considered failed
+    #[serde(default = "Probe::default_timeout")]
+    pub timeout: u64,
+    /// consecutive failures before the probe turns unhealthy
+    #[serde(default = "Probe::default_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+impl Probe {
+    fn default_interval() -> u64 {
+        10
+    }
+
+    fn default_timeout() -> u64 {
+        1
+    }
+
+    fn default_failure_threshold() -> u32 {
+        3
+    }
+}
+
+/// probes of a supervised job, a failed liveness probe restarts the process,
+/// a failed readiness probe is only reported
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Default)]
+pub struct HealthCheck {
+    pub liveness: Option<Probe>,
+    pub readiness: Option<Probe>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Default)]
+pub struct ProbeResult {
+    pub healthy: bool,
+    /// consecutive failures
+    pub failures: u32,
+    /// error of the last failed check
+    pub message: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Default)]
+pub struct ProbeStatus {
+    pub liveness: Option<ProbeResult>,
+    pub readiness: Option<ProbeResult>,
+}
+
 #[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct BaseJob {
     pub eid: String,
@@ -122,6 +300,19 @@ pub struct BaseJob {
     pub work_user: Option<String>,
     pub max_retry: Option<u8>,
     pub max_parallel: Option<u32>,
+    /// signal sent to the process group on timeout or kill, default SIGTERM
+    #[serde(default)]
+    pub stop_signal: Option<String>,
+    /// seconds to wait after `stop_signal` before SIGKILL, None or 0 kills at once
+    #[serde(default)]
+    pub stop_grace_period: Option<u64>,
+    /// run the job inside a container instead of on the host
+    #[serde(default)]
+    pub container: Option<ContainerOptions>,
+    /// output files published as artifacts after a run, relative paths are
+    /// resolved against work_dir
+    #[serde(default)]
+    pub publish_artifacts: Option<Vec<String>>,
 }
 
 impl BaseJob {
@@ -140,6 +331,10 @@ impl BaseJob {
             work_user: self.work_user.clone(),
             max_retry: self.max_retry,
             max_parallel: self.max_parallel,
+            stop_signal: self.stop_signal.clone(),
+            stop_grace_period: self.stop_grace_period,
+            container: self.container.clone(),
+            publish_artifacts: self.publish_artifacts.clone(),
         }
     }
 }
@@ -150,12 +345,112 @@ pub struct BundleScript {
     pub cmd_name: String,
     pub args: Vec<String>,
     pub code: String,
+    #[serde(default)]
+    pub container: Option<ContainerOptions>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PullPolicy {
+    Always,
+    #[default]
+    Missing,
+    Never,
+}
+
+impl fmt::Display for PullPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PullPolicy::Always => write!(f, "always"),
+            PullPolicy::Missing => write!(f, "missing"),
+            PullPolicy::Never => write!(f, "never"),
+        }
+    }
+}
+
+/// container a job runs in, started through the docker or podman cli
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ContainerOptions {
+    /// docker or podman, default docker
+    pub runtime: String,
+    pub image: String,
+    pub pull_policy: PullPolicy,
+    /// bind mounts like "/data:/data:ro"
+    pub mounts: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub network: Option<String>,
+    /// cpu limit like "1.5"
+    pub cpus: Option<String>,
+    /// memory limit like "512m"
+    pub memory: Option<String>,
+}
+
+impl ContainerOptions {
+    pub fn runtime(&self) -> &str {
+        if self.runtime.is_empty() {
+            "docker"
+        } else {
+            &self.runtime
+        }
+    }
+
+    /// arguments of `<runtime> run` up to and including the image,
+    /// the command to run inside the container goes after them. Only the
+    /// names of the environment variables are passed, the runtime takes the
+    /// values from its own environment so they stay off the process list.
+    pub fn run_args(
+        &self,
+        name: &str,
+        work_dir: Option<&str>,
+        work_user: Option<&str>,
+        env: &HashMap<String, String>,
+        interactive: bool,
+    ) -> Vec<String> {
+        let mut args = vec![
+            "run".to_string(),
+            "--rm".to_string(),
+            "--init".to_string(),
+            format!("--name={name}"),
+            format!("--pull={}", self.pull_policy),
+        ];
+        if interactive {
+            args.push("-i".to_string());
+        }
+        args.extend(self.mounts.iter().map(|v| format!("--volume={v}")));
+        args.extend(
+            self.env
+                .keys()
+                .chain(env.keys())
+                .map(|k| format!("--env={k}")),
+        );
+        if let Some(network) = self.network.as_deref().filter(|v| !v.is_empty()) {
+            args.push(format!("--network={network}"));
+        }
+        if let Some(cpus) = self.cpus.as_deref().filter(|v| !v.is_empty()) {
+            args.push(format!("--cpus={cpus}"));
+        }
+        if let Some(memory) = self.memory.as_deref().filter(|v| !v.is_empty()) {
+            args.push(format!("--memory={memory}"));
+        }
+        if let Some(work_dir) = work_dir {
+            args.push(format!("--workdir={work_dir}"));
+        }
+        if let Some(work_user) = work_user {
+            args.push(format!("--user={work_user}"));
+        }
+        args.push(self.image.clone());
+        args
+    }
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
 pub struct UploadFile {
     pub filename: String,
     pub data: Option<Vec<u8>>,
+    /// artifact fetched from comet by the agent when data is not inlined
+    #[serde(default)]
+    pub sha256: Option<String>,
 }
 
 #[derive(Debug, Serialize, PartialEq, Deserialize, Default, Clone)]
@@ -193,6 +488,25 @@ impl fmt::Display for ScheduleType {
     }
 }
 
+/// how the agent stopped a job before it exited by itself
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stopped {
+    pub is_timeout: bool,
+    /// the process outlived the grace period and got SIGKILL
+    pub force_killed: bool,
+}
+
+impl Stopped {
+    pub fn describe(&self, exit_status: &str) -> String {
+        let reason = if self.is_timeout { "timeout" } else { "killed" };
+        if self.force_killed {
+            format!("{exit_status}, {reason}, force killed")
+        } else {
+            format!("{exit_status}, {reason}, exited gracefully")
+        }
+    }
+}
+
 pub enum BundleOutput {
     Output(Output),
     Bundle(HashMap<String, Output>),
@@ -239,6 +553,10 @@ impl BundleOutput {
 pub struct SshConnectionOption {
     pub user: String,
     pub password: String,
+    #[serde(default)]
+    pub private_key: Option<String>,
+    #[serde(default)]
+    pub passphrase: Option<String>,
     pub port: u16,
 }
 
@@ -246,12 +564,19 @@ impl SshConnectionOption {
     pub fn build(
         user: Option<String>,
         password: Option<String>,
+        private_key: Option<String>,
+        passphrase: Option<String>,
         port: Option<u16>,
     ) -> Option<SshConnectionOption> {
-        if let (Some(user), Some(password), Some(port)) = (user, password, port) {
+        if password.is_none() && private_key.is_none() {
+            return None;
+        }
+        if let (Some(user), Some(port)) = (user, port) {
             Some(SshConnectionOption {
                 user,
-                password,
+                password: password.unwrap_or_default(),
+                private_key,
+                passphrase,
                 port,
             })
         } else {
@@ -275,3 +600,59 @@ impl AssignUserOption {
         }
     }
 }
+
+#[test]
+fn test_restart_policy() {
+    let policy = RestartPolicy {
+        mode: RestartMode::OnFailure,
+        expected_exit_codes: vec![0, 2],
+        max_backoff: 10,
+        max_restarts: 3,
+        ..Default::default()
+    };
+    assert!(!policy.should_restart(Some(2)));
+    assert!(policy.should_restart(Some(1)));
+    assert!(policy.should_restart(None));
+
+    let interval = Duration::from_secs(1);
+    let delays: Vec<u64> = (1..=5)
+        .map(|n| policy.backoff(interval, n).as_secs())
+        .collect();
+    assert_eq!(delays, vec![1, 2, 4, 8, 10]);
+
+    let mut state = RestartState::default();
+    assert_eq!(state.record(&policy), Some(1));
+    assert_eq!(state.record(&policy), Some(2));
+    assert_eq!(state.record(&policy), Some(3));
+    assert_eq!(state.record(&policy), None);
+    assert_eq!(state.count, 3);
+}
+
+#[test]
+fn test_container_run_args() {
+    let opts = ContainerOptions {
+        image: "python:3.12".to_string(),
+        mounts: vec!["/data:/data:ro".to_string()],
+        memory: Some("512m".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(opts.runtime(), "docker");
+
+    let env = HashMap::from([("DB_PASSWORD".to_string(), "secret".to_string())]);
+    let args = opts.run_args("job", Some("/data"), None, &env, false);
+    assert_eq!(
+        args,
+        vec![
+            "run",
+            "--rm",
+            "--init",
+            "--name=job",
+            "--pull=missing",
+            "--volume=/data:/data:ro",
+            "--env=DB_PASSWORD",
+            "--memory=512m",
+            "--workdir=/data",
+            "python:3.12",
+        ]
+    );
+}
@@ -42,19 +42,72 @@ pub struct Session {
     session: client::Handle<Client>,
 }
 
-pub struct ConnectParams<A: ToSocketAddrs, U: Into<String>, P: Into<String>> {
+/// Credential used to authenticate a ssh session.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub enum SshAuth {
+    Password(String),
+    /// OpenSSH or PEM encoded private key with an optional passphrase.
+    PrivateKey {
+        key: String,
+        passphrase: Option<String>,
+    },
+}
+
+impl SshAuth {
+    /// Prefers the private key when one is given, otherwise falls back to the password.
+    pub fn build(
+        password: String,
+        private_key: Option<String>,
+        passphrase: Option<String>,
+    ) -> Self {
+        match private_key.filter(|v| !v.trim().is_empty()) {
+            Some(key) => SshAuth::PrivateKey {
+                key,
+                passphrase: passphrase.filter(|v| !v.is_empty()),
+            },
+            None => SshAuth::Password(password),
+        }
+    }
+
+    /// Splits into the password, private key and passphrase fields used on the wire.
+    pub fn into_parts(self) -> (String, Option<String>, Option<String>) {
+        match self {
+            SshAuth::Password(password) => (password, None, None),
+            SshAuth::PrivateKey { key, passphrase } => (String::new(), Some(key), passphrase),
+        }
+    }
+
+    pub async fn authenticate<H: client::Handler>(
+        self,
+        session: &mut client::Handle<H>,
+        user: String,
+    ) -> Result<()> {
+        let auth_res = match self {
+            SshAuth::Password(password) => session.authenticate_password(user, password).await?,
+            SshAuth::PrivateKey { key, passphrase } => {
+                let key_pair = decode_secret_key(&key, passphrase.as_deref())?;
+                session
+                    .authenticate_publickey(user, Arc::new(key_pair))
+                    .await?
+            }
+        };
+
+        if !auth_res {
+            anyhow::bail!("Authentication failed");
+        }
+        Ok(())
+    }
+}
+
+pub struct ConnectParams<A: ToSocketAddrs, U: Into<String>> {
     pub user: U,
-    pub password: P,
+    pub auth: SshAuth,
     pub addrs: A,
 }
 
 impl Session {
-    pub async fn connect<A: ToSocketAddrs, U: Into<String>, P: Into<String>>(
-        ConnectParams {
-            user,
-            password,
-            addrs,
-        }: ConnectParams<A, U, P>,
+    pub async fn connect<A: ToSocketAddrs, U: Into<String>>(
+        ConnectParams { user, auth, addrs }: ConnectParams<A, U>,
     ) -> Result<Self> {
         let config = client::Config {
             inactivity_timeout: Some(Duration::from_secs(90)),
@@ -68,18 +121,14 @@ impl Session {
         let mut session =
             timeout(Duration::from_secs(1), client::connect(config, addrs, sh)).await??;
 
-        let auth_res = session.authenticate_password(user, password).await?;
-
-        if !auth_res {
-            anyhow::bail!("Authentication failed");
-        }
+        auth.authenticate(&mut session, user.into()).await?;
 
         Ok(Self { session })
     }
 
     pub async fn connect_stream<T: AsyncRead + AsyncWrite + Unpin + Send + 'static>(
         user: String,
-        password: String,
+        auth: SshAuth,
         stream: T,
     ) -> Result<Self> {
         let config = client::Config {
@@ -97,11 +146,7 @@ impl Session {
         )
         .await??;
 
-        let auth_res = session.authenticate_password(user, password).await?;
-
-        if !auth_res {
-            anyhow::bail!("Authentication failed");
-        }
+        auth.authenticate(&mut session, user).await?;
 
         Ok(Self { session })
     }
@@ -318,12 +363,12 @@ pub async fn read_dir(
     _ip: &str,
     port: u16,
     user: &str,
-    password: &str,
+    auth: SshAuth,
     dir: Option<&str>,
 ) -> Result<DirDetail> {
     let ssh_session = Session::connect(ConnectParams {
         user,
-        password,
+        auth,
         addrs: ("127.0.0.1", port),
     })
     .await?;
@@ -378,13 +423,14 @@ pub async fn read_dir(
     Ok(ret)
 }
 
-pub async fn upload(
+/// Streams the reader into a remote file, the parent dir is created if missing.
+pub async fn upload<R: AsyncRead + Unpin>(
     _ip: &str,
     port: u16,
     user: &str,
-    password: &str,
+    auth: SshAuth,
     filepath: &str,
-    data: Vec<u8>,
+    reader: &mut R,
 ) -> Result<()> {
     let dir = std::path::Path::new(filepath)
         .parent()
@@ -393,7 +439,7 @@ pub async fn upload(
 
     let ssh_session = Session::connect(ConnectParams {
         user,
-        password,
+        auth,
         addrs: ("127.0.0.1", port),
     })
     .await?;
@@ -407,7 +453,8 @@ pub async fn upload(
     }
 
     let mut file = sftp_session.create(filepath).await?;
-    file.write_all(&data).await?;
+    tokio::io::copy(reader, &mut file).await?;
+    file.shutdown().await?;
     Ok(())
 }
 
@@ -415,13 +462,13 @@ pub async fn remove(
     _ip: &str,
     port: u16,
     user: &str,
-    password: &str,
+    auth: SshAuth,
     remove_type: &str,
     filepath: &str,
 ) -> Result<()> {
     let ssh_session = Session::connect(ConnectParams {
         user,
-        password,
+        auth,
         addrs: ("127.0.0.1", port),
     })
     .await?;
@@ -441,12 +488,12 @@ pub async fn download(
     _ip: &str,
     port: u16,
     user: &str,
-    password: &str,
+    auth: SshAuth,
     filepath: &str,
 ) -> Result<Vec<u8>> {
     let ssh_session = Session::connect(ConnectParams {
         user,
-        password,
+        auth,
         addrs: ("127.0.0.1", port),
     })
     .await?;
@@ -456,3 +503,44 @@ pub async fn download(
     let data = sftp_session.read(filepath).await?;
     Ok(data)
 }
+
+/// Streams a remote file into a local file and returns the number of bytes.
+pub async fn download_to(
+    _ip: &str,
+    port: u16,
+    user: &str,
+    auth: SshAuth,
+    filepath: &str,
+    target: &std::path::Path,
+) -> Result<u64> {
+    let ssh_session = Session::connect(ConnectParams {
+        user,
+        auth,
+        addrs: ("127.0.0.1", port),
+    })
+    .await?;
+
+    let sftp_session = ssh_session.sftp_client().await?;
+
+    let mut file = sftp_session.open(filepath).await?;
+    let mut local_file = tokio::fs::File::create(target).await?;
+    let size = tokio::io::copy(&mut file, &mut local_file).await?;
+    local_file.flush().await?;
+    Ok(size)
+}
+
+#[test]
+fn test_ssh_auth_build() {
+    let auth = SshAuth::build("secret".into(), Some(" ".into()), Some("x".into()));
+    assert_eq!(auth, SshAuth::Password("secret".into()));
+
+    let auth = SshAuth::build(String::new(), Some("key".into()), Some(String::new()));
+    assert_eq!(
+        auth.clone(),
+        SshAuth::PrivateKey {
+            key: "key".into(),
+            passphrase: None
+        }
+    );
+    assert_eq!(auth.into_parts(), (String::new(), Some("key".into()), None));
+}
@@ -0,0 +1,126 @@
+//! OpenTelemetry tracing of a dispatch. The W3C trace context travels in
+//! [`DispatchJobParams`](crate::DispatchJobParams) and
+//! [`UpdateJobParams`](crate::bridge::msg::UpdateJobParams), every hop opens a
+//! span under it and the spans are exported over OTLP.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use opentelemetry::{propagation::TextMapPropagator, trace::TracerProvider as _};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+use tracing::{Level, Span, error};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{Layer, filter::Targets, layer::SubscriberExt, util::SubscriberInitExt};
+
+/// `traceparent` and `tracestate` of the W3C trace context.
+pub type TraceContext = HashMap<String, String>;
+
+/// Target of the spans that are exported, anything else is only logged.
+pub const TARGET: &str = "jiascheduler";
+
+/// Flushes the exporter when dropped.
+pub struct Telemetry(Option<SdkTracerProvider>);
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(provider) = self.0.take()
+            && let Err(e) = provider.shutdown()
+        {
+            error!("failed shutdown tracer provider - {e}");
+        }
+    }
+}
+
+/// Sets up logging as filtered by `RUST_LOG`, and span export when an OTLP
+/// http endpoint such as "http://127.0.0.1:4318" is given.
+pub fn init(service_name: &'static str, otlp_endpoint: Option<&str>) -> Result<Telemetry> {
+    let targets = std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|v| v.parse::<Targets>().ok())
+        .unwrap_or_else(|| Targets::new().with_default(Level::INFO));
+    let fmt = tracing_subscriber::fmt::layer().with_filter(targets);
+
+    let Some(endpoint) = otlp_endpoint.filter(|v| !v.is_empty()) else {
+        tracing_subscriber::registry().with(fmt).try_init()?;
+        return Ok(Telemetry(None));
+    };
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build();
+    let otel = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(service_name))
+        .with_filter(Targets::new().with_target(TARGET, Level::INFO));
+    tracing_subscriber::registry()
+        .with(fmt)
+        .with(otel)
+        .try_init()?;
+    Ok(Telemetry(Some(provider)))
+}
+
+/// Continues the trace of `ctx` in `span`.
+pub fn set_parent(span: &Span, ctx: Option<&TraceContext>) {
+    if let Some(ctx) = ctx {
+        let _ = span.set_parent(TraceContextPropagator::new().extract(ctx));
+    }
+}
+
+/// The trace context to hand to the next hop, which is `span` when it is
+/// exported and the incoming context otherwise, so that a hop without an
+/// exporter does not break the trace.
+pub fn forward(span: &Span, incoming: Option<&TraceContext>) -> Option<TraceContext> {
+    let mut ctx = TraceContext::new();
+    TraceContextPropagator::new().inject_context(&span.context(), &mut ctx);
+    if ctx.is_empty() {
+        incoming.cloned()
+    } else {
+        Some(ctx)
+    }
+}
+
+/// Trace id of a `traceparent` such as
+/// "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".
+pub fn trace_id(ctx: &TraceContext) -> Option<&str> {
+    ctx.get("traceparent")?
+        .split('-')
+        .nth(1)
+        .filter(|v| v.len() == 32)
+}
+
+/// Environment variables that let a job join the trace of its run.
+pub fn job_env(ctx: Option<&TraceContext>) -> Vec<(String, String)> {
+    let Some(ctx) = ctx else {
+        return vec![];
+    };
+    let mut env = vec![];
+    if let Some(v) = ctx.get("traceparent") {
+        env.push(("TRACEPARENT".to_string(), v.to_string()));
+    }
+    if let Some(v) = ctx.get("tracestate").filter(|v| !v.is_empty()) {
+        env.push(("TRACESTATE".to_string(), v.to_string()));
+    }
+    if let Some(v) = trace_id(ctx) {
+        env.push(("JIASCHEDULER_TRACE_ID".to_string(), v.to_string()));
+    }
+    env
+}
+
+#[test]
+fn test_job_env() {
+    let ctx = TraceContext::from([(
+        "traceparent".to_string(),
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string(),
+    )]);
+    assert_eq!(trace_id(&ctx), Some("4bf92f3577b34da6a3ce929d0e0e4736"));
+    let env = job_env(Some(&ctx));
+    assert_eq!(env.len(), 2);
+    assert_eq!(env[1].1, "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert!(job_env(None).is_empty());
+    assert_eq!(forward(&Span::none(), Some(&ctx)), Some(ctx));
+}
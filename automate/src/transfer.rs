@@ -0,0 +1,471 @@
+use std::{
+    io::SeekFrom,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use crypto::{digest::Digest, sha2::Sha256};
+use reqwest::{Client, StatusCode, header};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWriteExt},
+};
+use tracing::{debug, warn};
+
+use crate::artifact::{ArtifactCache, comet_http_addr};
+
+/// Size of a chunk sent in a single request, the body is raw bytes.
+pub const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+/// Attempts of a transfer before giving up, each attempt resumes from the
+/// offset which was already received.
+const MAX_ATTEMPTS: usize = 5;
+
+/// State of an upload to comet, polled by the console to report progress.
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Default)]
+pub struct TransferProgress {
+    pub transfer_id: String,
+    /// bytes received so far
+    pub offset: u64,
+    pub total: u64,
+    /// sha256 of the file once the transfer is completed
+    pub sha256: Option<String>,
+}
+
+impl TransferProgress {
+    pub fn is_completed(&self) -> bool {
+        self.sha256.is_some()
+    }
+}
+
+/// Transfer ids become file names on comet, only url safe characters are allowed.
+pub fn is_transfer_id(v: &str) -> bool {
+    !v.is_empty()
+        && v.len() <= 64
+        && v.bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+}
+
+pub fn new_transfer_id() -> String {
+    nanoid::nanoid!(
+        21,
+        &"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ"
+            .chars()
+            .collect::<Vec<_>>()
+    )
+}
+
+/// Computes the sha256 and size without loading the whole file into memory.
+pub async fn file_sha256(path: &Path) -> Result<(String, u64)> {
+    let mut file = File::open(path).await?;
+    reader_sha256(&mut file).await
+}
+
+/// Same as [`file_sha256`] for a reader, which is left at its end.
+pub async fn reader_sha256<R: AsyncRead + Unpin>(reader: &mut R) -> Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut size = 0u64;
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.input(&buf[..n]);
+        size += n as u64;
+    }
+    Ok((hasher.result_str(), size))
+}
+
+/// Reads until the buffer is full or the reader is exhausted.
+async fn read_chunk<R: AsyncRead + Unpin>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = reader.read(&mut buf[filled..]).await?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+/// Partial uploads received by comet, a completed transfer is verified and
+/// moved into the artifact cache.
+#[derive(Clone, Debug)]
+pub struct TransferStore {
+    dir: PathBuf,
+    artifacts: ArtifactCache,
+}
+
+impl TransferStore {
+    pub fn new(artifacts: ArtifactCache) -> Self {
+        Self {
+            dir: artifacts.dir().join("transfer"),
+            artifacts,
+        }
+    }
+
+    fn part_path(&self, transfer_id: &str) -> Result<PathBuf> {
+        if !is_transfer_id(transfer_id) {
+            anyhow::bail!("invalid transfer id {transfer_id}");
+        }
+        Ok(self.dir.join(format!("{transfer_id}.part")))
+    }
+
+    fn meta_path(&self, transfer_id: &str) -> Result<PathBuf> {
+        Ok(self.part_path(transfer_id)?.with_extension("json"))
+    }
+
+    async fn save_meta(&self, progress: &TransferProgress) -> Result<()> {
+        fs::create_dir_all(&self.dir).await?;
+        fs::write(
+            self.meta_path(&progress.transfer_id)?,
+            serde_json::to_vec(progress)?,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Returns None if comet never received the transfer.
+    pub async fn progress(&self, transfer_id: &str) -> Result<Option<TransferProgress>> {
+        let meta_path = self.meta_path(transfer_id)?;
+        if !fs::try_exists(&meta_path).await? {
+            return Ok(None);
+        }
+        let mut progress: TransferProgress = serde_json::from_slice(&fs::read(meta_path).await?)?;
+        if !progress.is_completed() {
+            progress.offset = match fs::metadata(self.part_path(transfer_id)?).await {
+                Ok(v) => v.len(),
+                Err(_) => 0,
+            };
+        }
+        Ok(Some(progress))
+    }
+
+    /// Appends a chunk at the given offset, a chunk at offset 0 restarts the
+    /// transfer. Err(progress) is returned when the offset does not match what
+    /// comet holds, the sender should resume from `progress.offset`.
+    pub async fn append(
+        &self,
+        transfer_id: &str,
+        offset: u64,
+        total: u64,
+        data: &[u8],
+    ) -> Result<std::result::Result<TransferProgress, TransferProgress>> {
+        let part_path = self.part_path(transfer_id)?;
+        if offset == 0 {
+            self.save_meta(&TransferProgress {
+                transfer_id: transfer_id.to_string(),
+                total,
+                ..Default::default()
+            })
+            .await?;
+            fs::write(&part_path, data).await?;
+        } else {
+            let Some(progress) = self.progress(transfer_id).await? else {
+                return Ok(Err(TransferProgress {
+                    transfer_id: transfer_id.to_string(),
+                    total,
+                    ..Default::default()
+                }));
+            };
+            if progress.is_completed() || progress.offset != offset {
+                return Ok(Err(progress));
+            }
+            let mut file = OpenOptions::new().append(true).open(&part_path).await?;
+            file.write_all(data).await?;
+            file.flush().await?;
+        }
+
+        Ok(Ok(TransferProgress {
+            transfer_id: transfer_id.to_string(),
+            offset: offset + data.len() as u64,
+            total,
+            sha256: None,
+        }))
+    }
+
+    /// Verifies the received file against the checksum of the sender and moves
+    /// it into the artifact cache.
+    pub async fn complete(
+        &self,
+        transfer_id: &str,
+        sha256: &str,
+        total: u64,
+    ) -> Result<TransferProgress> {
+        let part_path = self.part_path(transfer_id)?;
+        let mut progress = self
+            .progress(transfer_id)
+            .await?
+            .unwrap_or(TransferProgress {
+                transfer_id: transfer_id.to_string(),
+                total,
+                ..Default::default()
+            });
+
+        if !self.artifacts.contains(sha256).await? {
+            if !fs::try_exists(&part_path).await? {
+                anyhow::bail!("transfer {transfer_id} not found");
+            }
+            if progress.offset != total {
+                anyhow::bail!(
+                    "transfer {transfer_id} is incomplete, received {} of {} bytes",
+                    progress.offset,
+                    total
+                );
+            }
+            if let Err(e) = self.artifacts.import(sha256, &part_path).await {
+                // a corrupted file cannot be resumed, the sender has to start over
+                let _ = fs::remove_file(&part_path).await;
+                return Err(e);
+            }
+        } else if fs::try_exists(&part_path).await? {
+            fs::remove_file(&part_path).await?;
+        }
+
+        progress.total = total;
+        progress.offset = total;
+        progress.sha256 = Some(sha256.to_string());
+        self.save_meta(&progress).await?;
+        Ok(progress)
+    }
+}
+
+/// Uploads to comet in chunks, resuming from the offset comet already holds.
+/// Nothing is sent when comet already has an artifact with the same content.
+pub async fn upload<R: AsyncRead + AsyncSeek + Unpin>(
+    client: &Client,
+    comet_addr: &str,
+    secret: &str,
+    transfer_id: &str,
+    reader: &mut R,
+) -> Result<TransferProgress> {
+    let base_url = comet_http_addr(comet_addr);
+    let (sha256, total) = reader_sha256(reader).await?;
+    let complete_url =
+        format!("{base_url}/transfer/{transfer_id}/complete?sha256={sha256}&total={total}");
+
+    let status = client
+        .head(format!("{base_url}/artifact/{sha256}"))
+        .bearer_auth(secret)
+        .send()
+        .await?
+        .status();
+    if status.is_success() {
+        return complete(client, &complete_url, secret).await;
+    }
+
+    let mut offset = match get_progress(client, &base_url, secret, transfer_id).await? {
+        Some(v) if v.total == total && v.offset <= total && !v.is_completed() => v.offset,
+        _ => 0,
+    };
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut attempts = 0;
+
+    loop {
+        if offset == total && total != 0 {
+            break;
+        }
+        reader.seek(SeekFrom::Start(offset)).await?;
+        let n = read_chunk(reader, &mut buf).await?;
+        let ret = client
+            .patch(format!(
+                "{base_url}/transfer/{transfer_id}?offset={offset}&total={total}"
+            ))
+            .bearer_auth(secret)
+            .header(header::CONTENT_TYPE, "application/octet-stream")
+            .body(buf[..n].to_vec())
+            .send()
+            .await;
+
+        let progress = match ret {
+            Ok(resp) if resp.status().is_success() || resp.status() == StatusCode::CONFLICT => {
+                resp.json::<TransferProgress>().await?
+            }
+            Ok(resp) => {
+                attempts += 1;
+                if attempts >= MAX_ATTEMPTS {
+                    anyhow::bail!(
+                        "failed send chunk of transfer {transfer_id}, status {}",
+                        resp.status()
+                    );
+                }
+                continue;
+            }
+            Err(e) => {
+                attempts += 1;
+                if attempts >= MAX_ATTEMPTS {
+                    return Err(e.into());
+                }
+                warn!("failed send chunk of transfer {transfer_id}, retry - {e}");
+                match get_progress(client, &base_url, secret, transfer_id).await {
+                    Ok(Some(v)) if v.total == total => v,
+                    _ => continue,
+                }
+            }
+        };
+
+        debug!(
+            "transfer {transfer_id} progress {}/{}",
+            progress.offset, progress.total
+        );
+        if progress.offset > total {
+            anyhow::bail!(
+                "invalid offset {} of transfer {transfer_id}",
+                progress.offset
+            );
+        }
+        offset = progress.offset;
+        if total == 0 {
+            break;
+        }
+    }
+
+    complete(client, &complete_url, secret).await
+}
+
+async fn complete(client: &Client, url: &str, secret: &str) -> Result<TransferProgress> {
+    let resp = client.post(url).bearer_auth(secret).send().await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        anyhow::bail!(
+            "failed complete transfer, status {status}, {}",
+            resp.text().await.unwrap_or_default()
+        );
+    }
+    Ok(resp.json().await?)
+}
+
+/// Queries the progress of an upload to comet, None if comet has not received it.
+pub async fn get_progress(
+    client: &Client,
+    comet_addr: &str,
+    secret: &str,
+    transfer_id: &str,
+) -> Result<Option<TransferProgress>> {
+    let resp = client
+        .get(format!(
+            "{}/transfer/{transfer_id}",
+            comet_http_addr(comet_addr)
+        ))
+        .bearer_auth(secret)
+        .send()
+        .await?;
+    if resp.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    Ok(Some(resp.error_for_status()?.json().await?))
+}
+
+/// Downloads an artifact from comet into `target`. The data is written to a
+/// `.part` file first, an interrupted download is resumed with a range request
+/// and the file is only moved into place once the checksum matches.
+pub async fn download(
+    client: &Client,
+    comet_addr: &str,
+    secret: &str,
+    sha256: &str,
+    target: &Path,
+) -> Result<()> {
+    let url = format!("{}/artifact/{sha256}", comet_http_addr(comet_addr));
+    let part_path = target.with_extension(format!(
+        "{}part",
+        target
+            .extension()
+            .map_or(String::new(), |v| format!("{}.", v.to_string_lossy()))
+    ));
+    if let Some(dir) = target.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+
+    let mut attempts = 0;
+    loop {
+        match download_part(client, &url, secret, &part_path).await {
+            Ok(_) => break,
+            Err(e) => {
+                attempts += 1;
+                if attempts >= MAX_ATTEMPTS {
+                    return Err(e);
+                }
+                warn!("failed download artifact {sha256}, retry - {e}");
+            }
+        }
+    }
+
+    let (actual, _) = file_sha256(&part_path).await?;
+    if actual != sha256 {
+        fs::remove_file(&part_path).await?;
+        anyhow::bail!("artifact checksum mismatch, expected {sha256}, got {actual}");
+    }
+    fs::rename(&part_path, target).await?;
+    Ok(())
+}
+
+async fn download_part(client: &Client, url: &str, secret: &str, part_path: &Path) -> Result<()> {
+    let offset = match fs::metadata(part_path).await {
+        Ok(v) => v.len(),
+        Err(_) => 0,
+    };
+    let mut resp = client
+        .get(url)
+        .bearer_auth(secret)
+        .header(header::RANGE, format!("bytes={offset}-"))
+        .send()
+        .await?;
+
+    let mut file = match resp.status() {
+        StatusCode::PARTIAL_CONTENT => OpenOptions::new().append(true).open(part_path).await?,
+        // the part file already holds everything
+        StatusCode::RANGE_NOT_SATISFIABLE => return Ok(()),
+        v if v.is_success() => File::create(part_path).await?,
+        v => return Err(anyhow!("failed download {url}, status {v}")),
+    };
+
+    while let Some(chunk) = resp.chunk().await? {
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+/// Parses a range header of the form "bytes=<start>-", other forms are not
+/// used by the transfer client and are served as a full response.
+pub fn parse_range_start(v: &str) -> Option<u64> {
+    v.strip_prefix("bytes=")?.strip_suffix('-')?.parse().ok()
+}
+
+#[tokio::test]
+async fn test_transfer_store() {
+    use crate::artifact::sha256_hex;
+
+    let dir = std::env::temp_dir().join(format!("jiascheduler-transfer-{}", new_transfer_id()));
+    let store = TransferStore::new(ArtifactCache::new(dir.to_str().unwrap()).unwrap());
+    let data = b"hello world".to_vec();
+    let sha256 = sha256_hex(&data);
+
+    let progress = store.append("t1", 0, 11, &data[..5]).await.unwrap();
+    assert_eq!(progress.unwrap().offset, 5);
+
+    // a chunk at a stale offset reports where to resume
+    let progress = store.append("t1", 3, 11, &data[3..]).await.unwrap();
+    assert_eq!(progress.unwrap_err().offset, 5);
+
+    assert!(store.complete("t1", &sha256, 11).await.is_err());
+
+    store
+        .append("t1", 5, 11, &data[5..])
+        .await
+        .unwrap()
+        .unwrap();
+    let progress = store.complete("t1", &sha256, 11).await.unwrap();
+    assert_eq!(progress.offset, 11);
+    assert_eq!(progress.sha256, Some(sha256.clone()));
+    assert_eq!(store.artifacts.read(&sha256).await.unwrap(), data);
+
+    assert!(!is_transfer_id("../t1"));
+    assert_eq!(parse_range_start("bytes=5-"), Some(5));
+    assert_eq!(parse_range_start("bytes=0-10"), None);
+
+    let _ = fs::remove_dir_all(dir).await;
+}
@@ -7,13 +7,13 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "agent_release_version")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
+    pub id: i64,
     pub name: String,
     pub info: String,
     pub url: String,
     #[sea_orm(unique)]
     pub release_version: String,
-    pub release_scope: i8,
+    pub release_scope: i16,
     pub release_ip: Option<Json>,
     pub created_user: String,
     pub updated_user: String,
@@ -7,13 +7,14 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "executor")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
+    pub id: i64,
     #[sea_orm(unique)]
     pub name: String,
     pub command: String,
     pub platform: String,
     pub info: String,
-    pub read_code_from_stdin: i8,
+    pub read_code_from_stdin: i16,
+    pub container: Option<Json>,
     pub created_user: String,
     pub updated_user: String,
     pub created_time: DateTimeLocal,
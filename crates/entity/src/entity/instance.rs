@@ -7,17 +7,22 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "instance")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
+    pub id: i64,
     pub instance_id: String,
     pub ip: String,
     pub mac_addr: String,
     pub namespace: String,
-    pub instance_group_id: u64,
+    pub instance_group_id: i64,
     pub info: String,
-    pub status: i8,
+    pub status: i16,
     pub sys_user: String,
     pub password: String,
-    pub ssh_port: u16,
+    pub ssh_port: i32,
+    pub auth_type: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub private_key: Option<String>,
+    pub key_passphrase: String,
+    pub credential_id: i64,
     pub created_time: DateTimeLocal,
     pub updated_time: DateTimeLocal,
 }
@@ -7,10 +7,10 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "instance_role")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
-    pub role_id: u64,
+    pub id: i64,
+    pub role_id: i64,
     pub instance_id: String,
-    pub instance_group_id: u64,
+    pub instance_group_id: i64,
     pub created_time: DateTimeLocal,
 }
 
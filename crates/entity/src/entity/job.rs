@@ -7,10 +7,10 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "job")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
+    pub id: i64,
     pub eid: String,
-    pub team_id: u64,
-    pub executor_id: u64,
+    pub team_id: i64,
+    pub executor_id: i64,
     pub job_type: String,
     #[sea_orm(unique)]
     pub name: String,
@@ -21,11 +21,15 @@ pub struct Model {
     pub upload_file: String,
     pub work_dir: String,
     pub work_user: String,
-    pub timeout: u64,
-    pub max_retry: u8,
-    pub max_parallel: u8,
+    pub timeout: i64,
+    pub max_retry: i16,
+    pub max_parallel: i16,
+    pub stop_signal: String,
+    pub stop_grace_period: i32,
     pub completed_callback: Option<Json>,
-    pub is_public: i8,
+    #[serde(default)]
+    pub publish_artifacts: Option<Json>,
+    pub is_public: i16,
     pub display_on_dashboard: bool,
     pub created_user: String,
     pub updated_user: String,
@@ -37,6 +41,12 @@ pub struct Model {
     pub deleted_at: Option<DateTimeLocal>,
     #[serde(default)]
     pub deleted_by: String,
+    /// template the job inherits from, 0 for none
+    #[serde(default)]
+    pub template_id: i64,
+    /// version of the template last applied to the job
+    #[serde(default)]
+    pub template_version: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
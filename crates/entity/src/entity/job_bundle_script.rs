@@ -7,10 +7,10 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "job_bundle_script")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
+    pub id: i64,
     pub eid: String,
-    pub executor_id: u64,
-    pub team_id: u64,
+    pub executor_id: i64,
+    pub team_id: i64,
     #[sea_orm(unique)]
     pub name: String,
     #[sea_orm(column_type = "Text")]
@@ -7,13 +7,13 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "job_organizer")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
+    pub id: i64,
     pub eid: String,
     pub name: String,
     pub nodes: Option<Json>,
     pub edges: Option<Json>,
     pub info: String,
-    pub is_public: i8,
+    pub is_public: i16,
     pub created_user: String,
     pub updated_user: String,
     pub created_time: DateTimeLocal,
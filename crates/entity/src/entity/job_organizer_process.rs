@@ -7,9 +7,9 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "job_organizer_process")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
+    pub id: i64,
     pub name: String,
-    pub organizer_id: u64,
+    pub organizer_id: i64,
     pub organizer_version: String,
     pub process_id: String,
     pub status: String,
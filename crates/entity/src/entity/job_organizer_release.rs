@@ -7,12 +7,12 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "job_organizer_release")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
-    pub organizer_id: u64,
+    pub id: i64,
+    pub organizer_id: i64,
     pub version: String,
     pub name: String,
     pub info: String,
-    pub is_public: i8,
+    pub is_public: i16,
     pub nodes: Option<Json>,
     pub edges: Option<Json>,
     pub created_user: String,
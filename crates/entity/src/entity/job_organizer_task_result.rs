@@ -7,11 +7,11 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "job_organizer_task_result")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
+    pub id: i64,
     pub process_id: String,
     pub node_id: String,
     pub bind_ip: String,
-    pub exit_code: i8,
+    pub exit_code: i16,
     pub exit_status: String,
     #[sea_orm(column_type = "Text")]
     pub output: String,
@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "job_running_status")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
+    pub id: i64,
     pub instance_id: String,
     pub schedule_type: String,
     pub job_type: String,
@@ -17,6 +17,9 @@ pub struct Model {
     pub run_status: String,
     pub exit_status: String,
     pub exit_code: i32,
+    pub restart_count: i32,
+    pub last_crash_reason: String,
+    pub probe_status: Option<Json>,
     pub dispatch_result: Option<Json>,
     pub start_time: Option<DateTimeLocal>,
     pub end_time: Option<DateTimeLocal>,
@@ -7,11 +7,15 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "job_supervisor")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
+    pub id: i64,
     #[sea_orm(unique)]
     pub name: String,
     pub eid: String,
-    pub restart_interval: u64,
+    pub restart_interval: i64,
+    pub restart_policy: Option<Json>,
+    pub health_check: Option<Json>,
+    pub stop_signal: String,
+    pub stop_grace_period: Option<i32>,
     pub info: String,
     pub created_user: String,
     pub updated_user: String,
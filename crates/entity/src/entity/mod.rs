@@ -3,6 +3,9 @@
 pub mod prelude;
 
 pub mod agent_release_version;
+pub mod artifact;
+pub mod blackout_window;
+pub mod calendar;
 pub mod casbin_rule;
 pub mod executor;
 pub mod instance;
@@ -21,11 +24,20 @@ pub mod job_organizer_task_result;
 pub mod job_running_status;
 pub mod job_schedule_history;
 pub mod job_supervisor;
+pub mod job_template;
 pub mod job_timer;
+pub mod resource_permission;
+pub mod retention_policy;
+pub mod retention_prune_log;
 pub mod role;
+pub mod script_module;
+pub mod script_module_version;
+pub mod ssh_credential;
 pub mod tag;
 pub mod tag_resource;
 pub mod team;
 pub mod team_member;
+pub mod terminal_session;
 pub mod user;
+pub mod user_mfa;
 pub mod user_server;
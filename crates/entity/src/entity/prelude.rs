@@ -1,6 +1,9 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
 pub use super::agent_release_version::Entity as AgentReleaseVersion;
+pub use super::artifact::Entity as Artifact;
+pub use super::blackout_window::Entity as BlackoutWindow;
+pub use super::calendar::Entity as Calendar;
 pub use super::casbin_rule::Entity as CasbinRule;
 pub use super::executor::Entity as Executor;
 pub use super::instance::Entity as Instance;
@@ -19,11 +22,20 @@ pub use super::job_organizer_task_result::Entity as JobOrganizerTaskResult;
 pub use super::job_running_status::Entity as JobRunningStatus;
 pub use super::job_schedule_history::Entity as JobScheduleHistory;
 pub use super::job_supervisor::Entity as JobSupervisor;
+pub use super::job_template::Entity as JobTemplate;
 pub use super::job_timer::Entity as JobTimer;
+pub use super::resource_permission::Entity as ResourcePermission;
+pub use super::retention_policy::Entity as RetentionPolicy;
+pub use super::retention_prune_log::Entity as RetentionPruneLog;
 pub use super::role::Entity as Role;
+pub use super::script_module::Entity as ScriptModule;
+pub use super::script_module_version::Entity as ScriptModuleVersion;
+pub use super::ssh_credential::Entity as SshCredential;
 pub use super::tag::Entity as Tag;
 pub use super::tag_resource::Entity as TagResource;
 pub use super::team::Entity as Team;
 pub use super::team_member::Entity as TeamMember;
+pub use super::terminal_session::Entity as TerminalSession;
 pub use super::user::Entity as User;
+pub use super::user_mfa::Entity as UserMfa;
 pub use super::user_server::Entity as UserServer;
@@ -7,10 +7,10 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "tag_resource")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
-    pub tag_id: u64,
+    pub id: i64,
+    pub tag_id: i64,
     pub resource_type: String,
-    pub resource_id: u64,
+    pub resource_id: i64,
     pub created_user: String,
     pub created_time: DateTimeLocal,
 }
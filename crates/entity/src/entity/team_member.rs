@@ -7,8 +7,8 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "team_member")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
-    pub team_id: u64,
+    pub id: i64,
+    pub team_id: i64,
     pub user_id: String,
     pub is_admin: bool,
     pub created_user: String,
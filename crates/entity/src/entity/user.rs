@@ -7,14 +7,14 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "user")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: u64,
+    pub id: i64,
     #[sea_orm(unique)]
     pub user_id: String,
     #[sea_orm(unique)]
     pub username: String,
     pub nickname: String,
     pub is_root: bool,
-    pub role_id: u64,
+    pub role_id: i64,
     pub salt: String,
     pub password: String,
     pub avatar: String,
@@ -22,6 +22,8 @@ pub struct Model {
     pub phone: String,
     pub gender: String,
     pub introduction: String,
+    pub auth_provider: String,
+    pub external_id: String,
     pub created_time: DateTimeLocal,
     pub updated_time: DateTimeLocal,
 }
@@ -1,9 +1,12 @@
 use std::{fs, path::Path};
 
 use anyhow::Result;
+use automate::bus::BusOptions;
 use config::{Config, File};
 use serde::{Deserialize, Serialize};
 
+use crate::logic::{artifact::ArtifactOptions, auth::AuthOptions};
+
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Encrypt {
     pub private_key: String,
@@ -15,6 +18,50 @@ pub struct Admin {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Terminal {
+    /// directory where web ssh sessions are recorded in asciicast v2 format
+    pub record_dir: String,
+}
+
+impl Default for Terminal {
+    fn default() -> Self {
+        Self {
+            record_dir: "~/.jiascheduler/recordings".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct Retention {
+    /// how often the history tables are pruned
+    pub interval_secs: u64,
+    /// rows deleted in one statement
+    pub batch_size: u64,
+    /// directory where expired rows are archived as zstd compressed json lines
+    pub archive_dir: String,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            interval_secs: 3600,
+            batch_size: 500,
+            archive_dir: "~/.jiascheduler/archive".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct Metrics {
+    /// serve Prometheus metrics on /metrics of the bind address
+    pub enable: bool,
+    /// bearer token scrapers have to send, no auth when empty
+    pub token: String,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Conf {
     /// if enable debug mode
@@ -27,6 +74,23 @@ pub struct Conf {
     pub comet_secret: String,
     pub database_url: String,
     pub admin: Admin,
+    /// event bus between comet and console, default is redis streams
+    #[serde(default)]
+    pub bus: BusOptions,
+    #[serde(default)]
+    pub terminal: Terminal,
+    /// where uploaded files and job outputs are stored
+    #[serde(default)]
+    pub artifact: ArtifactOptions,
+    /// login providers besides local passwords
+    #[serde(default)]
+    pub auth: AuthOptions,
+    /// pruning of the execution and schedule history
+    #[serde(default)]
+    pub retention: Retention,
+    /// Prometheus metrics, disabled by default
+    #[serde(default)]
+    pub metrics: Metrics,
     #[serde(skip)]
     config_file: String,
 }
@@ -1,4 +1,5 @@
 pub mod logic;
+pub mod metrics;
 pub mod state;
 use chrono::Local;
 pub use entity;
@@ -14,6 +15,7 @@ impl IdGenerator {
     const FLOW_JOB_PREFIX: &'static str = "f";
     const SCHEDULE_ID_PREFIX: &'static str = "s";
     const INSTANCE_PREFIX: &'static str = "i";
+    const TERMINAL_SESSION_PREFIX: &'static str = "w";
 
     pub fn get_job_eid() -> String {
         Self::get_id(Self::JOB_PREFIX)
@@ -38,6 +40,10 @@ impl IdGenerator {
         Self::get_id(Self::INSTANCE_PREFIX)
     }
 
+    pub fn get_terminal_session_uid() -> String {
+        Self::get_id(Self::TERMINAL_SESSION_PREFIX)
+    }
+
     fn get_id(prefix: &str) -> String {
         format!("{prefix}-{}", nanoid!(10)).into()
     }
@@ -0,0 +1,373 @@
+use std::{env, io::SeekFrom};
+
+use anyhow::{Result, anyhow};
+use automate::{artifact::comet_http_addr, bridge::msg::UpdateJobParams, transfer};
+use reqwest::StatusCode;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, QueryTrait, Set, SqlErr, TransactionTrait,
+};
+use tokio::{
+    fs,
+    io::{self, AsyncSeekExt},
+};
+
+use crate::entity::{artifact, instance, job, prelude::*};
+use crate::state::AppContext;
+
+mod s3;
+mod storage;
+
+pub use storage::{
+    ArtifactOptions, ArtifactReader, ArtifactStorage, S3Options, SharedStorage, StorageKind,
+    VerifiedReader,
+};
+
+/// Prefix of an artifact reference saved in `job.upload_file`, eg:
+/// "artifact://deploy.tar.gz@3", the latest version is used when it is omitted.
+pub const ARTIFACT_SCHEME: &str = "artifact://";
+pub const SOURCE_UPLOAD: &str = "upload";
+pub const SOURCE_JOB: &str = "job";
+
+/// Attempts to register a version when concurrent uploads of the same name
+/// take the same version number.
+const REGISTER_ATTEMPTS: usize = 3;
+
+pub struct NewArtifact {
+    pub name: String,
+    /// team the artifact belongs to, 0 for none
+    pub team_id: i64,
+    pub filename: String,
+    pub source: &'static str,
+    pub eid: String,
+    pub run_id: String,
+    pub created_user: String,
+}
+
+pub struct ArtifactLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+impl<'a> ArtifactLogic<'a> {
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    pub fn reference(model: &artifact::Model) -> String {
+        format!("{ARTIFACT_SCHEME}{}@{}", model.name, model.version)
+    }
+
+    /// Splits a reference into name and version, None if it is not a reference.
+    pub fn parse_reference(reference: &str) -> Option<(String, Option<i32>)> {
+        let v = reference.strip_prefix(ARTIFACT_SCHEME)?;
+        match v.rsplit_once('@') {
+            Some((name, version)) if version.parse::<i32>().is_ok() => {
+                Some((name.to_string(), version.parse().ok()))
+            }
+            _ => Some((v.to_string(), None)),
+        }
+    }
+
+    /// Stores the content of the file once and registers it as the next
+    /// version of its name.
+    pub async fn create_artifact(
+        &self,
+        new_artifact: NewArtifact,
+        mut file: fs::File,
+    ) -> Result<artifact::Model> {
+        let (sha256, size) = transfer::reader_sha256(&mut file).await?;
+        if !self.ctx.artifact_storage.exists(&sha256).await? {
+            file.seek(SeekFrom::Start(0)).await?;
+            self.ctx.artifact_storage.put_file(&sha256, file).await?;
+        }
+        self.register(new_artifact, sha256, size as i64).await
+    }
+
+    async fn register(
+        &self,
+        new_artifact: NewArtifact,
+        sha256: String,
+        size: i64,
+    ) -> Result<artifact::Model> {
+        if new_artifact.name.is_empty() {
+            anyhow::bail!("artifact name is required");
+        }
+        let mut attempt = 1;
+        loop {
+            let txn = self.ctx.db.begin().await?;
+            let latest = Artifact::find()
+                .filter(artifact::Column::Name.eq(&new_artifact.name))
+                .order_by_desc(artifact::Column::Version)
+                .lock_exclusive()
+                .one(&txn)
+                .await?;
+
+            let ret = artifact::ActiveModel {
+                name: Set(new_artifact.name.clone()),
+                version: Set(latest.map_or(1, |v| v.version + 1)),
+                team_id: Set(new_artifact.team_id),
+                sha256: Set(sha256.clone()),
+                size: Set(size),
+                filename: Set(new_artifact.filename.clone()),
+                source: Set(new_artifact.source.to_string()),
+                eid: Set(new_artifact.eid.clone()),
+                run_id: Set(new_artifact.run_id.clone()),
+                created_user: Set(new_artifact.created_user.clone()),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await;
+            match ret {
+                Ok(model) => {
+                    txn.commit().await?;
+                    return Ok(model);
+                }
+                // the version was taken by a concurrent upload, (name, version)
+                // is unique
+                Err(e)
+                    if attempt < REGISTER_ATTEMPTS
+                        && matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) =>
+                {
+                    txn.rollback().await?;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    pub async fn get_artifact(&self, id: i64) -> Result<Option<artifact::Model>> {
+        Ok(Artifact::find_by_id(id).one(&self.ctx.db).await?)
+    }
+
+    pub async fn resolve(&self, reference: &str) -> Result<artifact::Model> {
+        let (name, version) = Self::parse_reference(reference)
+            .ok_or(anyhow!("invalid artifact reference {reference}"))?;
+        Artifact::find()
+            .filter(artifact::Column::Name.eq(&name))
+            .apply_if(version, |query, v| {
+                query.filter(artifact::Column::Version.eq(v))
+            })
+            .order_by_desc(artifact::Column::Version)
+            .one(&self.ctx.db)
+            .await?
+            .ok_or(anyhow!("cannot found artifact {reference}"))
+    }
+
+    /// Opens the content for streaming, the read fails at the end of the
+    /// stream when it does not match the recorded checksum.
+    pub async fn open(&self, model: &artifact::Model) -> Result<VerifiedReader<ArtifactReader>> {
+        let reader = self.ctx.artifact_storage.open(&model.sha256).await?;
+        Ok(VerifiedReader::new(reader, model.sha256.clone()))
+    }
+
+    /// Artifacts of the team, of those without a team only the ones created
+    /// by `created_user` when it is given.
+    pub async fn query_artifact(
+        &self,
+        team_id: i64,
+        created_user: Option<String>,
+        name: Option<String>,
+        eid: Option<String>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<artifact::Model>, u64)> {
+        let model = Artifact::find()
+            .filter(artifact::Column::TeamId.eq(team_id))
+            .apply_if(created_user.filter(|_| team_id == 0), |query, v| {
+                query.filter(artifact::Column::CreatedUser.eq(v))
+            })
+            .apply_if(name, |query, v| {
+                query.filter(artifact::Column::Name.contains(v))
+            })
+            .apply_if(eid, |query, v| query.filter(artifact::Column::Eid.eq(v)));
+
+        let total = model.clone().count(&self.ctx.db).await?;
+
+        let list = model
+            .order_by_desc(artifact::Column::Id)
+            .paginate(&self.ctx.db, page_size)
+            .fetch_page(page)
+            .await?;
+        Ok((list, total))
+    }
+
+    /// Jobs whose upload file resolves to the version.
+    pub async fn referencing_jobs(&self, record: &artifact::Model) -> Result<Vec<String>> {
+        let latest = Artifact::find()
+            .filter(artifact::Column::Name.eq(&record.name))
+            .order_by_desc(artifact::Column::Version)
+            .one(&self.ctx.db)
+            .await?
+            .is_some_and(|v| v.id == record.id);
+        let mut references =
+            Condition::any().add(job::Column::UploadFile.eq(Self::reference(record)));
+        if latest {
+            references = references
+                .add(job::Column::UploadFile.eq(format!("{ARTIFACT_SCHEME}{}", record.name)));
+        }
+        Ok(Job::find()
+            .filter(job::Column::IsDeleted.eq(false))
+            .filter(references)
+            .all(&self.ctx.db)
+            .await?
+            .into_iter()
+            .map(|v| v.name)
+            .collect())
+    }
+
+    /// Deletes a version, refused while jobs upload it. The content is
+    /// removed once no version refers to it.
+    pub async fn delete_artifact(&self, record: &artifact::Model) -> Result<u64> {
+        let jobs = self.referencing_jobs(record).await?;
+        if !jobs.is_empty() {
+            anyhow::bail!(
+                "artifact {} is still uploaded by job {}",
+                Self::reference(record),
+                jobs.join(", ")
+            );
+        }
+        let ret = Artifact::delete_by_id(record.id).exec(&self.ctx.db).await?;
+
+        let shared = Artifact::find()
+            .select_only()
+            .column(artifact::Column::Id)
+            .filter(artifact::Column::Sha256.eq(&record.sha256))
+            .into_tuple::<i64>()
+            .one(&self.ctx.db)
+            .await?;
+        if shared.is_none() {
+            self.ctx.artifact_storage.delete(&record.sha256).await?;
+        }
+        Ok(ret.rows_affected)
+    }
+
+    /// Makes sure comet holds the artifact so agents can fetch it lazily.
+    pub async fn push_to_comet(
+        http_client: &reqwest::Client,
+        storage: &SharedStorage,
+        comet_addr: &str,
+        comet_secret: &str,
+        sha256: &str,
+    ) -> Result<()> {
+        let url = format!("{}/artifact/{sha256}", comet_http_addr(comet_addr));
+        let status = http_client.head(&url).send().await?.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        if status != StatusCode::NOT_FOUND {
+            anyhow::bail!("failed check artifact {sha256} on comet, status {status}");
+        }
+        // the upload resumes by offset, so the content is spooled to a
+        // temporary file and verified on the way
+        let transfer_id = transfer::new_transfer_id();
+        let tmp_dir = env::temp_dir().join("jiascheduler");
+        fs::create_dir_all(&tmp_dir).await?;
+        let tmp_path = tmp_dir.join(format!("{transfer_id}.artifact"));
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&tmp_path)
+            .await;
+        let _ = fs::remove_file(&tmp_path).await;
+        let mut file = file?;
+
+        let mut reader = VerifiedReader::new(storage.open(sha256).await?, sha256.to_string());
+        io::copy(&mut reader, &mut file).await?;
+        file.seek(SeekFrom::Start(0)).await?;
+        transfer::upload(
+            http_client,
+            comet_addr,
+            comet_secret,
+            &transfer_id,
+            &mut file,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Downloads an artifact held by comet in chunks to a temporary file, the
+    /// checksum is verified before the file is returned.
+    pub async fn download_from_comet(&self, comet_addr: &str, sha256: &str) -> Result<fs::File> {
+        let tmp_path = env::temp_dir()
+            .join("jiascheduler")
+            .join(format!("{}.artifact", transfer::new_transfer_id()));
+        transfer::download(
+            &self.ctx.http_client,
+            comet_addr,
+            &self.ctx.conf.comet_secret,
+            sha256,
+            &tmp_path,
+        )
+        .await?;
+        let file = fs::File::open(&tmp_path).await;
+        // the open handle keeps the content readable until it is stored
+        let _ = fs::remove_file(&tmp_path).await;
+        Ok(file?)
+    }
+
+    /// Pulls the outputs published by a finished run from comet and registers
+    /// them as `<eid>/<file name>`.
+    pub async fn collect_run_artifacts(&self, params: &UpdateJobParams) -> Result<()> {
+        let Some(artifacts) = params.artifacts.as_ref().filter(|v| !v.is_empty()) else {
+            return Ok(());
+        };
+        let ins = Instance::find()
+            .filter(instance::Column::InstanceId.eq(&params.instance_id))
+            .one(&self.ctx.db)
+            .await?
+            .ok_or(anyhow!("cannot found instance {}", params.instance_id))?;
+        let (_, pair) = automate::Logic::new(self.ctx.redis())
+            .get_link_pair(ins.ip, ins.mac_addr)
+            .await?;
+
+        // published artifacts belong to the team of the job
+        let team_id = Job::find()
+            .filter(job::Column::Eid.eq(&params.base_job.eid))
+            .one(&self.ctx.db)
+            .await?
+            .map_or(0, |v| v.team_id);
+
+        for v in artifacts {
+            if !self.ctx.artifact_storage.exists(&v.sha256).await? {
+                let file = self
+                    .download_from_comet(&pair.comet_addr, &v.sha256)
+                    .await?;
+                self.ctx.artifact_storage.put_file(&v.sha256, file).await?;
+            }
+
+            self.register(
+                NewArtifact {
+                    name: format!("{}/{}", params.base_job.eid, v.name),
+                    team_id,
+                    filename: v.name.clone(),
+                    source: SOURCE_JOB,
+                    eid: params.base_job.eid.clone(),
+                    run_id: params.run_id.clone(),
+                    created_user: params.created_user.clone(),
+                },
+                v.sha256.clone(),
+                v.size as i64,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse_reference() {
+    assert_eq!(
+        ArtifactLogic::parse_reference("artifact://deploy.tar.gz@3"),
+        Some(("deploy.tar.gz".to_string(), Some(3)))
+    );
+    assert_eq!(
+        ArtifactLogic::parse_reference("artifact://deploy@latest"),
+        Some(("deploy@latest".to_string(), None))
+    );
+    assert_eq!(
+        ArtifactLogic::parse_reference("/tmp/jiascheduler/a.sh"),
+        None
+    );
+}
@@ -0,0 +1,229 @@
+use std::{
+    io,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use crypto::{digest::Digest, sha2::Sha256};
+use nanoid::nanoid;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs,
+    io::{AsyncRead, AsyncReadExt, ReadBuf},
+};
+
+use super::s3::S3Storage;
+
+pub type SharedStorage = Arc<dyn ArtifactStorage>;
+pub type ArtifactReader = Box<dyn AsyncRead + Send + Unpin>;
+
+/// Blob store of artifact contents, keys are the sha256 of the content.
+#[async_trait]
+pub trait ArtifactStorage: Send + Sync {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()>;
+
+    /// Stores the rest of the file, the default implementation reads it into
+    /// memory first.
+    async fn put_file(&self, key: &str, mut file: fs::File) -> Result<()> {
+        let mut data = vec![];
+        file.read_to_end(&mut data).await?;
+        self.put(key, data).await
+    }
+
+    /// Opens the content for streaming, it is not verified against the key.
+    async fn open(&self, key: &str) -> Result<ArtifactReader>;
+
+    async fn exists(&self, key: &str) -> Result<bool>;
+
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    /// Directory on the console host
+    #[default]
+    Local,
+    /// Any S3 compatible object storage, eg: AWS S3, MinIO
+    S3,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct S3Options {
+    /// eg: "https://s3.us-east-1.amazonaws.com" or "http://127.0.0.1:9000"
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArtifactOptions {
+    #[serde(default)]
+    pub storage: StorageKind,
+    /// directory of the local storage
+    #[serde(default = "ArtifactOptions::default_local_dir")]
+    pub local_dir: String,
+    #[serde(default)]
+    pub s3: S3Options,
+}
+
+impl Default for ArtifactOptions {
+    fn default() -> Self {
+        Self {
+            storage: StorageKind::default(),
+            local_dir: Self::default_local_dir(),
+            s3: S3Options::default(),
+        }
+    }
+}
+
+impl ArtifactOptions {
+    fn default_local_dir() -> String {
+        "~/.jiascheduler/artifacts".to_string()
+    }
+
+    pub fn connect(&self) -> Result<SharedStorage> {
+        let storage: SharedStorage = match self.storage {
+            StorageKind::Local => Arc::new(LocalStorage::new(&self.local_dir)?),
+            StorageKind::S3 => Arc::new(S3Storage::new(self.s3.clone())?),
+        };
+        Ok(storage)
+    }
+}
+
+pub struct LocalStorage {
+    dir: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(dir: &str) -> Result<Self> {
+        let dir = shellexpand::full(dir)?.to_string();
+        Ok(Self {
+            dir: PathBuf::from(dir),
+        })
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        if !automate::artifact::is_sha256(key) {
+            anyhow::bail!("invalid artifact key {key}");
+        }
+        Ok(self.dir.join(&key[..2]).join(key))
+    }
+}
+
+#[async_trait]
+impl ArtifactStorage for LocalStorage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        let path = self.path(key)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        let tmp_path = path.with_extension(format!("{}.tmp", nanoid!(8)));
+        fs::write(&tmp_path, data).await?;
+        fs::rename(tmp_path, path).await?;
+        Ok(())
+    }
+
+    async fn put_file(&self, key: &str, mut file: fs::File) -> Result<()> {
+        let path = self.path(key)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        let tmp_path = path.with_extension(format!("{}.tmp", nanoid!(8)));
+        let mut tmp_file = fs::File::create(&tmp_path).await?;
+        tokio::io::copy(&mut file, &mut tmp_file).await?;
+        tmp_file.sync_all().await?;
+        fs::rename(tmp_path, path).await?;
+        Ok(())
+    }
+
+    async fn open(&self, key: &str) -> Result<ArtifactReader> {
+        Ok(Box::new(fs::File::open(self.path(key)?).await?))
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        Ok(fs::try_exists(self.path(key)?).await?)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let path = self.path(key)?;
+        if fs::try_exists(&path).await? {
+            fs::remove_file(path).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Hashes the content while it is read and fails the read at the end of the
+/// stream when it does not match the expected sha256.
+pub struct VerifiedReader<R> {
+    inner: R,
+    hasher: Sha256,
+    sha256: String,
+    verified: bool,
+}
+
+impl<R> VerifiedReader<R> {
+    pub fn new(inner: R, sha256: String) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            sha256,
+            verified: false,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for VerifiedReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+        match Pin::new(&mut this.inner).poll_read(cx, buf) {
+            Poll::Ready(Ok(())) => {}
+            v => return v,
+        }
+        let data = &buf.filled()[filled..];
+        if !data.is_empty() {
+            this.hasher.input(data);
+        } else if buf.remaining() > 0 && !this.verified {
+            this.verified = true;
+            let actual = this.hasher.result_str();
+            if actual != this.sha256 {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("checksum mismatch, expected {}, got {actual}", this.sha256),
+                )));
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn test_verified_reader() {
+    let data = b"hello artifact".to_vec();
+    let sha256 = automate::artifact::sha256_hex(&data);
+
+    let mut out = vec![];
+    VerifiedReader::new(data.as_slice(), sha256.clone())
+        .read_to_end(&mut out)
+        .await
+        .unwrap();
+    assert_eq!(out, data);
+
+    let err = VerifiedReader::new(&b"tampered"[..], sha256)
+        .read_to_end(&mut vec![])
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
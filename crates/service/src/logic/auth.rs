@@ -0,0 +1,359 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Result, anyhow};
+use nanoid::nanoid;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::entity::{prelude::*, team_member, user};
+use crate::state::AppContext;
+
+use super::{
+    mfa::MfaOptions,
+    security::{LockoutOptions, PasswordPolicy},
+    types::UserRecord,
+    user::UserLogic,
+};
+
+mod ldap;
+mod oidc;
+
+pub use ldap::LdapOptions;
+pub use oidc::{OidcOptions, OidcState};
+
+pub const PROVIDER_LOCAL: &str = "local";
+pub const PROVIDER_OIDC: &str = "oidc";
+pub const PROVIDER_LDAP: &str = "ldap";
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AuthOptions {
+    /// password login of console users, the root admin can always log in
+    #[serde(default = "default_true")]
+    pub local_login: bool,
+    /// whether admins may create local users
+    #[serde(default = "default_true")]
+    pub local_register: bool,
+    /// create users on their first sso login, otherwise only local accounts
+    /// prepared by an admin under the same username can log in and get linked
+    #[serde(default = "default_true")]
+    pub auto_provision: bool,
+    /// role of provisioned users whose groups match no mapping
+    #[serde(default)]
+    pub default_role_id: i64,
+    #[serde(default)]
+    pub group_mapping: Vec<GroupMapping>,
+    pub oidc: Option<OidcOptions>,
+    pub ldap: Option<LdapOptions>,
+    #[serde(default)]
+    pub mfa: MfaOptions,
+    #[serde(default)]
+    pub lockout: LockoutOptions,
+    #[serde(default)]
+    pub password_policy: PasswordPolicy,
+}
+
+impl Default for AuthOptions {
+    fn default() -> Self {
+        Self {
+            local_login: true,
+            local_register: true,
+            auto_provision: true,
+            default_role_id: 0,
+            group_mapping: vec![],
+            oidc: None,
+            ldap: None,
+            mfa: MfaOptions::default(),
+            lockout: LockoutOptions::default(),
+            password_policy: PasswordPolicy::default(),
+        }
+    }
+}
+
+/// Maps a group of the identity provider to a role and teams, the first
+/// mapping with a role wins. Teams listed in any mapping are kept in sync
+/// with the user's groups on every login, other teams are left alone.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct GroupMapping {
+    /// group name, for ldap either the group dn or its cn
+    pub group: String,
+    pub role_id: Option<i64>,
+    #[serde(default)]
+    pub team_ids: Vec<i64>,
+}
+
+/// An identity asserted by an external provider.
+#[derive(Debug, Clone, Default)]
+pub struct ExternalUser {
+    pub provider: &'static str,
+    /// oidc subject or ldap dn
+    pub external_id: String,
+    pub username: String,
+    pub nickname: String,
+    pub email: String,
+    pub groups: Vec<String>,
+}
+
+/// Resolves the mapped role and the teams to join and leave.
+fn map_groups(
+    mappings: &[GroupMapping],
+    groups: &[String],
+) -> (Option<i64>, BTreeSet<i64>, BTreeSet<i64>) {
+    let mut role_id = None;
+    let mut joined = BTreeSet::new();
+    let mut managed = BTreeSet::new();
+    for mapping in mappings {
+        managed.extend(mapping.team_ids.iter().copied());
+        if !groups
+            .iter()
+            .any(|v| v.eq_ignore_ascii_case(&mapping.group))
+        {
+            continue;
+        }
+        role_id = role_id.or(mapping.role_id);
+        joined.extend(mapping.team_ids.iter().copied());
+    }
+    let left = managed.difference(&joined).copied().collect();
+    (role_id, joined, left)
+}
+
+pub struct AuthLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+impl<'a> AuthLogic<'a> {
+    pub const OIDC_SESS_KEY: &'static str = "OIDC_STATE";
+
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    pub fn options(&self) -> &AuthOptions {
+        &self.ctx.conf.auth
+    }
+
+    pub fn can_login_locally(&self, user: &UserRecord) -> bool {
+        user.is_root || (self.options().local_login && user.auth_provider == PROVIDER_LOCAL)
+    }
+
+    /// A client of its own, the shared one carries the comet secret.
+    fn http_client() -> Result<reqwest::Client> {
+        Ok(reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()?)
+    }
+
+    pub async fn ldap_login(&self, username: &str, password: &str) -> Result<UserRecord> {
+        let opts = self
+            .options()
+            .ldap
+            .as_ref()
+            .ok_or(anyhow!("ldap login is not enabled"))?;
+        let external = ldap::authenticate(opts, username, password).await?;
+        self.provision(external).await
+    }
+
+    /// Url of the identity provider the browser is sent to, the returned
+    /// state must be kept in the session until the callback.
+    pub async fn oidc_authorize_url(&self) -> Result<(String, OidcState)> {
+        let opts = self
+            .options()
+            .oidc
+            .as_ref()
+            .ok_or(anyhow!("oidc login is not enabled"))?;
+        let discovery = oidc::discover(&Self::http_client()?, opts).await?;
+        let state = OidcState {
+            state: nanoid!(32),
+            nonce: nanoid!(32),
+        };
+        let url = oidc::authorize_url(&discovery, opts, &state)?;
+        Ok((url, state))
+    }
+
+    pub async fn oidc_login(
+        &self,
+        code: &str,
+        state: &str,
+        expected: &OidcState,
+    ) -> Result<UserRecord> {
+        let opts = self
+            .options()
+            .oidc
+            .as_ref()
+            .ok_or(anyhow!("oidc login is not enabled"))?;
+        if state != expected.state {
+            anyhow::bail!("invalid oidc state");
+        }
+        let client = Self::http_client()?;
+        let discovery = oidc::discover(&client, opts).await?;
+        let external = oidc::exchange(&client, &discovery, opts, code, expected).await?;
+        self.provision(external).await
+    }
+
+    /// Just in time provisioning, also syncs role and teams from the groups.
+    pub async fn provision(&self, external: ExternalUser) -> Result<UserRecord> {
+        if external.username.is_empty() {
+            anyhow::bail!("identity provider returned no username");
+        }
+        let (role_id, joined, left) = map_groups(&self.options().group_mapping, &external.groups);
+
+        let linked = User::find()
+            .filter(user::Column::AuthProvider.eq(external.provider))
+            .filter(user::Column::ExternalId.eq(&external.external_id))
+            .one(&self.ctx.db)
+            .await?;
+
+        let record = match linked {
+            Some(v) => {
+                let mut model: user::ActiveModel = v.clone().into();
+                if let Some(role_id) = role_id.filter(|&r| r != v.role_id) {
+                    model.role_id = Set(role_id);
+                }
+                if !external.email.is_empty() && external.email != v.email {
+                    model.email = Set(external.email.clone());
+                }
+                if model.is_changed() {
+                    model.update(&self.ctx.db).await?
+                } else {
+                    v
+                }
+            }
+            None => {
+                let taken = User::find()
+                    .filter(user::Column::Username.eq(&external.username))
+                    .one(&self.ctx.db)
+                    .await?;
+                match taken {
+                    // accounts prepared by an admin are linked on first login
+                    Some(v)
+                        if !self.options().auto_provision
+                            && !v.is_root
+                            && v.auth_provider == PROVIDER_LOCAL =>
+                    {
+                        let mut model: user::ActiveModel = v.clone().into();
+                        model.auth_provider = Set(external.provider.to_string());
+                        model.external_id = Set(external.external_id.clone());
+                        if let Some(role_id) = role_id {
+                            model.role_id = Set(role_id);
+                        }
+                        model.update(&self.ctx.db).await?
+                    }
+                    Some(_) => anyhow::bail!(
+                        "username {} is already taken by another account",
+                        external.username
+                    ),
+                    None if !self.options().auto_provision => {
+                        anyhow::bail!("user {} is not registered", external.username)
+                    }
+                    None => self.create_external_user(&external, role_id).await?,
+                }
+            }
+        };
+
+        self.ctx
+            .set_role_for_user(&record.user_id, &record.role_id.to_string())
+            .await?;
+        self.sync_teams(&record, joined, left).await?;
+
+        UserLogic::new(self.ctx)
+            .get_user(None, Some(&record.user_id))
+            .await?
+            .ok_or(anyhow!("cannot found user {}", record.username))
+    }
+
+    async fn create_external_user(
+        &self,
+        external: &ExternalUser,
+        role_id: Option<i64>,
+    ) -> Result<user::Model> {
+        info!("provision {} user {}", external.provider, external.username);
+        let salt = nanoid!();
+        let record = user::ActiveModel {
+            user_id: Set(nanoid!(10)),
+            username: Set(external.username.clone()),
+            nickname: Set(if external.nickname.is_empty() {
+                external.username.clone()
+            } else {
+                external.nickname.clone()
+            }),
+            // never matches, sso users cannot log in with a password
+            password: Set(UserLogic::encry_password(nanoid!(32), salt.clone())),
+            salt: Set(salt),
+            email: Set(external.email.clone()),
+            role_id: Set(role_id.unwrap_or(self.options().default_role_id)),
+            auth_provider: Set(external.provider.to_string()),
+            external_id: Set(external.external_id.clone()),
+            ..Default::default()
+        }
+        .insert(&self.ctx.db)
+        .await?;
+        Ok(record)
+    }
+
+    async fn sync_teams(
+        &self,
+        record: &user::Model,
+        joined: BTreeSet<i64>,
+        left: BTreeSet<i64>,
+    ) -> Result<()> {
+        let txn = self.ctx.db.begin().await?;
+        if !left.is_empty() {
+            TeamMember::delete_many()
+                .filter(team_member::Column::UserId.eq(&record.user_id))
+                .filter(team_member::Column::TeamId.is_in(left))
+                .exec(&txn)
+                .await?;
+        }
+        for team_id in joined {
+            let exists = TeamMember::find()
+                .filter(team_member::Column::UserId.eq(&record.user_id))
+                .filter(team_member::Column::TeamId.eq(team_id))
+                .one(&txn)
+                .await?
+                .is_some();
+            if exists {
+                continue;
+            }
+            team_member::ActiveModel {
+                team_id: Set(team_id),
+                user_id: Set(record.user_id.clone()),
+                is_admin: Set(false),
+                created_user: Set(record.username.clone()),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_map_groups() {
+    let mappings = vec![
+        GroupMapping {
+            group: "ops".to_string(),
+            role_id: Some(2),
+            team_ids: vec![1],
+        },
+        GroupMapping {
+            group: "dev".to_string(),
+            role_id: Some(3),
+            team_ids: vec![2, 3],
+        },
+    ];
+
+    let (role_id, joined, left) = map_groups(&mappings, &["DEV".to_string()]);
+    assert_eq!(role_id, Some(3));
+    assert_eq!(joined.into_iter().collect::<Vec<_>>(), vec![2, 3]);
+    assert_eq!(left.into_iter().collect::<Vec<_>>(), vec![1]);
+
+    let (role_id, joined, _) = map_groups(&mappings, &["ops".to_string(), "dev".to_string()]);
+    assert_eq!(role_id, Some(2));
+    assert_eq!(joined.len(), 3);
+}
@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use ldap3::{LdapConnAsync, LdapConnSettings, Scope, SearchEntry, ldap_escape};
+use serde::{Deserialize, Serialize};
+
+use super::{ExternalUser, PROVIDER_LDAP};
+
+fn default_user_filter() -> String {
+    "(uid={username})".to_string()
+}
+
+fn default_username_attr() -> String {
+    "uid".to_string()
+}
+
+fn default_name_attr() -> String {
+    "cn".to_string()
+}
+
+fn default_email_attr() -> String {
+    "mail".to_string()
+}
+
+fn default_group_attr() -> String {
+    "memberOf".to_string()
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct LdapOptions {
+    /// eg: ldaps://ldap.example.com:636
+    pub url: String,
+    #[serde(default)]
+    pub starttls: bool,
+    /// account used to look up users, anonymous when empty
+    #[serde(default)]
+    pub bind_dn: String,
+    #[serde(default)]
+    pub bind_password: String,
+    pub base_dn: String,
+    /// `{username}` is replaced by the escaped login name
+    #[serde(default = "default_user_filter")]
+    pub user_filter: String,
+    #[serde(default = "default_username_attr")]
+    pub username_attr: String,
+    #[serde(default = "default_name_attr")]
+    pub name_attr: String,
+    #[serde(default = "default_email_attr")]
+    pub email_attr: String,
+    /// attribute listing the dn of the user's groups
+    #[serde(default = "default_group_attr")]
+    pub group_attr: String,
+}
+
+/// The dn of a group and the value of its first rdn, so mappings may use
+/// either "cn=ops,ou=groups,dc=example,dc=com" or "ops".
+fn group_names(dn: &str) -> Vec<String> {
+    let mut names = vec![dn.to_string()];
+    if let Some((_, v)) = dn.split(',').next().and_then(|v| v.split_once('=')) {
+        names.push(v.trim().to_string());
+    }
+    names
+}
+
+/// Looks the user up and binds as the user to check the password.
+pub(super) async fn authenticate(
+    opts: &LdapOptions,
+    username: &str,
+    password: &str,
+) -> Result<ExternalUser> {
+    // an empty password is an unauthenticated bind, which always succeeds
+    if username.is_empty() || password.is_empty() {
+        anyhow::bail!("invalid username or password");
+    }
+
+    let settings = LdapConnSettings::new()
+        .set_conn_timeout(Duration::from_secs(5))
+        .set_starttls(opts.starttls);
+    let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &opts.url).await?;
+    ldap3::drive!(conn);
+
+    if !opts.bind_dn.is_empty() {
+        ldap.simple_bind(&opts.bind_dn, &opts.bind_password)
+            .await?
+            .success()
+            .map_err(|e| anyhow!("failed bind ldap service account - {e}"))?;
+    }
+
+    let filter = opts
+        .user_filter
+        .replace("{username}", ldap_escape(username).as_ref());
+    let (mut entries, _) = ldap
+        .search(
+            &opts.base_dn,
+            Scope::Subtree,
+            &filter,
+            vec![
+                opts.username_attr.as_str(),
+                opts.name_attr.as_str(),
+                opts.email_attr.as_str(),
+                opts.group_attr.as_str(),
+            ],
+        )
+        .await?
+        .success()?;
+    if entries.len() != 1 {
+        anyhow::bail!("invalid username or password");
+    }
+    let entry = SearchEntry::construct(entries.remove(0));
+
+    ldap.simple_bind(&entry.dn, password)
+        .await?
+        .success()
+        .map_err(|_| anyhow!("invalid username or password"))?;
+    let _ = ldap.unbind().await;
+
+    let first = |attr: &str| {
+        entry
+            .attrs
+            .get(attr)
+            .and_then(|v| v.first())
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    Ok(ExternalUser {
+        provider: PROVIDER_LDAP,
+        external_id: entry.dn.clone(),
+        username: Some(first(&opts.username_attr))
+            .filter(|v| !v.is_empty())
+            .unwrap_or(username.to_string()),
+        nickname: first(&opts.name_attr),
+        email: first(&opts.email_attr),
+        groups: entry
+            .attrs
+            .get(&opts.group_attr)
+            .map(|v| v.iter().flat_map(|v| group_names(v)).collect())
+            .unwrap_or_default(),
+    })
+}
+
+#[test]
+fn test_group_names() {
+    assert_eq!(
+        group_names("cn=ops,ou=groups,dc=example,dc=com"),
+        vec!["cn=ops,ou=groups,dc=example,dc=com", "ops"]
+    );
+}
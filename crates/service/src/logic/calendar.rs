@@ -0,0 +1,410 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{Result, anyhow};
+use automate::{
+    BlackoutWindow, CalendarMode, TimerCalendar,
+    scheduler::types::{ScheduleStatus, ScheduleType},
+};
+use chrono::{Datelike, NaiveDate};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, QueryTrait,
+};
+use serde_json::Value;
+
+use crate::{
+    entity::{
+        blackout_window, calendar, instance, job_running_status, job_schedule_history, job_timer,
+        prelude::*,
+    },
+    state::AppContext,
+};
+
+/// Occurrences of a yearly recurring event without UNTIL or COUNT.
+const DEFAULT_YEARLY_OCCURRENCES: i32 = 10;
+/// Most occurrences of a yearly recurring event, whatever its COUNT.
+const MAX_YEARLY_OCCURRENCES: i32 = 100;
+/// Longest event accepted from an imported calendar.
+const MAX_EVENT_DAYS: i64 = 366;
+
+/// Named calendars that timers are bound to, and blackout windows that keep
+/// anything from running on the instances of a namespace or group.
+pub struct CalendarLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+impl<'a> CalendarLogic<'a> {
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    pub async fn save_calendar(
+        &self,
+        model: calendar::ActiveModel,
+    ) -> Result<calendar::ActiveModel> {
+        Ok(model.save(&self.ctx.db).await?)
+    }
+
+    pub async fn query_calendar(
+        &self,
+        name: Option<String>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<calendar::Model>, u64)> {
+        let model = Calendar::find().apply_if(name, |query, v| {
+            query.filter(calendar::Column::Name.contains(v))
+        });
+        let total = model.clone().count(&self.ctx.db).await?;
+        let list = model
+            .order_by_desc(calendar::Column::UpdatedTime)
+            .paginate(&self.ctx.db, page_size)
+            .fetch_page(page)
+            .await?;
+        Ok((list, total))
+    }
+
+    /// Deletes the calendar, refused while timers are still bound to it or
+    /// running schedules were started with it.
+    pub async fn delete_calendar(&self, id: i64) -> Result<u64> {
+        let record = Calendar::find_by_id(id)
+            .one(&self.ctx.db)
+            .await?
+            .ok_or(anyhow!("cannot found calendar {id}"))?;
+        let timers: Vec<String> = JobTimer::find()
+            .filter(job_timer::Column::IsDeleted.eq(false))
+            .all(&self.ctx.db)
+            .await?
+            .into_iter()
+            .filter(|v| {
+                v.timer_expr
+                    .as_ref()
+                    .and_then(|v| v.get("calendar_ids"))
+                    .and_then(Value::as_array)
+                    .is_some_and(|ids| ids.iter().any(|v| v.as_i64() == Some(id)))
+            })
+            .map(|v| v.name)
+            .collect();
+        if !timers.is_empty() {
+            anyhow::bail!("calendar is still used by timer {}", timers.join(", "));
+        }
+
+        // running timers hold the calendars they were dispatched with by name
+        let schedule_ids: Vec<String> = JobRunningStatus::find()
+            .select_only()
+            .column(job_running_status::Column::ScheduleId)
+            .filter(job_running_status::Column::ScheduleType.eq(ScheduleType::Timer.to_string()))
+            .filter(
+                job_running_status::Column::ScheduleStatus
+                    .eq(ScheduleStatus::Scheduling.to_string()),
+            )
+            .filter(job_running_status::Column::IsDeleted.eq(false))
+            .into_tuple()
+            .all(&self.ctx.db)
+            .await?;
+        let mut schedules: Vec<String> = JobScheduleHistory::find()
+            .filter(job_schedule_history::Column::ScheduleId.is_in(schedule_ids))
+            .all(&self.ctx.db)
+            .await?
+            .into_iter()
+            .filter(|v| {
+                v.dispatch_data
+                    .as_ref()
+                    .and_then(|v| v.pointer("/params/calendars"))
+                    .and_then(Value::as_array)
+                    .is_some_and(|calendars| {
+                        calendars
+                            .iter()
+                            .any(|v| v.get("name").and_then(Value::as_str) == Some(&record.name))
+                    })
+            })
+            .map(|v| v.name)
+            .collect();
+        schedules.sort();
+        schedules.dedup();
+        if !schedules.is_empty() {
+            anyhow::bail!(
+                "calendar is still used by running schedule {}",
+                schedules.join(", ")
+            );
+        }
+        let ret = Calendar::delete_by_id(id).exec(&self.ctx.db).await?;
+        Ok(ret.rows_affected)
+    }
+
+    /// Calendars by id in the form sent to agents, unknown ids are left out.
+    pub async fn get_calendar_map(&self, ids: &[i64]) -> Result<HashMap<i64, TimerCalendar>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let records = Calendar::find()
+            .filter(calendar::Column::Id.is_in(ids.to_vec()))
+            .all(&self.ctx.db)
+            .await?;
+        records
+            .into_iter()
+            .map(|v| Ok((v.id, to_timer_calendar(v)?)))
+            .collect()
+    }
+
+    /// The calendars a timer is bound to, fails when one was deleted.
+    pub async fn get_timer_calendars(&self, ids: &[i64]) -> Result<Vec<TimerCalendar>> {
+        let calendars = self.get_calendar_map(ids).await?;
+        ids.iter()
+            .map(|id| {
+                calendars
+                    .get(id)
+                    .cloned()
+                    .ok_or(anyhow!("cannot found calendar {id}"))
+            })
+            .collect()
+    }
+
+    pub async fn save_blackout_window(
+        &self,
+        model: blackout_window::ActiveModel,
+    ) -> Result<blackout_window::ActiveModel> {
+        Ok(model.save(&self.ctx.db).await?)
+    }
+
+    pub async fn query_blackout_window(
+        &self,
+        name: Option<String>,
+        namespace: Option<String>,
+        instance_group_id: Option<i64>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<blackout_window::Model>, u64)> {
+        let model = blackout_window::Entity::find()
+            .apply_if(name, |query, v| {
+                query.filter(blackout_window::Column::Name.contains(v))
+            })
+            .apply_if(namespace, |query, v| {
+                query.filter(blackout_window::Column::Namespace.eq(v))
+            })
+            .apply_if(instance_group_id, |query, v| {
+                query.filter(blackout_window::Column::InstanceGroupId.eq(v))
+            });
+        let total = model.clone().count(&self.ctx.db).await?;
+        let list = model
+            .order_by_desc(blackout_window::Column::UpdatedTime)
+            .paginate(&self.ctx.db, page_size)
+            .fetch_page(page)
+            .await?;
+        Ok((list, total))
+    }
+
+    pub async fn delete_blackout_window(&self, id: i64) -> Result<u64> {
+        let ret = blackout_window::Entity::delete_by_id(id)
+            .exec(&self.ctx.db)
+            .await?;
+        Ok(ret.rows_affected)
+    }
+
+    /// Enabled blackout windows by instance id, from the namespace and the
+    /// group of every instance.
+    pub async fn get_instance_blackouts(
+        &self,
+        instances: &[instance::Model],
+    ) -> Result<HashMap<String, Vec<BlackoutWindow>>> {
+        let namespaces: BTreeSet<String> = instances
+            .iter()
+            .map(|v| v.namespace.clone())
+            .filter(|v| !v.is_empty())
+            .collect();
+        let group_ids: BTreeSet<i64> = instances
+            .iter()
+            .map(|v| v.instance_group_id)
+            .filter(|&v| v > 0)
+            .collect();
+
+        let records = blackout_window::Entity::find()
+            .filter(blackout_window::Column::Enabled.eq(true))
+            .filter(
+                Condition::any()
+                    .add(blackout_window::Column::Namespace.is_in(namespaces))
+                    .add(blackout_window::Column::InstanceGroupId.is_in(group_ids)),
+            )
+            .all(&self.ctx.db)
+            .await?;
+
+        Ok(instances
+            .iter()
+            .map(|instance| {
+                let windows = records
+                    .iter()
+                    .filter(|v| {
+                        (!v.namespace.is_empty() && v.namespace == instance.namespace)
+                            || (v.instance_group_id > 0
+                                && v.instance_group_id == instance.instance_group_id)
+                    })
+                    .map(to_blackout_window)
+                    .collect();
+                (instance.instance_id.clone(), windows)
+            })
+            .collect())
+    }
+}
+
+fn to_timer_calendar(record: calendar::Model) -> Result<TimerCalendar> {
+    Ok(TimerCalendar {
+        mode: serde_json::from_value::<CalendarMode>(Value::String(record.mode))?,
+        dates: record
+            .dates
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default(),
+        name: record.name,
+    })
+}
+
+pub fn to_blackout_window(record: &blackout_window::Model) -> BlackoutWindow {
+    BlackoutWindow {
+        name: record.name.clone(),
+        start_expr: record.start_expr.clone(),
+        duration_secs: record.duration_secs.max(0) as u64,
+        timezone: Some(record.timezone.clone()).filter(|v| !v.is_empty()),
+    }
+}
+
+fn parse_ics_date(value: &str) -> Result<NaiveDate> {
+    value
+        .get(..8)
+        .and_then(|v| NaiveDate::parse_from_str(v, "%Y%m%d").ok())
+        .ok_or(anyhow!("invalid icalendar date {value}"))
+}
+
+/// Dates covered by the events of an iCalendar file. All-day events end the
+/// day before DTEND. Yearly recurring events, as holiday calendars use them,
+/// are expanded until their UNTIL or COUNT, otherwise for ten years, and for
+/// a hundred years at most, other recurrences are not supported.
+pub fn parse_ics(content: &str) -> Result<BTreeSet<NaiveDate>> {
+    // continuation lines start with a space or a tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.trim_end().to_string()),
+        }
+    }
+
+    let mut dates = BTreeSet::new();
+    let mut event: Option<HashMap<String, String>> = None;
+    for line in lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = key.split(';').next().unwrap_or_default().to_uppercase();
+        match (name.as_str(), value.to_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") => event = Some(HashMap::new()),
+            ("END", "VEVENT") => {
+                if let Some(props) = event.take() {
+                    dates.extend(event_dates(&props)?);
+                }
+            }
+            _ => {
+                if let Some(ref mut props) = event {
+                    props.insert(name, value.to_string());
+                }
+            }
+        }
+    }
+    if dates.is_empty() {
+        anyhow::bail!("no events found in the calendar file");
+    }
+    Ok(dates)
+}
+
+fn event_dates(props: &HashMap<String, String>) -> Result<Vec<NaiveDate>> {
+    let dtstart = props
+        .get("DTSTART")
+        .ok_or(anyhow!("calendar event without DTSTART"))?;
+    let start = parse_ics_date(dtstart)?;
+    let end = match props.get("DTEND") {
+        // a date end is exclusive, so is a timed end at midnight
+        Some(v) if v.len() == 8 || v.get(8..15) == Some("T000000") => {
+            parse_ics_date(v)?.pred_opt().unwrap_or(start)
+        }
+        Some(v) => parse_ics_date(v)?,
+        None => start,
+    }
+    .max(start);
+    let days = (end - start).num_days();
+    if days >= MAX_EVENT_DAYS {
+        anyhow::bail!("calendar event from {start} to {end} is too long");
+    }
+
+    let mut starts = vec![start];
+    if let Some(rrule) = props.get("RRULE") {
+        let rule: HashMap<&str, &str> =
+            rrule.split(';').filter_map(|v| v.split_once('=')).collect();
+        if !rule
+            .get("FREQ")
+            .is_some_and(|v| v.eq_ignore_ascii_case("YEARLY"))
+        {
+            anyhow::bail!("unsupported recurrence {rrule}");
+        }
+        let count = rule
+            .get("COUNT")
+            .and_then(|v| v.parse::<i32>().ok())
+            .unwrap_or(DEFAULT_YEARLY_OCCURRENCES)
+            .min(MAX_YEARLY_OCCURRENCES);
+        let until = rule.get("UNTIL").map(|v| parse_ics_date(v)).transpose()?;
+        starts = (0..count)
+            .filter_map(|n| start.with_year(start.year() + n))
+            .take_while(|v| until.is_none_or(|until| *v <= until))
+            .collect();
+    }
+
+    Ok(starts
+        .into_iter()
+        .flat_map(|v| {
+            (0..=days).filter_map(move |n| v.checked_add_days(chrono::Days::new(n as u64)))
+        })
+        .collect())
+}
+
+#[test]
+fn test_parse_ics() {
+    let content = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        BEGIN:VEVENT\r\n\
+        DTSTART;VALUE=DATE:20241225\r\n\
+        DTEND;VALUE=DATE:20241227\r\n\
+        SUMMARY:Christmas\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        DTSTART;VALUE=DATE:20250101\r\n\
+        RRULE:FREQ=YEARLY;\r\n COUNT=2\r\n\
+        SUMMARY:New year\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        DTSTART:20250301T220000Z\r\n\
+        DTEND:20250302T020000Z\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+    let date = |v: &str| NaiveDate::parse_from_str(v, "%Y-%m-%d").unwrap();
+
+    let dates = parse_ics(content).unwrap();
+    assert_eq!(
+        dates.into_iter().collect::<Vec<_>>(),
+        vec![
+            date("2024-12-25"),
+            date("2024-12-26"),
+            date("2025-01-01"),
+            date("2025-03-01"),
+            date("2025-03-02"),
+            date("2026-01-01"),
+        ]
+    );
+
+    assert!(parse_ics("BEGIN:VCALENDAR\nEND:VCALENDAR\n").is_err());
+    assert!(parse_ics("BEGIN:VEVENT\nDTSTART:20250101\nRRULE:FREQ=WEEKLY\nEND:VEVENT\n").is_err());
+    assert_eq!(
+        parse_ics(
+            "BEGIN:VEVENT\nDTSTART:20250101\nRRULE:FREQ=YEARLY;COUNT=2000000000\nEND:VEVENT\n"
+        )
+        .unwrap()
+        .len(),
+        MAX_YEARLY_OCCURRENCES as usize
+    );
+}
@@ -0,0 +1,175 @@
+use anyhow::{Context, Result, anyhow};
+use automate::ssh::SshAuth;
+use russh_keys::decode_secret_key;
+use sea_orm::ActiveValue::{self, NotSet, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QueryTrait,
+};
+
+use crate::entity::{instance, prelude::*, ssh_credential};
+use crate::state::AppContext;
+
+use super::types;
+
+pub const SSH_AUTH_PASSWORD: &str = "password";
+pub const SSH_AUTH_PRIVATE_KEY: &str = "private_key";
+
+/// Encrypted auth_type, private_key and key_passphrase columns.
+type EncryptedAuth = (
+    ActiveValue<String>,
+    ActiveValue<Option<String>>,
+    ActiveValue<String>,
+);
+
+/// Decrypted ssh login of an instance.
+pub struct SshLogin {
+    pub user: String,
+    pub auth: SshAuth,
+}
+
+pub struct CredentialLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+impl<'a> CredentialLogic<'a> {
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    pub async fn save_credential(
+        &self,
+        model: ssh_credential::ActiveModel,
+    ) -> Result<ssh_credential::ActiveModel> {
+        let model = model.save(&self.ctx.db).await?;
+        Ok(model)
+    }
+
+    pub async fn query_credential(
+        &self,
+        name: Option<String>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<ssh_credential::Model>, u64)> {
+        let model = SshCredential::find().apply_if(name, |query, v| {
+            query.filter(ssh_credential::Column::Name.contains(v))
+        });
+
+        let total = model.clone().count(&self.ctx.db).await?;
+
+        let list = model
+            .order_by_desc(ssh_credential::Column::UpdatedTime)
+            .paginate(&self.ctx.db, page_size)
+            .fetch_page(page)
+            .await?;
+        Ok((list, total))
+    }
+
+    pub async fn delete_credential(&self, id: i64) -> Result<u64> {
+        let record = Instance::find()
+            .filter(instance::Column::CredentialId.eq(id))
+            .one(&self.ctx.db)
+            .await?;
+        if record.is_some() {
+            anyhow::bail!("cannot delete in used credential {id}")
+        }
+        let ret = SshCredential::delete_by_id(id).exec(&self.ctx.db).await?;
+        Ok(ret.rows_affected)
+    }
+
+    /// Resolves the login of an instance, a shared credential takes precedence
+    /// over the credential stored on the instance itself.
+    pub async fn get_ssh_login(&self, server: &types::UserServer) -> Result<SshLogin> {
+        let credential_id = server.credential_id.unwrap_or_default();
+        let (user, auth) = if credential_id > 0 {
+            let credential = SshCredential::find_by_id(credential_id)
+                .one(&self.ctx.db)
+                .await?
+                .ok_or(anyhow!("not found ssh credential {credential_id}"))?;
+            let user = Some(credential.sys_user)
+                .filter(|v| !v.is_empty())
+                .or(server.sys_user.clone());
+            let auth = self.decrypt_auth(
+                &credential.auth_type,
+                credential.password,
+                credential.private_key,
+                credential.key_passphrase,
+            )?;
+            (user, auth)
+        } else {
+            let auth = self.decrypt_auth(
+                server.auth_type.as_deref().unwrap_or(SSH_AUTH_PASSWORD),
+                server.password.clone().unwrap_or_default(),
+                server.private_key.clone(),
+                server.key_passphrase.clone().unwrap_or_default(),
+            )?;
+            (server.sys_user.clone(), auth)
+        };
+
+        let user = user
+            .filter(|v| !v.is_empty())
+            .ok_or(anyhow!("no system user"))?;
+
+        Ok(SshLogin { user, auth })
+    }
+
+    /// Validates and encrypts submitted auth fields, empty fields keep the stored value.
+    pub fn encrypt_auth(
+        &self,
+        auth_type: Option<String>,
+        private_key: Option<String>,
+        passphrase: Option<String>,
+    ) -> Result<EncryptedAuth> {
+        let auth_type = auth_type.filter(|v| !v.is_empty());
+        if let Some(v) = auth_type
+            .as_deref()
+            .filter(|&v| v != SSH_AUTH_PASSWORD && v != SSH_AUTH_PRIVATE_KEY)
+        {
+            anyhow::bail!("invalid auth type {v}");
+        }
+
+        let private_key = private_key.filter(|v| !v.trim().is_empty());
+        let passphrase = passphrase.filter(|v| !v.is_empty());
+        if let Some(ref key) = private_key {
+            decode_secret_key(key, passphrase.as_deref()).context("invalid private key")?;
+        }
+
+        Ok((
+            auth_type.map_or(NotSet, Set),
+            private_key
+                .map(|v| self.ctx.encrypt(v))
+                .transpose()?
+                .map_or(NotSet, |v| Set(Some(v))),
+            passphrase
+                .map(|v| self.ctx.encrypt(v))
+                .transpose()?
+                .map_or(NotSet, Set),
+        ))
+    }
+
+    fn decrypt_auth(
+        &self,
+        auth_type: &str,
+        password: String,
+        private_key: Option<String>,
+        passphrase: String,
+    ) -> Result<SshAuth> {
+        if auth_type == SSH_AUTH_PRIVATE_KEY {
+            let key = private_key
+                .filter(|v| !v.is_empty())
+                .ok_or(anyhow!("no private key"))?;
+            let passphrase = Some(passphrase)
+                .filter(|v| !v.is_empty())
+                .map(|v| self.ctx.decrypt(v))
+                .transpose()?;
+            return Ok(SshAuth::PrivateKey {
+                key: self.ctx.decrypt(key)?,
+                passphrase,
+            });
+        }
+
+        if password.is_empty() {
+            anyhow::bail!("no password");
+        }
+        Ok(SshAuth::Password(self.ctx.decrypt(password)?))
+    }
+}
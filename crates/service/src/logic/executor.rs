@@ -10,7 +10,7 @@ use sea_orm::{
 pub struct ExecutorList(Vec<entity::executor::Model>);
 
 impl ExecutorList {
-    pub fn get_by_id(&self, executor_id: u64) -> Option<executor::Model> {
+    pub fn get_by_id(&self, executor_id: i64) -> Option<executor::Model> {
         let v = self
             .0
             .iter()
@@ -36,7 +36,7 @@ impl<'a> ExecutorLogic<'a> {
 
     pub async fn query_executor(
         &self,
-        default_id: Option<u64>,
+        default_id: Option<i64>,
         name: Option<String>,
         page: u64,
         page_size: u64,
@@ -58,7 +58,7 @@ impl<'a> ExecutorLogic<'a> {
         Ok((list, total))
     }
 
-    pub async fn get_all_by_executor_id(&self, executor_id: Vec<u64>) -> Result<ExecutorList> {
+    pub async fn get_all_by_executor_id(&self, executor_id: Vec<i64>) -> Result<ExecutorList> {
         let model = Executor::find().filter(entity::executor::Column::Id.is_in(executor_id));
 
         let list = model
@@ -7,16 +7,14 @@ use chrono::Local;
 use chrono::Utc;
 use sea_orm::ActiveValue::NotSet;
 use sea_orm::Condition;
-use sea_orm::DbBackend;
 use sea_orm::FromQueryResult;
 use sea_orm::Order;
 use sea_orm::Statement;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, EntityTrait, JoinType, PaginatorTrait, QueryFilter, QueryOrder,
-    QuerySelect, QueryTrait, Set,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, JoinType, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, QueryTrait, Set,
 };
 
-use sea_query::MysqlQueryBuilder;
 use sea_query::UnionType;
 use sea_query::{ConditionType, Expr, IntoCondition, OnConflict};
 use tracing::warn;
@@ -26,19 +24,21 @@ use crate::entity::tag;
 use crate::entity::tag_resource;
 use crate::entity::user;
 use crate::entity::{self, instance, instance_group, prelude::*, user_server};
+use crate::logic::permission::{GrantAction, GrantResource, PermissionLogic};
 use crate::state::AppContext;
 use crate::state::AppState;
 use crate::IdGenerator;
 use anyhow::Result;
 
+use super::credential::SSH_AUTH_PRIVATE_KEY;
 use super::job::types::InstanceStatSummary;
 use super::types;
 use super::user::UserLogic;
 
 #[derive(Debug, FromQueryResult)]
 struct InstanceStatusCount {
-    status: bool,
-    total: u64,
+    status: i16,
+    total: i64,
 }
 
 pub struct InstanceLogic<'a> {
@@ -55,15 +55,29 @@ impl<'a> InstanceLogic<'a> {
         namespace: Option<String>,
         agent_ip: String,
         mac_addr: String,
-        status: i8,
+        status: i16,
         assign_user: Option<(String, String)>,
         ssh_connection_option: Option<SshConnectionOption>,
     ) -> Result<()> {
-        let (sys_user, password, ssh_port) = match ssh_connection_option {
+        let (sys_user, password, ssh_port) = match ssh_connection_option.clone() {
             Some(opt) => (
                 Set(opt.user),
                 Set(self.ctx.encrypt(opt.password)?),
-                Set(opt.port),
+                Set(opt.port.into()),
+            ),
+            None => (NotSet, NotSet, NotSet),
+        };
+
+        let (auth_type, private_key, key_passphrase) = match ssh_connection_option
+            .and_then(|opt| opt.private_key.map(|key| (key, opt.passphrase)))
+        {
+            Some((key, passphrase)) => (
+                Set(SSH_AUTH_PRIVATE_KEY.to_string()),
+                Set(Some(self.ctx.encrypt(key)?)),
+                Set(passphrase
+                    .map(|v| self.ctx.encrypt(v))
+                    .transpose()?
+                    .unwrap_or_default()),
             ),
             None => (NotSet, NotSet, NotSet),
         };
@@ -87,7 +101,7 @@ impl<'a> InstanceLogic<'a> {
                     .await?;
                 }
                 if ins_vec.len() > 1 {
-                    let id_vec = ins_vec.iter().map(|v| v.id).collect::<Vec<u64>>();
+                    let id_vec = ins_vec.iter().map(|v| v.id).collect::<Vec<i64>>();
                     if id_vec.len() > 0 {
                         Instance::update_many()
                             .set(instance::ActiveModel {
@@ -121,6 +135,16 @@ impl<'a> InstanceLogic<'a> {
             updated.value(instance::Column::Namespace, namespace.clone());
         }
 
+        if private_key.is_set() {
+            updated
+                .value(instance::Column::AuthType, auth_type.clone().unwrap())
+                .value(instance::Column::PrivateKey, private_key.clone().unwrap())
+                .value(
+                    instance::Column::KeyPassphrase,
+                    key_passphrase.clone().unwrap(),
+                );
+        }
+
         let instance_id = IdGenerator::get_instance_uid();
 
         if status == 1 {
@@ -133,6 +157,9 @@ impl<'a> InstanceLogic<'a> {
                 sys_user,
                 password,
                 ssh_port,
+                auth_type,
+                private_key,
+                key_passphrase,
                 ..Default::default()
             })
             .on_conflict(updated)
@@ -213,8 +240,8 @@ impl<'a> InstanceLogic<'a> {
         &self,
         ip: Option<String>,
         status: Option<u8>,
-        role_id: u64,
-        ignore_role_id: Option<u64>,
+        role_id: i64,
+        ignore_role_id: Option<i64>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<types::InstanceRecord>, u64)> {
@@ -231,6 +258,8 @@ impl<'a> InstanceLogic<'a> {
                 instance::Column::SysUser,
                 instance::Column::SshPort,
                 instance::Column::Password,
+                instance::Column::AuthType,
+                instance::Column::CredentialId,
                 instance::Column::InstanceGroupId,
                 instance::Column::CreatedTime,
                 instance::Column::UpdatedTime,
@@ -274,7 +303,7 @@ impl<'a> InstanceLogic<'a> {
         &self,
         ip: Option<String>,
         status: Option<u8>,
-        ignore_role_id: Option<u64>,
+        ignore_role_id: Option<i64>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<types::InstanceRecord>, u64)> {
@@ -324,7 +353,7 @@ impl<'a> InstanceLogic<'a> {
         user_id: Vec<String>,
         instance_ids: Option<Vec<String>>,
         instance_group_ids: Option<Vec<i64>>,
-    ) -> Result<u64> {
+    ) -> Result<i64> {
         let mut models = vec![];
 
         if let Some(instance_ids) = instance_ids {
@@ -364,10 +393,10 @@ impl<'a> InstanceLogic<'a> {
     pub async fn query_server_by_tag(
         &self,
         user_id: Option<String>,
-        instance_group_id: Option<u64>,
+        instance_group_id: Option<i64>,
         status: Option<u8>,
         ip: Option<String>,
-        tag_id: Option<Vec<u64>>,
+        tag_id: Option<Vec<i64>>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<types::UserServer>, u64)> {
@@ -434,7 +463,7 @@ impl<'a> InstanceLogic<'a> {
     pub async fn query_admin_server(
         &self,
         instance_id: Option<String>,
-        instance_group_id: Option<u64>,
+        instance_group_id: Option<i64>,
         status: Option<u8>,
         ip: Option<String>,
         page: u64,
@@ -514,6 +543,18 @@ impl<'a> InstanceLogic<'a> {
         Ok(())
     }
 
+    /// Number of instances per status, 1 for online and 0 for offline.
+    pub async fn count_by_status(&self) -> Result<Vec<(i16, i64)>> {
+        Ok(Instance::find()
+            .select_only()
+            .column(instance::Column::Status)
+            .column_as(instance::Column::Id.count(), "total")
+            .group_by(instance::Column::Status)
+            .into_tuple()
+            .all(&self.ctx.db)
+            .await?)
+    }
+
     pub async fn get_instance_summary(
         &self,
         user_id: Option<String>,
@@ -521,6 +562,7 @@ impl<'a> InstanceLogic<'a> {
         let mut summary = InstanceStatSummary::default();
 
         if let Some(user_id) = user_id {
+            let backend = self.ctx.db.get_database_backend();
             let (sql, vals) = User::find()
                 .select_only()
                 .column(instance::Column::Status)
@@ -576,25 +618,23 @@ impl<'a> InstanceLogic<'a> {
                         .clone(),
                 )
                 .group_by_col(instance::Column::Status)
-                .build(MysqlQueryBuilder);
+                .build_any(&*backend.get_query_builder());
 
             let list = InstanceStatusCount::find_by_statement(Statement::from_sql_and_values(
-                DbBackend::MySql,
-                sql,
-                vals,
+                backend, sql, vals,
             ))
             .all(&self.ctx.db)
             .await?;
 
             list.iter().for_each(|v| {
-                if v.status {
-                    summary.online += v.total;
+                if v.status == 1 {
+                    summary.online += v.total as u64;
                 } else {
-                    summary.offline += v.total;
+                    summary.offline += v.total as u64;
                 }
             });
         } else {
-            let list: Vec<(u64, i8)> = Instance::find()
+            let list: Vec<(i64, i16)> = Instance::find()
                 .select_only()
                 .column_as(instance::Column::Id.count(), "total")
                 .column(instance::Column::Status)
@@ -604,9 +644,9 @@ impl<'a> InstanceLogic<'a> {
                 .await?;
             list.iter().for_each(|v| {
                 if v.1 == 1 {
-                    summary.online += v.0;
+                    summary.online += v.0 as u64;
                 } else {
-                    summary.offline += v.0;
+                    summary.offline += v.0 as u64;
                 }
             });
         };
@@ -618,7 +658,7 @@ impl<'a> InstanceLogic<'a> {
         &self,
         user_id: String,
         instance_id: Option<String>,
-        instance_group_id: Option<u64>,
+        instance_group_id: Option<i64>,
         status: Option<u8>,
         ip: Option<String>,
         page: u64,
@@ -738,13 +778,14 @@ impl<'a> InstanceLogic<'a> {
             model
         };
 
+        let backend = self.ctx.db.get_database_backend();
         let (sql, vals) = model
             .clone()
             .order_by(instance::Column::UpdatedTime, Order::Desc)
-            .build(MysqlQueryBuilder);
+            .build_any(&*backend.get_query_builder());
 
         let total = types::UserServer::find_by_statement(Statement::from_sql_and_values(
-            sea_orm::DatabaseBackend::MySql,
+            backend,
             &sql,
             vals.clone(),
         ))
@@ -752,9 +793,7 @@ impl<'a> InstanceLogic<'a> {
         .await?;
 
         let list = types::UserServer::find_by_statement(Statement::from_sql_and_values(
-            sea_orm::DatabaseBackend::MySql,
-            &sql,
-            vals,
+            backend, &sql, vals,
         ))
         .paginate(&self.ctx.db, page_size)
         .fetch_page(page)
@@ -782,7 +821,7 @@ impl<'a> InstanceLogic<'a> {
     pub async fn query_group(
         &self,
         name: Option<String>,
-        ignore_role_id: Option<u64>,
+        ignore_role_id: Option<i64>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<instance_group::Model>, u64)> {
@@ -818,8 +857,8 @@ impl<'a> InstanceLogic<'a> {
     pub async fn query_group_by_role_id(
         &self,
         name: Option<String>,
-        role_id: u64,
-        ignore_role_id: Option<u64>,
+        role_id: i64,
+        ignore_role_id: Option<i64>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<instance_group::Model>, u64)> {
@@ -860,7 +899,7 @@ impl<'a> InstanceLogic<'a> {
         Ok((list, total))
     }
 
-    pub async fn delete_group(&self, id: u64) -> Result<u64> {
+    pub async fn delete_group(&self, id: i64) -> Result<u64> {
         let record = Instance::find()
             .filter(instance::Column::InstanceGroupId.eq(id))
             .one(&self.ctx.db)
@@ -889,6 +928,10 @@ impl<'a> InstanceLogic<'a> {
             .column(instance::Column::Password)
             .column(instance::Column::SysUser)
             .column(instance::Column::SshPort)
+            .column(instance::Column::AuthType)
+            .column(instance::Column::PrivateKey)
+            .column(instance::Column::KeyPassphrase)
+            .column(instance::Column::CredentialId)
             .column(instance::Column::InstanceGroupId)
             .column_as(instance_group::Column::Name, "instance_group_name")
             .column(instance::Column::Status)
@@ -933,6 +976,10 @@ impl<'a> InstanceLogic<'a> {
             .column(instance::Column::SysUser)
             .column(instance::Column::SshPort)
             .column(instance::Column::Password)
+            .column(instance::Column::AuthType)
+            .column(instance::Column::PrivateKey)
+            .column(instance::Column::KeyPassphrase)
+            .column(instance::Column::CredentialId)
             .column(instance::Column::Status)
             .column(instance::Column::InstanceGroupId)
             .column_as(instance_group::Column::Name, "instance_group_name")
@@ -989,6 +1036,10 @@ impl<'a> InstanceLogic<'a> {
                 .column(instance::Column::SysUser)
                 .column(instance::Column::SshPort)
                 .column(instance::Column::Password)
+                .column(instance::Column::AuthType)
+                .column(instance::Column::PrivateKey)
+                .column(instance::Column::KeyPassphrase)
+                .column(instance::Column::CredentialId)
                 .column(instance::Column::Status)
                 .column(instance::Column::InstanceGroupId)
                 .column_as(instance_group::Column::Name, "instance_group_name")
@@ -1030,12 +1081,11 @@ impl<'a> InstanceLogic<'a> {
             model.and_where(instance::Column::Ip.contains(ip));
         }
 
-        let (sql, vals) = model.clone().build(MysqlQueryBuilder);
+        let backend = self.ctx.db.get_database_backend();
+        let (sql, vals) = model.clone().build_any(&*backend.get_query_builder());
 
         let one = types::UserServer::find_by_statement(Statement::from_sql_and_values(
-            sea_orm::DatabaseBackend::MySql,
-            &sql,
-            vals,
+            backend, &sql, vals,
         ))
         .one(&self.ctx.db)
         .await?;
@@ -1043,6 +1093,9 @@ impl<'a> InstanceLogic<'a> {
         Ok(one)
     }
 
+    /// Resolves an instance for ssh and sftp. Besides the role based access an
+    /// ssh grant on the instance group opens it, while a group with ssh grants
+    /// is reserved to its grantees.
     pub async fn get_one_user_server_with_permission(
         &self,
         state: AppState,
@@ -1050,22 +1103,53 @@ impl<'a> InstanceLogic<'a> {
         instance_id: String,
     ) -> Result<Option<types::UserServer>> {
         let can_manage_instance = state.can_manage_instance(&user_info.user_id).await?;
-        let instance_record = if can_manage_instance {
-            self.get_one_admin_server(None, None, Some(instance_id))
-                .await
-        } else {
-            self.get_one_user_server(None, None, Some(instance_id), user_info.user_id.to_string())
-                .await
-        };
+        if can_manage_instance {
+            return self
+                .get_one_admin_server(None, None, Some(instance_id))
+                .await;
+        }
+
+        let permission = PermissionLogic::new(self.ctx);
+        if let Some(v) = self
+            .get_one_user_server(
+                None,
+                None,
+                Some(instance_id.clone()),
+                user_info.user_id.to_string(),
+            )
+            .await?
+        {
+            let group_id = v.instance_group_id.unwrap_or_default();
+            return Ok(permission
+                .can_use_instance_group(user_info, group_id, GrantAction::Ssh)
+                .await?
+                .then_some(v));
+        }
 
-        instance_record
+        let Some(v) = self
+            .get_one_admin_server(None, None, Some(instance_id))
+            .await?
+        else {
+            return Ok(None);
+        };
+        let group_id = v.instance_group_id.unwrap_or_default();
+        let is_granted = group_id > 0
+            && permission
+                .is_granted(
+                    user_info,
+                    GrantResource::InstanceGroup,
+                    group_id,
+                    GrantAction::Ssh,
+                )
+                .await?;
+        Ok(is_granted.then_some(v))
     }
 
     pub async fn set_status(
         &self,
         _state: AppState,
         _user_info: &types::UserInfo,
-        id: Vec<u64>,
+        id: Vec<i64>,
         status: bool,
     ) -> Result<u64> {
         let ret = Instance::update_many()
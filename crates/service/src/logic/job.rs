@@ -10,8 +10,8 @@ mod timer;
 use automate::scheduler::types::ScheduleType;
 use chrono::Local;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::Set, ColumnTrait, Condition, EntityTrait, PaginatorTrait,
-    QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, Condition, ConnectionTrait, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait,
 };
 use sea_query::{Expr, Query};
 
@@ -25,7 +25,10 @@ use crate::{
 };
 use sea_orm::JoinType;
 
-use super::types::{ResourceType, UserInfo};
+use super::{
+    permission::{GrantAction, GrantResource, PermissionLogic},
+    types::{ResourceType, UserInfo},
+};
 
 pub mod types;
 
@@ -37,6 +40,30 @@ impl<'a> JobLogic<'a> {
     pub fn new(ctx: &'a AppContext) -> Self {
         Self { ctx }
     }
+
+    /// Whether the user created the record, or was granted to edit one of
+    /// the resources it belongs to.
+    async fn granted_or_owner(
+        &self,
+        user_info: &UserInfo,
+        created_user: &str,
+        resources: &[(GrantResource, i64)],
+    ) -> Result<bool> {
+        if created_user == user_info.username {
+            return Ok(true);
+        }
+        let permission_logic = PermissionLogic::new(self.ctx);
+        for &(resource, id) in resources {
+            if permission_logic
+                .is_granted(user_info, resource, id, GrantAction::Edit)
+                .await?
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     pub async fn save_job(
         &self,
         model: entity::job::ActiveModel,
@@ -48,7 +75,7 @@ impl<'a> JobLogic<'a> {
     pub async fn can_write_bundle_script(
         &self,
         user_info: &UserInfo,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         eid: Option<String>,
     ) -> Result<bool> {
         let is_allowed = self.ctx.can_manage_job(&user_info.user_id).await?;
@@ -100,8 +127,8 @@ impl<'a> JobLogic<'a> {
     pub async fn can_write_bundle_script_by_id(
         &self,
         user_info: &UserInfo,
-        team_id: Option<u64>,
-        id: Option<u64>,
+        team_id: Option<i64>,
+        id: Option<i64>,
     ) -> Result<bool> {
         let is_allowed = self.ctx.can_manage_job(&user_info.user_id).await?;
         if is_allowed {
@@ -152,7 +179,7 @@ impl<'a> JobLogic<'a> {
     pub async fn can_write_job(
         &self,
         user_info: &UserInfo,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         eid: Option<String>,
     ) -> Result<bool> {
         let is_allowed = self.ctx.can_manage_job(&user_info.user_id).await?;
@@ -183,7 +210,14 @@ impl<'a> JobLogic<'a> {
             return Ok(false);
         };
 
-        if job_record.created_user == user_info.username {
+        if self
+            .granted_or_owner(
+                user_info,
+                &job_record.created_user,
+                &[(GrantResource::Job, job_record.id)],
+            )
+            .await?
+        {
             return Ok(true);
         }
 
@@ -204,8 +238,8 @@ impl<'a> JobLogic<'a> {
     pub async fn can_write_job_by_id(
         &self,
         user_info: &UserInfo,
-        team_id: Option<u64>,
-        job_id: Option<u64>,
+        team_id: Option<i64>,
+        job_id: Option<i64>,
     ) -> Result<bool> {
         let is_allowed = self.ctx.can_manage_job(&user_info.user_id).await?;
         if is_allowed {
@@ -235,7 +269,14 @@ impl<'a> JobLogic<'a> {
             return Ok(false);
         };
 
-        if job_record.created_user == user_info.username {
+        if self
+            .granted_or_owner(
+                user_info,
+                &job_record.created_user,
+                &[(GrantResource::Job, job_record.id)],
+            )
+            .await?
+        {
             return Ok(true);
         }
 
@@ -256,7 +297,7 @@ impl<'a> JobLogic<'a> {
     pub async fn can_write_schedule_by_id(
         &self,
         user_info: &UserInfo,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         schedule_id: Option<String>,
     ) -> Result<bool> {
         let is_allowed = self.ctx.can_manage_job(&user_info.user_id).await?;
@@ -295,7 +336,14 @@ impl<'a> JobLogic<'a> {
             return Ok(false);
         };
 
-        if schedule_record.created_user == user_info.username {
+        if self
+            .granted_or_owner(
+                user_info,
+                &schedule_record.created_user,
+                &[(GrantResource::Job, job_record.id)],
+            )
+            .await?
+        {
             return Ok(true);
         }
 
@@ -316,8 +364,8 @@ impl<'a> JobLogic<'a> {
     pub async fn can_write_job_timer_by_id(
         &self,
         user_info: &UserInfo,
-        team_id: Option<u64>,
-        timer_id: Option<u64>,
+        team_id: Option<i64>,
+        timer_id: Option<i64>,
     ) -> Result<bool> {
         let is_allowed = self.ctx.can_manage_job(&user_info.user_id).await?;
         if is_allowed {
@@ -355,7 +403,17 @@ impl<'a> JobLogic<'a> {
             return Ok(false);
         };
 
-        if timer_record.created_user == user_info.username {
+        if self
+            .granted_or_owner(
+                user_info,
+                &timer_record.created_user,
+                &[
+                    (GrantResource::Timer, timer_record.id),
+                    (GrantResource::Job, job_record.id),
+                ],
+            )
+            .await?
+        {
             return Ok(true);
         }
 
@@ -376,8 +434,8 @@ impl<'a> JobLogic<'a> {
     pub async fn can_write_job_supervisor_by_id(
         &self,
         user_info: &UserInfo,
-        team_id: Option<u64>,
-        supvervisor_id: Option<u64>,
+        team_id: Option<i64>,
+        supvervisor_id: Option<i64>,
     ) -> Result<bool> {
         let is_allowed = self.ctx.can_manage_job(&user_info.user_id).await?;
         if is_allowed {
@@ -415,7 +473,17 @@ impl<'a> JobLogic<'a> {
             return Ok(false);
         };
 
-        if supervisor_record.created_user == user_info.username {
+        if self
+            .granted_or_owner(
+                user_info,
+                &supervisor_record.created_user,
+                &[
+                    (GrantResource::Supervisor, supervisor_record.id),
+                    (GrantResource::Job, job_record.id),
+                ],
+            )
+            .await?
+        {
             return Ok(true);
         }
 
@@ -433,13 +501,33 @@ impl<'a> JobLogic<'a> {
             == Some(true));
     }
 
+    /// Writers of a job may dispatch it, others need a grant for the action.
+    /// Only the original scheduler may act on an existing schedule unless the
+    /// action was granted explicitly.
     pub async fn can_dispatch_job(
         &self,
         user_info: &UserInfo,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         schedule_user: Option<&str>,
         eid: &str,
+        action: GrantAction,
     ) -> Result<bool> {
+        let is_granted = match Job::find()
+            .filter(job::Column::Eid.eq(eid))
+            .one(&self.ctx.db)
+            .await?
+        {
+            Some(v) => {
+                PermissionLogic::new(self.ctx)
+                    .is_granted(user_info, GrantResource::Job, v.id, action)
+                    .await?
+            }
+            None => false,
+        };
+        if is_granted {
+            return Ok(true);
+        }
+
         if !self
             .can_write_job(user_info, team_id, Some(eid.to_string()))
             .await?
@@ -458,7 +546,7 @@ impl<'a> JobLogic<'a> {
     pub async fn get_authorized_job(
         &self,
         username: &str,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         eid: &str,
     ) -> Result<job::Model> {
         let ok = self.ctx.can_manage_job(username).await?;
@@ -485,13 +573,14 @@ impl<'a> JobLogic<'a> {
     pub async fn query_job(
         &self,
         created_user: Option<String>,
+        granted_ids: Vec<i64>,
         job_type: Option<String>,
         name: Option<String>,
         updated_time_range: Option<(String, String)>,
-        default_id: Option<u64>,
+        default_id: Option<i64>,
         default_eid: Option<String>,
-        team_id: Option<u64>,
-        tag_ids: Option<Vec<u64>>,
+        team_id: Option<i64>,
+        tag_ids: Option<Vec<i64>>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<types::JobRelatedExecutorModel>, u64)> {
@@ -516,7 +605,11 @@ impl<'a> JobLogic<'a> {
             )
             .filter(job::Column::IsDeleted.eq(false))
             .apply_if(created_user, |query, v| {
-                query.filter(job::Column::CreatedUser.eq(v))
+                query.filter(
+                    Condition::any()
+                        .add(job::Column::CreatedUser.eq(v))
+                        .add(job::Column::Id.is_in(granted_ids)),
+                )
             })
             .apply_if(job_type, |query, v| {
                 query.filter(job::Column::JobType.eq(v))
@@ -569,77 +662,19 @@ impl<'a> JobLogic<'a> {
     }
 
     pub async fn delete_job(&self, user_info: &UserInfo, eid: String) -> Result<u64> {
-        if JobTimer::find()
-            .filter(job_timer::Column::Eid.eq(&eid))
-            .filter(job_timer::Column::IsDeleted.eq(false))
-            .one(&self.ctx.db)
-            .await?
-            .is_some()
-        {
-            anyhow::bail!("do not delete jobs linked to timers")
-        }
-
-        if JobSupervisor::find()
-            .filter(job_supervisor::Column::Eid.eq(&eid))
-            .filter(job_supervisor::Column::IsDeleted.eq(false))
-            .one(&self.ctx.db)
-            .await?
-            .is_some()
-        {
-            anyhow::bail!("do not delete jobs linked to supervisors")
-        }
-
-        let ret = Job::update_many()
-            .set(job::ActiveModel {
-                is_deleted: Set(true),
-                deleted_at: Set(Some(Local::now())),
-                deleted_by: Set(user_info.username.clone()),
-                ..Default::default()
-            })
-            .filter(job::Column::Eid.eq(&eid))
-            .exec(&self.ctx.db)
-            .await?;
-
-        JobRunningStatus::update_many()
-            .set(job_running_status::ActiveModel {
-                is_deleted: Set(true),
-                deleted_at: Set(Some(Local::now())),
-                deleted_by: Set(user_info.username.clone()),
-                ..Default::default()
-            })
-            .filter(job_running_status::Column::Eid.eq(&eid))
-            .exec(&self.ctx.db)
-            .await?;
-
-        JobScheduleHistory::update_many()
-            .set(job_schedule_history::ActiveModel {
-                is_deleted: Set(true),
-                deleted_at: Set(Some(Local::now())),
-                deleted_by: Set(user_info.username.clone()),
-                ..Default::default()
-            })
-            .filter(job_schedule_history::Column::Eid.eq(&eid))
-            .exec(&self.ctx.db)
-            .await?;
-
-        JobExecHistory::delete_many()
-            .filter(job_exec_history::Column::Eid.eq(&eid))
-            .exec(&self.ctx.db)
-            .await?;
-
-        Ok(ret.rows_affected)
+        delete_job(&self.ctx.db, user_info, &eid).await
     }
 
     pub async fn query_run_list(
         &self,
         created_user: Option<String>,
         bind_ip: Option<String>,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         schedule_name: Option<String>,
         schedule_type: Option<String>,
         job_type: Option<String>,
         updated_time_range: Option<(String, String)>,
-        tag_ids: Option<Vec<u64>>,
+        tag_ids: Option<Vec<i64>>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<types::RunStatusRelatedScheduleJobModel>, u64)> {
@@ -804,8 +839,8 @@ impl<'a> JobLogic<'a> {
         &self,
         user_info: &UserInfo,
         eid: Option<&str>,
-        default_team_id: Option<u64>,
-    ) -> Result<Option<u64>> {
+        default_team_id: Option<i64>,
+    ) -> Result<Option<i64>> {
         let Some(eid) = eid else {
             return Ok(default_team_id);
         };
@@ -828,3 +863,71 @@ impl<'a> JobLogic<'a> {
         }
     }
 }
+
+/// Deletes a job that is no longer linked to a timer or a supervisor, on any
+/// connection so that it can be part of a transaction.
+pub(crate) async fn delete_job<C: ConnectionTrait>(
+    db: &C,
+    user_info: &UserInfo,
+    eid: &str,
+) -> Result<u64> {
+    if JobTimer::find()
+        .filter(job_timer::Column::Eid.eq(eid))
+        .filter(job_timer::Column::IsDeleted.eq(false))
+        .one(db)
+        .await?
+        .is_some()
+    {
+        anyhow::bail!("do not delete jobs linked to timers")
+    }
+
+    if JobSupervisor::find()
+        .filter(job_supervisor::Column::Eid.eq(eid))
+        .filter(job_supervisor::Column::IsDeleted.eq(false))
+        .one(db)
+        .await?
+        .is_some()
+    {
+        anyhow::bail!("do not delete jobs linked to supervisors")
+    }
+
+    let ret = Job::update_many()
+        .set(job::ActiveModel {
+            is_deleted: Set(true),
+            deleted_at: Set(Some(Local::now())),
+            deleted_by: Set(user_info.username.clone()),
+            ..Default::default()
+        })
+        .filter(job::Column::Eid.eq(eid))
+        .exec(db)
+        .await?;
+
+    JobRunningStatus::update_many()
+        .set(job_running_status::ActiveModel {
+            is_deleted: Set(true),
+            deleted_at: Set(Some(Local::now())),
+            deleted_by: Set(user_info.username.clone()),
+            ..Default::default()
+        })
+        .filter(job_running_status::Column::Eid.eq(eid))
+        .exec(db)
+        .await?;
+
+    JobScheduleHistory::update_many()
+        .set(job_schedule_history::ActiveModel {
+            is_deleted: Set(true),
+            deleted_at: Set(Some(Local::now())),
+            deleted_by: Set(user_info.username.clone()),
+            ..Default::default()
+        })
+        .filter(job_schedule_history::Column::Eid.eq(eid))
+        .exec(db)
+        .await?;
+
+    JobExecHistory::delete_many()
+        .filter(job_exec_history::Column::Eid.eq(eid))
+        .exec(db)
+        .await?;
+
+    Ok(ret.rows_affected)
+}
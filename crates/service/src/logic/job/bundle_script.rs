@@ -15,6 +15,7 @@ use sea_orm::QueryTrait;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
 };
+use sea_orm::{ConnectionTrait, DbBackend};
 use sea_query::Expr;
 
 use super::types;
@@ -32,7 +33,7 @@ impl<'a> JobLogic<'a> {
     pub async fn query_bundle_script(
         &self,
         username: Option<String>,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         default_eid: Option<String>,
         name: Option<String>,
         updated_time_range: Option<(String, String)>,
@@ -88,10 +89,21 @@ impl<'a> JobLogic<'a> {
     }
 
     pub async fn delete_bundle_script(&self, user_info: &UserInfo, eid: String) -> Result<u64> {
-        let cond = Condition::all().add(Expr::cust_with_values(
-            "JSON_CONTAINS(bundle_script, ?)",
-            vec![serde_json::json!({ "eid": eid.clone() })],
-        ));
+        let used_by_job = match self.ctx.db.get_database_backend() {
+            DbBackend::MySql => Expr::cust_with_values(
+                "JSON_CONTAINS(bundle_script, ?)",
+                vec![serde_json::json!({ "eid": eid.clone() })],
+            ),
+            DbBackend::Postgres => Expr::cust_with_values(
+                "bundle_script @> ?",
+                vec![serde_json::json!([{ "eid": eid.clone() }])],
+            ),
+            DbBackend::Sqlite => Expr::cust_with_values(
+                "exists (select 1 from json_each(bundle_script) where json_extract(value, '$.eid') = ?)",
+                vec![eid.clone()],
+            ),
+        };
+        let cond = Condition::all().add(used_by_job);
 
         let has = Job::find()
             .filter(cond)
@@ -130,8 +142,8 @@ impl<'a> JobLogic<'a> {
         &self,
         user_info: &UserInfo,
         eid: Option<&str>,
-        default_team_id: Option<u64>,
-    ) -> Result<Option<u64>> {
+        default_team_id: Option<i64>,
+    ) -> Result<Option<i64>> {
         let Some(eid) = eid else {
             return Ok(default_team_id);
         };
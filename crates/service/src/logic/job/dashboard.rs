@@ -4,8 +4,8 @@ use anyhow::Result;
 
 use automate::scheduler::types::RunStatus;
 use sea_orm::{
-    ColumnTrait, DbBackend, EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter, QueryOrder,
-    QuerySelect, QueryTrait, Statement,
+    ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, FromQueryResult, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, QueryTrait, Statement,
 };
 use sea_query::{Alias, Expr};
 use serde::{Deserialize, Serialize};
@@ -118,31 +118,59 @@ impl<'a> JobLogic<'a> {
         schedule_id: String,
         run_id: Option<String>,
     ) -> Result<Vec<BundleScriptExecCount>> {
-        let mut sb = SqlBuilder::select_from("job_exec_history jeh")
-            .and_table(
+        let backend = self.ctx.db.get_database_backend();
+        // expand bundle_script_result into one row per script
+        let (source, eid, exit_code, eval_err, result) = match backend {
+            DbBackend::MySql => (
                 r#"json_table(jeh.bundle_script_result , '$[*]' columns(
                     eid varchar(20) path '$.eid',
                     exit_code int path '$.exit_code',
                     eval_err varchar(500) path '$.eval_err',
                     result bool path '$.result')) bst"#,
-            )
-            .fields(&[
                 "bst.eid",
-                "count(1) total",
                 "bst.exit_code",
-                "if(bst.eval_err='', false,true) is_eval_err",
+                "bst.eval_err",
+                "bst.result",
+            ),
+            DbBackend::Postgres => (
+                r#"jsonb_to_recordset(jeh.bundle_script_result) bst(
+                    eid varchar(20),
+                    exit_code bigint,
+                    eval_err varchar(500),
+                    result bool)"#,
+                "bst.eid",
+                "bst.exit_code",
+                "bst.eval_err",
                 "bst.result",
+            ),
+            DbBackend::Sqlite => (
+                "json_each(jeh.bundle_script_result) bst",
+                "json_extract(bst.value, '$.eid')",
+                "json_extract(bst.value, '$.exit_code')",
+                "json_extract(bst.value, '$.eval_err')",
+                "json_extract(bst.value, '$.result')",
+            ),
+        };
+
+        let mut sb = SqlBuilder::select_from("job_exec_history jeh")
+            .and_table(source)
+            .fields(&[
+                format!("{eid} eid"),
+                "count(1) total".to_string(),
+                format!("{exit_code} exit_code"),
+                format!("case when {eval_err}='' then false else true end is_eval_err"),
+                format!("{result} result"),
             ])
             .and_where("jeh.schedule_id = ?".bind(&schedule_id))
             .to_owned();
         if let Some(run_id) = run_id {
             sb.and_where("jeh.run_id=?".bind(&run_id));
         }
-        sb.group_by("bst.eid,bst.exit_code,bst.result,eval_err");
+        sb.group_by(format!("{eid},{exit_code},{result},{eval_err}"));
 
         let sql = sb.sql()?;
         let ret: Vec<BundleScriptExecCount> =
-            BundleScriptExecCount::find_by_statement(Statement::from_string(DbBackend::MySql, sql))
+            BundleScriptExecCount::find_by_statement(Statement::from_string(backend, sql))
                 .all(&self.ctx.db)
                 .await?;
         Ok(ret)
@@ -204,7 +232,7 @@ impl<'a> JobLogic<'a> {
                 .await?
                 .into_iter()
                 .map(|v| v.id)
-                .collect::<Vec<u64>>();
+                .collect::<Vec<i64>>();
             (Some(user_info.username.clone()), Some(team_ids))
         };
 
@@ -290,7 +318,7 @@ impl<'a> JobLogic<'a> {
                 .await?
                 .into_iter()
                 .map(|v| v.id)
-                .collect::<Vec<u64>>();
+                .collect::<Vec<i64>>();
             (Some(user_info.username.clone()), Some(team_ids))
         };
 
@@ -21,7 +21,7 @@ impl<'a> JobLogic<'a> {
         job_type: String,
         schedule_id: Option<String>,
         schedule_type: Option<String>,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         eid: Option<String>,
         schedule_name: Option<String>,
         username: Option<String>,
@@ -29,7 +29,7 @@ impl<'a> JobLogic<'a> {
         bind_namespace: Option<String>,
         bind_ip: Option<String>,
         start_time_range: Option<(String, String)>,
-        tag_ids: Option<Vec<u64>>,
+        tag_ids: Option<Vec<i64>>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<ExecHistoryRelatedScheduleModel>, u64)> {
@@ -144,7 +144,7 @@ impl<'a> JobLogic<'a> {
         schedule_type: Option<ScheduleType>,
         instance_id: Option<String>,
         eid: Option<String>,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         username: Option<String>,
         time_range_start: Option<String>,
         time_range_end: Option<String>,
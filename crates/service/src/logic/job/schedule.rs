@@ -1,4 +1,9 @@
-use std::{path::PathBuf, str::FromStr, time::Duration};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Result, anyhow};
 
@@ -6,6 +11,7 @@ use automate::{
     JobAction,
     bridge::msg::{BundleOutputParams, UpdateJobParams},
     scheduler::types::{BundleScript, RunStatus, ScheduleStatus, ScheduleType, UploadFile},
+    telemetry,
 };
 
 use chrono::Local;
@@ -21,28 +27,115 @@ use sea_query::{OnConflict, Query};
 
 use serde_json::{Value, json};
 use tokio::fs;
-use tracing::{debug, error};
+use tracing::{debug, error, info_span};
 
 use crate::{
     IdGenerator,
     entity::{
         self, executor, instance, job, job_exec_history, job_running_status, job_schedule_history,
-        prelude::*, tag_resource, team,
+        job_supervisor, prelude::*, tag_resource, team,
     },
     logic::{
+        artifact::{ARTIFACT_SCHEME, ArtifactLogic},
+        calendar::CalendarLogic,
         executor::ExecutorLogic,
         job::types::DispatchResult,
+        permission::{GrantAction, PermissionLogic},
+        script_module::ScriptModuleLogic,
         types::{CompletedCallbackOpts, CompletedCallbackTriggerType, ResourceType, UserInfo},
     },
+    metrics,
 };
 
 use utils::file_name;
 
 use super::{
     JobLogic,
-    types::{self, BundleScriptRecord, BundleScriptResult, DispatchData, DispatchTarget},
+    types::{
+        self, BundleScriptRecord, BundleScriptResult, DispatchData, DispatchRequest, DispatchTarget,
+    },
 };
 
+/// A job as built for a dispatch.
+struct BuiltJob {
+    base_job: automate::BaseJob,
+    /// pushed to the comet of every target, agents fetch it from there
+    upload_artifact: Option<String>,
+    job_type: String,
+    /// of the supervisor of a daemon job, used unless the dispatch sets one
+    restart_policy: Option<automate::RestartPolicy>,
+    health_check: Option<automate::HealthCheck>,
+}
+
+/// Shown in a preview instead of values that may be credentials.
+const SECRET_MASK: &str = "******";
+
+/// Masks the container environment of the job and its bundle scripts, which
+/// may hold credentials.
+fn mask_secrets(base_job: &mut automate::BaseJob) {
+    let bundle_containers = base_job
+        .bundle_script
+        .iter_mut()
+        .flatten()
+        .filter_map(|v| v.container.as_mut());
+    for container in base_job.container.iter_mut().chain(bundle_containers) {
+        container
+            .env
+            .values_mut()
+            .for_each(|v| *v = SECRET_MASK.to_string());
+    }
+}
+
+/// Blackout windows of the instance, and the error of the one in effect when
+/// it refuses the action. Timers skip their ticks themselves on the agent,
+/// anything that runs at once is refused here.
+fn instance_blackouts(
+    blackouts: &HashMap<String, Vec<automate::BlackoutWindow>>,
+    instance_id: &str,
+    action: JobAction,
+) -> (Option<Vec<automate::BlackoutWindow>>, Option<String>) {
+    let windows = blackouts
+        .get(instance_id)
+        .filter(|v| !v.is_empty())
+        .cloned();
+    let runs_now = matches!(
+        action,
+        JobAction::Exec | JobAction::StartSupervising | JobAction::RestartSupervising
+    );
+    let err = windows
+        .as_deref()
+        .filter(|_| runs_now)
+        .and_then(|v| automate::BlackoutWindow::find_active(v, chrono::Utc::now()))
+        .map(|v| format!("blackout window {} is in effect", v.name));
+    (windows, err)
+}
+
+#[test]
+fn test_mask_secrets() {
+    use automate::scheduler::types::ContainerOptions;
+
+    let container = ContainerOptions {
+        image: "alpine".to_string(),
+        env: [("DB_PASSWORD".to_string(), "hunter2".to_string())].into(),
+        ..Default::default()
+    };
+    let mut base_job = automate::BaseJob {
+        container: Some(container.clone()),
+        bundle_script: Some(vec![BundleScript {
+            container: Some(container),
+            ..Default::default()
+        }]),
+        ..Default::default()
+    };
+    mask_secrets(&mut base_job);
+    assert_eq!(base_job.container.unwrap().env["DB_PASSWORD"], SECRET_MASK);
+    let bundle_script = base_job.bundle_script.unwrap();
+    assert_eq!(
+        bundle_script[0].container.as_ref().unwrap().env["DB_PASSWORD"],
+        SECRET_MASK
+    );
+}
+
 #[test]
 fn test_hello() {
     match eval_boolean("$v=10;true") {
@@ -171,7 +264,15 @@ impl<'a> JobLogic<'a> {
         Ok(())
     }
 
-    pub async fn update_job_status(&self, params: UpdateJobParams) -> Result<u64> {
+    pub async fn update_job_status(&self, params: UpdateJobParams) -> Result<i64> {
+        let span = info_span!(
+            target: telemetry::TARGET,
+            "console.update_job_status",
+            eid = %params.base_job.eid,
+            run_id = %params.run_id,
+        );
+        telemetry::set_parent(&span, params.trace_context.as_ref());
+
         let mut update_values = vec![
             (
                 job_running_status::Column::ScheduleId,
@@ -227,6 +328,27 @@ impl<'a> JobLogic<'a> {
                 schedule_status.to_string().into(),
             ))
         }
+
+        if let Some(restart_count) = params.restart_count {
+            update_values.push((
+                job_running_status::Column::RestartCount,
+                restart_count.into(),
+            ))
+        }
+
+        if let Some(ref crash_reason) = params.crash_reason {
+            update_values.push((
+                job_running_status::Column::LastCrashReason,
+                crash_reason.into(),
+            ))
+        }
+
+        if let Some(ref probe_status) = params.probe_status {
+            update_values.push((
+                job_running_status::Column::ProbeStatus,
+                serde_json::to_value(probe_status)?.into(),
+            ))
+        }
         // if let Some(prev_time) = params.prev_time {
         //     update_values.push((job_running_status::Column::PrevTime, prev_time.into()))
         // }
@@ -279,11 +401,36 @@ impl<'a> JobLogic<'a> {
 
         let ret = active_model.exec(&self.ctx.db).await?;
 
+        if let Some(reason) = params.suppressed_by {
+            let ret = JobExecHistory::insert(entity::job_exec_history::ActiveModel {
+                schedule_id: Set(params.schedule_id),
+                instance_id: Set(params.instance_id),
+                exit_status: Set("suppressed".to_string()),
+                output: Set(format!("skipped, {reason} in effect")),
+                run_id: Set(params.run_id),
+                eid: Set(params.base_job.eid),
+                start_time: Set(params.start_time),
+                end_time: Set(params.end_time),
+                created_user: Set(params.created_user),
+                job_type: Set(job_type.to_string()),
+                ..Default::default()
+            })
+            .exec(&self.ctx.db)
+            .await?;
+            return Ok(ret.last_insert_id);
+        }
+
         match params.run_status {
             Some(RunStatus::Stop) => {
                 if let Err(e) = self.completed_callback(params.clone()).await {
                     error!("failed to send callback request: {}", e);
                 }
+                if let Err(e) = ArtifactLogic::new(self.ctx)
+                    .collect_run_artifacts(&params)
+                    .await
+                {
+                    error!("failed to collect run artifacts: {}", e);
+                }
                 let (bundle_script_result, job_type) = if params.bundle_output.is_some() {
                     let schedule_record = self.get_schedule(&params.schedule_id).await?.ok_or(
                         anyhow::format_err!("cannot get schedule record {}", params.schedule_id),
@@ -372,62 +519,79 @@ impl<'a> JobLogic<'a> {
         Ok(())
     }
 
-    pub async fn dispatch_job(
+    /// The job as agents run it, the supervisor overriding how a daemon is
+    /// stopped, restarted and checked and script modules expanded. Legacy upload files
+    /// are only read when `inline_upload` is set.
+    async fn build_base_job(
         &self,
-        secret: String,
-        instance_ids: Vec<String>,
-        eid: String,
-        is_sync: bool,
-        schedule_name: String,
-        schedule_type: ScheduleType,
-        action: automate::JobAction,
-        timer_expr: Option<String>,
-        restart_interval: Option<Duration>,
-        created_user: String,
-    ) -> Result<u64> {
-        self.check_schedule_type(action.clone(), schedule_type.clone())?;
-        let schedule_id = IdGenerator::get_schedule_uid();
-        let endpoints = Instance::find()
-            .filter(instance::Column::InstanceId.is_in(instance_ids))
-            .all(&self.ctx.db)
-            .await?;
-        if endpoints.len() == 0 {
-            anyhow::bail!("cannot found valid instance");
+        job_record: &job::Model,
+        executor_record: &executor::Model,
+        schedule_type: &ScheduleType,
+        inline_upload: bool,
+    ) -> Result<BuiltJob> {
+        let (mut stop_signal, mut stop_grace_period) =
+            (job_record.stop_signal.clone(), job_record.stop_grace_period);
+        let (mut restart_policy, mut health_check) = (None, None);
+        if *schedule_type == ScheduleType::Daemon {
+            // a supervisor may override how its job is stopped
+            if let Some(v) = JobSupervisor::find()
+                .filter(job_supervisor::Column::Eid.eq(job_record.eid.clone()))
+                .filter(job_supervisor::Column::IsDeleted.eq(false))
+                .one(&self.ctx.db)
+                .await?
+            {
+                if !v.stop_signal.is_empty() {
+                    stop_signal = v.stop_signal;
+                }
+                if let Some(grace_period) = v.stop_grace_period {
+                    stop_grace_period = grace_period;
+                }
+                restart_policy = v
+                    .restart_policy
+                    .filter(|v| !v.is_null())
+                    .map(serde_json::from_value)
+                    .transpose()?;
+                health_check = v
+                    .health_check
+                    .filter(|v| !v.is_null())
+                    .map(serde_json::from_value)
+                    .transpose()?;
+            }
         }
 
-        let job_record = Job::find()
-            .filter(job::Column::Eid.eq(eid.clone()))
-            .filter(job::Column::IsDeleted.eq(false))
-            .one(&self.ctx.db)
-            .await?
-            .ok_or(anyhow!("cannot found job {}", eid))?;
-
-        let executor_record = Executor::find()
-            .filter(executor::Column::Id.eq(job_record.executor_id))
-            .one(&self.ctx.db)
-            .await?
-            .ok_or(anyhow!(
-                "cannot found executor {}",
-                job_record.executor_id.clone()
-            ))?;
-
-        let mut dispatch_result = Vec::new();
-
         let mut upload_file: Option<UploadFile> = None;
+        let mut upload_artifact: Option<String> = None;
 
-        if job_record.upload_file != "" {
-            let data = fs::read(job_record.upload_file.clone()).await?;
+        if job_record.upload_file.starts_with(ARTIFACT_SCHEME) {
+            let record = ArtifactLogic::new(self.ctx)
+                .resolve(&job_record.upload_file)
+                .await?;
+            upload_file = Some(UploadFile {
+                filename: record.filename,
+                data: None,
+                sha256: Some(record.sha256.clone()),
+            });
+            upload_artifact = Some(record.sha256);
+        } else if job_record.upload_file != "" {
+            // files uploaded before the artifact store are still inlined
+            let data = match inline_upload {
+                true => Some(fs::read(job_record.upload_file.clone()).await?),
+                false => None,
+            };
             upload_file = Some(UploadFile {
                 filename: file_name!(job_record.upload_file.clone()),
-                data: Some(data),
+                data,
+                sha256: None,
             });
         }
 
+        // script modules are expanded here, agents only ever see plain code
+        let script_module_logic = ScriptModuleLogic::new(self.ctx);
         let (bundle_script, job_type): (Option<Vec<BundleScript>>, String) =
             match job_record.clone().bundle_script {
                 Some(v) => {
                     let list: Vec<BundleScriptRecord> = serde_json::from_value(v)?;
-                    let executor_id = list.iter().map(|v| v.executor_id).collect::<Vec<u64>>();
+                    let executor_id = list.iter().map(|v| v.executor_id).collect::<Vec<i64>>();
                     let executor_list = ExecutorLogic::new(self.ctx)
                         .get_all_by_executor_id(executor_id)
                         .await?;
@@ -444,10 +608,13 @@ impl<'a> JobLogic<'a> {
                             cmd_name: command_slice
                                 .get(0)
                                 .map_or("".to_string(), |&v| v.to_owned()),
-                            code: v.code.clone(),
+                            code: script_module_logic
+                                .expand(job_record.team_id, &v.code)
+                                .await?,
                             args: command_slice
                                 .get(1..)
                                 .map_or(vec![], |v| v.into_iter().map(|&v| v.to_owned()).collect()),
+                            container: e.container.map(serde_json::from_value).transpose()?,
                         })
                     }
 
@@ -456,36 +623,132 @@ impl<'a> JobLogic<'a> {
                 None => (None, "default".to_string()),
             };
 
+        let code = script_module_logic
+            .expand(job_record.team_id, &job_record.code)
+            .await?;
         let command_slice: Vec<&str> = executor_record.command.split(" ").collect();
 
+        let base_job = automate::BaseJob {
+            eid: job_record.eid.clone(),
+            cmd_name: command_slice
+                .get(0)
+                .map_or("".to_string(), |&v| v.to_owned()),
+            bundle_script,
+            code,
+            args: command_slice
+                .get(1..)
+                .map_or(vec![], |v| v.into_iter().map(|&v| v.to_owned()).collect()),
+            upload_file,
+            work_dir: Some(job_record.work_dir.clone()).filter(|v| !v.is_empty()),
+            work_user: Some(job_record.work_user.clone()).filter(|v| !v.is_empty()),
+            timeout: job_record.timeout as u64,
+            max_retry: Some(job_record.max_retry as u8),
+            max_parallel: Some(job_record.max_parallel as u32),
+            read_code_from_stdin: false,
+            stop_signal: Some(stop_signal).filter(|v| !v.is_empty()),
+            stop_grace_period: Some(stop_grace_period as u64),
+            container: executor_record
+                .container
+                .clone()
+                .map(serde_json::from_value)
+                .transpose()?,
+            publish_artifacts: job_record
+                .publish_artifacts
+                .clone()
+                .map(serde_json::from_value)
+                .transpose()?,
+        };
+        Ok(BuiltJob {
+            base_job,
+            upload_artifact,
+            job_type,
+            restart_policy,
+            health_check,
+        })
+    }
+
+    pub async fn dispatch_job(&self, secret: String, req: DispatchRequest) -> Result<i64> {
+        let DispatchRequest {
+            instance_ids,
+            eid,
+            is_sync,
+            schedule_name,
+            schedule_type,
+            action,
+            timer,
+            calendar_ids,
+            restart_interval,
+            restart_policy,
+            health_check,
+            created_user,
+        } = req;
+        self.check_schedule_type(action.clone(), schedule_type.clone())?;
+        if let Some(ref timer) = timer {
+            timer.validate()?;
+        }
+        let schedule_id = IdGenerator::get_schedule_uid();
+        let span = info_span!(
+            target: telemetry::TARGET,
+            "console.dispatch_job",
+            eid = %eid,
+            action = %action,
+            schedule_id = %schedule_id,
+        );
+        let endpoints = Instance::find()
+            .filter(instance::Column::InstanceId.is_in(instance_ids))
+            .all(&self.ctx.db)
+            .await?;
+        if endpoints.len() == 0 {
+            anyhow::bail!("cannot found valid instance");
+        }
+        let calendar_logic = CalendarLogic::new(self.ctx);
+        let calendars = calendar_logic.get_timer_calendars(&calendar_ids).await?;
+        let blackouts = calendar_logic.get_instance_blackouts(&endpoints).await?;
+
+        let job_record = Job::find()
+            .filter(job::Column::Eid.eq(eid.clone()))
+            .filter(job::Column::IsDeleted.eq(false))
+            .one(&self.ctx.db)
+            .await?
+            .ok_or(anyhow!("cannot found job {}", eid))?;
+
+        let executor_record = Executor::find()
+            .filter(executor::Column::Id.eq(job_record.executor_id))
+            .one(&self.ctx.db)
+            .await?
+            .ok_or(anyhow!(
+                "cannot found executor {}",
+                job_record.executor_id.clone()
+            ))?;
+
+        let BuiltJob {
+            base_job,
+            upload_artifact,
+            job_type,
+            restart_policy: supervisor_restart_policy,
+            health_check: supervisor_health_check,
+        } = self
+            .build_base_job(&job_record, &executor_record, &schedule_type, true)
+            .await?;
+        let mut dispatch_result = Vec::new();
+
         let dispatch_params = automate::DispatchJobParams {
-            base_job: automate::BaseJob {
-                eid: job_record.eid.clone(),
-                cmd_name: command_slice
-                    .get(0)
-                    .map_or("".to_string(), |&v| v.to_owned()),
-                bundle_script,
-                code: job_record.code.clone(),
-                args: command_slice
-                    .get(1..)
-                    .map_or(vec![], |v| v.into_iter().map(|&v| v.to_owned()).collect()),
-                upload_file: upload_file.clone(),
-                work_dir: Some(job_record.work_dir.clone()).filter(|v| !v.is_empty()),
-                work_user: Some(job_record.work_user.clone()).filter(|v| !v.is_empty()),
-                timeout: job_record.timeout,
-                max_retry: Some(job_record.max_retry),
-                max_parallel: Some(job_record.max_parallel.into()),
-                read_code_from_stdin: false,
-            },
+            base_job,
             run_id: IdGenerator::get_run_id(),
             instance_id: None,
             fields: None,
             restart_interval,
+            restart_policy: restart_policy.or(supervisor_restart_policy),
+            health_check: health_check.or(supervisor_health_check),
             created_user: created_user.clone(),
             schedule_id: schedule_id.clone(),
-            timer_expr: timer_expr.clone(),
+            timer_expr: timer.as_ref().and_then(|v| v.legacy_expr()),
+            timer,
+            calendars: Some(calendars).filter(|v| !v.is_empty()),
+            blackouts: None,
             is_sync,
             action: action.clone(),
+            trace_context: None,
         };
 
         let mut dispatch_data = DispatchData {
@@ -504,14 +767,24 @@ impl<'a> JobLogic<'a> {
 
         let logic = automate::Logic::new(self.ctx.redis().clone());
         let http_client = self.ctx.http_client.clone();
+        let artifact_storage = self.ctx.artifact_storage.clone();
 
+        let started = Instant::now();
         let batch_push_ret = utils::async_batch_do(dispatch_data.target.clone(), move |v| {
             let mut dispatch_params = dispatch_params.clone();
             let logic = logic.clone();
             let http_client = http_client.clone();
+            let artifact_storage = artifact_storage.clone();
+            let upload_artifact = upload_artifact.clone();
             let secret = secret.clone();
             dispatch_params.instance_id = Some(v.instance_id.clone());
+            dispatch_params.trace_context = telemetry::forward(&span, None);
+            let (windows, blackout_err) = instance_blackouts(&blackouts, &v.instance_id, action);
+            dispatch_params.blackouts = windows;
             Box::pin(async move {
+                if let Some(err) = blackout_err {
+                    return Ok(DispatchResult::failed(&v, err));
+                }
                 let body = automate::DispatchJobRequest {
                     agent_ip: v.ip.clone(),
                     mac_addr: v.mac_addr.clone(),
@@ -530,6 +803,29 @@ impl<'a> JobLogic<'a> {
                         });
                     }
                 };
+                let pushed = match upload_artifact {
+                    Some(ref sha256) => {
+                        ArtifactLogic::push_to_comet(
+                            &http_client,
+                            &artifact_storage,
+                            &pair.1.comet_addr,
+                            &secret,
+                            sha256,
+                        )
+                        .await
+                    }
+                    None => Ok(()),
+                };
+                if let Err(e) = pushed {
+                    return Ok(DispatchResult {
+                        namespace: v.namespace.clone(),
+                        instance_id: v.instance_id.clone(),
+                        bind_ip: v.ip.clone(),
+                        response: json!(null),
+                        has_err: true,
+                        err: Some(format!("failed push artifact to comet, {e}")),
+                    });
+                }
                 let api_url = format!(
                     "http://{}/dispatch?secret={}",
                     pair.1.comet_addr,
@@ -603,6 +899,7 @@ impl<'a> JobLogic<'a> {
             }
             dispatch_result.push(v)
         });
+        metrics::observe_dispatch(action, started, &dispatch_result);
 
         dispatch_data
             .params
@@ -635,6 +932,180 @@ impl<'a> JobLogic<'a> {
         Ok(ret.last_insert_id)
     }
 
+    /// What [`dispatch_job`](Self::dispatch_job) would send to each target,
+    /// without contacting comet or recording a schedule. `problems` already
+    /// found with the request are reported on every target, along with those
+    /// of the job and of the target itself.
+    pub async fn preview_dispatch(
+        &self,
+        user_info: &UserInfo,
+        grant_action: GrantAction,
+        req: DispatchRequest,
+        mut problems: Vec<String>,
+    ) -> Result<Vec<types::DispatchPreview>> {
+        let DispatchRequest {
+            instance_ids,
+            eid,
+            is_sync,
+            schedule_type,
+            action,
+            timer,
+            calendar_ids,
+            restart_interval,
+            restart_policy,
+            health_check,
+            created_user,
+            ..
+        } = req;
+        if instance_ids.is_empty() {
+            anyhow::bail!("no instance is selected");
+        }
+        if let Err(e) = self.check_schedule_type(action, schedule_type.clone()) {
+            problems.push(e.to_string());
+        }
+        if let Some(Err(e)) = timer.as_ref().map(|v| v.validate()) {
+            problems.push(format!("invalid timer, {e}"));
+        }
+
+        let job_record = Job::find()
+            .filter(job::Column::Eid.eq(eid.clone()))
+            .filter(job::Column::IsDeleted.eq(false))
+            .one(&self.ctx.db)
+            .await?
+            .ok_or(anyhow!("cannot found job {}", eid))?;
+
+        let executor_record = match Executor::find()
+            .filter(executor::Column::Id.eq(job_record.executor_id))
+            .one(&self.ctx.db)
+            .await?
+        {
+            Some(v) => v,
+            None => {
+                problems.push(format!("cannot found executor {}", job_record.executor_id));
+                executor::Model::default()
+            }
+        };
+
+        let calendar_logic = CalendarLogic::new(self.ctx);
+        let calendars = calendar_logic
+            .get_timer_calendars(&calendar_ids)
+            .await
+            .unwrap_or_else(|e| {
+                problems.push(e.to_string());
+                vec![]
+            });
+
+        let params = match self
+            .build_base_job(&job_record, &executor_record, &schedule_type, false)
+            .await
+        {
+            Ok(built) => {
+                let mut params = automate::DispatchJobParams {
+                    base_job: built.base_job,
+                    run_id: String::new(),
+                    instance_id: None,
+                    fields: None,
+                    restart_interval,
+                    restart_policy: restart_policy.or(built.restart_policy),
+                    health_check: health_check.or(built.health_check),
+                    created_user,
+                    schedule_id: String::new(),
+                    timer_expr: timer.as_ref().and_then(|v| v.legacy_expr()),
+                    timer,
+                    calendars: Some(calendars).filter(|v| !v.is_empty()),
+                    blackouts: None,
+                    is_sync,
+                    action,
+                    trace_context: None,
+                };
+                mask_secrets(&mut params.base_job);
+                Some(params)
+            }
+            Err(e) => {
+                problems.push(e.to_string());
+                None
+            }
+        };
+
+        let endpoints = Instance::find()
+            .filter(instance::Column::InstanceId.is_in(instance_ids.clone()))
+            .all(&self.ctx.db)
+            .await?;
+        let blackouts = calendar_logic.get_instance_blackouts(&endpoints).await?;
+        // the agent refuses a run beyond max_parallel, see Scheduler::can_execute
+        let running = JobRunningStatus::find()
+            .filter(job_running_status::Column::Eid.eq(eid.clone()))
+            .filter(job_running_status::Column::InstanceId.is_in(instance_ids.clone()))
+            .filter(job_running_status::Column::ScheduleType.eq(ScheduleType::Once.to_string()))
+            .filter(job_running_status::Column::RunStatus.eq(RunStatus::Running.to_string()))
+            .filter(job_running_status::Column::IsDeleted.eq(false))
+            .all(&self.ctx.db)
+            .await?;
+        let max_parallel = job_record.max_parallel.max(1) as usize;
+
+        let logic = automate::Logic::new(self.ctx.redis().clone());
+        let permission_logic = PermissionLogic::new(self.ctx);
+        let mut ret = vec![];
+        for instance_id in instance_ids {
+            let mut target_problems = problems.clone();
+            let Some(v) = endpoints.iter().find(|v| v.instance_id == instance_id) else {
+                target_problems.push("cannot found instance".to_string());
+                ret.push(types::DispatchPreview {
+                    instance_id,
+                    problems: target_problems,
+                    ..Default::default()
+                });
+                continue;
+            };
+
+            if !permission_logic
+                .can_operate_instances(user_info, std::slice::from_ref(&instance_id), grant_action)
+                .await?
+            {
+                target_problems
+                    .push("no permission to run the job on the instance group".to_string());
+            }
+            if v.status != 1 {
+                target_problems.push("instance is offline".to_string());
+            }
+            let comet_addr = match logic.get_link_pair(v.ip.clone(), v.mac_addr.clone()).await {
+                Ok(pair) => Some(pair.1.comet_addr),
+                Err(e) => {
+                    target_problems.push(e.to_string());
+                    None
+                }
+            };
+
+            let (windows, blackout_err) = instance_blackouts(&blackouts, &instance_id, action);
+            target_problems.extend(blackout_err);
+            let parallel = running
+                .iter()
+                .filter(|v| v.instance_id == instance_id)
+                .count();
+            if action == JobAction::Exec && parallel >= max_parallel {
+                target_problems.push(format!(
+                    "job {} is running, max parallel {}",
+                    eid, max_parallel
+                ));
+            }
+
+            ret.push(types::DispatchPreview {
+                namespace: v.namespace.clone(),
+                bind_ip: v.ip.clone(),
+                is_online: v.status == 1,
+                comet_addr,
+                params: params.clone().map(|mut params| {
+                    params.instance_id = Some(instance_id.clone());
+                    params.blackouts = windows;
+                    params
+                }),
+                instance_id,
+                problems: target_problems,
+            });
+        }
+        Ok(ret)
+    }
+
     pub async fn dispatch_runnable_job_to_endpoint(
         &self,
         bind_namespace: String,
@@ -741,24 +1212,48 @@ impl<'a> JobLogic<'a> {
         Ok(())
     }
 
+    /// Repeats the recorded dispatch on the targets of `req` that it had.
     pub async fn redispatch_job(
         &self,
         schedule_id: &str,
-        action: JobAction,
-        job_schedule_record: job_schedule_history::Model,
-        created_user: String,
+        mut dispatch_data: DispatchData,
+        req: DispatchRequest,
     ) -> Result<Vec<Result<DispatchResult>>> {
-        let mut dispatch_data: DispatchData = job_schedule_record
-            .dispatch_data
-            .ok_or(anyhow!("cannot found job dispatch data"))?
-            .try_into()?;
+        let DispatchRequest {
+            instance_ids,
+            action,
+            created_user,
+            ..
+        } = req;
+        dispatch_data
+            .target
+            .retain(|v| instance_ids.contains(&v.instance_id));
 
         dispatch_data.params.run_id = IdGenerator::get_run_id();
+        let span = info_span!(
+            target: telemetry::TARGET,
+            "console.redispatch_job",
+            eid = %dispatch_data.params.base_job.eid,
+            action = %action,
+            schedule_id = %schedule_id,
+        );
+
+        let endpoints = Instance::find()
+            .filter(
+                instance::Column::InstanceId
+                    .is_in(dispatch_data.target.iter().map(|v| v.instance_id.clone())),
+            )
+            .all(&self.ctx.db)
+            .await?;
+        let blackouts = CalendarLogic::new(self.ctx)
+            .get_instance_blackouts(&endpoints)
+            .await?;
 
         let logic = automate::Logic::new(self.ctx.redis().clone());
 
         let http_client = self.ctx.http_client.clone();
 
+        let started = Instant::now();
         let batch_push_ret = utils::async_batch_do(dispatch_data.target, move |v| {
             let mut dispatch_params = dispatch_data.params.clone();
             let logic = logic.clone();
@@ -766,8 +1261,14 @@ impl<'a> JobLogic<'a> {
             let instance_id = v.instance_id.clone();
             dispatch_params.action = action;
             dispatch_params.instance_id = Some(instance_id.clone());
+            dispatch_params.trace_context = telemetry::forward(&span, None);
             dispatch_params.created_user = created_user.clone();
+            let (windows, blackout_err) = instance_blackouts(&blackouts, &instance_id, action);
+            dispatch_params.blackouts = windows;
             Box::pin(async move {
+                if let Some(err) = blackout_err {
+                    return Ok(DispatchResult::failed(&v, err));
+                }
                 let body = automate::DispatchJobRequest {
                     agent_ip: v.ip.clone(),
                     mac_addr: v.mac_addr.clone(),
@@ -844,6 +1345,7 @@ impl<'a> JobLogic<'a> {
             }
             dispatch_result.push(v)
         });
+        metrics::observe_dispatch(action, started, &dispatch_result);
 
         JobScheduleHistory::update_many()
             .set(job_schedule_history::ActiveModel {
@@ -868,9 +1370,9 @@ impl<'a> JobLogic<'a> {
         created_user: Option<String>,
         job_type: String,
         name: Option<String>,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         updated_time_range: Option<(String, String)>,
-        tag_ids: Option<Vec<u64>>,
+        tag_ids: Option<Vec<i64>>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<types::ScheduleJobTeamModel>, u64)> {
@@ -985,7 +1487,7 @@ impl<'a> JobLogic<'a> {
         schedule_id: String,
         instance_id: String,
         user_info: &UserInfo,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         action: JobAction,
     ) -> Result<Value> {
         let ins = Instance::find()
@@ -1002,12 +1504,14 @@ impl<'a> JobLogic<'a> {
                 schedule_id.clone()
             ))?;
 
+        let grant_action = GrantAction::for_job_action(&action);
         if !self
             .can_dispatch_job(
                 &user_info,
                 team_id,
                 Some(&schedule_record.created_user),
                 &schedule_record.eid,
+                grant_action,
             )
             .await?
         {
@@ -1016,6 +1520,13 @@ impl<'a> JobLogic<'a> {
             );
         }
 
+        if !PermissionLogic::new(self.ctx)
+            .can_operate_instances(user_info, std::slice::from_ref(&instance_id), grant_action)
+            .await?
+        {
+            anyhow::bail!("no permission to {grant_action} on the instance group of {instance_id}");
+        }
+
         let dispatch_data = schedule_record
             .dispatch_data
             .ok_or(anyhow!("cannot get dispatch data"))?;
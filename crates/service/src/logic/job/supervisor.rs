@@ -21,9 +21,9 @@ impl<'a> JobLogic<'a> {
         created_user: Option<&String>,
         name: Option<String>,
         eid: Option<String>,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         updated_time_range: Option<(String, String)>,
-        tag_ids: Option<Vec<u64>>,
+        tag_ids: Option<Vec<i64>>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<JobSupervisorRelatedJobModel>, u64)> {
@@ -113,7 +113,7 @@ impl<'a> JobLogic<'a> {
         Ok(active_model.save(&self.ctx.db).await?)
     }
 
-    pub async fn delete_job_supervisor(&self, user_info: &UserInfo, id: u64) -> Result<u64> {
+    pub async fn delete_job_supervisor(&self, user_info: &UserInfo, id: i64) -> Result<u64> {
         let ret = JobSupervisor::update_many()
             .set(job_supervisor::ActiveModel {
                 is_deleted: Set(true),
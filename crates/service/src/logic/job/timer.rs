@@ -22,12 +22,12 @@ impl<'a> JobLogic<'a> {
 
     pub async fn query_job_timer(
         &self,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         created_user: Option<&String>,
         name: Option<String>,
         job_type: Option<String>,
         updated_time_range: Option<(String, String)>,
-        tag_ids: Option<Vec<u64>>,
+        tag_ids: Option<Vec<i64>>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<JobTimerRelatedJobModel>, u64)> {
@@ -112,7 +112,7 @@ impl<'a> JobLogic<'a> {
         Ok((list, total))
     }
 
-    pub async fn delete_job_timer(&self, user_info: &UserInfo, id: u64) -> Result<u64> {
+    pub async fn delete_job_timer(&self, user_info: &UserInfo, id: i64) -> Result<u64> {
         let ret = JobTimer::update_many()
             .set(job_timer::ActiveModel {
                 is_deleted: Set(true),
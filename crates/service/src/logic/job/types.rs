@@ -1,18 +1,23 @@
-use automate::DispatchJobParams;
-use sea_orm::{prelude::DateTimeLocal, FromQueryResult};
+use std::time::Duration;
+
+use automate::{
+    DispatchJobParams, HealthCheck, JobAction, RestartPolicy, TimerSchedule,
+    scheduler::types::ScheduleType,
+};
+use sea_orm::{FromQueryResult, prelude::DateTimeLocal};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromQueryResult)]
 pub struct RunStatusRelatedScheduleJobModel {
-    pub id: u64,
-    pub job_id: u64,
-    pub executor_id: u64,
+    pub id: i64,
+    pub job_id: i64,
+    pub executor_id: i64,
     pub executor_name: String,
     pub instance_id: String,
     pub bind_ip: String,
     pub bind_namespace: String,
-    pub team_id: Option<u64>,
+    pub team_id: Option<i64>,
     pub team_name: Option<String>,
     pub schedule_type: String,
     pub job_type: String,
@@ -24,6 +29,9 @@ pub struct RunStatusRelatedScheduleJobModel {
     pub run_status: String,
     pub exit_status: String,
     pub exit_code: i32,
+    pub restart_count: i32,
+    pub last_crash_reason: String,
+    pub probe_status: Option<serde_json::Value>,
     pub dispatch_data: Option<serde_json::Value>,
     pub dispatch_result: Option<serde_json::Value>,
     pub start_time: Option<DateTimeLocal>,
@@ -37,19 +45,19 @@ pub struct RunStatusRelatedScheduleJobModel {
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromQueryResult)]
 pub struct ExecHistoryRelatedScheduleModel {
-    pub id: u64,
-    pub job_id: u64,
+    pub id: i64,
+    pub job_id: i64,
     pub job_name: String,
     pub schedule_id: String,
     pub ip: String,
     pub namespace: String,
     pub job_type: String,
     pub output: String,
-    pub team_id: Option<u64>,
+    pub team_id: Option<i64>,
     pub team_name: Option<String>,
     pub bundle_script_result: Option<serde_json::Value>,
     pub created_user: String,
-    pub exit_code: i64,
+    pub exit_code: i32,
     pub exit_status: String,
     pub start_time: Option<DateTimeLocal>,
     pub end_time: Option<DateTimeLocal>,
@@ -61,9 +69,9 @@ pub struct ExecHistoryRelatedScheduleModel {
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromQueryResult)]
 pub struct JobRelatedExecutorModel {
-    pub id: u64,
+    pub id: i64,
     pub eid: String,
-    pub executor_id: u64,
+    pub executor_id: i64,
     pub executor_name: String,
     pub executor_platform: String,
     pub executor_command: String,
@@ -71,32 +79,37 @@ pub struct JobRelatedExecutorModel {
     pub name: String,
     pub code: String,
     pub info: String,
-    pub team_id: Option<u64>,
+    pub team_id: Option<i64>,
     pub team_name: Option<String>,
     pub bundle_script: Option<serde_json::Value>,
     pub work_dir: String,
     pub work_user: String,
     pub upload_file: String,
-    pub max_retry: u8,
-    pub max_parallel: u8,
-    pub timeout: u64,
-    pub is_public: i8,
+    pub max_retry: i16,
+    pub max_parallel: i16,
+    pub timeout: i64,
+    pub stop_signal: String,
+    pub stop_grace_period: i32,
+    pub is_public: i16,
     pub created_user: String,
     pub updated_user: String,
     pub display_on_dashboard: bool,
     pub completed_callback: Option<serde_json::Value>,
+    pub publish_artifacts: Option<serde_json::Value>,
     pub args: Option<serde_json::Value>,
+    pub template_id: i64,
+    pub template_version: i32,
     pub created_time: DateTimeLocal,
     pub updated_time: DateTimeLocal,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromQueryResult)]
 pub struct BundleScriptRelatedExecutorModel {
-    pub id: u64,
+    pub id: i64,
     pub eid: String,
-    pub executor_id: u64,
+    pub executor_id: i64,
     pub executor_name: String,
-    pub team_id: Option<u64>,
+    pub team_id: Option<i64>,
     pub team_name: Option<String>,
     pub name: String,
     pub code: String,
@@ -110,16 +123,16 @@ pub struct BundleScriptRelatedExecutorModel {
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromQueryResult)]
 pub struct JobTimerRelatedJobModel {
-    pub id: u64,
-    pub job_id: u64,
+    pub id: i64,
+    pub job_id: i64,
     pub eid: String,
     pub name: String,
     pub job_name: String,
     pub job_type: String,
-    pub executor_id: u64,
+    pub executor_id: i64,
     pub executor_name: String,
     pub executor_platform: String,
-    pub team_id: Option<u64>,
+    pub team_id: Option<i64>,
     pub team_name: Option<String>,
     pub timer_expr: Option<serde_json::Value>,
     pub info: String,
@@ -152,6 +165,58 @@ impl TryFrom<Value> for DispatchData {
     }
 }
 
+/// What to dispatch to which instances, shared by dispatch, its preview and
+/// redispatch so that they check the same targets.
+#[derive(Debug, Clone)]
+pub struct DispatchRequest {
+    pub instance_ids: Vec<String>,
+    pub eid: String,
+    pub is_sync: bool,
+    pub schedule_name: String,
+    pub schedule_type: ScheduleType,
+    pub action: JobAction,
+    pub timer: Option<TimerSchedule>,
+    pub calendar_ids: Vec<i64>,
+    pub restart_interval: Option<Duration>,
+    /// overrides the one of the supervisor of a daemon job
+    pub restart_policy: Option<RestartPolicy>,
+    /// overrides the one of the supervisor of a daemon job
+    pub health_check: Option<HealthCheck>,
+    pub created_user: String,
+}
+
+impl DispatchRequest {
+    /// Repeats a recorded schedule with another action. The calendars are
+    /// kept in the recorded params, so `calendar_ids` is left empty.
+    pub fn from_schedule(
+        schedule_name: String,
+        schedule_type: &str,
+        dispatch_data: &DispatchData,
+        action: JobAction,
+        created_user: String,
+    ) -> anyhow::Result<Self> {
+        let params = &dispatch_data.params;
+        Ok(Self {
+            instance_ids: dispatch_data
+                .target
+                .iter()
+                .map(|v| v.instance_id.clone())
+                .collect(),
+            eid: params.base_job.eid.clone(),
+            is_sync: params.is_sync,
+            schedule_name,
+            schedule_type: schedule_type.try_into()?,
+            action,
+            timer: params.timer.clone(),
+            calendar_ids: vec![],
+            restart_interval: params.restart_interval,
+            restart_policy: params.restart_policy.clone(),
+            health_check: params.health_check.clone(),
+            created_user,
+        })
+    }
+}
+
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Default)]
 pub struct ComputedBundleOutput {
     pub eid: String,
@@ -181,11 +246,25 @@ pub struct BundleScriptRecord {
     pub eid: String,
     pub name: String,
     pub code: String,
-    pub executor_id: u64,
+    pub executor_id: i64,
     pub info: String,
     pub cond_expr: String,
 }
 
+/// What a dispatch would send to a target and what keeps it from running.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct DispatchPreview {
+    pub namespace: String,
+    pub bind_ip: String,
+    pub instance_id: String,
+    pub is_online: bool,
+    /// comet the agent is connected to, none when it is not registered
+    pub comet_addr: Option<String>,
+    /// secrets masked, none when the job cannot be built
+    pub params: Option<automate::DispatchJobParams>,
+    pub problems: Vec<String>,
+}
+
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Default)]
 pub struct DispatchResult {
     pub namespace: String,
@@ -196,6 +275,20 @@ pub struct DispatchResult {
     pub err: Option<String>,
 }
 
+impl DispatchResult {
+    /// The target refused before anything was sent to it.
+    pub fn failed(target: &DispatchTarget, err: String) -> Self {
+        Self {
+            namespace: target.namespace.clone(),
+            bind_ip: target.ip.clone(),
+            instance_id: target.instance_id.clone(),
+            response: serde_json::Value::Null,
+            has_err: true,
+            err: Some(err),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Default)]
 pub struct JobRunResultStats {
     pub name: String,
@@ -235,15 +328,19 @@ pub struct InstanceStatSummary {
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromQueryResult)]
 pub struct JobSupervisorRelatedJobModel {
-    pub id: u64,
+    pub id: i64,
     pub name: String,
-    pub job_id: u64,
+    pub job_id: i64,
     pub job_name: String,
-    pub restart_interval: u64,
-    pub executor_id: u64,
+    pub restart_interval: i64,
+    pub restart_policy: Option<serde_json::Value>,
+    pub health_check: Option<serde_json::Value>,
+    pub stop_signal: String,
+    pub stop_grace_period: Option<i32>,
+    pub executor_id: i64,
     pub executor_name: String,
     pub executor_platform: String,
-    pub team_id: Option<u64>,
+    pub team_id: Option<i64>,
     pub team_name: Option<String>,
     pub eid: String,
     pub info: String,
@@ -263,13 +360,13 @@ pub struct TeamMemberModel {
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromQueryResult)]
 pub struct ScheduleJobTeamModel {
-    pub id: u64,
-    pub job_id: u64,
+    pub id: i64,
+    pub job_id: i64,
     pub schedule_id: String,
     pub name: String,
     pub job_type: String,
     pub eid: String,
-    pub team_id: Option<u64>,
+    pub team_id: Option<i64>,
     pub team_name: Option<String>,
     pub dispatch_result: Option<serde_json::Value>,
     pub schedule_type: String,
@@ -0,0 +1,335 @@
+use anyhow::{Result, anyhow};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QueryTrait, Set, TransactionTrait,
+};
+use serde_json::Value;
+
+use crate::{
+    entity::{job, job_template, prelude::*},
+    state::AppContext,
+};
+
+/// What a job inherits from its template.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Inherited {
+    pub executor_id: i64,
+    pub work_dir: String,
+    pub work_user: String,
+    pub timeout: i64,
+    pub max_retry: i16,
+    pub max_parallel: i16,
+    pub stop_signal: String,
+    pub stop_grace_period: i32,
+    pub args: Option<Value>,
+    pub completed_callback: Option<Value>,
+}
+
+/// A json null column reads back as `Some(Value::Null)` on some databases.
+fn non_null(v: &Option<Value>) -> Option<Value> {
+    v.clone().filter(|v| !v.is_null())
+}
+
+impl From<&job_template::Model> for Inherited {
+    fn from(v: &job_template::Model) -> Self {
+        Self {
+            executor_id: v.executor_id,
+            work_dir: v.work_dir.clone(),
+            work_user: v.work_user.clone(),
+            timeout: v.timeout,
+            max_retry: v.max_retry,
+            max_parallel: v.max_parallel,
+            stop_signal: v.stop_signal.clone(),
+            stop_grace_period: v.stop_grace_period,
+            args: non_null(&v.args),
+            completed_callback: non_null(&v.completed_callback),
+        }
+    }
+}
+
+impl From<&job::Model> for Inherited {
+    fn from(v: &job::Model) -> Self {
+        Self {
+            executor_id: v.executor_id,
+            work_dir: v.work_dir.clone(),
+            work_user: v.work_user.clone(),
+            timeout: v.timeout,
+            max_retry: v.max_retry,
+            max_parallel: v.max_parallel,
+            stop_signal: v.stop_signal.clone(),
+            stop_grace_period: v.stop_grace_period,
+            args: non_null(&v.args),
+            completed_callback: non_null(&v.completed_callback),
+        }
+    }
+}
+
+impl Inherited {
+    /// Fields in which `other` differs.
+    pub fn diff(&self, other: &Inherited) -> Vec<&'static str> {
+        let mut ret = vec![];
+        let mut check = |name, differs| {
+            if differs {
+                ret.push(name);
+            }
+        };
+        check("executor_id", self.executor_id != other.executor_id);
+        check("work_dir", self.work_dir != other.work_dir);
+        check("work_user", self.work_user != other.work_user);
+        check("timeout", self.timeout != other.timeout);
+        check("max_retry", self.max_retry != other.max_retry);
+        check("max_parallel", self.max_parallel != other.max_parallel);
+        check("stop_signal", self.stop_signal != other.stop_signal);
+        check(
+            "stop_grace_period",
+            self.stop_grace_period != other.stop_grace_period,
+        );
+        check("args", self.args != other.args);
+        check(
+            "completed_callback",
+            self.completed_callback != other.completed_callback,
+        );
+        ret
+    }
+
+    pub fn apply(self, model: &mut job::ActiveModel) {
+        model.executor_id = Set(self.executor_id);
+        model.work_dir = Set(self.work_dir);
+        model.work_user = Set(self.work_user);
+        model.timeout = Set(self.timeout);
+        model.max_retry = Set(self.max_retry);
+        model.max_parallel = Set(self.max_parallel);
+        model.stop_signal = Set(self.stop_signal);
+        model.stop_grace_period = Set(self.stop_grace_period);
+        model.args = Set(self.args);
+        model.completed_callback = Set(self.completed_callback);
+    }
+}
+
+/// A job derived from a template.
+pub struct DerivedJob {
+    pub eid: String,
+    pub name: String,
+    pub template_version: i32,
+    /// the template changed since it was last applied to the job
+    pub outdated: bool,
+    /// inherited fields the job no longer shares with the template
+    pub customized: Vec<&'static str>,
+}
+
+pub struct SavedTemplate {
+    pub id: i64,
+    pub version: i32,
+    /// jobs that followed the change
+    pub updated: Vec<String>,
+    /// customized jobs left on an older version
+    pub outdated: Vec<String>,
+}
+
+/// Job templates that new jobs inherit the executor, defaults, parameters,
+/// completed callback and timeouts from.
+pub struct JobTemplateLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+impl<'a> JobTemplateLogic<'a> {
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    /// Saves the template. A change of what jobs inherit bumps the version,
+    /// and with `propagate` updates the derived jobs that did not customize
+    /// any inherited field, the others are left outdated.
+    pub async fn save_template(
+        &self,
+        mut model: job_template::ActiveModel,
+        propagate: bool,
+        username: &str,
+    ) -> Result<SavedTemplate> {
+        let txn = self.ctx.db.begin().await?;
+        let old = match model.id {
+            Set(id) => Some(
+                JobTemplate::find_by_id(id)
+                    .one(&txn)
+                    .await?
+                    .ok_or(anyhow!("cannot found job template"))?,
+            ),
+            _ => None,
+        };
+
+        let saved = match old {
+            None => {
+                model.version = Set(1);
+                model.insert(&txn).await?
+            }
+            Some(ref old) => {
+                model.version = Set(old.version);
+                let mut saved = model.update(&txn).await?;
+                if Inherited::from(old) != Inherited::from(&saved) {
+                    saved = job_template::ActiveModel {
+                        id: Set(saved.id),
+                        version: Set(old.version + 1),
+                        ..Default::default()
+                    }
+                    .update(&txn)
+                    .await?;
+                }
+                saved
+            }
+        };
+
+        let mut ret = SavedTemplate {
+            id: saved.id,
+            version: saved.version,
+            updated: vec![],
+            outdated: vec![],
+        };
+        let Some(old) = old.filter(|v| v.version != saved.version) else {
+            txn.commit().await?;
+            return Ok(ret);
+        };
+
+        let (before, after) = (Inherited::from(&old), Inherited::from(&saved));
+        let jobs = Job::find()
+            .filter(job::Column::TemplateId.eq(saved.id))
+            .filter(job::Column::IsDeleted.eq(false))
+            .all(&txn)
+            .await?;
+        for v in jobs {
+            if !propagate || !Inherited::from(&v).diff(&before).is_empty() {
+                ret.outdated.push(v.eid);
+                continue;
+            }
+            let mut job_model = job::ActiveModel {
+                id: Set(v.id),
+                template_version: Set(saved.version),
+                updated_user: Set(username.to_string()),
+                ..Default::default()
+            };
+            after.clone().apply(&mut job_model);
+            job_model.update(&txn).await?;
+            ret.updated.push(v.eid);
+        }
+        txn.commit().await?;
+        Ok(ret)
+    }
+
+    pub async fn get_template(&self, id: i64) -> Result<Option<job_template::Model>> {
+        Ok(JobTemplate::find_by_id(id).one(&self.ctx.db).await?)
+    }
+
+    pub async fn query_template(
+        &self,
+        team_id: Option<i64>,
+        name: Option<String>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<job_template::Model>, u64)> {
+        let model = JobTemplate::find()
+            .apply_if(team_id, |query, v| {
+                query.filter(job_template::Column::TeamId.eq(v))
+            })
+            .apply_if(name, |query, v| {
+                query.filter(job_template::Column::Name.contains(v))
+            });
+        let total = model.clone().count(&self.ctx.db).await?;
+        let list = model
+            .order_by_desc(job_template::Column::UpdatedTime)
+            .paginate(&self.ctx.db, page_size)
+            .fetch_page(page)
+            .await?;
+        Ok((list, total))
+    }
+
+    /// Deletes the template, its jobs keep what they inherited.
+    pub async fn delete_template(&self, id: i64) -> Result<u64> {
+        let txn = self.ctx.db.begin().await?;
+        Job::update_many()
+            .set(job::ActiveModel {
+                template_id: Set(0),
+                template_version: Set(0),
+                ..Default::default()
+            })
+            .filter(job::Column::TemplateId.eq(id))
+            .exec(&txn)
+            .await?;
+        let ret = JobTemplate::delete_by_id(id).exec(&txn).await?;
+        txn.commit().await?;
+        Ok(ret.rows_affected)
+    }
+
+    pub async fn derived_jobs(&self, template: &job_template::Model) -> Result<Vec<DerivedJob>> {
+        let inherited = Inherited::from(template);
+        let jobs = Job::find()
+            .filter(job::Column::TemplateId.eq(template.id))
+            .filter(job::Column::IsDeleted.eq(false))
+            .order_by_asc(job::Column::Id)
+            .all(&self.ctx.db)
+            .await?;
+        Ok(jobs
+            .into_iter()
+            .map(|v| DerivedJob {
+                customized: Inherited::from(&v).diff(&inherited),
+                outdated: v.template_version < template.version,
+                template_version: v.template_version,
+                eid: v.eid,
+                name: v.name,
+            })
+            .collect())
+    }
+
+    /// Overwrites what the derived jobs inherit with the template, all of
+    /// them when no eid is given.
+    pub async fn sync_jobs(
+        &self,
+        template: &job_template::Model,
+        eids: Vec<String>,
+        username: &str,
+    ) -> Result<u64> {
+        let mut model = job::ActiveModel {
+            id: NotSet,
+            template_version: Set(template.version),
+            updated_user: Set(username.to_string()),
+            ..Default::default()
+        };
+        Inherited::from(template).apply(&mut model);
+        let ret = Job::update_many()
+            .set(model)
+            .filter(job::Column::TemplateId.eq(template.id))
+            .filter(job::Column::IsDeleted.eq(false))
+            .apply_if(Some(eids).filter(|v| !v.is_empty()), |query, v| {
+                query.filter(job::Column::Eid.is_in(v))
+            })
+            .exec(&self.ctx.db)
+            .await?;
+        Ok(ret.rows_affected)
+    }
+}
+
+#[test]
+fn test_inherited_diff() {
+    let template = job_template::Model {
+        executor_id: 1,
+        timeout: 60,
+        stop_signal: "SIGTERM".to_string(),
+        args: Some(serde_json::json!({"env": "prod"})),
+        ..Default::default()
+    };
+    let mut job = job::Model {
+        executor_id: 1,
+        timeout: 60,
+        stop_signal: "SIGTERM".to_string(),
+        args: Some(serde_json::json!({"env": "prod"})),
+        completed_callback: Some(Value::Null),
+        ..Default::default()
+    };
+    let inherited = Inherited::from(&template);
+    assert!(Inherited::from(&job).diff(&inherited).is_empty());
+
+    job.timeout = 120;
+    job.args = None;
+    assert_eq!(
+        Inherited::from(&job).diff(&inherited),
+        vec!["timeout", "args"]
+    );
+}
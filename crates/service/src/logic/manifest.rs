@@ -0,0 +1,1242 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+};
+
+use anyhow::{Result, anyhow};
+use automate::{ContainerOptions, HealthCheck, RestartPolicy};
+use chrono::Local;
+use sea_orm::{
+    ActiveModelTrait,
+    ActiveValue::{NotSet, Set},
+    ColumnTrait, ConnectionTrait, DatabaseTransaction, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    job::{delete_job, types::BundleScriptRecord},
+    types::{CompletedCallbackOpts, ResourceType, UserInfo},
+};
+use crate::{
+    IdGenerator,
+    entity::{
+        executor, job, job_bundle_script, job_supervisor, job_timer, prelude::*, tag, tag_resource,
+        team,
+    },
+    state::AppContext,
+};
+
+/// Version written to and expected in manifests.
+pub const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum ManifestFormat {
+    #[default]
+    Yaml,
+    Toml,
+}
+
+impl TryFrom<&str> for ManifestFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        match value {
+            "" | "yaml" | "yml" => Ok(Self::Yaml),
+            "toml" => Ok(Self::Toml),
+            _ => anyhow::bail!("unsupported manifest format {value}"),
+        }
+    }
+}
+
+/// Declarative definition of the jobs of a team. Resources refer to each
+/// other by name, so that the same file can be applied to any environment.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Manifest {
+    pub version: u32,
+    /// name of the team owning the jobs, no team when empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+    pub executors: Vec<ExecutorSpec>,
+    pub bundle_scripts: Vec<BundleScriptSpec>,
+    pub jobs: Vec<JobSpec>,
+    pub timers: Vec<TimerSpec>,
+    pub supervisors: Vec<SupervisorSpec>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecutorSpec {
+    pub name: String,
+    pub command: String,
+    pub platform: String,
+    pub info: String,
+    pub read_code_from_stdin: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<ContainerOptions>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BundleScriptSpec {
+    pub name: String,
+    pub executor: String,
+    pub info: String,
+    pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobBundleScriptSpec {
+    pub name: String,
+    pub cond_expr: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobSpec {
+    pub name: String,
+    pub executor: String,
+    pub info: String,
+    pub code: String,
+    /// bundle scripts run in order, makes a bundle job when not empty
+    pub bundle_scripts: Vec<JobBundleScriptSpec>,
+    pub work_dir: String,
+    pub work_user: String,
+    pub timeout: i64,
+    pub max_retry: i16,
+    pub max_parallel: i16,
+    pub stop_signal: String,
+    pub stop_grace_period: i32,
+    pub upload_file: String,
+    pub publish_artifacts: Vec<String>,
+    pub is_public: bool,
+    pub display_on_dashboard: bool,
+    pub args: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_callback: Option<Value>,
+    pub tags: Vec<String>,
+}
+
+impl Default for JobSpec {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            executor: String::new(),
+            info: String::new(),
+            code: String::new(),
+            bundle_scripts: Vec::new(),
+            work_dir: String::new(),
+            work_user: String::new(),
+            timeout: 60,
+            max_retry: 1,
+            max_parallel: 1,
+            stop_signal: "SIGTERM".to_string(),
+            stop_grace_period: 10,
+            upload_file: String::new(),
+            publish_artifacts: Vec::new(),
+            is_public: false,
+            display_on_dashboard: false,
+            args: BTreeMap::new(),
+            completed_callback: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimerSpec {
+    pub name: String,
+    pub job: String,
+    pub info: String,
+    /// the timer as saved by the console
+    pub timer_expr: Value,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SupervisorSpec {
+    pub name: String,
+    pub job: String,
+    pub info: String,
+    pub restart_interval: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restart_policy: Option<RestartPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health_check: Option<HealthCheck>,
+    pub stop_signal: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_grace_period: Option<i32>,
+}
+
+impl Default for SupervisorSpec {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            job: String::new(),
+            info: String::new(),
+            restart_interval: 1,
+            restart_policy: None,
+            health_check: None,
+            stop_signal: String::new(),
+            stop_grace_period: None,
+        }
+    }
+}
+
+impl Manifest {
+    pub fn parse(content: &str, format: ManifestFormat) -> Result<Self> {
+        let manifest: Self = match format {
+            ManifestFormat::Yaml => serde_yaml::from_str(content)?,
+            ManifestFormat::Toml => toml::from_str(content)?,
+        };
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    pub fn render(&self, format: ManifestFormat) -> Result<String> {
+        Ok(match format {
+            ManifestFormat::Yaml => serde_yaml::to_string(self)?,
+            ManifestFormat::Toml => toml::to_string(self)?,
+        })
+    }
+
+    /// Checks that names are unique, those of timers and supervisors per job,
+    /// and that timers and supervisors refer to jobs of the manifest.
+    pub fn validate(&self) -> Result<()> {
+        if self.version != MANIFEST_VERSION {
+            anyhow::bail!(
+                "unsupported manifest version {}, expected {MANIFEST_VERSION}",
+                self.version
+            );
+        }
+        unique_names("executor", self.executors.iter().map(|v| &v.name))?;
+        unique_names("bundle script", self.bundle_scripts.iter().map(|v| &v.name))?;
+        let jobs = unique_names("job", self.jobs.iter().map(|v| &v.name))?;
+
+        for v in self.jobs.iter() {
+            if v.executor.is_empty() {
+                anyhow::bail!("job {} has no executor", v.name);
+            }
+            if let Some(callback) = v.completed_callback.clone() {
+                serde_json::from_value::<CompletedCallbackOpts>(callback)
+                    .map_err(|e| anyhow!("invalid completed callback of job {}, {e}", v.name))?;
+            }
+        }
+        for v in self.bundle_scripts.iter() {
+            if v.executor.is_empty() {
+                anyhow::bail!("bundle script {} has no executor", v.name);
+            }
+        }
+        let mut seen = BTreeSet::new();
+        for (kind, name, job) in self
+            .timers
+            .iter()
+            .map(|v| ("timer", &v.name, &v.job))
+            .chain(
+                self.supervisors
+                    .iter()
+                    .map(|v| ("supervisor", &v.name, &v.job)),
+            )
+        {
+            if name.is_empty() {
+                anyhow::bail!("{kind} without a name");
+            }
+            if !jobs.contains(job.as_str()) {
+                anyhow::bail!("{kind} {name} refers to job {job} missing from the manifest");
+            }
+            if !seen.insert((kind, job.as_str(), name.as_str())) {
+                anyhow::bail!("duplicate {kind} {name} of job {job}");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn unique_names<'b>(
+    kind: &str,
+    names: impl Iterator<Item = &'b String>,
+) -> Result<BTreeSet<&'b str>> {
+    let mut seen = BTreeSet::new();
+    for name in names {
+        if name.is_empty() {
+            anyhow::bail!("{kind} without a name");
+        }
+        if !seen.insert(name.as_str()) {
+            anyhow::bail!("duplicate {kind} {name}");
+        }
+    }
+    Ok(seen)
+}
+
+/// Drops null values from a json document, toml cannot represent them and
+/// they make no difference to a saved resource.
+pub fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, without_nulls(v)))
+                .collect(),
+        ),
+        Value::Array(list) => Value::Array(list.into_iter().map(without_nulls).collect()),
+        v => v,
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeAction {
+    Create,
+    Update,
+    Delete,
+}
+
+impl Display for ChangeAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeAction::Create => write!(f, "create"),
+            ChangeAction::Update => write!(f, "update"),
+            ChangeAction::Delete => write!(f, "delete"),
+        }
+    }
+}
+
+/// A resource created, updated or deleted by applying a manifest, with the
+/// names of the fields that differ.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ManifestChange {
+    pub kind: String,
+    pub name: String,
+    pub action: ChangeAction,
+    pub fields: Vec<String>,
+}
+
+fn changed_fields<T: Serialize>(current: &T, desired: &T) -> Result<Vec<String>> {
+    let current = serde_json::to_value(current)?;
+    let desired = serde_json::to_value(desired)?;
+    let (Value::Object(current), Value::Object(desired)) = (current, desired) else {
+        return Ok(vec![]);
+    };
+    let keys: BTreeSet<&String> = current.keys().chain(desired.keys()).collect();
+    Ok(keys
+        .into_iter()
+        .filter(|k| current.get(*k) != desired.get(*k))
+        .map(|k| k.to_string())
+        .collect())
+}
+
+/// Exports the jobs of a team to a manifest and applies manifests back, in
+/// one transaction.
+pub struct ManifestLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+impl<'a> ManifestLogic<'a> {
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    /// The team a manifest belongs to, 0 for jobs without a team.
+    pub async fn resolve_team(&self, manifest: &Manifest) -> Result<i64> {
+        let Some(name) = manifest.team.as_ref().filter(|v| !v.is_empty()) else {
+            return Ok(0);
+        };
+        Team::find()
+            .filter(team::Column::Name.eq(name))
+            .one(&self.ctx.db)
+            .await?
+            .map(|v| v.id)
+            .ok_or(anyhow!("cannot found team {name}"))
+    }
+
+    /// Current definitions of the jobs of a team along with the executors
+    /// they run on.
+    pub async fn export(&self, team_id: i64) -> Result<Manifest> {
+        let db = &self.ctx.db;
+        let team = match team_id {
+            0 => None,
+            v => Some(
+                Team::find_by_id(v)
+                    .one(db)
+                    .await?
+                    .ok_or(anyhow!("cannot found team {v}"))?,
+            ),
+        };
+        let executors = executor_names(db).await?;
+
+        let bundle_scripts = JobBundleScript::find()
+            .filter(job_bundle_script::Column::TeamId.eq(team_id))
+            .filter(job_bundle_script::Column::IsDeleted.eq(false))
+            .order_by_asc(job_bundle_script::Column::Name)
+            .all(db)
+            .await?;
+        let jobs = Job::find()
+            .filter(job::Column::TeamId.eq(team_id))
+            .filter(job::Column::IsDeleted.eq(false))
+            .order_by_asc(job::Column::Name)
+            .all(db)
+            .await?;
+        let job_names: HashMap<&str, &str> = jobs
+            .iter()
+            .map(|v| (v.eid.as_str(), v.name.as_str()))
+            .collect();
+        let eids: Vec<String> = jobs.iter().map(|v| v.eid.clone()).collect();
+        let timers = JobTimer::find()
+            .filter(job_timer::Column::Eid.is_in(eids.clone()))
+            .filter(job_timer::Column::IsDeleted.eq(false))
+            .order_by_asc(job_timer::Column::Name)
+            .all(db)
+            .await?;
+        let supervisors = JobSupervisor::find()
+            .filter(job_supervisor::Column::Eid.is_in(eids))
+            .filter(job_supervisor::Column::IsDeleted.eq(false))
+            .order_by_asc(job_supervisor::Column::Name)
+            .all(db)
+            .await?;
+        let mut tags = job_tags(db, jobs.iter().map(|v| v.id).collect()).await?;
+
+        let executor_ids: BTreeSet<i64> = jobs
+            .iter()
+            .map(|v| v.executor_id)
+            .chain(bundle_scripts.iter().map(|v| v.executor_id))
+            .collect();
+        let executor_specs = Executor::find()
+            .filter(executor::Column::Id.is_in(executor_ids))
+            .order_by_asc(executor::Column::Name)
+            .all(db)
+            .await?
+            .iter()
+            .map(executor_spec)
+            .collect();
+
+        Ok(Manifest {
+            version: MANIFEST_VERSION,
+            team: team.map(|v| v.name),
+            executors: executor_specs,
+            bundle_scripts: bundle_scripts
+                .iter()
+                .map(|v| bundle_script_spec(v, &executors))
+                .collect(),
+            jobs: jobs
+                .iter()
+                .map(|v| job_spec(v, &executors, tags.remove(&v.id).unwrap_or_default()))
+                .collect::<Result<_>>()?,
+            timers: timers
+                .iter()
+                .filter_map(|v| Some(timer_spec(v, job_names.get(v.eid.as_str())?)))
+                .collect(),
+            supervisors: supervisors
+                .iter()
+                .filter_map(|v| Some(supervisor_spec(v, job_names.get(v.eid.as_str())?)))
+                .collect(),
+        })
+    }
+
+    /// Brings the jobs of the manifest's team in line with it and returns the
+    /// changes. Executors and tags are only created or updated, the other
+    /// resources of the team missing from the manifest are deleted when
+    /// `prune` is set. A dry run rolls everything back.
+    pub async fn apply(
+        &self,
+        user_info: &UserInfo,
+        manifest: &Manifest,
+        prune: bool,
+        can_change_executor: bool,
+        dry_run: bool,
+    ) -> Result<Vec<ManifestChange>> {
+        manifest.validate()?;
+        let team_id = self.resolve_team(manifest).await?;
+        let txn = self.ctx.db.begin().await?;
+        let mut applier = Applier {
+            txn: &txn,
+            user_info,
+            team_id,
+            changes: Vec::new(),
+        };
+
+        for v in manifest.executors.iter() {
+            applier.apply_executor(v, can_change_executor).await?;
+        }
+        let executors = executor_names(&txn).await?;
+        for v in manifest.bundle_scripts.iter() {
+            applier.apply_bundle_script(v, &executors).await?;
+        }
+        for v in manifest.jobs.iter() {
+            applier.apply_job(v, &executors).await?;
+        }
+        for v in manifest.timers.iter() {
+            applier.apply_timer(v).await?;
+        }
+        for v in manifest.supervisors.iter() {
+            applier.apply_supervisor(v).await?;
+        }
+        if prune {
+            applier.prune(manifest).await?;
+        }
+
+        let changes = applier.changes;
+        if dry_run {
+            txn.rollback().await?;
+        } else {
+            txn.commit().await?;
+        }
+        Ok(changes)
+    }
+}
+
+struct Applier<'b> {
+    txn: &'b DatabaseTransaction,
+    user_info: &'b UserInfo,
+    team_id: i64,
+    changes: Vec<ManifestChange>,
+}
+
+impl<'b> Applier<'b> {
+    /// Records the change from `current` to `desired`, None when there is
+    /// nothing to do.
+    fn record<T: Serialize>(
+        &mut self,
+        kind: &str,
+        name: &str,
+        current: Option<&T>,
+        desired: &T,
+    ) -> Result<Option<ChangeAction>> {
+        let (action, fields) = match current {
+            None => (ChangeAction::Create, vec![]),
+            Some(current) => match changed_fields(current, desired)? {
+                v if v.is_empty() => return Ok(None),
+                v => (ChangeAction::Update, v),
+            },
+        };
+        self.changes.push(ManifestChange {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            action,
+            fields,
+        });
+        Ok(Some(action))
+    }
+
+    fn record_delete(&mut self, kind: &str, name: &str) {
+        self.changes.push(ManifestChange {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            action: ChangeAction::Delete,
+            fields: vec![],
+        });
+    }
+
+    /// A job of the team by name, the one a timer or supervisor refers to.
+    async fn team_job(&self, name: &str) -> Result<job::Model> {
+        Job::find()
+            .filter(job::Column::Name.eq(name))
+            .filter(job::Column::TeamId.eq(self.team_id))
+            .filter(job::Column::IsDeleted.eq(false))
+            .one(self.txn)
+            .await?
+            .ok_or(anyhow!("cannot found job {name}"))
+    }
+
+    async fn apply_executor(&mut self, spec: &ExecutorSpec, can_change: bool) -> Result<()> {
+        let record = Executor::find()
+            .filter(executor::Column::Name.eq(&spec.name))
+            .one(self.txn)
+            .await?;
+        let current = record.as_ref().map(executor_spec);
+        if self
+            .record("executor", &spec.name, current.as_ref(), spec)?
+            .is_none()
+        {
+            return Ok(());
+        }
+        if !can_change {
+            anyhow::bail!("no permission to change executor {}", spec.name);
+        }
+
+        executor::ActiveModel {
+            id: record.as_ref().map_or(NotSet, |v| Set(v.id)),
+            name: Set(spec.name.clone()),
+            command: Set(spec.command.clone()),
+            platform: Set(spec.platform.clone()),
+            info: Set(spec.info.clone()),
+            read_code_from_stdin: Set(spec.read_code_from_stdin as i16),
+            container: Set(spec
+                .container
+                .as_ref()
+                .map(serde_json::to_value)
+                .transpose()?),
+            created_user: record
+                .as_ref()
+                .map_or(Set(self.user_info.username.clone()), |_| NotSet),
+            updated_user: Set(self.user_info.username.clone()),
+            ..Default::default()
+        }
+        .save(self.txn)
+        .await?;
+        Ok(())
+    }
+
+    async fn apply_bundle_script(
+        &mut self,
+        spec: &BundleScriptSpec,
+        executors: &HashMap<i64, String>,
+    ) -> Result<()> {
+        // deleted scripts keep their name, they are brought back
+        let record = JobBundleScript::find()
+            .filter(job_bundle_script::Column::Name.eq(&spec.name))
+            .one(self.txn)
+            .await?;
+        if record
+            .as_ref()
+            .is_some_and(|v| !v.is_deleted && v.team_id != self.team_id)
+        {
+            anyhow::bail!("bundle script {} belongs to another team", spec.name);
+        }
+        let current = record
+            .as_ref()
+            .filter(|v| !v.is_deleted)
+            .map(|v| bundle_script_spec(v, executors));
+        if self
+            .record("bundle script", &spec.name, current.as_ref(), spec)?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        job_bundle_script::ActiveModel {
+            id: record.as_ref().map_or(NotSet, |v| Set(v.id)),
+            eid: record
+                .as_ref()
+                .map_or(Set(IdGenerator::get_job_bundle_script_uid()), |_| NotSet),
+            executor_id: Set(executor_id(executors, &spec.executor)?),
+            team_id: Set(self.team_id),
+            name: Set(spec.name.clone()),
+            code: Set(spec.code.clone()),
+            info: Set(spec.info.clone()),
+            args: Set(spec.args.clone()),
+            created_user: record
+                .as_ref()
+                .map_or(Set(self.user_info.username.clone()), |_| NotSet),
+            updated_user: Set(self.user_info.username.clone()),
+            is_deleted: Set(false),
+            deleted_at: Set(None),
+            deleted_by: Set(String::new()),
+            ..Default::default()
+        }
+        .save(self.txn)
+        .await?;
+        Ok(())
+    }
+
+    async fn apply_job(&mut self, spec: &JobSpec, executors: &HashMap<i64, String>) -> Result<()> {
+        let record = Job::find()
+            .filter(job::Column::Name.eq(&spec.name))
+            .one(self.txn)
+            .await?;
+        if record
+            .as_ref()
+            .is_some_and(|v| !v.is_deleted && v.team_id != self.team_id)
+        {
+            anyhow::bail!("job {} belongs to another team", spec.name);
+        }
+
+        // jobs keep a copy of their bundle scripts, a changed script is a
+        // changed job
+        let mut bundle_script = Vec::new();
+        for v in spec.bundle_scripts.iter() {
+            let script = JobBundleScript::find()
+                .filter(job_bundle_script::Column::Name.eq(&v.name))
+                .filter(job_bundle_script::Column::IsDeleted.eq(false))
+                .one(self.txn)
+                .await?
+                .ok_or(anyhow!(
+                    "job {} refers to unknown bundle script {}",
+                    spec.name,
+                    v.name
+                ))?;
+            bundle_script.push(BundleScriptRecord {
+                eid: script.eid,
+                name: script.name,
+                code: script.code,
+                executor_id: script.executor_id,
+                info: script.info,
+                cond_expr: v.cond_expr.clone(),
+            });
+        }
+        let bundle_script = match bundle_script.is_empty() {
+            true => None,
+            false => Some(serde_json::to_value(bundle_script)?),
+        };
+
+        let current = record.as_ref().filter(|v| !v.is_deleted);
+        let current_tags = match record.as_ref() {
+            Some(v) => job_tags(self.txn, vec![v.id])
+                .await?
+                .remove(&v.id)
+                .unwrap_or_default(),
+            None => vec![],
+        };
+        let current_spec = current
+            .map(|v| job_spec(v, executors, current_tags.clone()))
+            .transpose()?;
+        let mut desired = spec.clone();
+        desired.tags = desired
+            .tags
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let scripts_changed = current.is_some_and(|v| v.bundle_script != bundle_script);
+        match self.record("job", &spec.name, current_spec.as_ref(), &desired)? {
+            None if scripts_changed => self.changes.push(ManifestChange {
+                kind: "job".to_string(),
+                name: spec.name.clone(),
+                action: ChangeAction::Update,
+                fields: vec!["bundle_scripts".to_string()],
+            }),
+            None => return Ok(()),
+            Some(_) => {}
+        };
+
+        let args = match desired.args.is_empty() {
+            true => None,
+            false => Some(serde_json::to_value(&desired.args)?),
+        };
+        let publish_artifacts = match desired.publish_artifacts.is_empty() {
+            true => None,
+            false => Some(serde_json::to_value(&desired.publish_artifacts)?),
+        };
+        let saved = job::ActiveModel {
+            id: record.as_ref().map_or(NotSet, |v| Set(v.id)),
+            eid: record
+                .as_ref()
+                .map_or(Set(IdGenerator::get_job_eid()), |_| NotSet),
+            team_id: Set(self.team_id),
+            executor_id: Set(executor_id(executors, &desired.executor)?),
+            job_type: Set(match bundle_script {
+                Some(_) => "bundle".to_string(),
+                None => "default".to_string(),
+            }),
+            name: Set(desired.name.clone()),
+            code: Set(desired.code.clone()),
+            info: Set(desired.info.clone()),
+            bundle_script: Set(bundle_script),
+            upload_file: Set(desired.upload_file.clone()),
+            work_dir: Set(desired.work_dir.clone()),
+            work_user: Set(desired.work_user.clone()),
+            timeout: Set(desired.timeout),
+            max_retry: Set(desired.max_retry),
+            max_parallel: Set(desired.max_parallel),
+            stop_signal: Set(desired.stop_signal.clone()),
+            stop_grace_period: Set(desired.stop_grace_period),
+            completed_callback: Set(desired.completed_callback.clone()),
+            publish_artifacts: Set(publish_artifacts),
+            is_public: Set(desired.is_public as i16),
+            display_on_dashboard: Set(desired.display_on_dashboard),
+            created_user: record
+                .as_ref()
+                .map_or(Set(self.user_info.username.clone()), |_| NotSet),
+            updated_user: Set(self.user_info.username.clone()),
+            args: Set(args),
+            is_deleted: Set(false),
+            deleted_at: Set(None),
+            deleted_by: Set(String::new()),
+            ..Default::default()
+        }
+        .save(self.txn)
+        .await?;
+        self.sync_tags(saved.id.as_ref().to_owned(), &current_tags, &desired.tags)
+            .await
+    }
+
+    async fn sync_tags(&self, job_id: i64, current: &[String], desired: &[String]) -> Result<()> {
+        let removed: Vec<&String> = current.iter().filter(|v| !desired.contains(v)).collect();
+        if !removed.is_empty() {
+            let tag_ids: Vec<i64> = Tag::find()
+                .filter(tag::Column::TagName.is_in(removed))
+                .all(self.txn)
+                .await?
+                .into_iter()
+                .map(|v| v.id)
+                .collect();
+            TagResource::delete_many()
+                .filter(tag_resource::Column::TagId.is_in(tag_ids))
+                .filter(tag_resource::Column::ResourceType.eq(ResourceType::Job.to_string()))
+                .filter(tag_resource::Column::ResourceId.eq(job_id))
+                .exec(self.txn)
+                .await?;
+        }
+
+        for name in desired.iter().filter(|v| !current.contains(v)) {
+            let tag_id = match Tag::find()
+                .filter(tag::Column::TagName.eq(name))
+                .one(self.txn)
+                .await?
+            {
+                Some(v) => v.id,
+                None => {
+                    tag::ActiveModel {
+                        tag_name: Set(name.clone()),
+                        created_user: Set(self.user_info.username.clone()),
+                        ..Default::default()
+                    }
+                    .insert(self.txn)
+                    .await?
+                    .id
+                }
+            };
+            tag_resource::ActiveModel {
+                tag_id: Set(tag_id),
+                resource_type: Set(ResourceType::Job.to_string()),
+                resource_id: Set(job_id),
+                created_user: Set(self.user_info.username.clone()),
+                ..Default::default()
+            }
+            .insert(self.txn)
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn apply_timer(&mut self, spec: &TimerSpec) -> Result<()> {
+        let job = self.team_job(&spec.job).await?;
+        // names are unique per job only
+        let record = JobTimer::find()
+            .filter(job_timer::Column::Name.eq(&spec.name))
+            .filter(job_timer::Column::Eid.eq(&job.eid))
+            .one(self.txn)
+            .await?;
+        let current = record
+            .as_ref()
+            .filter(|v| !v.is_deleted)
+            .map(|v| timer_spec(v, &job.name));
+        let desired = TimerSpec {
+            timer_expr: without_nulls(spec.timer_expr.clone()),
+            ..spec.clone()
+        };
+        if self
+            .record("timer", &spec.name, current.as_ref(), &desired)?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        job_timer::ActiveModel {
+            id: record.as_ref().map_or(NotSet, |v| Set(v.id)),
+            name: Set(desired.name),
+            eid: Set(job.eid),
+            timer_expr: Set(Some(desired.timer_expr)),
+            job_type: Set(job.job_type),
+            info: Set(desired.info),
+            created_user: record
+                .as_ref()
+                .map_or(Set(self.user_info.username.clone()), |_| NotSet),
+            updated_user: Set(self.user_info.username.clone()),
+            is_deleted: Set(false),
+            deleted_at: Set(None),
+            deleted_by: Set(String::new()),
+            ..Default::default()
+        }
+        .save(self.txn)
+        .await?;
+        Ok(())
+    }
+
+    async fn apply_supervisor(&mut self, spec: &SupervisorSpec) -> Result<()> {
+        let job = self.team_job(&spec.job).await?;
+        // names are unique per job only
+        let record = JobSupervisor::find()
+            .filter(job_supervisor::Column::Name.eq(&spec.name))
+            .filter(job_supervisor::Column::Eid.eq(&job.eid))
+            .one(self.txn)
+            .await?;
+        let current = record
+            .as_ref()
+            .filter(|v| !v.is_deleted)
+            .map(|v| supervisor_spec(v, &job.name));
+        let desired = SupervisorSpec {
+            restart_interval: spec.restart_interval.max(1),
+            ..spec.clone()
+        };
+        if self
+            .record("supervisor", &spec.name, current.as_ref(), &desired)?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        job_supervisor::ActiveModel {
+            id: record.as_ref().map_or(NotSet, |v| Set(v.id)),
+            name: Set(desired.name),
+            eid: Set(job.eid),
+            restart_interval: Set(desired.restart_interval),
+            restart_policy: Set(desired
+                .restart_policy
+                .map(serde_json::to_value)
+                .transpose()?),
+            health_check: Set(desired.health_check.map(serde_json::to_value).transpose()?),
+            stop_signal: Set(desired.stop_signal),
+            stop_grace_period: Set(desired.stop_grace_period),
+            info: Set(desired.info),
+            created_user: record
+                .as_ref()
+                .map_or(Set(self.user_info.username.clone()), |_| NotSet),
+            updated_user: Set(self.user_info.username.clone()),
+            is_deleted: Set(false),
+            deleted_at: Set(None),
+            deleted_by: Set(String::new()),
+            ..Default::default()
+        }
+        .save(self.txn)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes what the team has beyond the manifest, timers and supervisors
+    /// first since jobs linked to them cannot be deleted.
+    async fn prune(&mut self, manifest: &Manifest) -> Result<()> {
+        let jobs = Job::find()
+            .filter(job::Column::TeamId.eq(self.team_id))
+            .filter(job::Column::IsDeleted.eq(false))
+            .all(self.txn)
+            .await?;
+        let eids: Vec<String> = jobs.iter().map(|v| v.eid.clone()).collect();
+        let job_names: HashMap<String, String> = jobs
+            .iter()
+            .map(|v| (v.eid.clone(), v.name.clone()))
+            .collect();
+        let job_name = |eid: &str| job_names.get(eid).map_or("", |v| v.as_str());
+        let deleted = |user_info: &UserInfo| {
+            (
+                Set(true),
+                Set(Some(Local::now())),
+                Set(user_info.username.clone()),
+            )
+        };
+
+        let timers = JobTimer::find()
+            .filter(job_timer::Column::Eid.is_in(eids.clone()))
+            .filter(job_timer::Column::IsDeleted.eq(false))
+            .all(self.txn)
+            .await?;
+        for v in timers.into_iter().filter(|v| {
+            !manifest
+                .timers
+                .iter()
+                .any(|t| t.name == v.name && t.job == job_name(&v.eid))
+        }) {
+            let (is_deleted, deleted_at, deleted_by) = deleted(self.user_info);
+            job_timer::ActiveModel {
+                id: Set(v.id),
+                is_deleted,
+                deleted_at,
+                deleted_by,
+                ..Default::default()
+            }
+            .update(self.txn)
+            .await?;
+            self.record_delete("timer", &v.name);
+        }
+
+        let supervisors = JobSupervisor::find()
+            .filter(job_supervisor::Column::Eid.is_in(eids))
+            .filter(job_supervisor::Column::IsDeleted.eq(false))
+            .all(self.txn)
+            .await?;
+        for v in supervisors.into_iter().filter(|v| {
+            !manifest
+                .supervisors
+                .iter()
+                .any(|s| s.name == v.name && s.job == job_name(&v.eid))
+        }) {
+            let (is_deleted, deleted_at, deleted_by) = deleted(self.user_info);
+            job_supervisor::ActiveModel {
+                id: Set(v.id),
+                is_deleted,
+                deleted_at,
+                deleted_by,
+                ..Default::default()
+            }
+            .update(self.txn)
+            .await?;
+            self.record_delete("supervisor", &v.name);
+        }
+
+        for v in jobs
+            .into_iter()
+            .filter(|v| !manifest.jobs.iter().any(|j| j.name == v.name))
+        {
+            delete_job(self.txn, self.user_info, &v.eid).await?;
+            self.record_delete("job", &v.name);
+        }
+
+        let bundle_scripts = JobBundleScript::find()
+            .filter(job_bundle_script::Column::TeamId.eq(self.team_id))
+            .filter(job_bundle_script::Column::IsDeleted.eq(false))
+            .all(self.txn)
+            .await?;
+        for v in bundle_scripts
+            .into_iter()
+            .filter(|v| !manifest.bundle_scripts.iter().any(|b| b.name == v.name))
+        {
+            let (is_deleted, deleted_at, deleted_by) = deleted(self.user_info);
+            job_bundle_script::ActiveModel {
+                id: Set(v.id),
+                is_deleted,
+                deleted_at,
+                deleted_by,
+                ..Default::default()
+            }
+            .update(self.txn)
+            .await?;
+            self.record_delete("bundle script", &v.name);
+        }
+        Ok(())
+    }
+}
+
+async fn executor_names<C: ConnectionTrait>(db: &C) -> Result<HashMap<i64, String>> {
+    Ok(Executor::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|v| (v.id, v.name))
+        .collect())
+}
+
+fn executor_id(executors: &HashMap<i64, String>, name: &str) -> Result<i64> {
+    executors
+        .iter()
+        .find(|(_, v)| v.as_str() == name)
+        .map(|(k, _)| *k)
+        .ok_or(anyhow!("cannot found executor {name}"))
+}
+
+/// Sorted tag names by job id.
+async fn job_tags<C: ConnectionTrait>(
+    db: &C,
+    job_ids: Vec<i64>,
+) -> Result<HashMap<i64, Vec<String>>> {
+    let binds = TagResource::find()
+        .filter(tag_resource::Column::ResourceType.eq(ResourceType::Job.to_string()))
+        .filter(tag_resource::Column::ResourceId.is_in(job_ids))
+        .all(db)
+        .await?;
+    let names: HashMap<i64, String> = Tag::find()
+        .filter(tag::Column::Id.is_in(binds.iter().map(|v| v.tag_id).collect::<Vec<_>>()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|v| (v.id, v.tag_name))
+        .collect();
+
+    let mut tags: HashMap<i64, BTreeSet<String>> = HashMap::new();
+    for v in binds {
+        if let Some(name) = names.get(&v.tag_id) {
+            tags.entry(v.resource_id).or_default().insert(name.clone());
+        }
+    }
+    Ok(tags
+        .into_iter()
+        .map(|(k, v)| (k, v.into_iter().collect()))
+        .collect())
+}
+
+fn executor_spec(record: &executor::Model) -> ExecutorSpec {
+    ExecutorSpec {
+        name: record.name.clone(),
+        command: record.command.clone(),
+        platform: record.platform.clone(),
+        info: record.info.clone(),
+        read_code_from_stdin: record.read_code_from_stdin != 0,
+        container: record
+            .container
+            .clone()
+            .and_then(|v| serde_json::from_value(v).ok()),
+    }
+}
+
+fn bundle_script_spec(
+    record: &job_bundle_script::Model,
+    executors: &HashMap<i64, String>,
+) -> BundleScriptSpec {
+    BundleScriptSpec {
+        name: record.name.clone(),
+        executor: executors
+            .get(&record.executor_id)
+            .cloned()
+            .unwrap_or_default(),
+        info: record.info.clone(),
+        code: record.code.clone(),
+        args: record.args.clone().map(without_nulls),
+    }
+}
+
+fn job_spec(
+    record: &job::Model,
+    executors: &HashMap<i64, String>,
+    tags: Vec<String>,
+) -> Result<JobSpec> {
+    let bundle_scripts: Vec<BundleScriptRecord> = record
+        .bundle_script
+        .clone()
+        .map(serde_json::from_value)
+        .transpose()?
+        .unwrap_or_default();
+    Ok(JobSpec {
+        name: record.name.clone(),
+        executor: executors
+            .get(&record.executor_id)
+            .cloned()
+            .unwrap_or_default(),
+        info: record.info.clone(),
+        code: record.code.clone(),
+        bundle_scripts: bundle_scripts
+            .into_iter()
+            .map(|v| JobBundleScriptSpec {
+                name: v.name,
+                cond_expr: v.cond_expr,
+            })
+            .collect(),
+        work_dir: record.work_dir.clone(),
+        work_user: record.work_user.clone(),
+        timeout: record.timeout,
+        max_retry: record.max_retry,
+        max_parallel: record.max_parallel,
+        stop_signal: record.stop_signal.clone(),
+        stop_grace_period: record.stop_grace_period,
+        upload_file: record.upload_file.clone(),
+        publish_artifacts: record
+            .publish_artifacts
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default(),
+        is_public: record.is_public != 0,
+        display_on_dashboard: record.display_on_dashboard,
+        args: record
+            .args
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default(),
+        completed_callback: record.completed_callback.clone().map(without_nulls),
+        tags,
+    })
+}
+
+fn timer_spec(record: &job_timer::Model, job_name: &str) -> TimerSpec {
+    TimerSpec {
+        name: record.name.clone(),
+        job: job_name.to_string(),
+        info: record.info.clone(),
+        timer_expr: record
+            .timer_expr
+            .clone()
+            .map(without_nulls)
+            .unwrap_or_default(),
+    }
+}
+
+fn supervisor_spec(record: &job_supervisor::Model, job_name: &str) -> SupervisorSpec {
+    SupervisorSpec {
+        name: record.name.clone(),
+        job: job_name.to_string(),
+        info: record.info.clone(),
+        restart_interval: record.restart_interval,
+        restart_policy: record
+            .restart_policy
+            .clone()
+            .and_then(|v| serde_json::from_value(v).ok()),
+        health_check: record
+            .health_check
+            .clone()
+            .and_then(|v| serde_json::from_value(v).ok()),
+        stop_signal: record.stop_signal.clone(),
+        stop_grace_period: record.stop_grace_period,
+    }
+}
+
+#[test]
+fn test_manifest() {
+    let content = r#"
+version: 1
+team: ops
+executors:
+  - name: bash
+    command: bash -c
+    platform: linux
+jobs:
+  - name: backup
+    executor: bash
+    code: tar czf /tmp/etc.tgz /etc
+    args:
+      target: /tmp
+    tags: [nightly]
+timers:
+  - name: backup-nightly
+    job: backup
+    timer_expr:
+      kind: cron
+      hour: "2"
+      run_at: null
+"#;
+    let manifest = Manifest::parse(content, ManifestFormat::Yaml).unwrap();
+    assert_eq!(manifest.team.as_deref(), Some("ops"));
+    assert_eq!(manifest.jobs[0].timeout, 60);
+    assert_eq!(manifest.jobs[0].stop_signal, "SIGTERM");
+
+    let mut exported = manifest.clone();
+    exported.timers[0].timer_expr = without_nulls(exported.timers[0].timer_expr.clone());
+    let toml = exported.render(ManifestFormat::Toml).unwrap();
+    assert_eq!(
+        Manifest::parse(&toml, ManifestFormat::Toml).unwrap(),
+        exported
+    );
+    let yaml = exported.render(ManifestFormat::Yaml).unwrap();
+    assert_eq!(
+        Manifest::parse(&yaml, ManifestFormat::Yaml).unwrap(),
+        exported
+    );
+
+    let mut changed = manifest.jobs[0].clone();
+    changed.timeout = 120;
+    changed.tags.push("weekly".to_string());
+    assert_eq!(
+        changed_fields(&manifest.jobs[0], &changed).unwrap(),
+        vec!["tags", "timeout"]
+    );
+
+    assert!(Manifest::parse("version: 2", ManifestFormat::Yaml).is_err());
+    assert!(Manifest::parse("version: 1\nunknown: 1", ManifestFormat::Yaml).is_err());
+    assert!(
+        Manifest::parse(
+            "version: 1\ntimers:\n  - name: t\n    job: missing\n",
+            ManifestFormat::Yaml
+        )
+        .is_err()
+    );
+    let duplicate = "version = 1\n[[jobs]]\nname = \"a\"\nexecutor = \"bash\"\n\
+        [[jobs]]\nname = \"a\"\nexecutor = \"bash\"\n";
+    assert!(Manifest::parse(duplicate, ManifestFormat::Toml).is_err());
+
+    // timer names are unique per job
+    let mut timers = manifest.clone();
+    timers.jobs.push(JobSpec {
+        name: "restore".to_string(),
+        ..manifest.jobs[0].clone()
+    });
+    timers.timers.push(TimerSpec {
+        job: "restore".to_string(),
+        ..manifest.timers[0].clone()
+    });
+    assert!(timers.validate().is_ok());
+    timers.timers.push(manifest.timers[0].clone());
+    assert!(timers.validate().is_err());
+}
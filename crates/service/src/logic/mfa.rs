@@ -0,0 +1,364 @@
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use crypto::{hmac::Hmac, mac::Mac, sha1::Sha1};
+use nanoid::nanoid;
+use reqwest::Url;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::entity::{prelude::*, user_mfa};
+use crate::state::AppContext;
+
+use super::permission::{GrantAction, PermissionLogic};
+use super::user::UserLogic;
+
+const BASE32_ALPHABET: [char; 32] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
+    'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '2', '3', '4', '5', '6', '7',
+];
+
+const RECOVERY_ALPHABET: [char; 32] = [
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'j', 'k', 'm', 'n', 'p', 'q', 'r', 's', 't', 'u', 'v',
+    'w', 'x', 'y', 'z', '2', '3', '4', '5', '6', '7', '8', '9', '0',
+];
+
+const RECOVERY_CODE_COUNT: usize = 10;
+const TOTP_PERIOD: i64 = 30;
+const TOTP_DIGITS: u32 = 6;
+
+fn default_issuer() -> String {
+    "jiascheduler".to_string()
+}
+
+fn default_step_up_secs() -> i64 {
+    300
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MfaOptions {
+    /// shown by authenticator apps next to the account
+    #[serde(default = "default_issuer")]
+    pub issuer: String,
+    /// require 2fa for users who can manage instances or were granted ssh
+    /// on some instance group, roles with the mfa_require permission always
+    /// need it
+    #[serde(default)]
+    pub require_for_privileged: bool,
+    /// how long a verified code allows opening web ssh
+    #[serde(default = "default_step_up_secs")]
+    pub step_up_secs: i64,
+}
+
+impl Default for MfaOptions {
+    fn default() -> Self {
+        Self {
+            issuer: default_issuer(),
+            require_for_privileged: false,
+            step_up_secs: default_step_up_secs(),
+        }
+    }
+}
+
+/// A login whose password was checked and which still waits for the second
+/// factor, kept in the session instead of the user info.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MfaPending {
+    pub user_id: String,
+    pub username: String,
+    pub expired_time: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MfaStatus {
+    pub enabled: bool,
+    pub required: bool,
+    pub recovery_codes_left: usize,
+}
+
+fn base32_decode(input: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for c in input.trim_end_matches('=').chars() {
+        let v = BASE32_ALPHABET
+            .iter()
+            .position(|&v| v == c.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | v as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
+/// RFC 6238 code of a time step, HMAC-SHA1 with six digits.
+fn totp(key: &[u8], step: i64) -> u32 {
+    let mut mac = Hmac::new(Sha1::new(), key);
+    mac.input(&step.to_be_bytes());
+    let hash = mac.result();
+    let hash = hash.code();
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let bin = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    bin % 10u32.pow(TOTP_DIGITS)
+}
+
+/// The matching step within one step of clock drift.
+fn match_totp(key: &[u8], code: &str, now: i64) -> Option<i64> {
+    let code: u32 = code.parse().ok()?;
+    let current = now / TOTP_PERIOD;
+    (current - 1..=current + 1).find(|&step| totp(key, step) == code)
+}
+
+fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+pub struct MfaLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+impl<'a> MfaLogic<'a> {
+    pub const PENDING_SESS_KEY: &'static str = "MFA_PENDING";
+    pub const VERIFIED_SESS_KEY: &'static str = "MFA_VERIFIED_TIME";
+    /// how long the second step of a login may take
+    pub const PENDING_SECS: i64 = 300;
+
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    pub fn options(&self) -> &MfaOptions {
+        &self.ctx.conf.auth.mfa
+    }
+
+    async fn get(&self, user_id: &str) -> Result<Option<user_mfa::Model>> {
+        Ok(UserMfa::find()
+            .filter(user_mfa::Column::UserId.eq(user_id))
+            .one(&self.ctx.db)
+            .await?)
+    }
+
+    async fn get_enabled(&self, user_id: &str) -> Result<user_mfa::Model> {
+        self.get(user_id)
+            .await?
+            .filter(|v| v.enabled)
+            .ok_or(anyhow!("two-factor authentication is not enabled"))
+    }
+
+    pub async fn is_enabled(&self, user_id: &str) -> Result<bool> {
+        Ok(self.get(user_id).await?.is_some_and(|v| v.enabled))
+    }
+
+    pub async fn is_required(&self, user_id: &str) -> Result<bool> {
+        if self.ctx.enforce((user_id, "mfa", "require")).await? {
+            return Ok(true);
+        }
+        if !self.options().require_for_privileged {
+            return Ok(false);
+        }
+        Ok(self.ctx.can_manage_instance(user_id).await?
+            || PermissionLogic::new(self.ctx)
+                .has_granted_action(user_id, GrantAction::Ssh)
+                .await?)
+    }
+
+    pub async fn status(&self, user_id: &str) -> Result<MfaStatus> {
+        let record = self.get(user_id).await?.filter(|v| v.enabled);
+        Ok(MfaStatus {
+            enabled: record.is_some(),
+            required: self.is_required(user_id).await?,
+            recovery_codes_left: record
+                .map(|v| {
+                    serde_json::from_str::<Vec<String>>(&v.recovery_codes)
+                        .unwrap_or_default()
+                        .len()
+                })
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Starts an enrollment with a new secret, returns the secret and the
+    /// otpauth url to be shown as a qr code.
+    pub async fn enroll(&self, user_id: &str, username: &str) -> Result<(String, String)> {
+        let record = self.get(user_id).await?;
+        if record.as_ref().is_some_and(|v| v.enabled) {
+            anyhow::bail!("two-factor authentication is already enabled");
+        }
+
+        let secret = nanoid!(32, &BASE32_ALPHABET);
+        let encrypted = self.ctx.encrypt(secret.clone())?;
+        match record {
+            Some(v) => {
+                let mut model: user_mfa::ActiveModel = v.into();
+                model.secret = Set(encrypted);
+                model.last_used_step = Set(0);
+                model.update(&self.ctx.db).await?;
+            }
+            None => {
+                user_mfa::ActiveModel {
+                    user_id: Set(user_id.to_string()),
+                    secret: Set(encrypted),
+                    enabled: Set(false),
+                    recovery_codes: Set("[]".to_string()),
+                    ..Default::default()
+                }
+                .insert(&self.ctx.db)
+                .await?;
+            }
+        }
+
+        let issuer = &self.options().issuer;
+        let mut url = Url::parse("otpauth://totp/")?;
+        url.set_path(&format!("{issuer}:{username}"));
+        url.query_pairs_mut()
+            .append_pair("secret", &secret)
+            .append_pair("issuer", issuer)
+            .append_pair("algorithm", "SHA1")
+            .append_pair("digits", &TOTP_DIGITS.to_string())
+            .append_pair("period", &TOTP_PERIOD.to_string());
+        Ok((secret, url.to_string()))
+    }
+
+    /// Finishes the enrollment once the app produced a valid code, returns
+    /// the recovery codes which are shown only this once.
+    pub async fn activate(&self, user_id: &str, code: &str) -> Result<Vec<String>> {
+        let record = self
+            .get(user_id)
+            .await?
+            .ok_or(anyhow!("two-factor enrollment was not started"))?;
+        if record.enabled {
+            anyhow::bail!("two-factor authentication is already enabled");
+        }
+        let step = self
+            .match_code(&record, code)?
+            .ok_or(anyhow!("invalid two-factor code"))?;
+
+        let (codes, hashed) = Self::new_recovery_codes(user_id);
+        let mut model: user_mfa::ActiveModel = record.into();
+        model.enabled = Set(true);
+        model.recovery_codes = Set(hashed);
+        model.last_used_step = Set(step);
+        model.update(&self.ctx.db).await?;
+        Ok(codes)
+    }
+
+    /// Checks a code of the authenticator app or consumes a recovery code.
+    pub async fn verify(&self, user_id: &str, code: &str) -> Result<()> {
+        let record = self.get_enabled(user_id).await?;
+
+        if let Some(step) = self.match_code(&record, code)? {
+            let mut model: user_mfa::ActiveModel = record.into();
+            model.last_used_step = Set(step);
+            model.update(&self.ctx.db).await?;
+            return Ok(());
+        }
+
+        let mut codes: Vec<String> =
+            serde_json::from_str(&record.recovery_codes).unwrap_or_default();
+        let hashed = UserLogic::encry_password(normalize_code(code), user_id.to_string());
+        let Some(pos) = codes.iter().position(|v| v == &hashed) else {
+            anyhow::bail!("invalid two-factor code");
+        };
+        codes.remove(pos);
+        let mut model: user_mfa::ActiveModel = record.into();
+        model.recovery_codes = Set(serde_json::to_string(&codes)?);
+        model.update(&self.ctx.db).await?;
+        Ok(())
+    }
+
+    pub async fn regenerate_recovery_codes(
+        &self,
+        user_id: &str,
+        code: &str,
+    ) -> Result<Vec<String>> {
+        self.verify(user_id, code).await?;
+        let record = self.get_enabled(user_id).await?;
+        let (codes, hashed) = Self::new_recovery_codes(user_id);
+        let mut model: user_mfa::ActiveModel = record.into();
+        model.recovery_codes = Set(hashed);
+        model.update(&self.ctx.db).await?;
+        Ok(codes)
+    }
+
+    pub async fn disable(&self, user_id: &str, code: &str) -> Result<()> {
+        if self.is_required(user_id).await? {
+            anyhow::bail!("two-factor authentication is required for your role");
+        }
+        self.verify(user_id, code).await?;
+        self.reset(user_id).await
+    }
+
+    /// Drops the enrollment, used by admins when a user lost the device.
+    pub async fn reset(&self, user_id: &str) -> Result<()> {
+        UserMfa::delete_many()
+            .filter(user_mfa::Column::UserId.eq(user_id))
+            .exec(&self.ctx.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Web ssh needs a code verified within the last `step_up_secs`.
+    pub async fn check_step_up(&self, user_id: &str, verified_time: Option<i64>) -> Result<()> {
+        if !self.is_enabled(user_id).await? {
+            if self.is_required(user_id).await? {
+                anyhow::bail!("two-factor authentication is required, enroll it first");
+            }
+            return Ok(());
+        }
+        let fresh = verified_time
+            .is_some_and(|v| Utc::now().timestamp() - v <= self.options().step_up_secs);
+        if !fresh {
+            anyhow::bail!("enter a two-factor code again before opening a terminal");
+        }
+        Ok(())
+    }
+
+    /// Steps already used are rejected so a code cannot be replayed.
+    fn match_code(&self, record: &user_mfa::Model, code: &str) -> Result<Option<i64>> {
+        let secret = self.ctx.decrypt(record.secret.clone())?;
+        let key = base32_decode(&secret).ok_or(anyhow!("invalid two-factor secret"))?;
+        Ok(match_totp(&key, code.trim(), Utc::now().timestamp())
+            .filter(|&step| step > record.last_used_step))
+    }
+
+    fn new_recovery_codes(user_id: &str) -> (Vec<String>, String) {
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| {
+                let v = nanoid!(10, &RECOVERY_ALPHABET);
+                format!("{}-{}", &v[..5], &v[5..])
+            })
+            .collect();
+        let hashed: Vec<String> = codes
+            .iter()
+            .map(|v| UserLogic::encry_password(normalize_code(v), user_id.to_string()))
+            .collect();
+        (
+            codes,
+            serde_json::to_string(&hashed).unwrap_or("[]".to_string()),
+        )
+    }
+}
+
+#[test]
+fn test_totp() {
+    // RFC 6238 appendix B, sha1 seed truncated to six digits
+    let key = base32_decode("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap();
+    assert_eq!(key, b"12345678901234567890");
+    assert_eq!(totp(&key, 59 / TOTP_PERIOD), 287082);
+    assert_eq!(totp(&key, 1111111109 / TOTP_PERIOD), 81804);
+    assert_eq!(totp(&key, 20000000000 / TOTP_PERIOD), 353130);
+
+    assert_eq!(match_totp(&key, "287082", 59 + 30), Some(1));
+    assert_eq!(match_totp(&key, "287082", 59 + 90), None);
+    assert_eq!(normalize_code(" AbCde-12345 "), "abcde12345");
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use sea_orm::{ConnectionTrait, Statement};
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
 
 use crate::state::AppContext;
 
@@ -56,6 +56,9 @@ impl<'a> MigrationLogic<'a> {
     }
 
     pub async fn migrate(&self, ver: &str) -> Result<u64> {
+        if self.ctx.db.get_database_backend() != DbBackend::MySql {
+            anyhow::bail!("version {ver} can only be migrated on mysql");
+        }
         let sql = self.version(ver)?;
         let ret = self.ctx.db.execute_unprepared(sql).await?;
         Ok(ret.rows_affected())
@@ -63,18 +66,28 @@ impl<'a> MigrationLogic<'a> {
 
     pub async fn get_database(&self, db: &str) -> Result<Option<(String, String)>> {
         let backend = self.ctx.db.get_database_backend();
-        let ret = self
-            .ctx
-            .db
-            .query_one(Statement::from_string(
+        let stmt = match backend {
+            DbBackend::MySql => {
+                Statement::from_string(backend, format!("show create database {db}"))
+            }
+            DbBackend::Postgres => Statement::from_sql_and_values(
+                backend,
+                r#"select datname::text,
+                    'CREATE DATABASE ' || quote_ident(datname) || ' ENCODING ' || quote_literal(pg_encoding_to_char(encoding))
+                from pg_database where datname = $1"#,
+                [db.into()],
+            ),
+            // sqlite has a single database per file
+            DbBackend::Sqlite => Statement::from_string(
                 backend,
-                format!("show create database {db}"),
-            ))
-            .await?
-            .map_or(Ok(None::<(String, String)>), |ret| {
-                let v1 = ret.try_get_by_index::<String>(0);
-                v1.and_then(|v1| ret.try_get_by_index::<String>(1).map(|v2| Some((v1, v2))))
-            })?;
+                "select name, file from pragma_database_list where name = 'main'",
+            ),
+        };
+        let ret = self.ctx.db.query_one(stmt).await?;
+        let ret = ret.map_or(Ok(None::<(String, String)>), |ret| {
+            let v1 = ret.try_get_by_index::<String>(0);
+            v1.and_then(|v1| ret.try_get_by_index::<String>(1).map(|v2| Some((v1, v2))))
+        })?;
         Ok(ret)
     }
 }
@@ -1,13 +1,26 @@
 use sea_orm::ActiveValue::{self, NotSet, Set};
 
+pub mod artifact;
+pub mod auth;
+pub mod calendar;
+pub mod credential;
 pub mod executor;
 pub mod instance;
 pub mod job;
+pub mod job_template;
+pub mod manifest;
+pub mod mfa;
 pub mod migration;
+pub mod permission;
+pub mod retention;
 pub mod role;
+pub mod script_module;
+pub mod security;
+pub mod session;
 pub mod ssh;
 pub mod tag;
 pub mod team;
+pub mod terminal;
 pub mod types;
 pub mod user;
 
@@ -0,0 +1,560 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use anyhow::{Result, anyhow};
+use automate::scheduler::types::JobAction;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryTrait, Set,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::entity::{
+    instance, instance_group, instance_role, job, job_supervisor, job_timer, prelude::*,
+    resource_permission, role, team, team_member, user,
+};
+use crate::state::AppContext;
+
+use super::types::UserInfo;
+
+/// Resources which can be granted to a user or a team.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GrantResource {
+    Job,
+    Timer,
+    Supervisor,
+    InstanceGroup,
+}
+
+impl TryFrom<&str> for GrantResource {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(match value {
+            "job" => GrantResource::Job,
+            "timer" => GrantResource::Timer,
+            "supervisor" => GrantResource::Supervisor,
+            "instance_group" => GrantResource::InstanceGroup,
+            v => return Err(anyhow!("invalid resource type {v}")),
+        })
+    }
+}
+
+impl Display for GrantResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GrantResource::Job => write!(f, "job"),
+            GrantResource::Timer => write!(f, "timer"),
+            GrantResource::Supervisor => write!(f, "supervisor"),
+            GrantResource::InstanceGroup => write!(f, "instance_group"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GrantAction {
+    View,
+    Edit,
+    Dispatch,
+    Kill,
+    Ssh,
+}
+
+impl GrantAction {
+    /// Stopping a running job needs kill, everything else starts one.
+    pub fn for_job_action(action: &JobAction) -> Self {
+        match action {
+            JobAction::Kill | JobAction::StopTimer | JobAction::StopSupervising => {
+                GrantAction::Kill
+            }
+            _ => GrantAction::Dispatch,
+        }
+    }
+}
+
+impl TryFrom<&str> for GrantAction {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(match value {
+            "view" => GrantAction::View,
+            "edit" => GrantAction::Edit,
+            "dispatch" => GrantAction::Dispatch,
+            "kill" => GrantAction::Kill,
+            "ssh" => GrantAction::Ssh,
+            v => return Err(anyhow!("invalid action {v}")),
+        })
+    }
+}
+
+impl Display for GrantAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GrantAction::View => write!(f, "view"),
+            GrantAction::Edit => write!(f, "edit"),
+            GrantAction::Dispatch => write!(f, "dispatch"),
+            GrantAction::Kill => write!(f, "kill"),
+            GrantAction::Ssh => write!(f, "ssh"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Grantee {
+    User(String),
+    Team(i64),
+}
+
+impl Grantee {
+    pub fn new(grantee_type: &str, grantee_id: &str) -> Result<Self> {
+        Ok(match grantee_type {
+            "user" => Grantee::User(grantee_id.to_string()),
+            "team" => Grantee::Team(grantee_id.parse()?),
+            v => anyhow::bail!("invalid grantee type {v}"),
+        })
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Grantee::User(_) => "user",
+            Grantee::Team(_) => "team",
+        }
+    }
+
+    fn id(&self) -> String {
+        match self {
+            Grantee::User(v) => v.clone(),
+            Grantee::Team(v) => v.to_string(),
+        }
+    }
+}
+
+/// One line of the answer to "who can do what on this resource".
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct GrantEntry {
+    pub grantee_type: String,
+    pub grantee_id: String,
+    pub grantee_name: String,
+    pub actions: Vec<String>,
+    /// grant, owner, team or role, only grants can be revoked
+    pub source: String,
+}
+
+pub struct PermissionLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+impl<'a> PermissionLogic<'a> {
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    async fn grantee_condition(&self, user_id: &str) -> Result<Condition> {
+        let team_ids: Vec<String> = TeamMember::find()
+            .filter(team_member::Column::UserId.eq(user_id))
+            .all(&self.ctx.db)
+            .await?
+            .into_iter()
+            .map(|v| v.team_id.to_string())
+            .collect();
+
+        Ok(Condition::any()
+            .add(
+                resource_permission::Column::GranteeType
+                    .eq("user")
+                    .and(resource_permission::Column::GranteeId.eq(user_id)),
+            )
+            .add(
+                resource_permission::Column::GranteeType
+                    .eq("team")
+                    .and(resource_permission::Column::GranteeId.is_in(team_ids)),
+            ))
+    }
+
+    /// Whether the user, or one of the user's teams, was granted the action.
+    pub async fn is_granted(
+        &self,
+        user_info: &UserInfo,
+        resource: GrantResource,
+        resource_id: i64,
+        action: GrantAction,
+    ) -> Result<bool> {
+        Ok(ResourcePermission::find()
+            .filter(resource_permission::Column::ResourceType.eq(resource.to_string()))
+            .filter(resource_permission::Column::ResourceId.eq(resource_id))
+            .filter(resource_permission::Column::Action.eq(action.to_string()))
+            .filter(self.grantee_condition(&user_info.user_id).await?)
+            .one(&self.ctx.db)
+            .await?
+            .is_some())
+    }
+
+    /// Ids of the resources of a kind the user was granted any action on.
+    pub async fn granted_resource_ids(
+        &self,
+        user_info: &UserInfo,
+        resource: GrantResource,
+    ) -> Result<Vec<i64>> {
+        let mut ids: Vec<i64> = ResourcePermission::find()
+            .filter(resource_permission::Column::ResourceType.eq(resource.to_string()))
+            .filter(self.grantee_condition(&user_info.user_id).await?)
+            .all(&self.ctx.db)
+            .await?
+            .into_iter()
+            .map(|v| v.resource_id)
+            .collect();
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    /// Whether the user holds the action on any resource.
+    pub async fn has_granted_action(&self, user_id: &str, action: GrantAction) -> Result<bool> {
+        Ok(ResourcePermission::find()
+            .filter(resource_permission::Column::Action.eq(action.to_string()))
+            .filter(self.grantee_condition(user_id).await?)
+            .one(&self.ctx.db)
+            .await?
+            .is_some())
+    }
+
+    /// An instance group with grants for an action is reserved to its grantees,
+    /// groups without any keep the role based access.
+    pub async fn can_use_instance_group(
+        &self,
+        user_info: &UserInfo,
+        instance_group_id: i64,
+        action: GrantAction,
+    ) -> Result<bool> {
+        if instance_group_id <= 0 {
+            return Ok(true);
+        }
+        let is_restricted = ResourcePermission::find()
+            .filter(
+                resource_permission::Column::ResourceType
+                    .eq(GrantResource::InstanceGroup.to_string()),
+            )
+            .filter(resource_permission::Column::ResourceId.eq(instance_group_id))
+            .filter(resource_permission::Column::Action.eq(action.to_string()))
+            .one(&self.ctx.db)
+            .await?
+            .is_some();
+        if !is_restricted {
+            return Ok(true);
+        }
+        self.is_granted(
+            user_info,
+            GrantResource::InstanceGroup,
+            instance_group_id,
+            action,
+        )
+        .await
+    }
+
+    /// Checks every instance group the instances belong to, instance managers
+    /// are never restricted.
+    pub async fn can_operate_instances(
+        &self,
+        user_info: &UserInfo,
+        instance_ids: &[String],
+        action: GrantAction,
+    ) -> Result<bool> {
+        if instance_ids.is_empty() || self.ctx.can_manage_instance(&user_info.user_id).await? {
+            return Ok(true);
+        }
+        let mut group_ids: Vec<i64> = Instance::find()
+            .filter(instance::Column::InstanceId.is_in(instance_ids.to_vec()))
+            .all(&self.ctx.db)
+            .await?
+            .into_iter()
+            .map(|v| v.instance_group_id)
+            .collect();
+        group_ids.sort();
+        group_ids.dedup();
+
+        for group_id in group_ids {
+            if !self
+                .can_use_instance_group(user_info, group_id, action)
+                .await?
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Grants are managed by the job owner and the admins of the job's team,
+    /// instance groups by instance managers. Being granted edit is not enough,
+    /// otherwise a grantee could hand out more than it got.
+    pub async fn can_grant(
+        &self,
+        user_info: &UserInfo,
+        resource: GrantResource,
+        resource_id: i64,
+    ) -> Result<bool> {
+        if resource == GrantResource::InstanceGroup {
+            return self.ctx.can_manage_instance(&user_info.user_id).await;
+        }
+        if self.ctx.can_manage_job(&user_info.user_id).await? {
+            return Ok(true);
+        }
+        let Some((_, job_record)) = self.get_resource_job(resource, resource_id).await? else {
+            return Ok(false);
+        };
+        if job_record.created_user == user_info.username {
+            return Ok(true);
+        }
+        Ok(TeamMember::find()
+            .filter(team_member::Column::TeamId.eq(job_record.team_id))
+            .filter(team_member::Column::UserId.eq(&user_info.user_id))
+            .one(&self.ctx.db)
+            .await?
+            .is_some_and(|v| v.is_admin))
+    }
+
+    pub async fn grant(
+        &self,
+        resource: GrantResource,
+        resource_id: i64,
+        grantee: &Grantee,
+        actions: Vec<GrantAction>,
+        created_user: &str,
+    ) -> Result<u64> {
+        let mut affected = 0;
+        for action in actions {
+            let exists = ResourcePermission::find()
+                .filter(resource_permission::Column::ResourceType.eq(resource.to_string()))
+                .filter(resource_permission::Column::ResourceId.eq(resource_id))
+                .filter(resource_permission::Column::GranteeType.eq(grantee.kind()))
+                .filter(resource_permission::Column::GranteeId.eq(grantee.id()))
+                .filter(resource_permission::Column::Action.eq(action.to_string()))
+                .one(&self.ctx.db)
+                .await?
+                .is_some();
+            if exists {
+                continue;
+            }
+
+            resource_permission::ActiveModel {
+                resource_type: Set(resource.to_string()),
+                resource_id: Set(resource_id),
+                grantee_type: Set(grantee.kind().to_string()),
+                grantee_id: Set(grantee.id()),
+                action: Set(action.to_string()),
+                created_user: Set(created_user.to_string()),
+                ..Default::default()
+            }
+            .insert(&self.ctx.db)
+            .await?;
+            affected += 1;
+        }
+        Ok(affected)
+    }
+
+    /// Revokes the given actions, or everything granted to the grantee when empty.
+    pub async fn revoke(
+        &self,
+        resource: GrantResource,
+        resource_id: i64,
+        grantee: &Grantee,
+        actions: Vec<GrantAction>,
+    ) -> Result<u64> {
+        let ret = ResourcePermission::delete_many()
+            .filter(resource_permission::Column::ResourceType.eq(resource.to_string()))
+            .filter(resource_permission::Column::ResourceId.eq(resource_id))
+            .filter(resource_permission::Column::GranteeType.eq(grantee.kind()))
+            .filter(resource_permission::Column::GranteeId.eq(grantee.id()))
+            .apply_if(Some(actions).filter(|v| !v.is_empty()), |q, v| {
+                q.filter(
+                    resource_permission::Column::Action.is_in(v.into_iter().map(|v| v.to_string())),
+                )
+            })
+            .exec(&self.ctx.db)
+            .await?;
+        Ok(ret.rows_affected)
+    }
+
+    /// The job a resource belongs to, used for the owner and team entries.
+    async fn get_resource_job(
+        &self,
+        resource: GrantResource,
+        resource_id: i64,
+    ) -> Result<Option<(String, job::Model)>> {
+        let (created_user, eid) = match resource {
+            GrantResource::Job => {
+                let Some(v) = Job::find_by_id(resource_id).one(&self.ctx.db).await? else {
+                    return Ok(None);
+                };
+                return Ok(Some((v.created_user.clone(), v)));
+            }
+            GrantResource::Timer => {
+                let Some(v) = JobTimer::find()
+                    .filter(job_timer::Column::Id.eq(resource_id))
+                    .one(&self.ctx.db)
+                    .await?
+                else {
+                    return Ok(None);
+                };
+                (v.created_user, v.eid)
+            }
+            GrantResource::Supervisor => {
+                let Some(v) = JobSupervisor::find()
+                    .filter(job_supervisor::Column::Id.eq(resource_id))
+                    .one(&self.ctx.db)
+                    .await?
+                else {
+                    return Ok(None);
+                };
+                (v.created_user, v.eid)
+            }
+            GrantResource::InstanceGroup => return Ok(None),
+        };
+
+        Ok(Job::find()
+            .filter(job::Column::Eid.eq(eid))
+            .one(&self.ctx.db)
+            .await?
+            .map(|v| (created_user, v)))
+    }
+
+    /// Who can do what on the resource. Users allowed to manage jobs or
+    /// instances can do everything and are not listed.
+    pub async fn list_grants(
+        &self,
+        resource: GrantResource,
+        resource_id: i64,
+    ) -> Result<Vec<GrantEntry>> {
+        let mut list = Vec::new();
+
+        if resource == GrantResource::InstanceGroup {
+            if InstanceGroup::find()
+                .filter(instance_group::Column::Id.eq(resource_id))
+                .one(&self.ctx.db)
+                .await?
+                .is_none()
+            {
+                anyhow::bail!("cannot found instance group {resource_id}");
+            }
+            let role_ids: Vec<i64> = InstanceRole::find()
+                .filter(instance_role::Column::InstanceGroupId.eq(resource_id))
+                .all(&self.ctx.db)
+                .await?
+                .into_iter()
+                .map(|v| v.role_id)
+                .collect();
+            for v in Role::find()
+                .filter(role::Column::Id.is_in(role_ids))
+                .all(&self.ctx.db)
+                .await?
+            {
+                list.push(GrantEntry {
+                    grantee_type: "role".to_string(),
+                    grantee_id: v.id.to_string(),
+                    grantee_name: v.name,
+                    actions: vec![
+                        GrantAction::View.to_string(),
+                        GrantAction::Dispatch.to_string(),
+                        GrantAction::Ssh.to_string(),
+                    ],
+                    source: "role".to_string(),
+                });
+            }
+        } else {
+            let (created_user, job_record) = self
+                .get_resource_job(resource, resource_id)
+                .await?
+                .ok_or(anyhow!("cannot found {resource} {resource_id}"))?;
+            let job_actions: Vec<String> = [
+                GrantAction::View,
+                GrantAction::Edit,
+                GrantAction::Dispatch,
+                GrantAction::Kill,
+            ]
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+
+            if let Some(owner) = User::find()
+                .filter(user::Column::Username.eq(&created_user))
+                .one(&self.ctx.db)
+                .await?
+            {
+                list.push(GrantEntry {
+                    grantee_type: "user".to_string(),
+                    grantee_id: owner.user_id,
+                    grantee_name: owner.username,
+                    actions: job_actions.clone(),
+                    source: "owner".to_string(),
+                });
+            }
+            if job_record.team_id > 0
+                && let Some(v) = Team::find_by_id(job_record.team_id)
+                    .one(&self.ctx.db)
+                    .await?
+            {
+                list.push(GrantEntry {
+                    grantee_type: "team".to_string(),
+                    grantee_id: v.id.to_string(),
+                    grantee_name: v.name,
+                    actions: job_actions,
+                    source: "team".to_string(),
+                });
+            }
+        }
+
+        let mut grants: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+        for v in ResourcePermission::find()
+            .filter(resource_permission::Column::ResourceType.eq(resource.to_string()))
+            .filter(resource_permission::Column::ResourceId.eq(resource_id))
+            .all(&self.ctx.db)
+            .await?
+        {
+            grants
+                .entry((v.grantee_type, v.grantee_id))
+                .or_default()
+                .push(v.action);
+        }
+
+        for ((grantee_type, grantee_id), actions) in grants {
+            let grantee_name = match Grantee::new(&grantee_type, &grantee_id)? {
+                Grantee::User(v) => User::find()
+                    .filter(user::Column::UserId.eq(v))
+                    .one(&self.ctx.db)
+                    .await?
+                    .map(|v| v.username),
+                Grantee::Team(v) => Team::find()
+                    .filter(team::Column::Id.eq(v))
+                    .one(&self.ctx.db)
+                    .await?
+                    .map(|v| v.name),
+            };
+            list.push(GrantEntry {
+                grantee_type,
+                grantee_id,
+                grantee_name: grantee_name.unwrap_or_default(),
+                actions,
+                source: "grant".to_string(),
+            });
+        }
+
+        Ok(list)
+    }
+}
+
+#[test]
+fn test_grant_names() {
+    for v in ["job", "timer", "supervisor", "instance_group"] {
+        assert_eq!(GrantResource::try_from(v).unwrap().to_string(), v);
+    }
+    for v in ["view", "edit", "dispatch", "kill", "ssh"] {
+        assert_eq!(GrantAction::try_from(v).unwrap().to_string(), v);
+    }
+    assert!(GrantAction::try_from("manage").is_err());
+    assert_eq!(
+        GrantAction::for_job_action(&JobAction::StopTimer),
+        GrantAction::Kill
+    );
+    assert_eq!(
+        GrantAction::for_job_action(&JobAction::StartTimer),
+        GrantAction::Dispatch
+    );
+    assert_eq!(Grantee::new("team", "3").unwrap(), Grantee::Team(3));
+}
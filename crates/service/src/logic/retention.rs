@@ -0,0 +1,506 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::Write,
+    path::PathBuf,
+};
+
+use anyhow::Result;
+use automate::scheduler::types::{RunStatus, ScheduleStatus};
+use chrono::{DateTime, Local, TimeDelta};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, QueryTrait, Set,
+};
+use sea_query::Query;
+use serde::Serialize;
+use serde_json::json;
+use tracing::info;
+
+use crate::{
+    entity::{
+        job, job_exec_history, job_running_status, job_schedule_history, prelude::*,
+        retention_policy, retention_prune_log,
+    },
+    state::AppContext,
+};
+
+/// Retention policies of the execution history, schedule history and running
+/// status of jobs, and the pruning that enforces them.
+pub struct RetentionLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+/// Enabled policies by what they apply to. The policy of a job wins over the
+/// one of its team, which wins over the default policy of team 0 without a job.
+#[derive(Default)]
+pub struct PolicySet {
+    jobs: HashMap<String, retention_policy::Model>,
+    teams: HashMap<i64, retention_policy::Model>,
+    default: Option<retention_policy::Model>,
+}
+
+impl PolicySet {
+    pub fn new(policies: Vec<retention_policy::Model>) -> Self {
+        let mut set = Self::default();
+        for v in policies {
+            match (v.team_id, v.eid.as_str()) {
+                (0, "") => set.default = Some(v),
+                (team_id, "") => {
+                    set.teams.insert(team_id, v);
+                }
+                _ => {
+                    set.jobs.insert(v.eid.clone(), v);
+                }
+            }
+        }
+        set
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty() && self.teams.is_empty() && self.default.is_none()
+    }
+
+    pub fn resolve(&self, eid: &str, team_id: i64) -> Option<&retention_policy::Model> {
+        self.jobs
+            .get(eid)
+            .or_else(|| self.teams.get(&team_id).filter(|_| team_id != 0))
+            .or(self.default.as_ref())
+    }
+}
+
+/// Rows deleted for a job in one run.
+#[derive(Default)]
+struct PruneCount {
+    exec_history: u64,
+    schedule_history: u64,
+    running_status: u64,
+}
+
+impl PruneCount {
+    fn is_empty(&self) -> bool {
+        self.exec_history + self.schedule_history + self.running_status == 0
+    }
+}
+
+/// Expired rows written as zstd compressed json lines before they are deleted,
+/// one file per run created on the first write.
+struct Archive {
+    dir: String,
+    file: Option<(PathBuf, zstd::Encoder<'static, File>)>,
+}
+
+impl Archive {
+    fn new(dir: &str) -> Self {
+        Self {
+            dir: dir.to_string(),
+            file: None,
+        }
+    }
+
+    fn path(&self) -> String {
+        self.file
+            .as_ref()
+            .map(|(path, _)| path.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// The file is written on the blocking pool, rows are serialized first.
+    async fn write<T: Serialize>(&mut self, table: &str, rows: &[T]) -> Result<()> {
+        let mut buf = Vec::new();
+        for row in rows {
+            serde_json::to_writer(&mut buf, &json!({"table": table, "row": row}))?;
+            buf.push(b'\n');
+        }
+
+        let dir = self.dir.clone();
+        let file = self.file.take();
+        self.file = Some(
+            tokio::task::spawn_blocking(move || -> Result<_> {
+                let (path, mut encoder) = match file {
+                    Some(v) => v,
+                    None => {
+                        let now = Local::now();
+                        let dir = PathBuf::from(shellexpand::full(&dir)?.to_string())
+                            .join(now.format("%Y%m%d").to_string());
+                        fs::create_dir_all(&dir)?;
+                        let path =
+                            dir.join(format!("retention-{}.jsonl.zst", now.format("%H%M%S")));
+                        let encoder = zstd::Encoder::new(File::create(&path)?, 0)?;
+                        (path, encoder)
+                    }
+                };
+                encoder.write_all(&buf)?;
+                // rows are deleted right after, keep what was archived readable
+                encoder.flush()?;
+                Ok((path, encoder))
+            })
+            .await??,
+        );
+        Ok(())
+    }
+
+    async fn finish(self) -> Result<()> {
+        if let Some((_, encoder)) = self.file {
+            tokio::task::spawn_blocking(move || encoder.finish()).await??;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> RetentionLogic<'a> {
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    pub async fn save_policy(
+        &self,
+        model: retention_policy::ActiveModel,
+    ) -> Result<retention_policy::ActiveModel> {
+        Ok(model.save(&self.ctx.db).await?)
+    }
+
+    pub async fn get_policy(&self, id: i64) -> Result<Option<retention_policy::Model>> {
+        Ok(RetentionPolicy::find_by_id(id).one(&self.ctx.db).await?)
+    }
+
+    pub async fn query_policy(
+        &self,
+        team_id: Option<i64>,
+        name: Option<String>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<retention_policy::Model>, u64)> {
+        let model = RetentionPolicy::find()
+            .apply_if(team_id, |query, v| {
+                query.filter(retention_policy::Column::TeamId.eq(v))
+            })
+            .apply_if(name, |query, v| {
+                query.filter(retention_policy::Column::Name.contains(v))
+            });
+        let total = model.clone().count(&self.ctx.db).await?;
+        let list = model
+            .order_by_desc(retention_policy::Column::UpdatedTime)
+            .paginate(&self.ctx.db, page_size)
+            .fetch_page(page)
+            .await?;
+        Ok((list, total))
+    }
+
+    pub async fn delete_policy(&self, id: i64) -> Result<u64> {
+        let ret = RetentionPolicy::delete_by_id(id).exec(&self.ctx.db).await?;
+        Ok(ret.rows_affected)
+    }
+
+    pub async fn query_prune_log(
+        &self,
+        policy_id: Option<i64>,
+        eid: Option<String>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<retention_prune_log::Model>, u64)> {
+        let model = RetentionPruneLog::find()
+            .apply_if(policy_id, |query, v| {
+                query.filter(retention_prune_log::Column::PolicyId.eq(v))
+            })
+            .apply_if(eid, |query, v| {
+                query.filter(retention_prune_log::Column::Eid.eq(v))
+            });
+        let total = model.clone().count(&self.ctx.db).await?;
+        let list = model
+            .order_by_desc(retention_prune_log::Column::Id)
+            .paginate(&self.ctx.db, page_size)
+            .fetch_page(page)
+            .await?;
+        Ok((list, total))
+    }
+
+    /// Hard deletes in batches what the enabled policies let expire, archiving
+    /// it first when the policy asks to, and logs what was pruned per job.
+    /// Meant to run on the leader console only.
+    pub async fn prune(&self) -> Result<Vec<retention_prune_log::Model>> {
+        let policies = PolicySet::new(
+            RetentionPolicy::find()
+                .filter(retention_policy::Column::Enabled.eq(true))
+                .all(&self.ctx.db)
+                .await?,
+        );
+        if policies.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let jobs: Vec<(String, i64)> = Job::find()
+            .select_only()
+            .column(job::Column::Eid)
+            .column(job::Column::TeamId)
+            .distinct()
+            .into_tuple()
+            .all(&self.ctx.db)
+            .await?;
+
+        let mut archive = Archive::new(&self.ctx.conf.retention.archive_dir);
+        let mut pruned = Vec::new();
+        for (eid, team_id) in jobs {
+            let Some(policy) = policies.resolve(&eid, team_id) else {
+                continue;
+            };
+            let count = self
+                .prune_job(policy, &eid, policy.archive.then_some(&mut archive))
+                .await?;
+            if !count.is_empty() {
+                pruned.push((policy, eid, count));
+            }
+        }
+        let archive_file = archive.path();
+        archive.finish().await?;
+
+        let mut logs = Vec::new();
+        for (policy, eid, count) in pruned {
+            info!(
+                "pruned {eid} by retention policy {}, {} exec history, {} schedule history, {} running status",
+                policy.name, count.exec_history, count.schedule_history, count.running_status
+            );
+            let log = retention_prune_log::ActiveModel {
+                policy_id: Set(policy.id),
+                eid: Set(eid),
+                exec_history: Set(count.exec_history as i64),
+                schedule_history: Set(count.schedule_history as i64),
+                running_status: Set(count.running_status as i64),
+                archive_file: Set(match policy.archive {
+                    true => archive_file.clone(),
+                    false => String::new(),
+                }),
+                ..Default::default()
+            }
+            .insert(&self.ctx.db)
+            .await?;
+            logs.push(log);
+        }
+        Ok(logs)
+    }
+
+    async fn prune_job(
+        &self,
+        policy: &retention_policy::Model,
+        eid: &str,
+        mut archive: Option<&mut Archive>,
+    ) -> Result<PruneCount> {
+        let mut count = PruneCount::default();
+        let age_cutoff: Option<DateTime<Local>> = (policy.max_age_days > 0)
+            .then(|| Local::now() - TimeDelta::days(policy.max_age_days as i64));
+
+        // finished runs, the status of scheduled timers and supervisors stays
+        if let Some(cutoff) = age_cutoff {
+            let cond = Condition::all()
+                .add(job_running_status::Column::Eid.eq(eid))
+                .add(job_running_status::Column::UpdatedTime.lt(cutoff))
+                .add(
+                    Condition::any()
+                        .add(job_running_status::Column::IsDeleted.eq(true))
+                        .add(
+                            Condition::all()
+                                .add(
+                                    job_running_status::Column::RunStatus
+                                        .eq(RunStatus::Stop.to_string()),
+                                )
+                                .add(job_running_status::Column::ScheduleStatus.is_not_in([
+                                    ScheduleStatus::Scheduling.to_string(),
+                                    ScheduleStatus::Supervising.to_string(),
+                                ])),
+                        ),
+                );
+            count.running_status = self
+                .prune_rows::<JobRunningStatus>(
+                    job_running_status::Column::Id,
+                    cond,
+                    archive.as_deref_mut(),
+                )
+                .await?;
+        }
+
+        // schedules still referenced by a running status can be redispatched
+        let count_cutoff = self
+            .count_cutoff::<JobScheduleHistory>(
+                job_schedule_history::Column::Id,
+                job_schedule_history::Column::Eid.eq(eid),
+                policy.max_count,
+            )
+            .await?;
+        if let Some(expired) = expired(
+            job_schedule_history::Column::Id,
+            job_schedule_history::Column::CreatedTime,
+            count_cutoff,
+            age_cutoff,
+        ) {
+            let cond = Condition::all()
+                .add(job_schedule_history::Column::Eid.eq(eid))
+                .add(expired)
+                .add(
+                    job_schedule_history::Column::ScheduleId.not_in_subquery(
+                        Query::select()
+                            .column(job_running_status::Column::ScheduleId)
+                            .from(JobRunningStatus)
+                            .and_where(job_running_status::Column::Eid.eq(eid))
+                            .and_where(job_running_status::Column::IsDeleted.eq(false))
+                            .to_owned(),
+                    ),
+                );
+            count.schedule_history = self
+                .prune_rows::<JobScheduleHistory>(
+                    job_schedule_history::Column::Id,
+                    cond,
+                    archive.as_deref_mut(),
+                )
+                .await?;
+        }
+
+        let count_cutoff = self
+            .count_cutoff::<JobExecHistory>(
+                job_exec_history::Column::Id,
+                job_exec_history::Column::Eid.eq(eid),
+                policy.max_count,
+            )
+            .await?;
+        if let Some(expired) = expired(
+            job_exec_history::Column::Id,
+            job_exec_history::Column::CreatedTime,
+            count_cutoff,
+            age_cutoff,
+        ) {
+            let mut cond = Condition::all()
+                .add(job_exec_history::Column::Eid.eq(eid))
+                .add(expired);
+            if policy.keep_failed > 0 {
+                let failed: Vec<i64> = JobExecHistory::find()
+                    .select_only()
+                    .column(job_exec_history::Column::Id)
+                    .filter(job_exec_history::Column::Eid.eq(eid))
+                    .filter(job_exec_history::Column::ExitCode.ne(0))
+                    .order_by_desc(job_exec_history::Column::Id)
+                    .limit(policy.keep_failed as u64)
+                    .into_tuple()
+                    .all(&self.ctx.db)
+                    .await?;
+                if !failed.is_empty() {
+                    cond = cond.add(job_exec_history::Column::Id.is_not_in(failed));
+                }
+            }
+            count.exec_history = self
+                .prune_rows::<JobExecHistory>(job_exec_history::Column::Id, cond, archive)
+                .await?;
+        }
+
+        Ok(count)
+    }
+
+    /// The newest id beyond the latest `max_count` rows, rows up to it are
+    /// over the count.
+    async fn count_cutoff<E: EntityTrait>(
+        &self,
+        id: E::Column,
+        filter: sea_query::SimpleExpr,
+        max_count: i32,
+    ) -> Result<Option<i64>> {
+        if max_count <= 0 {
+            return Ok(None);
+        }
+        Ok(E::find()
+            .select_only()
+            .column(id)
+            .filter(filter)
+            .order_by_desc(id)
+            .offset(max_count as u64)
+            .limit(1)
+            .into_tuple()
+            .one(&self.ctx.db)
+            .await?)
+    }
+
+    async fn prune_rows<E>(
+        &self,
+        id: E::Column,
+        cond: Condition,
+        mut archive: Option<&mut Archive>,
+    ) -> Result<u64>
+    where
+        E: EntityTrait,
+        E::Model: Serialize,
+    {
+        let batch_size = self.ctx.conf.retention.batch_size.max(1);
+        let mut total = 0;
+        loop {
+            let ids: Vec<i64> = E::find()
+                .select_only()
+                .column(id)
+                .filter(cond.clone())
+                .order_by_asc(id)
+                .limit(batch_size)
+                .into_tuple()
+                .all(&self.ctx.db)
+                .await?;
+            if ids.is_empty() {
+                break;
+            }
+            if let Some(archive) = archive.as_deref_mut() {
+                let rows = E::find()
+                    .filter(id.is_in(ids.clone()))
+                    .all(&self.ctx.db)
+                    .await?;
+                archive.write(E::default().table_name(), &rows).await?;
+            }
+            let ret = E::delete_many()
+                .filter(id.is_in(ids.clone()))
+                .exec(&self.ctx.db)
+                .await?;
+            total += ret.rows_affected;
+            if (ids.len() as u64) < batch_size {
+                break;
+            }
+        }
+        Ok(total)
+    }
+}
+
+/// Rows over the count or older than the age, `None` when neither limits.
+fn expired<C: ColumnTrait>(
+    id: C,
+    created_time: C,
+    count_cutoff: Option<i64>,
+    age_cutoff: Option<DateTime<Local>>,
+) -> Option<Condition> {
+    if count_cutoff.is_none() && age_cutoff.is_none() {
+        return None;
+    }
+    Some(
+        Condition::any()
+            .add_option(count_cutoff.map(|v| id.lte(v)))
+            .add_option(age_cutoff.map(|v| created_time.lt(v))),
+    )
+}
+
+#[test]
+fn test_policy_set() {
+    let policy = |id, team_id, eid: &str| retention_policy::Model {
+        id,
+        team_id,
+        eid: eid.to_string(),
+        ..Default::default()
+    };
+    let set = PolicySet::new(vec![
+        policy(1, 0, ""),
+        policy(2, 7, ""),
+        policy(3, 7, "job-a"),
+        policy(4, 0, "job-b"),
+    ]);
+
+    let resolve = |eid, team_id| set.resolve(eid, team_id).map(|v| v.id);
+    assert_eq!(resolve("job-a", 7), Some(3));
+    assert_eq!(resolve("job-b", 0), Some(4));
+    assert_eq!(resolve("job-c", 7), Some(2));
+    assert_eq!(resolve("job-c", 8), Some(1));
+    assert_eq!(resolve("job-c", 0), Some(1));
+
+    let set = PolicySet::new(vec![policy(2, 7, "")]);
+    assert_eq!(set.resolve("job-c", 7).map(|v| v.id), Some(2));
+    assert!(set.resolve("job-c", 0).is_none());
+}
@@ -44,6 +44,12 @@ const POLICY_ALLOW_UPLOAD_FILE: Permission = Permission {
     action: "upload",
 };
 
+const POLICY_REQUIRE_MFA: Permission = Permission {
+    name: "Require two-factor login",
+    object: "mfa",
+    action: "require",
+};
+
 pub static PERMISSIONS: LazyLock<Vec<Permission>> = LazyLock::new(|| {
     // vec![
     //     Permission {
@@ -68,6 +74,7 @@ pub static PERMISSIONS: LazyLock<Vec<Permission>> = LazyLock::new(|| {
         POLICY_DO_NOT_ALLOW_CHANGE_DATA,
         POLICY_ALLOW_CHANGE_ALL_JOB,
         POLICY_ALLOW_UPLOAD_FILE,
+        POLICY_REQUIRE_MFA,
     ]
 });
 
@@ -84,7 +91,7 @@ impl<'a> RoleLogic<'a> {
         &self,
         active_model: role::ActiveModel,
         user_ids: Option<Vec<String>>,
-    ) -> Result<u64> {
+    ) -> Result<i64> {
         let active_model = active_model.save(&self.ctx.db).await?;
         if let Some(user_ids) = user_ids {
             let role_ids = Role::find()
@@ -113,7 +120,7 @@ impl<'a> RoleLogic<'a> {
 
     pub async fn set_user<T, F>(
         &self,
-        role_id: u64,
+        role_id: i64,
         user_ids: Option<Vec<String>>,
         update_role: F,
     ) -> Result<u64>
@@ -162,8 +169,8 @@ impl<'a> RoleLogic<'a> {
         &self,
         name: Option<String>,
         created_user: Option<String>,
-        id: Option<u64>,
-        default_id: Option<u64>,
+        id: Option<i64>,
+        default_id: Option<i64>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<role::Model>, u64)> {
@@ -190,7 +197,7 @@ impl<'a> RoleLogic<'a> {
         Ok((list, total))
     }
 
-    pub async fn delete_role(&self, id: u64) -> Result<u64> {
+    pub async fn delete_role(&self, id: i64) -> Result<u64> {
         let record = User::find()
             .filter(user::Column::RoleId.eq(id))
             .one(&self.ctx.db)
@@ -210,10 +217,10 @@ impl<'a> RoleLogic<'a> {
 
     pub async fn bind_instance(
         &self,
-        role_id: u64,
-        instance_group_ids: Option<Vec<u64>>,
+        role_id: i64,
+        instance_group_ids: Option<Vec<i64>>,
         instance_ids: Option<Vec<String>>,
-    ) -> Result<u64> {
+    ) -> Result<i64> {
         if Role::find_by_id(role_id).one(&self.ctx.db).await?.is_none() {
             anyhow::bail!("invalid role");
         }
@@ -259,8 +266,8 @@ impl<'a> RoleLogic<'a> {
 
     pub async fn unbind_instance(
         &self,
-        role_id: u64,
-        instance_group_ids: Option<Vec<u64>>,
+        role_id: i64,
+        instance_group_ids: Option<Vec<i64>>,
         instance_ids: Option<Vec<String>>,
     ) -> Result<u64> {
         Ok(InstanceRole::delete_many()
@@ -284,7 +291,7 @@ impl<'a> RoleLogic<'a> {
             .rows_affected)
     }
 
-    pub async fn is_admin(&self, role_id: u64) -> Result<bool> {
+    pub async fn is_admin(&self, role_id: i64) -> Result<bool> {
         Ok(Role::find_by_id(role_id)
             .one(&self.ctx.db)
             .await?
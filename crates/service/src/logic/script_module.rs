@@ -0,0 +1,436 @@
+use std::{collections::HashMap, fmt};
+
+use anyhow::{Result, anyhow};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QueryTrait, Set, TransactionTrait,
+};
+
+use crate::{
+    entity::{job, job_bundle_script, prelude::*, script_module, script_module_version},
+    state::AppContext,
+};
+
+/// Lines of job code starting with one of these include a script module,
+/// eg: "#@include retry" or "#@include retry@3" for a fixed version.
+const INCLUDE_DIRECTIVES: [&str; 2] = ["#@include", "//@include"];
+
+/// Distinct modules a single job may pull in, nested includes counted.
+const MAX_INCLUDES: usize = 100;
+
+/// Bytes of the expanded code, a module included by several others is
+/// expanded each time so nested includes can grow the code exponentially.
+const MAX_EXPANDED_LEN: usize = 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IncludeRef {
+    pub name: String,
+    /// the latest version when not given
+    pub version: Option<i32>,
+}
+
+impl fmt::Display for IncludeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.version {
+            Some(v) => write!(f, "{}@{v}", self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+pub fn valid_module_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 100
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'))
+}
+
+/// The module included by `line`, none when it is not an include line.
+pub fn parse_include(line: &str) -> Result<Option<IncludeRef>> {
+    let line = line.trim();
+    let Some(rest) = INCLUDE_DIRECTIVES
+        .iter()
+        .find_map(|v| line.strip_prefix(v))
+        .filter(|v| v.starts_with(char::is_whitespace))
+    else {
+        return Ok(None);
+    };
+    let rest = rest.trim();
+    let (name, version) = match rest.rsplit_once('@') {
+        Some((name, version)) => {
+            let version = version
+                .parse::<i32>()
+                .map_err(|_| anyhow!("invalid script module version in `{line}`"))?;
+            (name, Some(version))
+        }
+        None => (rest, None),
+    };
+    if !valid_module_name(name) {
+        anyhow::bail!("invalid script module name in `{line}`");
+    }
+    Ok(Some(IncludeRef {
+        name: name.to_string(),
+        version,
+    }))
+}
+
+pub fn parse_includes(code: &str) -> Result<Vec<IncludeRef>> {
+    let mut ret = vec![];
+    for line in code.lines() {
+        if let Some(v) = parse_include(line)? {
+            ret.push(v);
+        }
+    }
+    Ok(ret)
+}
+
+/// Replaces every include line of `code` with the code of the module,
+/// recursively, failing on a missing module, an include cycle or code
+/// expanded beyond [`MAX_EXPANDED_LEN`].
+pub fn expand_includes(code: &str, modules: &HashMap<IncludeRef, String>) -> Result<String> {
+    fn walk(
+        code: &str,
+        modules: &HashMap<IncludeRef, String>,
+        stack: &mut Vec<IncludeRef>,
+        out: &mut String,
+    ) -> Result<()> {
+        for line in code.split_inclusive('\n') {
+            let Some(include) = parse_include(line)? else {
+                out.push_str(line);
+                if out.len() > MAX_EXPANDED_LEN {
+                    anyhow::bail!("expanded code exceeds {MAX_EXPANDED_LEN} bytes");
+                }
+                continue;
+            };
+            if stack.contains(&include) {
+                anyhow::bail!("script module {include} includes itself");
+            }
+            let module = modules
+                .get(&include)
+                .ok_or(anyhow!("cannot found script module {include}"))?;
+            stack.push(include);
+            walk(module, modules, stack, out)?;
+            stack.pop();
+            if line.ends_with('\n') && !out.ends_with('\n') {
+                out.push('\n');
+            }
+        }
+        Ok(())
+    }
+
+    let mut out = String::with_capacity(code.len());
+    walk(code, modules, &mut vec![], &mut out)?;
+    Ok(out)
+}
+
+/// Versioned script modules that job code and bundle scripts include by name.
+/// Modules of team 0 are shared by all teams.
+pub struct ScriptModuleLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+impl<'a> ScriptModuleLogic<'a> {
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    /// Creates the module or updates it, a changed code becomes a new
+    /// version. Returns the module and its latest version.
+    pub async fn save_module(
+        &self,
+        mut model: script_module::ActiveModel,
+        code: String,
+        changelog: String,
+    ) -> Result<(i64, i32)> {
+        if !valid_module_name(model.name.as_ref()) {
+            anyhow::bail!("a script module name only contains letters, digits and _-./");
+        }
+        parse_includes(&code)?;
+        let username = model.updated_user.as_ref().to_owned();
+
+        let txn = self.ctx.db.begin().await?;
+        let (module, latest_code) = match model.id {
+            Set(id) => {
+                let module = ScriptModule::find_by_id(id)
+                    .one(&txn)
+                    .await?
+                    .ok_or(anyhow!("cannot found script module"))?;
+                let latest = ScriptModuleVersion::find()
+                    .filter(script_module_version::Column::ModuleId.eq(id))
+                    .filter(script_module_version::Column::Version.eq(module.latest_version))
+                    .one(&txn)
+                    .await?;
+                (Some(module), latest.map(|v| v.code))
+            }
+            _ => (None, None),
+        };
+
+        let mut latest_version = module.as_ref().map_or(0, |v| v.latest_version);
+        let changed = latest_code.as_ref() != Some(&code);
+        if changed {
+            latest_version += 1;
+        }
+
+        model.latest_version = Set(latest_version);
+        let module = model.save(&txn).await?;
+        let module_id = module.id.as_ref().to_owned();
+
+        if changed {
+            script_module_version::ActiveModel {
+                module_id: Set(module_id),
+                version: Set(latest_version),
+                code: Set(code),
+                changelog: Set(changelog),
+                created_user: Set(username),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+        }
+        txn.commit().await?;
+        Ok((module_id, latest_version))
+    }
+
+    pub async fn get_module(&self, id: i64) -> Result<Option<script_module::Model>> {
+        Ok(ScriptModule::find_by_id(id).one(&self.ctx.db).await?)
+    }
+
+    pub async fn query_module(
+        &self,
+        team_id: Option<i64>,
+        name: Option<String>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<script_module::Model>, u64)> {
+        let model = ScriptModule::find()
+            .apply_if(team_id, |query, v| {
+                query.filter(script_module::Column::TeamId.eq(v))
+            })
+            .apply_if(name, |query, v| {
+                query.filter(script_module::Column::Name.contains(v))
+            });
+        let total = model.clone().count(&self.ctx.db).await?;
+        let list = model
+            .order_by_desc(script_module::Column::UpdatedTime)
+            .paginate(&self.ctx.db, page_size)
+            .fetch_page(page)
+            .await?;
+        Ok((list, total))
+    }
+
+    /// Versions of the module, the latest first.
+    pub async fn query_version(
+        &self,
+        module_id: i64,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<script_module_version::Model>, u64)> {
+        let model = ScriptModuleVersion::find()
+            .filter(script_module_version::Column::ModuleId.eq(module_id));
+        let total = model.clone().count(&self.ctx.db).await?;
+        let list = model
+            .order_by_desc(script_module_version::Column::Version)
+            .paginate(&self.ctx.db, page_size)
+            .fetch_page(page)
+            .await?;
+        Ok((list, total))
+    }
+
+    /// Jobs, bundle scripts and latest versions of other modules that
+    /// include the module. A shared module counts includes of all teams.
+    pub async fn includers(&self, module: &script_module::Model) -> Result<Vec<String>> {
+        let includes = |code: &str| {
+            parse_includes(code)
+                .unwrap_or_default()
+                .iter()
+                .any(|v| v.name == module.name)
+        };
+        let team_id = Some(module.team_id).filter(|&v| v != 0);
+        let mut ret = vec![];
+
+        let jobs = Job::find()
+            .filter(job::Column::IsDeleted.eq(false))
+            .filter(job::Column::Code.contains(&module.name))
+            .apply_if(team_id, |query, v| query.filter(job::Column::TeamId.eq(v)))
+            .all(&self.ctx.db)
+            .await?;
+        ret.extend(
+            jobs.into_iter()
+                .filter(|v| includes(&v.code))
+                .map(|v| format!("job {}", v.name)),
+        );
+
+        let scripts = JobBundleScript::find()
+            .filter(job_bundle_script::Column::IsDeleted.eq(false))
+            .filter(job_bundle_script::Column::Code.contains(&module.name))
+            .apply_if(team_id, |query, v| {
+                query.filter(job_bundle_script::Column::TeamId.eq(v))
+            })
+            .all(&self.ctx.db)
+            .await?;
+        ret.extend(
+            scripts
+                .into_iter()
+                .filter(|v| includes(&v.code))
+                .map(|v| format!("bundle script {}", v.name)),
+        );
+
+        let versions = ScriptModuleVersion::find()
+            .filter(script_module_version::Column::ModuleId.ne(module.id))
+            .filter(script_module_version::Column::Code.contains(&module.name))
+            .all(&self.ctx.db)
+            .await?;
+        let modules = ScriptModule::find()
+            .filter(script_module::Column::Id.is_in(versions.iter().map(|v| v.module_id)))
+            .apply_if(team_id, |query, v| {
+                query.filter(script_module::Column::TeamId.eq(v))
+            })
+            .all(&self.ctx.db)
+            .await?;
+        ret.extend(
+            modules
+                .into_iter()
+                .filter(|m| {
+                    versions.iter().any(|v| {
+                        v.module_id == m.id && v.version == m.latest_version && includes(&v.code)
+                    })
+                })
+                .map(|m| format!("script module {}", m.name)),
+        );
+        Ok(ret)
+    }
+
+    /// Deletes the module with all its versions, refused while it is still
+    /// included.
+    pub async fn delete_module(&self, module: &script_module::Model) -> Result<u64> {
+        let includers = self.includers(module).await?;
+        if !includers.is_empty() {
+            anyhow::bail!(
+                "script module {} is still included by {}",
+                module.name,
+                includers.join(", ")
+            );
+        }
+        let id = module.id;
+        let txn = self.ctx.db.begin().await?;
+        ScriptModuleVersion::delete_many()
+            .filter(script_module_version::Column::ModuleId.eq(id))
+            .exec(&txn)
+            .await?;
+        let ret = ScriptModule::delete_by_id(id).exec(&txn).await?;
+        txn.commit().await?;
+        Ok(ret.rows_affected)
+    }
+
+    /// The version of the module that a job of `team_id` includes, the
+    /// module of the team wins over a shared one of the same name.
+    pub async fn resolve(
+        &self,
+        team_id: i64,
+        include: &IncludeRef,
+    ) -> Result<script_module_version::Model> {
+        let module = ScriptModule::find()
+            .filter(script_module::Column::Name.eq(&include.name))
+            .filter(script_module::Column::TeamId.is_in([team_id, 0]))
+            .order_by_desc(script_module::Column::TeamId)
+            .one(&self.ctx.db)
+            .await?
+            .ok_or(anyhow!("cannot found script module {}", include.name))?;
+        let version = include.version.unwrap_or(module.latest_version);
+        ScriptModuleVersion::find()
+            .filter(script_module_version::Column::ModuleId.eq(module.id))
+            .filter(script_module_version::Column::Version.eq(version))
+            .one(&self.ctx.db)
+            .await?
+            .ok_or(anyhow!(
+                "cannot found version {version} of script module {}",
+                include.name
+            ))
+    }
+
+    /// Expands the includes of code run by a job of `team_id`.
+    pub async fn expand(&self, team_id: i64, code: &str) -> Result<String> {
+        let mut pending = parse_includes(code)?;
+        if pending.is_empty() {
+            return Ok(code.to_string());
+        }
+        let mut modules = HashMap::new();
+        while let Some(include) = pending.pop() {
+            if modules.contains_key(&include) {
+                continue;
+            }
+            if modules.len() >= MAX_INCLUDES {
+                anyhow::bail!("more than {MAX_INCLUDES} script modules are included");
+            }
+            let version = self.resolve(team_id, &include).await?;
+            pending.extend(parse_includes(&version.code)?);
+            modules.insert(include, version.code);
+        }
+        expand_includes(code, &modules)
+    }
+}
+
+#[test]
+fn test_expand_includes() {
+    let modules = HashMap::from([
+        (
+            IncludeRef {
+                name: "log".to_string(),
+                version: None,
+            },
+            "log() { echo \"$@\"; }".to_string(),
+        ),
+        (
+            IncludeRef {
+                name: "retry".to_string(),
+                version: Some(2),
+            },
+            "#@include log\nretry() { \"$@\" || \"$@\"; }\n".to_string(),
+        ),
+        (
+            IncludeRef {
+                name: "loop".to_string(),
+                version: None,
+            },
+            "  #@include loop\n".to_string(),
+        ),
+    ]);
+
+    let code = "#!/bin/bash\n#@include retry@2\nretry ls\n";
+    assert_eq!(
+        expand_includes(code, &modules).unwrap(),
+        "#!/bin/bash\nlog() { echo \"$@\"; }\nretry() { \"$@\" || \"$@\"; }\nretry ls\n"
+    );
+    assert_eq!(
+        expand_includes("#@includes x", &modules).unwrap(),
+        "#@includes x"
+    );
+    assert!(expand_includes("#@include retry", &modules).is_err());
+    assert!(expand_includes("#@include loop", &modules).is_err());
+    assert!(parse_include("//@include a@b").is_err());
+    assert!(parse_include("#@include a b").is_err());
+}
+
+#[test]
+fn test_expand_includes_limit() {
+    // every level includes the next one twice, 2^30 copies of the leaf
+    let mut modules = HashMap::from([(
+        IncludeRef {
+            name: "m30".to_string(),
+            version: None,
+        },
+        format!("echo {}\n", "x".repeat(1024)),
+    )]);
+    for i in 0..30 {
+        modules.insert(
+            IncludeRef {
+                name: format!("m{i}"),
+                version: None,
+            },
+            format!("#@include m{0}\n#@include m{0}\n", i + 1),
+        );
+    }
+    let err = expand_includes("#@include m0\n", &modules).unwrap_err();
+    assert!(err.to_string().contains("exceeds"));
+}
@@ -0,0 +1,233 @@
+use anyhow::Result;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::state::AppContext;
+
+fn default_max_user_failures() -> u64 {
+    5
+}
+
+fn default_max_ip_failures() -> u64 {
+    20
+}
+
+fn default_window_secs() -> u64 {
+    900
+}
+
+fn default_lockout_secs() -> u64 {
+    900
+}
+
+fn default_min_length() -> usize {
+    8
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LockoutOptions {
+    /// failed logins of one account within the window before it is locked
+    #[serde(default = "default_max_user_failures")]
+    pub max_user_failures: u64,
+    /// failed logins from one address within the window before it is locked
+    #[serde(default = "default_max_ip_failures")]
+    pub max_ip_failures: u64,
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    #[serde(default = "default_lockout_secs")]
+    pub lockout_secs: u64,
+    /// take the client address from X-Forwarded-For, only behind a proxy
+    #[serde(default)]
+    pub trust_proxy_headers: bool,
+}
+
+impl Default for LockoutOptions {
+    fn default() -> Self {
+        Self {
+            max_user_failures: default_max_user_failures(),
+            max_ip_failures: default_max_ip_failures(),
+            window_secs: default_window_secs(),
+            lockout_secs: default_lockout_secs(),
+            trust_proxy_headers: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PasswordPolicy {
+    #[serde(default = "default_min_length")]
+    pub min_length: usize,
+    #[serde(default = "default_true")]
+    pub require_letter: bool,
+    #[serde(default = "default_true")]
+    pub require_digit: bool,
+    #[serde(default)]
+    pub require_symbol: bool,
+    /// reject passwords containing the username
+    #[serde(default = "default_true")]
+    pub forbid_username: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: default_min_length(),
+            require_letter: true,
+            require_digit: true,
+            require_symbol: false,
+            forbid_username: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn check(&self, username: &str, password: &str) -> Result<()> {
+        if password.chars().count() < self.min_length {
+            anyhow::bail!(
+                "password must be at least {} characters long",
+                self.min_length
+            );
+        }
+        if self.require_letter && !password.chars().any(|c| c.is_alphabetic()) {
+            anyhow::bail!("password must contain a letter");
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            anyhow::bail!("password must contain a digit");
+        }
+        if self.require_symbol && password.chars().all(|c| c.is_alphanumeric()) {
+            anyhow::bail!("password must contain a symbol");
+        }
+        if self.forbid_username
+            && !username.is_empty()
+            && password.to_lowercase().contains(&username.to_lowercase())
+        {
+            anyhow::bail!("password must not contain the username");
+        }
+        Ok(())
+    }
+}
+
+/// Per account and per address throttling of failed logins, counted in
+/// redis so every console instance shares them.
+pub struct SecurityLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+impl<'a> SecurityLogic<'a> {
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    pub fn options(&self) -> &LockoutOptions {
+        &self.ctx.conf.auth.lockout
+    }
+
+    pub fn check_password(&self, username: &str, password: &str) -> Result<()> {
+        self.ctx.conf.auth.password_policy.check(username, password)
+    }
+
+    fn subjects(username: &str, ip: &str) -> [(&'static str, String); 2] {
+        [("user", username.to_lowercase()), ("ip", ip.to_string())]
+    }
+
+    fn fail_key(kind: &str, id: &str) -> String {
+        format!("jiascheduler:login:fail:{kind}:{id}")
+    }
+
+    fn lock_key(kind: &str, id: &str) -> String {
+        format!("jiascheduler:login:lock:{kind}:{id}")
+    }
+
+    /// Fails while the account or the address is locked out.
+    pub async fn check_login_allowed(&self, username: &str, ip: &str) -> Result<()> {
+        let mut conn = self.ctx.redis().get_multiplexed_async_connection().await?;
+        for (kind, id) in Self::subjects(username, ip) {
+            if id.is_empty() {
+                continue;
+            }
+            let ttl: i64 = conn.ttl(Self::lock_key(kind, &id)).await?;
+            if ttl > 0 {
+                anyhow::bail!(
+                    "too many failed login attempts, try again in {} minutes",
+                    (ttl + 59) / 60
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn record_login_failure(&self, username: &str, ip: &str) -> Result<()> {
+        let opts = self.options();
+        let mut conn = self.ctx.redis().get_multiplexed_async_connection().await?;
+        for (kind, id) in Self::subjects(username, ip) {
+            if id.is_empty() {
+                continue;
+            }
+            let max = if kind == "user" {
+                opts.max_user_failures
+            } else {
+                opts.max_ip_failures
+            };
+            if max == 0 {
+                continue;
+            }
+            let key = Self::fail_key(kind, &id);
+            let failures: u64 = conn.incr(&key, 1).await?;
+            if failures == 1 {
+                let _: () = conn.expire(&key, opts.window_secs as i64).await?;
+            }
+            if failures >= max {
+                warn!("lock out login of {kind} {id} after {failures} failures");
+                let _: () = conn
+                    .set_ex(Self::lock_key(kind, &id), failures, opts.lockout_secs)
+                    .await?;
+                let _: () = conn.del(&key).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Clears the failures of the account, those of the address stay.
+    pub async fn record_login_success(&self, username: &str) -> Result<()> {
+        let mut conn = self.ctx.redis().get_multiplexed_async_connection().await?;
+        let _: () = conn
+            .del(Self::fail_key("user", &username.to_lowercase()))
+            .await?;
+        Ok(())
+    }
+
+    /// Lifts a lockout of the account before it expires.
+    pub async fn unlock_user(&self, username: &str) -> Result<()> {
+        let mut conn = self.ctx.redis().get_multiplexed_async_connection().await?;
+        let username = username.to_lowercase();
+        let _: () = conn
+            .del(&[
+                Self::fail_key("user", &username),
+                Self::lock_key("user", &username),
+            ])
+            .await?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_password_policy() {
+    let policy = PasswordPolicy::default();
+    assert!(policy.check("alice", "short1").is_err());
+    assert!(policy.check("alice", "longpassword").is_err());
+    assert!(policy.check("alice", "12345678").is_err());
+    assert!(policy.check("alice", "Alice2024xyz").is_err());
+    assert!(policy.check("alice", "correct horse 9").is_ok());
+
+    let strict = PasswordPolicy {
+        require_symbol: true,
+        ..Default::default()
+    };
+    assert!(strict.check("alice", "password99").is_err());
+    assert!(strict.check("alice", "password-99").is_ok());
+}
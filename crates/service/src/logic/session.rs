@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::Utc;
+use nanoid::nanoid;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+
+use crate::state::AppContext;
+
+/// A console login, listed to its user and revocable from anywhere.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct SessionInfo {
+    pub sid: String,
+    pub ip: String,
+    pub user_agent: String,
+    pub login_time: i64,
+    pub last_active_time: i64,
+}
+
+/// Keeps the logins of every user in a redis hash next to the cookie
+/// sessions. A cookie session whose entry is gone is treated as logged out,
+/// which is how sessions are revoked.
+pub struct SessionLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+impl<'a> SessionLogic<'a> {
+    /// key of the session id inside the cookie session
+    pub const SESS_KEY: &'static str = "SESSION_ID";
+    /// lifetime of the cookie and of idle entries
+    pub const TTL_SECS: u64 = 86400;
+    /// last activity is written at most once per interval
+    const TOUCH_INTERVAL_SECS: i64 = 60;
+
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    fn key(user_id: &str) -> String {
+        format!("jiascheduler:session:{user_id}")
+    }
+
+    pub async fn create(&self, user_id: &str, ip: &str, user_agent: &str) -> Result<String> {
+        let now = Utc::now().timestamp();
+        let info = SessionInfo {
+            sid: nanoid!(),
+            ip: ip.to_string(),
+            user_agent: user_agent.chars().take(255).collect(),
+            login_time: now,
+            last_active_time: now,
+        };
+        let key = Self::key(user_id);
+        let mut conn = self.ctx.redis_manager();
+        let _: () = conn
+            .hset(&key, &info.sid, serde_json::to_string(&info)?)
+            .await?;
+        let _: () = conn.expire(&key, Self::TTL_SECS as i64).await?;
+        Ok(info.sid)
+    }
+
+    /// Records activity, false when the session was revoked or expired.
+    pub async fn touch(&self, user_id: &str, sid: &str) -> Result<bool> {
+        let key = Self::key(user_id);
+        let mut conn = self.ctx.redis_manager();
+        let data: Option<String> = conn.hget(&key, sid).await?;
+        let Some(mut info) = data.and_then(|v| serde_json::from_str::<SessionInfo>(&v).ok()) else {
+            return Ok(false);
+        };
+
+        let now = Utc::now().timestamp();
+        if now - info.last_active_time >= Self::TOUCH_INTERVAL_SECS {
+            info.last_active_time = now;
+            let _: () = conn.hset(&key, sid, serde_json::to_string(&info)?).await?;
+            let _: () = conn.expire(&key, Self::TTL_SECS as i64).await?;
+        }
+        Ok(true)
+    }
+
+    /// Active sessions, most recently used first. Idle ones are dropped.
+    pub async fn list(&self, user_id: &str) -> Result<Vec<SessionInfo>> {
+        let key = Self::key(user_id);
+        let mut conn = self.ctx.redis_manager();
+        let all: HashMap<String, String> = conn.hgetall(&key).await?;
+
+        let deadline = Utc::now().timestamp() - Self::TTL_SECS as i64;
+        let mut list = Vec::new();
+        for (sid, data) in all {
+            match serde_json::from_str::<SessionInfo>(&data) {
+                Ok(v) if v.last_active_time > deadline => list.push(v),
+                _ => {
+                    let _: () = conn.hdel(&key, sid).await?;
+                }
+            }
+        }
+        list.sort_by_key(|v| std::cmp::Reverse(v.last_active_time));
+        Ok(list)
+    }
+
+    pub async fn revoke(&self, user_id: &str, sid: &str) -> Result<bool> {
+        let mut conn = self.ctx.redis_manager();
+        let removed: u64 = conn.hdel(Self::key(user_id), sid).await?;
+        Ok(removed > 0)
+    }
+
+    /// Logs the user out everywhere, except from the given session.
+    pub async fn revoke_all(&self, user_id: &str, except: Option<&str>) -> Result<u64> {
+        let key = Self::key(user_id);
+        let mut conn = self.ctx.redis_manager();
+        let sids: Vec<String> = conn.hkeys(&key).await?;
+        let sids: Vec<String> = sids
+            .into_iter()
+            .filter(|v| Some(v.as_str()) != except)
+            .collect();
+        if sids.is_empty() {
+            return Ok(0);
+        }
+        let removed: u64 = conn.hdel(&key, sids).await?;
+        Ok(removed)
+    }
+}
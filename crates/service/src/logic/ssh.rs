@@ -8,19 +8,23 @@ use async_trait::async_trait;
 use automate::bridge::msg::{
     SftpDownloadParams, SftpReadDirParams, SftpRemoveParams, SftpUploadParams,
 };
+use automate::ssh::SshAuth;
+use automate::transfer::{self, TransferProgress};
 use futures::stream::{SplitSink, SplitStream};
 use futures::{SinkExt, StreamExt};
+use poem::Body;
 use poem::web::websocket::{Message, WebSocketStream};
 use russh::*;
 use russh_keys::*;
 use russh_sftp::client::SftpSession;
 use serde_json::Value;
 
+use crate::logic::terminal::Recorder;
 use crate::state::AppContext;
 
 use serde::{self, Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
-use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite};
 use tokio::net::ToSocketAddrs;
 use tokio::time::timeout;
 use tracing::info;
@@ -62,19 +66,15 @@ pub struct Session {
     session: client::Handle<Client>,
 }
 
-pub struct ConnectParams<A: ToSocketAddrs, U: Into<String>, P: Into<String>> {
+pub struct ConnectParams<A: ToSocketAddrs, U: Into<String>> {
     pub user: U,
-    pub password: P,
+    pub auth: SshAuth,
     pub addrs: A,
 }
 
 impl Session {
-    pub async fn connect<A: ToSocketAddrs, U: Into<String>, P: Into<String>>(
-        ConnectParams {
-            user,
-            password,
-            addrs,
-        }: ConnectParams<A, U, P>,
+    pub async fn connect<A: ToSocketAddrs, U: Into<String>>(
+        ConnectParams { user, auth, addrs }: ConnectParams<A, U>,
     ) -> Result<Self> {
         let config = client::Config {
             inactivity_timeout: Some(Duration::from_secs(90)),
@@ -88,11 +88,7 @@ impl Session {
         let mut session =
             timeout(Duration::from_secs(1), client::connect(config, addrs, sh)).await??;
 
-        let auth_res = session.authenticate_password(user, password).await?;
-
-        if !auth_res {
-            anyhow::bail!("Authentication failed");
-        }
+        auth.authenticate(&mut session, user.into()).await?;
 
         Ok(Self { session })
     }
@@ -100,7 +96,7 @@ impl Session {
     #[allow(dead_code)]
     pub async fn connect_stream<T: AsyncRead + AsyncWrite + Unpin + Send + 'static>(
         user: String,
-        password: String,
+        auth: SshAuth,
         stream: T,
     ) -> Result<Self> {
         let config = client::Config {
@@ -118,11 +114,7 @@ impl Session {
         )
         .await??;
 
-        let auth_res = session.authenticate_password(user, password).await?;
-
-        if !auth_res {
-            anyhow::bail!("Authentication failed");
-        }
+        auth.authenticate(&mut session, user).await?;
 
         Ok(Self { session })
     }
@@ -134,6 +126,7 @@ impl Session {
         rows: u32,
         sink: &mut SplitSink<WebSocketStream, Message>,
         mut stream: SplitStream<WebSocketStream>,
+        recorder: &Recorder,
     ) -> Result<u32> {
         let mut channel = self.session.channel_open_session().await?;
 
@@ -180,10 +173,12 @@ impl Session {
                     match msg.r#type {
                         MsgType::Resize => {
                             info!("resize {},{}",msg.cols,msg.rows);
+                            recorder.resize(msg.cols, msg.rows).await;
                             channel.window_change(msg.cols, msg.rows, 0, 0).await.expect("failed resize windows");
 
                         },
                         MsgType::Data => {
+                            recorder.input(&msg.msg).await;
                             channel.data(msg.msg.as_ref()).await.expect("failed send msg");
                         },
                         MsgType::Ping => {
@@ -196,7 +191,9 @@ impl Session {
                     match msg {
                         // Write data to the terminal
                         ChannelMsg::Data { ref data } => {
-                            sink.send(Message::Text(String::from_utf8_lossy(&data.to_vec()).to_string())).await?;
+                            let text = String::from_utf8_lossy(data).to_string();
+                            recorder.output(&text).await;
+                            sink.send(Message::Text(text)).await?;
                         }
                         // The command has returned an exit code
                         ChannelMsg::ExitStatus { exit_status } => {
@@ -237,6 +234,18 @@ impl<'a> SshLogic<'a> {
         Self { ctx }
     }
 
+    /// A client may choose the transfer id to poll its progress, otherwise a
+    /// random one is used.
+    fn check_transfer_id(transfer_id: Option<String>) -> Result<String> {
+        match transfer_id.filter(|v| !v.is_empty()) {
+            Some(v) if !transfer::is_transfer_id(&v) => {
+                anyhow::bail!("invalid transfer id {v}")
+            }
+            Some(v) => Ok(v),
+            None => Ok(transfer::new_transfer_id()),
+        }
+    }
+
     pub async fn sftp_read_dir(
         &self,
         namespace: String,
@@ -245,18 +254,21 @@ impl<'a> SshLogic<'a> {
         port: u16,
         dir: Option<String>,
         user: String,
-        password: String,
+        auth: SshAuth,
     ) -> Result<Value> {
         let logic = automate::Logic::new(self.ctx.redis().clone());
         let pair = logic.get_link_pair(ip.clone(), mac_addr.clone()).await?;
         let api_url = format!("http://{}/sftp/tunnel/read-dir", pair.1.comet_addr);
 
+        let (password, private_key, passphrase) = auth.into_parts();
         let body = automate::SftpReadDirRequest {
             agent_ip: ip.clone(),
             namespace: namespace.clone(),
             params: SftpReadDirParams {
                 user,
                 password,
+                private_key,
+                passphrase,
                 ip,
                 dir,
                 port,
@@ -287,14 +299,27 @@ impl<'a> SshLogic<'a> {
         mac_addr: String,
         port: u16,
         user: String,
-        password: String,
+        auth: SshAuth,
         filepath: String,
-        data: Vec<u8>,
+        reader: &mut (impl AsyncRead + AsyncSeek + Unpin),
+        transfer_id: Option<String>,
     ) -> Result<String> {
+        let transfer_id = Self::check_transfer_id(transfer_id)?;
         let logic = automate::Logic::new(self.ctx.redis());
         let pair = logic.get_link_pair(ip.clone(), mac_addr.clone()).await?;
         let api_url = format!("http://{}/sftp/tunnel/upload", pair.1.comet_addr);
 
+        // the file goes to comet in chunks, the agent fetches it from there
+        let progress = transfer::upload(
+            &self.ctx.http_client,
+            &pair.1.comet_addr,
+            &self.ctx.conf.comet_secret,
+            &transfer_id,
+            reader,
+        )
+        .await?;
+
+        let (password, private_key, passphrase) = auth.into_parts();
         let body = automate::SftpUploadRequest {
             agent_ip: ip.clone(),
             namespace: namespace.clone(),
@@ -304,8 +329,11 @@ impl<'a> SshLogic<'a> {
                 port,
                 user,
                 password,
+                private_key,
+                passphrase,
                 filepath,
-                data,
+                data: vec![],
+                sha256: progress.sha256,
             },
         };
 
@@ -334,7 +362,7 @@ impl<'a> SshLogic<'a> {
         mac_addr: String,
         port: u16,
         user: String,
-        password: String,
+        auth: SshAuth,
         filepath: String,
         remove_type: String,
     ) -> Result<String> {
@@ -342,6 +370,7 @@ impl<'a> SshLogic<'a> {
         let pair = logic.get_link_pair(ip.clone(), mac_addr.clone()).await?;
         let api_url = format!("http://{}/sftp/tunnel/remove", pair.1.comet_addr);
 
+        let (password, private_key, passphrase) = auth.into_parts();
         let body = automate::SftpRemoveRequest {
             agent_ip: ip.clone(),
             namespace: namespace.clone(),
@@ -351,6 +380,8 @@ impl<'a> SshLogic<'a> {
                 port,
                 user,
                 password,
+                private_key,
+                passphrase,
                 filepath,
                 remove_type,
             },
@@ -380,13 +411,16 @@ impl<'a> SshLogic<'a> {
         mac_addr: String,
         port: u16,
         user: String,
-        password: String,
+        auth: SshAuth,
         filepath: String,
-    ) -> Result<Vec<u8>> {
+        transfer_id: Option<String>,
+    ) -> Result<Body> {
+        let transfer_id = Self::check_transfer_id(transfer_id)?;
         let logic = automate::Logic::new(self.ctx.redis().clone());
         let pair = logic.get_link_pair(ip.clone(), mac_addr.clone()).await?;
         let api_url = format!("http://{}/sftp/tunnel/download", pair.1.comet_addr);
 
+        let (password, private_key, passphrase) = auth.into_parts();
         let body = automate::SftpDownloadRequest {
             agent_ip: ip.clone(),
             namespace: namespace.clone(),
@@ -396,7 +430,10 @@ impl<'a> SshLogic<'a> {
                 port,
                 user,
                 password,
+                private_key,
+                passphrase,
                 filepath,
+                transfer_id: Some(transfer_id),
             },
         };
 
@@ -412,9 +449,53 @@ impl<'a> SshLogic<'a> {
 
         if ret["code"] != 20000 {
             anyhow::bail!(ret["msg"].take().to_string())
-        } else {
+        }
+        // agents without chunked transfer return the content inline
+        if ret["data"].is_array() {
             let data: Vec<u8> = serde_json::from_value(ret["data"].take())?;
-            Ok(data)
+            return Ok(Body::from(data));
         }
+
+        let progress: TransferProgress = serde_json::from_value(ret["data"].take())?;
+        let sha256 = progress.sha256.ok_or(anyhow::anyhow!(
+            "transfer {} is not completed",
+            progress.transfer_id
+        ))?;
+        let tmp_path = env::temp_dir()
+            .join("jiascheduler")
+            .join(format!("{}.download", progress.transfer_id));
+        transfer::download(
+            &self.ctx.http_client,
+            &pair.1.comet_addr,
+            &self.ctx.conf.comet_secret,
+            &sha256,
+            &tmp_path,
+        )
+        .await?;
+        let file = tokio::fs::File::open(&tmp_path).await?;
+        // the open handle keeps the content readable until the response is sent
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        Ok(Body::from_async_read(file))
+    }
+
+    /// Progress of a file the console or an agent is uploading to comet.
+    pub async fn sftp_transfer_progress(
+        &self,
+        ip: String,
+        mac_addr: String,
+        transfer_id: String,
+    ) -> Result<Option<TransferProgress>> {
+        if !transfer::is_transfer_id(&transfer_id) {
+            anyhow::bail!("invalid transfer id {transfer_id}");
+        }
+        let logic = automate::Logic::new(self.ctx.redis());
+        let pair = logic.get_link_pair(ip, mac_addr).await?;
+        transfer::get_progress(
+            &self.ctx.http_client,
+            &pair.1.comet_addr,
+            &self.ctx.conf.comet_secret,
+            &transfer_id,
+        )
+        .await
     }
 }
@@ -24,8 +24,8 @@ impl<'a> TagLogic<'a> {
         user_info: &types::UserInfo,
         tag_name: &str,
         resource_type: ResourceType,
-        resource_id: u64,
-    ) -> Result<u64> {
+        resource_id: i64,
+    ) -> Result<i64> {
         match resource_type {
             ResourceType::Job => {
                 let record = Job::find()
@@ -99,9 +99,9 @@ impl<'a> TagLogic<'a> {
     pub async fn unbind_tag(
         &self,
         _user_info: &types::UserInfo,
-        tag_id: u64,
+        tag_id: i64,
         resource_type: ResourceType,
-        resource_id: Vec<u64>,
+        resource_id: Vec<i64>,
     ) -> Result<u64> {
         let ret = TagResource::delete_many()
             .filter(tag_resource::Column::TagId.eq(tag_id))
@@ -116,7 +116,7 @@ impl<'a> TagLogic<'a> {
         &self,
         _user_info: &types::UserInfo,
         resource_type: ResourceType,
-        team_id: Option<u64>,
+        team_id: Option<i64>,
         username: Option<String>,
     ) -> Result<Vec<types::TagCount>> {
         let select = TagResource::find()
@@ -165,7 +165,7 @@ impl<'a> TagLogic<'a> {
 
     pub async fn get_all_tag_bind_by_job_ids(
         &self,
-        job_ids: Vec<u64>,
+        job_ids: Vec<i64>,
     ) -> Result<Vec<types::TagBind>> {
         let tags = TagResource::find()
             .column(tag::Column::TagName)
@@ -184,7 +184,7 @@ impl<'a> TagLogic<'a> {
         Ok(tags)
     }
 
-    pub async fn get_all_tag_by_job_ids(&self, job_ids: Vec<u64>) -> Result<Vec<tag::Model>> {
+    pub async fn get_all_tag_by_job_ids(&self, job_ids: Vec<i64>) -> Result<Vec<tag::Model>> {
         let tags = Tag::find()
             .filter(
                 Condition::any().add(
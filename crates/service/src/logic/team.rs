@@ -26,7 +26,7 @@ impl<'a> TeamLogic<'a> {
         Self { ctx }
     }
 
-    pub async fn get_team_member(&self, team_id: u64) -> Result<Vec<TeamMemberModel>> {
+    pub async fn get_team_member(&self, team_id: i64) -> Result<Vec<TeamMemberModel>> {
         Ok(TeamMember::find()
             .column(user::Column::Username)
             .filter(team_member::Column::TeamId.eq(team_id))
@@ -42,7 +42,7 @@ impl<'a> TeamLogic<'a> {
             .await?)
     }
 
-    pub async fn can_write_team(&self, team_id: Option<u64>, user_id: String) -> Result<bool> {
+    pub async fn can_write_team(&self, team_id: Option<i64>, user_id: String) -> Result<bool> {
         let Some(team_id) = team_id else {
             return Ok(true);
         };
@@ -77,7 +77,7 @@ impl<'a> TeamLogic<'a> {
         Ok(false)
     }
 
-    pub async fn can_read_team(&self, team_id: Option<u64>, user_id: String) -> Result<bool> {
+    pub async fn can_read_team(&self, team_id: Option<i64>, user_id: String) -> Result<bool> {
         let Some(team_id) = team_id else {
             return Ok(true);
         };
@@ -114,11 +114,11 @@ impl<'a> TeamLogic<'a> {
         Ok(false)
     }
 
-    pub async fn get_team(&self, team_id: u64) -> Result<Option<team::Model>> {
+    pub async fn get_team(&self, team_id: i64) -> Result<Option<team::Model>> {
         Ok(Team::find_by_id(team_id).one(&self.ctx.db).await?)
     }
 
-    pub async fn save_team(&self, active_model: team::ActiveModel) -> Result<u64> {
+    pub async fn save_team(&self, active_model: team::ActiveModel) -> Result<i64> {
         let active_model = active_model.save(&self.ctx.db).await?;
         let team_id = active_model.id.as_ref().to_owned();
         let admin_username = active_model.created_user.as_ref().to_string();
@@ -179,8 +179,8 @@ impl<'a> TeamLogic<'a> {
         &self,
         name: Option<String>,
         created_user: Option<String>,
-        id: Option<u64>,
-        default_id: Option<u64>,
+        id: Option<i64>,
+        default_id: Option<i64>,
         page: u64,
         page_size: u64,
     ) -> Result<(Vec<TeamRecord>, u64)> {
@@ -229,7 +229,7 @@ impl<'a> TeamLogic<'a> {
         Ok((list, total))
     }
 
-    pub async fn delete_team(&self, id: u64) -> Result<u64> {
+    pub async fn delete_team(&self, id: i64) -> Result<u64> {
         let record = Job::find()
             .filter(job::Column::TeamId.eq(id))
             .one(&self.ctx.db)
@@ -250,9 +250,9 @@ impl<'a> TeamLogic<'a> {
 
     pub async fn add_member(
         &self,
-        team_id: u64,
+        team_id: i64,
         members: Vec<team_member::ActiveModel>,
-    ) -> Result<u64> {
+    ) -> Result<i64> {
         if Team::find_by_id(team_id).one(&self.ctx.db).await?.is_none() {
             anyhow::bail!("invalid team");
         }
@@ -284,7 +284,7 @@ impl<'a> TeamLogic<'a> {
             .last_insert_id);
     }
 
-    pub async fn remove_member(&self, team_id: u64, user_ids: Option<Vec<String>>) -> Result<u64> {
+    pub async fn remove_member(&self, team_id: i64, user_ids: Option<Vec<String>>) -> Result<u64> {
         let Some(user_ids) = user_ids else {
             anyhow::bail!("empty users");
         };
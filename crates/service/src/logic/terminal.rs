@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::Local;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QueryTrait, Set,
+};
+
+use crate::IdGenerator;
+use crate::entity::{prelude::*, terminal_session};
+use crate::state::AppContext;
+
+use super::types::UserInfo;
+
+mod recorder;
+
+pub use recorder::{Header, Recorder};
+
+pub struct StartSessionParams {
+    pub instance_id: String,
+    pub ip: String,
+    pub sys_user: String,
+    pub cols: u32,
+    pub rows: u32,
+}
+
+#[derive(Clone)]
+pub struct TerminalLogic<'a> {
+    ctx: &'a AppContext,
+}
+
+impl<'a> TerminalLogic<'a> {
+    pub fn new(ctx: &'a AppContext) -> Self {
+        Self { ctx }
+    }
+
+    /// Creates the session record and starts recording, the session stays
+    /// watchable until [`TerminalLogic::finish_session`] is called.
+    pub async fn start_session(
+        &self,
+        user_info: &UserInfo,
+        params: StartSessionParams,
+    ) -> Result<Arc<Recorder>> {
+        let session_id = IdGenerator::get_terminal_session_uid();
+        let now = Local::now();
+        let record_dir = shellexpand::full(&self.ctx.conf.terminal.record_dir)?.to_string();
+        let record_file = PathBuf::from(record_dir)
+            .join(now.format("%Y%m%d").to_string())
+            .join(format!("{session_id}.cast"));
+
+        let header = Header::new(
+            params.cols,
+            params.rows,
+            format!("{}@{}", params.sys_user, params.ip),
+        );
+        let recorder = Arc::new(Recorder::create(&session_id, &record_file, header).await?);
+
+        terminal_session::ActiveModel {
+            session_id: Set(session_id.clone()),
+            instance_id: Set(params.instance_id),
+            ip: Set(params.ip),
+            sys_user: Set(params.sys_user),
+            user_id: Set(user_info.user_id.clone()),
+            username: Set(user_info.username.clone()),
+            cols: Set(params.cols as i32),
+            rows: Set(params.rows as i32),
+            record_file: Set(record_file.to_string_lossy().to_string()),
+            start_time: Set(now),
+            ..Default::default()
+        }
+        .insert(&self.ctx.db)
+        .await?;
+
+        self.ctx
+            .live_terminals
+            .write()
+            .await
+            .insert(session_id, recorder.clone());
+        Ok(recorder)
+    }
+
+    pub async fn finish_session(&self, recorder: &Recorder) -> Result<()> {
+        self.ctx
+            .live_terminals
+            .write()
+            .await
+            .remove(recorder.session_id());
+        recorder.finish().await?;
+
+        TerminalSession::update_many()
+            .set(terminal_session::ActiveModel {
+                end_time: Set(Some(Local::now())),
+                ..Default::default()
+            })
+            .filter(terminal_session::Column::SessionId.eq(recorder.session_id()))
+            .exec(&self.ctx.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_live_session(&self, session_id: &str) -> Option<Arc<Recorder>> {
+        self.ctx
+            .live_terminals
+            .read()
+            .await
+            .get(session_id)
+            .cloned()
+    }
+
+    pub async fn query_session(
+        &self,
+        instance_id: Option<String>,
+        user_id: Option<String>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<terminal_session::Model>, u64)> {
+        let model = TerminalSession::find()
+            .apply_if(instance_id, |q, v| {
+                q.filter(terminal_session::Column::InstanceId.eq(v))
+            })
+            .apply_if(user_id, |q, v| {
+                q.filter(terminal_session::Column::UserId.eq(v))
+            });
+
+        let total = model.clone().count(&self.ctx.db).await?;
+        let list = model
+            .order_by_desc(terminal_session::Column::Id)
+            .paginate(&self.ctx.db, page_size)
+            .fetch_page(page)
+            .await?;
+        Ok((list, total))
+    }
+
+    pub async fn get_session(&self, session_id: &str) -> Result<Option<terminal_session::Model>> {
+        let one = TerminalSession::find()
+            .filter(terminal_session::Column::SessionId.eq(session_id))
+            .one(&self.ctx.db)
+            .await?;
+        Ok(one)
+    }
+
+    pub async fn read_record(&self, session: &terminal_session::Model) -> Result<Vec<u8>> {
+        Ok(tokio::fs::read(&session.record_file).await?)
+    }
+}
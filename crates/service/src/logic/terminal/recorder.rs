@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex as StdMutex;
+use std::time::Instant;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, File};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, broadcast};
+use tracing::warn;
+
+/// asciicast v2 header, see https://docs.asciinema.org/manual/asciicast/v2/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Header {
+    pub version: u8,
+    pub width: u32,
+    pub height: u32,
+    pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+impl Header {
+    pub fn new(width: u32, height: u32, title: impl Into<String>) -> Self {
+        Self {
+            version: 2,
+            width,
+            height,
+            timestamp: chrono::Local::now().timestamp(),
+            title: Some(title.into()),
+            env: HashMap::from([("TERM".to_string(), "xterm".to_string())]),
+        }
+    }
+}
+
+/// Records a terminal session in asciicast v2 format and fans the events
+/// out to live watchers.
+pub struct Recorder {
+    session_id: String,
+    started: Instant,
+    header: StdMutex<Header>,
+    file: Mutex<File>,
+    live: broadcast::Sender<String>,
+}
+
+impl Recorder {
+    pub async fn create(
+        session_id: impl Into<String>,
+        path: impl AsRef<Path>,
+        header: Header,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        let mut file = File::create(path).await?;
+        file.write_all(format!("{}\n", serde_json::to_string(&header)?).as_bytes())
+            .await?;
+
+        let (live, _) = broadcast::channel(1024);
+        Ok(Self {
+            session_id: session_id.into(),
+            started: Instant::now(),
+            header: StdMutex::new(header),
+            file: Mutex::new(file),
+            live,
+        })
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub async fn input(&self, data: &str) {
+        self.event("i", data).await
+    }
+
+    pub async fn output(&self, data: &str) {
+        self.event("o", data).await
+    }
+
+    pub async fn resize(&self, cols: u32, rows: u32) {
+        {
+            let mut header = self.header.lock().unwrap();
+            header.width = cols;
+            header.height = rows;
+        }
+        self.event("r", &format!("{cols}x{rows}")).await
+    }
+
+    /// Returns a header carrying the current terminal size, followed by
+    /// every event recorded after the call.
+    pub fn watch(&self) -> Result<(String, broadcast::Receiver<String>)> {
+        let rx = self.live.subscribe();
+        let header = serde_json::to_string(&*self.header.lock().unwrap())?;
+        Ok((header, rx))
+    }
+
+    pub async fn finish(&self) -> Result<()> {
+        self.file.lock().await.flush().await?;
+        Ok(())
+    }
+
+    async fn event(&self, code: &str, data: &str) {
+        let elapsed = self.started.elapsed().as_micros() as f64 / 1_000_000.0;
+        let line = match serde_json::to_string(&(elapsed, code, data)) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("failed encode terminal event - {e}");
+                return;
+            }
+        };
+        let _ = self.live.send(line.clone());
+
+        let mut file = self.file.lock().await;
+        if let Err(e) = file.write_all(format!("{line}\n").as_bytes()).await {
+            warn!("failed write terminal record {} - {e}", self.session_id);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_recorder() {
+    let path = std::env::temp_dir().join(format!(
+        "{}.cast",
+        crate::IdGenerator::get_terminal_session_uid()
+    ));
+    let recorder = Recorder::create("w-test", &path, Header::new(80, 24, "root@127.0.0.1"))
+        .await
+        .unwrap();
+    let (_, mut rx) = recorder.watch().unwrap();
+    recorder.input("ls\r").await;
+    recorder.output("ls\r\n").await;
+    recorder.resize(120, 40).await;
+    recorder.finish().await.unwrap();
+
+    assert!(rx.recv().await.unwrap().contains(r#""i","ls\r""#));
+
+    let content = fs::read_to_string(&path).await.unwrap();
+    let mut lines = content.lines();
+    let header: Header = serde_json::from_str(lines.next().unwrap()).unwrap();
+    assert_eq!((header.version, header.width, header.height), (2, 80, 24));
+    let events: Vec<(f64, String, String)> =
+        lines.map(|v| serde_json::from_str(v).unwrap()).collect();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[2].1, "r");
+    assert_eq!(events[2].2, "120x40");
+    let _ = fs::remove_file(&path).await;
+}
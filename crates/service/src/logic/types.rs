@@ -15,7 +15,7 @@ pub struct UserInfo {
     pub user_id: String,
     pub is_root: bool,
     pub role: String,
-    pub role_id: u64,
+    pub role_id: i64,
     pub permissions: Vec<String>,
     pub created_time: String,
     pub updated_time: String,
@@ -23,12 +23,12 @@ pub struct UserInfo {
 
 #[derive(Clone, Serialize, Deserialize, Default, FromQueryResult)]
 pub struct UserRecord {
-    pub id: u64,
+    pub id: i64,
     pub user_id: String,
     pub username: String,
     pub nickname: String,
     pub is_root: bool,
-    pub role_id: u64,
+    pub role_id: i64,
     pub salt: String,
     pub password: String,
     pub avatar: String,
@@ -37,58 +37,65 @@ pub struct UserRecord {
     pub gender: String,
     pub role: Option<String>,
     pub introduction: String,
+    pub auth_provider: String,
     pub created_time: DateTimeLocal,
     pub updated_time: DateTimeLocal,
 }
 
 #[derive(Clone, Serialize, Deserialize, Default, FromQueryResult)]
 pub struct UserServer {
-    pub id: u64,
+    pub id: i64,
     pub ip: String,
     pub instance_id: String,
     pub mac_addr: String,
     pub info: String,
     pub namespace: String,
     pub sys_user: Option<String>,
-    pub ssh_port: Option<u16>,
+    pub ssh_port: Option<i32>,
     pub password: Option<String>,
-    pub instance_group_id: Option<u64>,
+    pub auth_type: Option<String>,
+    pub private_key: Option<String>,
+    pub key_passphrase: Option<String>,
+    pub credential_id: Option<i64>,
+    pub instance_group_id: Option<i64>,
     pub instance_group_name: Option<String>,
-    pub tag_id: Option<u64>,
-    pub status: i8,
+    pub tag_id: Option<i64>,
+    pub status: i16,
     pub created_time: DateTimeLocal,
     pub updated_time: DateTimeLocal,
 }
 
 #[derive(Clone, Serialize, Deserialize, Default, FromQueryResult)]
 pub struct UserRoleCount {
-    pub role_id: u64,
+    pub role_id: i64,
     pub total: i64,
 }
 
 pub struct UserRoleCountList(pub Vec<UserRoleCount>);
 
 impl UserRoleCountList {
-    pub fn get_by_role_id(&self, role_id: u64) -> Option<&UserRoleCount> {
+    pub fn get_by_role_id(&self, role_id: i64) -> Option<&UserRoleCount> {
         self.0.iter().find(|&v| v.role_id == role_id)
     }
 }
 
 #[derive(Clone, Serialize, Deserialize, Default, FromQueryResult)]
 pub struct InstanceRecord {
-    pub id: u64,
+    pub id: i64,
     pub instance_id: String,
     pub ip: String,
     pub namespace: String,
     pub info: String,
-    pub status: i8,
+    pub status: i16,
     pub sys_user: String,
     pub password: String,
-    pub role_id: Option<u64>,
+    pub role_id: Option<i64>,
     pub role_name: Option<String>,
     pub instance_group: Option<String>,
-    pub instance_group_id: u64,
-    pub ssh_port: u16,
+    pub instance_group_id: i64,
+    pub ssh_port: i32,
+    pub auth_type: String,
+    pub credential_id: i64,
     pub created_time: DateTimeLocal,
     pub updated_time: DateTimeLocal,
 }
@@ -114,21 +121,21 @@ impl Display for Permission {
 
 #[derive(Clone, Serialize, Deserialize, Default, FromQueryResult)]
 pub struct TeamMemberCount {
-    pub team_id: u64,
+    pub team_id: i64,
     pub total: i64,
 }
 
 pub struct TeamMemberCountList(pub Vec<TeamMemberCount>);
 
 impl TeamMemberCountList {
-    pub fn get_by_team_id(&self, team_id: u64) -> Option<&TeamMemberCount> {
+    pub fn get_by_team_id(&self, team_id: i64) -> Option<&TeamMemberCount> {
         self.0.iter().find(|&v| v.team_id == team_id)
     }
 }
 
 #[derive(Clone, Serialize, Deserialize, Default, FromQueryResult)]
 pub struct TeamRecord {
-    pub id: u64,
+    pub id: i64,
     pub name: String,
     pub info: String,
     pub is_admin: Option<bool>,
@@ -155,16 +162,16 @@ impl Display for ResourceType {
 
 #[derive(Clone, Serialize, Deserialize, FromQueryResult)]
 pub struct TagCount {
-    pub tag_id: u64,
+    pub tag_id: i64,
     pub tag_name: String,
     pub total: i64,
 }
 
 #[derive(Clone, Serialize, Deserialize, FromQueryResult)]
 pub struct TagBind {
-    pub tag_id: u64,
+    pub tag_id: i64,
     pub tag_name: String,
-    pub resource_id: u64,
+    pub resource_id: i64,
 }
 
 #[derive(Serialize, Deserialize, Default)]
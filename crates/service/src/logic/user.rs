@@ -14,7 +14,7 @@ use nanoid::nanoid;
 use entity::prelude::*;
 use sea_orm::*;
 
-use super::{omit_empty_active_value, types};
+use super::{omit_empty_active_value, security::SecurityLogic, types};
 
 #[derive(Clone)]
 pub struct UserLogic<'a> {
@@ -82,7 +82,8 @@ impl<'a> UserLogic<'a> {
         .await
     }
 
-    pub async fn create_user(&self, user: user::Model) -> Result<u64> {
+    pub async fn create_user(&self, user: user::Model) -> Result<i64> {
+        SecurityLogic::new(self.ctx).check_password(&user.username, &user.password)?;
         let salt = nanoid!();
         let user_id = nanoid!(10);
 
@@ -121,11 +122,6 @@ impl<'a> UserLogic<'a> {
         F: FnOnce(String, String) -> T,
         T: Future<Output = Result<()>>,
     {
-        if let ActiveValue::Set(password) = record.password {
-            let salt = nanoid!();
-            record.password = Set(Self::encry_password(&password, &salt));
-            record.salt = Set(salt)
-        }
         let user_id = if let ActiveValue::Set(ref v) = record.user_id {
             v.to_owned()
         } else {
@@ -137,6 +133,13 @@ impl<'a> UserLogic<'a> {
             .await?
             .ok_or(anyhow!("invalid user_id"))?;
 
+        if let ActiveValue::Set(password) = record.password {
+            SecurityLogic::new(self.ctx).check_password(&user_record.username, &password)?;
+            let salt = nanoid!();
+            record.password = Set(Self::encry_password(&password, &salt));
+            record.salt = Set(salt)
+        }
+
         if let ActiveValue::Set(role_id) = record.role_id {
             if user_record.is_root && role_id != user_record.role_id {
                 anyhow::bail!("root user cannot modify")
@@ -162,7 +165,7 @@ impl<'a> UserLogic<'a> {
         Ok(update_result.rows_affected)
     }
 
-    pub async fn set_role<T: Into<String>>(&self, user_id: T, role_id: u64) -> Result<u64> {
+    pub async fn set_role<T: Into<String>>(&self, user_id: T, role_id: i64) -> Result<u64> {
         let update_result = User::update_many()
             .set(user::ActiveModel {
                 role_id: Set(role_id),
@@ -271,8 +274,8 @@ impl<'a> UserLogic<'a> {
         username: Option<String>,
         nickname: Option<String>,
         phone: Option<String>,
-        role_id: Option<u64>,
-        ignore_role_id: Option<u64>,
+        role_id: Option<i64>,
+        ignore_role_id: Option<i64>,
         keyword: Option<String>,
         page: u64,
         page_size: u64,
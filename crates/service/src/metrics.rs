@@ -0,0 +1,67 @@
+//! Prometheus metrics of the console, exported next to the ones of
+//! [`automate::metrics`] through the default registry.
+
+use std::{sync::LazyLock, time::Instant};
+
+use automate::JobAction;
+use prometheus::{
+    HistogramVec, IntCounterVec, IntGaugeVec, exponential_buckets, register_histogram_vec,
+    register_int_counter_vec, register_int_gauge_vec,
+};
+
+use crate::logic::job::types::DispatchResult;
+
+pub static DISPATCH_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "jiascheduler_dispatch_total",
+        "Dispatches to single instances by action and result",
+        &["action", "result"]
+    )
+    .unwrap()
+});
+
+/// Time of a whole dispatch, labelled error as soon as one instance failed.
+pub static DISPATCH_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "jiascheduler_dispatch_duration_seconds",
+        "Time to dispatch a job to all its instances",
+        &["action", "result"],
+        exponential_buckets(0.01, 4.0, 8).unwrap()
+    )
+    .unwrap()
+});
+
+/// Set on every scrape of the console.
+pub static INSTANCES: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "jiascheduler_instances",
+        "Registered instances by status",
+        &["status"]
+    )
+    .unwrap()
+});
+
+fn dispatch_result(v: &DispatchResult) -> &'static str {
+    match v.err {
+        _ if !v.has_err => "ok",
+        Some(ref e) if e.starts_with("blackout window") => "blackout",
+        _ => "error",
+    }
+}
+
+pub fn observe_dispatch(action: JobAction, started: Instant, results: &[DispatchResult]) {
+    let action = action.to_string();
+    for v in results {
+        DISPATCH_TOTAL
+            .with_label_values(&[&action, dispatch_result(v)])
+            .inc();
+    }
+    let result = if results.iter().any(|v| v.has_err) {
+        "error"
+    } else {
+        "ok"
+    };
+    DISPATCH_DURATION
+        .with_label_values(&[&action, result])
+        .observe(started.elapsed().as_secs_f64());
+}
@@ -1,8 +1,21 @@
 use crate::config::Conf;
+use crate::logic::artifact::{ArtifactLogic, SharedStorage};
+use crate::logic::auth::AuthLogic;
+use crate::logic::calendar::CalendarLogic;
+use crate::logic::credential::CredentialLogic;
+use crate::logic::job_template::JobTemplateLogic;
+use crate::logic::manifest::ManifestLogic;
+use crate::logic::mfa::MfaLogic;
+use crate::logic::permission::PermissionLogic;
+use crate::logic::retention::RetentionLogic;
 use crate::logic::role;
+use crate::logic::script_module::ScriptModuleLogic;
+use crate::logic::security::SecurityLogic;
+use crate::logic::session::SessionLogic;
 use crate::logic::ssh::SshLogic;
 use crate::logic::tag::TagLogic;
 use crate::logic::team::TeamLogic;
+use crate::logic::terminal::{Recorder, TerminalLogic};
 use crate::logic::types::Permission;
 use crate::logic::{
     executor::ExecutorLogic, instance::InstanceLogic, job::JobLogic, migration::MigrationLogic,
@@ -12,12 +25,13 @@ use crate::logic::{
 use anyhow::{Ok, Result};
 use casbin::{CoreApi, EnforceArgs, Enforcer, MgmtApi, RbacApi};
 
-use redis::Client;
+use redis::{Client, aio::ConnectionManager};
 use rustc_serialize::hex::{FromHex, ToHex};
 use sea_orm::DatabaseConnection;
 use simple_crypt::{decrypt, encrypt};
 use tokio::time::Instant;
 
+use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 use std::time::Duration;
@@ -33,11 +47,24 @@ pub struct Service<'a> {
     pub ssh: SshLogic<'a>,
     pub team: TeamLogic<'a>,
     pub tag: TagLogic<'a>,
+    pub terminal: TerminalLogic<'a>,
+    pub credential: CredentialLogic<'a>,
+    pub artifact: ArtifactLogic<'a>,
+    pub permission: PermissionLogic<'a>,
+    pub auth: AuthLogic<'a>,
+    pub mfa: MfaLogic<'a>,
+    pub security: SecurityLogic<'a>,
+    pub session: SessionLogic<'a>,
+    pub calendar: CalendarLogic<'a>,
+    pub manifest: ManifestLogic<'a>,
+    pub retention: RetentionLogic<'a>,
+    pub script_module: ScriptModuleLogic<'a>,
+    pub job_template: JobTemplateLogic<'a>,
 }
 
 #[derive(Clone)]
 pub enum AppState {
-    Inner(AppContext),
+    Inner(Box<AppContext>),
     Uninitialized,
 }
 
@@ -64,6 +91,7 @@ impl DerefMut for AppState {
 pub struct AppContextBuilder {
     db: Option<DatabaseConnection>,
     redis: Option<Client>,
+    redis_manager: Option<ConnectionManager>,
     conf: Option<Conf>,
     http_client: Option<reqwest::Client>,
     enforcer: Option<Arc<RwLock<Enforcer>>>,
@@ -81,6 +109,11 @@ impl AppContextBuilder {
         self
     }
 
+    pub fn redis_manager(mut self, manager: ConnectionManager) -> Self {
+        self.redis_manager = Some(manager);
+        self
+    }
+
     pub fn conf(mut self, conf: Conf) -> Self {
         self.conf = Some(conf);
         self
@@ -101,6 +134,7 @@ impl AppContextBuilder {
     }
 
     pub fn build(self) -> Result<AppContext> {
+        let conf = self.conf.ok_or(anyhow::anyhow!("config is required"))?;
         Ok(AppContext {
             db: self
                 .db
@@ -108,7 +142,11 @@ impl AppContextBuilder {
             redis: self
                 .redis
                 .ok_or(anyhow::anyhow!("redis client is required"))?,
-            conf: self.conf.ok_or(anyhow::anyhow!("config is required"))?,
+            redis_manager: self
+                .redis_manager
+                .ok_or(anyhow::anyhow!("redis connection manager is required"))?,
+            artifact_storage: conf.artifact.connect()?,
+            conf,
             http_client: self
                 .http_client
                 .ok_or(anyhow::anyhow!("http client is required"))?,
@@ -119,6 +157,7 @@ impl AppContextBuilder {
                 self.rate_limiter
                     .ok_or(anyhow::anyhow!("rate limiter is required"))?,
             )),
+            live_terminals: Arc::new(RwLock::new(HashMap::new())),
         })
     }
 }
@@ -151,10 +190,16 @@ impl RateLimiter {
 pub struct AppContext {
     pub db: DatabaseConnection,
     redis: Client,
+    /// shared connection for the frequent commands, eg: on every request
+    redis_manager: ConnectionManager,
     pub conf: Conf,
     rate_limiter: Arc<RwLock<RateLimiter>>,
     pub http_client: reqwest::Client,
     pub enforcer: Arc<RwLock<Enforcer>>,
+    /// web ssh sessions running on this console, keyed by session id
+    pub live_terminals: Arc<RwLock<HashMap<String, Arc<Recorder>>>>,
+    /// content store of uploaded files and job outputs
+    pub artifact_storage: SharedStorage,
 }
 
 impl AppContext {
@@ -163,6 +208,7 @@ impl AppContext {
             enforcer: None,
             db: None,
             redis: None,
+            redis_manager: None,
             conf: None,
             http_client: None,
             rate_limiter: None,
@@ -180,6 +226,19 @@ impl AppContext {
             ssh: SshLogic::new(self),
             team: TeamLogic::new(self),
             tag: TagLogic::new(self),
+            terminal: TerminalLogic::new(self),
+            credential: CredentialLogic::new(self),
+            artifact: ArtifactLogic::new(self),
+            permission: PermissionLogic::new(self),
+            auth: AuthLogic::new(self),
+            mfa: MfaLogic::new(self),
+            security: SecurityLogic::new(self),
+            session: SessionLogic::new(self),
+            calendar: CalendarLogic::new(self),
+            manifest: ManifestLogic::new(self),
+            retention: RetentionLogic::new(self),
+            script_module: ScriptModuleLogic::new(self),
+            job_template: JobTemplateLogic::new(self),
         }
     }
 
@@ -187,6 +246,10 @@ impl AppContext {
         self.redis.clone()
     }
 
+    pub fn redis_manager(&self) -> ConnectionManager {
+        self.redis_manager.clone()
+    }
+
     pub async fn can_execute(&mut self) -> bool {
         let mut limiter = self.rate_limiter.write().await;
         limiter.can_execute()
@@ -217,7 +280,7 @@ impl AppContext {
         Ok(acts)
     }
 
-    pub async fn get_permissions_for_role(&self, role_id: u64) -> Result<Vec<String>> {
+    pub async fn get_permissions_for_role(&self, role_id: i64) -> Result<Vec<String>> {
         let e = self.enforcer.read().await;
         let acts = e
             .get_filtered_policy(0, vec![role_id.to_string()])
@@ -239,7 +302,7 @@ impl AppContext {
         Ok(())
     }
 
-    pub async fn delete_role(&self, role_id: u64) -> Result<()> {
+    pub async fn delete_role(&self, role_id: i64) -> Result<()> {
         let mut e = self.enforcer.write().await;
         e.remove_filtered_policy(0, vec![role_id.to_string()])
             .await?;
@@ -311,7 +374,7 @@ impl AppContext {
         return Ok(true);
     }
 
-    pub async fn set_permissions(&self, role_id: u64, keys: Vec<String>) -> Result<()> {
+    pub async fn set_permissions(&self, role_id: i64, keys: Vec<String>) -> Result<()> {
         self.delete_role(role_id).await?;
         for key in keys {
             let pair = key.splitn(2, "_").collect::<Vec<&str>>();
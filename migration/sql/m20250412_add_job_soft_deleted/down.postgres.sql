@@ -0,0 +1,29 @@
+ALTER TABLE job
+DROP COLUMN is_deleted,
+DROP COLUMN deleted_at,
+DROP COLUMN deleted_by;
+
+ALTER TABLE job_timer
+DROP COLUMN is_deleted,
+DROP COLUMN deleted_at,
+DROP COLUMN deleted_by;
+
+ALTER TABLE job_supervisor
+DROP COLUMN is_deleted,
+DROP COLUMN deleted_at,
+DROP COLUMN deleted_by;
+
+ALTER TABLE job_bundle_script
+DROP COLUMN is_deleted,
+DROP COLUMN deleted_at,
+DROP COLUMN deleted_by;
+
+ALTER TABLE job_schedule_history
+DROP COLUMN is_deleted,
+DROP COLUMN deleted_at,
+DROP COLUMN deleted_by;
+
+ALTER TABLE job_running_status
+DROP COLUMN is_deleted,
+DROP COLUMN deleted_at,
+DROP COLUMN deleted_by;
@@ -0,0 +1,23 @@
+ALTER TABLE job DROP COLUMN is_deleted;
+ALTER TABLE job DROP COLUMN deleted_at;
+ALTER TABLE job DROP COLUMN deleted_by;
+
+ALTER TABLE job_timer DROP COLUMN is_deleted;
+ALTER TABLE job_timer DROP COLUMN deleted_at;
+ALTER TABLE job_timer DROP COLUMN deleted_by;
+
+ALTER TABLE job_supervisor DROP COLUMN is_deleted;
+ALTER TABLE job_supervisor DROP COLUMN deleted_at;
+ALTER TABLE job_supervisor DROP COLUMN deleted_by;
+
+ALTER TABLE job_bundle_script DROP COLUMN is_deleted;
+ALTER TABLE job_bundle_script DROP COLUMN deleted_at;
+ALTER TABLE job_bundle_script DROP COLUMN deleted_by;
+
+ALTER TABLE job_schedule_history DROP COLUMN is_deleted;
+ALTER TABLE job_schedule_history DROP COLUMN deleted_at;
+ALTER TABLE job_schedule_history DROP COLUMN deleted_by;
+
+ALTER TABLE job_running_status DROP COLUMN is_deleted;
+ALTER TABLE job_running_status DROP COLUMN deleted_at;
+ALTER TABLE job_running_status DROP COLUMN deleted_by;
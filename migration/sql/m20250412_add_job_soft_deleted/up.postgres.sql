@@ -0,0 +1,29 @@
+ALTER TABLE job
+ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN deleted_at TIMESTAMPTZ NULL DEFAULT NULL,
+ADD COLUMN deleted_by varchar(50) NOT NULL DEFAULT '';
+
+ALTER TABLE job_timer
+ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN deleted_at TIMESTAMPTZ NULL DEFAULT NULL,
+ADD COLUMN deleted_by varchar(50) NOT NULL DEFAULT '';
+
+ALTER TABLE job_supervisor
+ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN deleted_at TIMESTAMPTZ NULL DEFAULT NULL,
+ADD COLUMN deleted_by varchar(50) NOT NULL DEFAULT '';
+
+ALTER TABLE job_bundle_script
+ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN deleted_at TIMESTAMPTZ NULL DEFAULT NULL,
+ADD COLUMN deleted_by varchar(50) NOT NULL DEFAULT '';
+
+ALTER TABLE job_schedule_history
+ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN deleted_at TIMESTAMPTZ NULL DEFAULT NULL,
+ADD COLUMN deleted_by varchar(50) NOT NULL DEFAULT '';
+
+ALTER TABLE job_running_status
+ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN deleted_at TIMESTAMPTZ NULL DEFAULT NULL,
+ADD COLUMN deleted_by varchar(50) NOT NULL DEFAULT '';
@@ -0,0 +1,23 @@
+ALTER TABLE job ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE job ADD COLUMN deleted_at DATETIME NULL DEFAULT NULL;
+ALTER TABLE job ADD COLUMN deleted_by varchar(50) NOT NULL DEFAULT '';
+
+ALTER TABLE job_timer ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE job_timer ADD COLUMN deleted_at DATETIME NULL DEFAULT NULL;
+ALTER TABLE job_timer ADD COLUMN deleted_by varchar(50) NOT NULL DEFAULT '';
+
+ALTER TABLE job_supervisor ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE job_supervisor ADD COLUMN deleted_at DATETIME NULL DEFAULT NULL;
+ALTER TABLE job_supervisor ADD COLUMN deleted_by varchar(50) NOT NULL DEFAULT '';
+
+ALTER TABLE job_bundle_script ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE job_bundle_script ADD COLUMN deleted_at DATETIME NULL DEFAULT NULL;
+ALTER TABLE job_bundle_script ADD COLUMN deleted_by varchar(50) NOT NULL DEFAULT '';
+
+ALTER TABLE job_schedule_history ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE job_schedule_history ADD COLUMN deleted_at DATETIME NULL DEFAULT NULL;
+ALTER TABLE job_schedule_history ADD COLUMN deleted_by varchar(50) NOT NULL DEFAULT '';
+
+ALTER TABLE job_running_status ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE job_running_status ADD COLUMN deleted_at DATETIME NULL DEFAULT NULL;
+ALTER TABLE job_running_status ADD COLUMN deleted_by varchar(50) NOT NULL DEFAULT '';
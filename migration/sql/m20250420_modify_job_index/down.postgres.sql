@@ -0,0 +1,11 @@
+DROP INDEX IF EXISTS idx_job_name;
+
+ALTER TABLE job ADD CONSTRAINT uk_job_name UNIQUE (name, team_id);
+
+DROP INDEX IF EXISTS idx_job_bundle_script_name;
+
+ALTER TABLE job_bundle_script ADD CONSTRAINT uk_job_bundle_script_name UNIQUE (name, team_id);
+
+ALTER TABLE job_timer ADD CONSTRAINT uk_job_timer_name UNIQUE (name, eid);
+
+ALTER TABLE job_supervisor ADD CONSTRAINT uk_job_supervisor_name UNIQUE (name, eid);
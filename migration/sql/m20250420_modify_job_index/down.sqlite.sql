@@ -0,0 +1,11 @@
+DROP INDEX IF EXISTS idx_job_name;
+
+CREATE UNIQUE INDEX uk_job_name ON job (name, team_id);
+
+DROP INDEX IF EXISTS idx_job_bundle_script_name;
+
+CREATE UNIQUE INDEX uk_job_bundle_script_name ON job_bundle_script (name, team_id);
+
+CREATE UNIQUE INDEX uk_job_timer_name ON job_timer (name, eid);
+
+CREATE UNIQUE INDEX uk_job_supervisor_name ON job_supervisor (name, eid);
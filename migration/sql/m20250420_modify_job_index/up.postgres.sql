@@ -0,0 +1,11 @@
+ALTER TABLE job DROP CONSTRAINT uk_job_name;
+
+CREATE INDEX idx_job_name ON job (team_id, name);
+
+ALTER TABLE job_bundle_script DROP CONSTRAINT uk_job_bundle_script_name;
+
+CREATE INDEX idx_job_bundle_script_name ON job_bundle_script (team_id, name);
+
+ALTER TABLE job_timer DROP CONSTRAINT uk_job_timer_name;
+
+ALTER TABLE job_supervisor DROP CONSTRAINT uk_job_supervisor_name;
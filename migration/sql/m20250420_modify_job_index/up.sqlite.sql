@@ -0,0 +1,11 @@
+DROP INDEX uk_job_name;
+
+CREATE INDEX idx_job_name ON job (team_id, name);
+
+DROP INDEX uk_job_bundle_script_name;
+
+CREATE INDEX idx_job_bundle_script_name ON job_bundle_script (team_id, name);
+
+DROP INDEX uk_job_timer_name;
+
+DROP INDEX uk_job_supervisor_name;
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS `artifact`;
+
+ALTER TABLE job DROP COLUMN `publish_artifacts`;
@@ -0,0 +1,18 @@
+ALTER TABLE job ADD COLUMN publish_artifacts JSONB DEFAULT NULL;
+
+CREATE TABLE artifact (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(200) NOT NULL DEFAULT '',
+    version INTEGER NOT NULL DEFAULT 1,
+    sha256 char(64) NOT NULL DEFAULT '',
+    size BIGINT NOT NULL DEFAULT 0,
+    filename varchar(200) NOT NULL DEFAULT '',
+    source varchar(20) NOT NULL DEFAULT 'upload',
+    eid varchar(100) NOT NULL DEFAULT '',
+    run_id varchar(40) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_artifact_name_version UNIQUE (name, version)
+);
+
+CREATE INDEX idx_artifact_sha256 ON artifact (sha256);
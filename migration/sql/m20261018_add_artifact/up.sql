@@ -0,0 +1,19 @@
+ALTER TABLE job
+ADD COLUMN `publish_artifacts` JSON DEFAULT NULL COMMENT '运行结束后发布为制品的输出文件';
+
+CREATE TABLE `artifact` (
+    `id` bigint NOT NULL AUTO_INCREMENT COMMENT '自增id',
+    `name` varchar(200) NOT NULL DEFAULT '' COMMENT '制品名称',
+    `version` int NOT NULL DEFAULT 1 COMMENT '版本号,同名制品递增',
+    `sha256` char(64) NOT NULL DEFAULT '' COMMENT '内容sha256,同时作为存储键',
+    `size` bigint NOT NULL DEFAULT 0 COMMENT '文件大小',
+    `filename` varchar(200) NOT NULL DEFAULT '' COMMENT '原始文件名',
+    `source` varchar(20) NOT NULL DEFAULT 'upload' COMMENT '来源: upload, job',
+    `eid` varchar(100) NOT NULL DEFAULT '' COMMENT '产出该制品的作业eid',
+    `run_id` varchar(40) NOT NULL DEFAULT '' COMMENT '产出该制品的运行id',
+    `created_user` varchar(50) NOT NULL DEFAULT '' COMMENT '创建人',
+    `created_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_name_version` (`name`, `version`),
+    KEY `idx_sha256` (`sha256`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '制品';
@@ -0,0 +1,19 @@
+ALTER TABLE job ADD COLUMN publish_artifacts TEXT DEFAULT NULL;
+
+CREATE TABLE artifact (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(200) NOT NULL DEFAULT '',
+    version INTEGER NOT NULL DEFAULT 1,
+    sha256 char(64) NOT NULL DEFAULT '',
+    size INTEGER NOT NULL DEFAULT 0,
+    filename varchar(200) NOT NULL DEFAULT '',
+    source varchar(20) NOT NULL DEFAULT 'upload',
+    eid varchar(100) NOT NULL DEFAULT '',
+    run_id varchar(40) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_artifact_name_version ON artifact (name, version);
+
+CREATE INDEX idx_artifact_sha256 ON artifact (sha256);
@@ -0,0 +1 @@
+ALTER TABLE executor DROP COLUMN `container`;
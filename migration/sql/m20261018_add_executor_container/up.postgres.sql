@@ -0,0 +1 @@
+ALTER TABLE executor ADD COLUMN container JSONB DEFAULT NULL;
@@ -0,0 +1,2 @@
+ALTER TABLE executor
+ADD COLUMN `container` JSON DEFAULT NULL COMMENT '容器配置,为空时直接在主机上执行';
@@ -0,0 +1 @@
+ALTER TABLE executor ADD COLUMN container TEXT DEFAULT NULL;
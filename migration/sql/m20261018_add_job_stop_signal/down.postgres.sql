@@ -0,0 +1,7 @@
+ALTER TABLE job
+DROP COLUMN stop_signal,
+DROP COLUMN stop_grace_period;
+
+ALTER TABLE job_supervisor
+DROP COLUMN stop_signal,
+DROP COLUMN stop_grace_period;
@@ -0,0 +1,5 @@
+ALTER TABLE job DROP COLUMN stop_signal;
+ALTER TABLE job DROP COLUMN stop_grace_period;
+
+ALTER TABLE job_supervisor DROP COLUMN stop_signal;
+ALTER TABLE job_supervisor DROP COLUMN stop_grace_period;
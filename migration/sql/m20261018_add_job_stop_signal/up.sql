@@ -0,0 +1,7 @@
+ALTER TABLE job
+ADD COLUMN `stop_signal` varchar(20) NOT NULL DEFAULT 'SIGTERM' COMMENT '停止信号',
+ADD COLUMN `stop_grace_period` INT UNSIGNED NOT NULL DEFAULT 10 COMMENT '停止宽限期,单位秒';
+
+ALTER TABLE job_supervisor
+ADD COLUMN `stop_signal` varchar(20) NOT NULL DEFAULT '' COMMENT '停止信号,为空时使用作业配置',
+ADD COLUMN `stop_grace_period` INT UNSIGNED NULL DEFAULT NULL COMMENT '停止宽限期,为空时使用作业配置';
@@ -0,0 +1,5 @@
+ALTER TABLE job ADD COLUMN stop_signal varchar(20) NOT NULL DEFAULT 'SIGTERM';
+ALTER TABLE job ADD COLUMN stop_grace_period INTEGER NOT NULL DEFAULT 10;
+
+ALTER TABLE job_supervisor ADD COLUMN stop_signal varchar(20) NOT NULL DEFAULT '';
+ALTER TABLE job_supervisor ADD COLUMN stop_grace_period INTEGER NULL DEFAULT NULL;
@@ -0,0 +1,7 @@
+DROP TABLE IF EXISTS `ssh_credential`;
+
+ALTER TABLE instance
+DROP COLUMN `auth_type`,
+DROP COLUMN `private_key`,
+DROP COLUMN `key_passphrase`,
+DROP COLUMN `credential_id`;
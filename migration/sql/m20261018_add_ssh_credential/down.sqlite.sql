@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS ssh_credential;
+
+ALTER TABLE instance DROP COLUMN auth_type;
+ALTER TABLE instance DROP COLUMN private_key;
+ALTER TABLE instance DROP COLUMN key_passphrase;
+ALTER TABLE instance DROP COLUMN credential_id;
@@ -0,0 +1,22 @@
+ALTER TABLE instance
+ADD COLUMN auth_type varchar(20) NOT NULL DEFAULT 'password',
+ADD COLUMN private_key TEXT NULL,
+ADD COLUMN key_passphrase varchar(1000) NOT NULL DEFAULT '',
+ADD COLUMN credential_id BIGINT NOT NULL DEFAULT 0;
+
+CREATE TABLE ssh_credential (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(200) NOT NULL DEFAULT '',
+    sys_user varchar(20) NOT NULL DEFAULT '',
+    auth_type varchar(20) NOT NULL DEFAULT 'password',
+    password varchar(1000) NOT NULL DEFAULT '',
+    private_key TEXT NULL,
+    key_passphrase varchar(1000) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_ssh_credential_name UNIQUE (name)
+);
+
+CREATE TRIGGER trg_ssh_credential_updated_time BEFORE UPDATE ON ssh_credential FOR EACH ROW EXECUTE FUNCTION set_updated_time();
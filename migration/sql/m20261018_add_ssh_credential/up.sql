@@ -0,0 +1,21 @@
+ALTER TABLE instance
+ADD COLUMN `auth_type` varchar(20) NOT NULL DEFAULT 'password' COMMENT '认证方式: password, private_key',
+ADD COLUMN `private_key` text NULL COMMENT '加密后的私钥',
+ADD COLUMN `key_passphrase` VARCHAR(1000) NOT NULL DEFAULT '' COMMENT '加密后的私钥密码',
+ADD COLUMN `credential_id` BIGINT NOT NULL DEFAULT 0 COMMENT '共享凭据id, 0表示使用节点自身凭据';
+
+CREATE TABLE `ssh_credential` (
+    `id` bigint NOT NULL AUTO_INCREMENT COMMENT '自增id',
+    `name` varchar(100) NOT NULL DEFAULT '' COMMENT '凭据名称',
+    `info` varchar(200) NOT NULL DEFAULT '' COMMENT '介绍',
+    `sys_user` varchar(20) NOT NULL DEFAULT '' COMMENT '系统用户,为空时使用节点配置',
+    `auth_type` varchar(20) NOT NULL DEFAULT 'password' COMMENT '认证方式: password, private_key',
+    `password` VARCHAR(1000) NOT NULL DEFAULT '' COMMENT '加密后的密码',
+    `private_key` text NULL COMMENT '加密后的私钥',
+    `key_passphrase` VARCHAR(1000) NOT NULL DEFAULT '' COMMENT '加密后的私钥密码',
+    `created_user` varchar(50) NOT NULL DEFAULT '' COMMENT '创建人',
+    `created_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '修改时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_name` (`name`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = 'ssh凭据';
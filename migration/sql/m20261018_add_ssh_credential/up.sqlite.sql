@@ -0,0 +1,26 @@
+ALTER TABLE instance ADD COLUMN auth_type varchar(20) NOT NULL DEFAULT 'password';
+ALTER TABLE instance ADD COLUMN private_key TEXT NULL;
+ALTER TABLE instance ADD COLUMN key_passphrase varchar(1000) NOT NULL DEFAULT '';
+ALTER TABLE instance ADD COLUMN credential_id INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE ssh_credential (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(200) NOT NULL DEFAULT '',
+    sys_user varchar(20) NOT NULL DEFAULT '',
+    auth_type varchar(20) NOT NULL DEFAULT 'password',
+    password varchar(1000) NOT NULL DEFAULT '',
+    private_key TEXT NULL,
+    key_passphrase varchar(1000) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_ssh_credential_name ON ssh_credential (name);
+
+CREATE TRIGGER trg_ssh_credential_updated_time AFTER UPDATE ON ssh_credential FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE ssh_credential SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
@@ -0,0 +1,3 @@
+ALTER TABLE job_supervisor DROP COLUMN `health_check`;
+
+ALTER TABLE job_running_status DROP COLUMN `probe_status`;
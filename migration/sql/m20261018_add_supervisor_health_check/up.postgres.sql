@@ -0,0 +1,3 @@
+ALTER TABLE job_supervisor ADD COLUMN health_check JSONB DEFAULT NULL;
+
+ALTER TABLE job_running_status ADD COLUMN probe_status JSONB DEFAULT NULL;
@@ -0,0 +1,5 @@
+ALTER TABLE job_supervisor
+ADD COLUMN `health_check` JSON DEFAULT NULL COMMENT '健康检查';
+
+ALTER TABLE job_running_status
+ADD COLUMN `probe_status` JSON DEFAULT NULL COMMENT '探针状态';
@@ -0,0 +1,3 @@
+ALTER TABLE job_supervisor ADD COLUMN health_check TEXT DEFAULT NULL;
+
+ALTER TABLE job_running_status ADD COLUMN probe_status TEXT DEFAULT NULL;
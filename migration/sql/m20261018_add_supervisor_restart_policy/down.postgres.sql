@@ -0,0 +1,5 @@
+ALTER TABLE job_supervisor DROP COLUMN restart_policy;
+
+ALTER TABLE job_running_status
+DROP COLUMN restart_count,
+DROP COLUMN last_crash_reason;
@@ -0,0 +1,4 @@
+ALTER TABLE job_supervisor DROP COLUMN restart_policy;
+
+ALTER TABLE job_running_status DROP COLUMN restart_count;
+ALTER TABLE job_running_status DROP COLUMN last_crash_reason;
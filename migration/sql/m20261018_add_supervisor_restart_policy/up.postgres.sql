@@ -0,0 +1,5 @@
+ALTER TABLE job_supervisor ADD COLUMN restart_policy JSONB DEFAULT NULL;
+
+ALTER TABLE job_running_status
+ADD COLUMN restart_count INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN last_crash_reason varchar(500) NOT NULL DEFAULT '';
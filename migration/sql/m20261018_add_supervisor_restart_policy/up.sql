@@ -0,0 +1,6 @@
+ALTER TABLE job_supervisor
+ADD COLUMN `restart_policy` JSON DEFAULT NULL COMMENT '重启策略';
+
+ALTER TABLE job_running_status
+ADD COLUMN `restart_count` INT UNSIGNED NOT NULL DEFAULT 0 COMMENT '重启次数',
+ADD COLUMN `last_crash_reason` varchar(500) NOT NULL DEFAULT '' COMMENT '最近一次崩溃原因';
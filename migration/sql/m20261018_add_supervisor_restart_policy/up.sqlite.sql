@@ -0,0 +1,4 @@
+ALTER TABLE job_supervisor ADD COLUMN restart_policy TEXT DEFAULT NULL;
+
+ALTER TABLE job_running_status ADD COLUMN restart_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE job_running_status ADD COLUMN last_crash_reason varchar(500) NOT NULL DEFAULT '';
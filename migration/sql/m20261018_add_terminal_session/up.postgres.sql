@@ -0,0 +1,20 @@
+CREATE TABLE terminal_session (
+    id BIGSERIAL PRIMARY KEY,
+    session_id varchar(40) NOT NULL DEFAULT '',
+    instance_id varchar(40) NOT NULL DEFAULT '',
+    ip varchar(100) NOT NULL DEFAULT '',
+    sys_user varchar(20) NOT NULL DEFAULT '',
+    user_id varchar(10) NOT NULL DEFAULT '',
+    username varchar(50) NOT NULL DEFAULT '',
+    cols INTEGER NOT NULL DEFAULT 0,
+    rows INTEGER NOT NULL DEFAULT 0,
+    record_file varchar(500) NOT NULL DEFAULT '',
+    start_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    end_time TIMESTAMPTZ NULL DEFAULT NULL,
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_terminal_session_session_id UNIQUE (session_id)
+);
+
+CREATE INDEX idx_terminal_session_instance_id ON terminal_session (instance_id);
+
+CREATE INDEX idx_terminal_session_user_id ON terminal_session (user_id);
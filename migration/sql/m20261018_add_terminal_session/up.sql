@@ -0,0 +1,19 @@
+CREATE TABLE `terminal_session` (
+    `id` bigint NOT NULL AUTO_INCREMENT COMMENT '自增id',
+    `session_id` varchar(40) NOT NULL DEFAULT '' COMMENT '会话id',
+    `instance_id` varchar(40) NOT NULL DEFAULT '' COMMENT '实例id',
+    `ip` varchar(100) NOT NULL DEFAULT '' COMMENT '节点ip',
+    `sys_user` varchar(20) NOT NULL DEFAULT '' COMMENT '系统用户',
+    `user_id` varchar(10) NOT NULL DEFAULT '' COMMENT '用户id',
+    `username` varchar(50) NOT NULL DEFAULT '' COMMENT '用户名',
+    `cols` int NOT NULL DEFAULT 0 COMMENT '终端列数',
+    `rows` int NOT NULL DEFAULT 0 COMMENT '终端行数',
+    `record_file` varchar(500) NOT NULL DEFAULT '' COMMENT '录像文件',
+    `start_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '开始时间',
+    `end_time` timestamp NULL DEFAULT NULL COMMENT '结束时间',
+    `created_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_session_id` (`session_id`),
+    KEY `idx_instance_id` (`instance_id`),
+    KEY `idx_user_id` (`user_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '终端会话录像';
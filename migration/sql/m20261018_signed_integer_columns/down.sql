@@ -0,0 +1,72 @@
+ALTER TABLE `user`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id',
+MODIFY COLUMN `role_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '用户角色';
+
+ALTER TABLE `team`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id';
+
+ALTER TABLE `team_member`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id',
+MODIFY COLUMN `team_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '团队id';
+
+ALTER TABLE `instance`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id',
+MODIFY COLUMN `instance_group_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '实例分组',
+MODIFY COLUMN `ssh_port` smallint unsigned NOT NULL DEFAULT 22 COMMENT 'ssh 连接端口';
+
+ALTER TABLE `instance_group`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id';
+
+ALTER TABLE `instance_role`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id',
+MODIFY COLUMN `role_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '角色id',
+MODIFY COLUMN `instance_group_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '实例分组id';
+
+ALTER TABLE `role`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id';
+
+ALTER TABLE `user_server`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id';
+
+ALTER TABLE `job_timer`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id';
+
+ALTER TABLE `job_supervisor`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id',
+MODIFY COLUMN `restart_interval` bigint unsigned NOT NULL DEFAULT 1 COMMENT '重启间隔,单位秒',
+MODIFY COLUMN `stop_grace_period` int unsigned NULL DEFAULT NULL COMMENT '停止宽限期,为空时使用作业配置';
+
+ALTER TABLE `job`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id',
+MODIFY COLUMN `executor_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '执行器',
+MODIFY COLUMN `team_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '团队id',
+MODIFY COLUMN `timeout` bigint unsigned NOT NULL DEFAULT 60 COMMENT '执行超时,单位秒',
+MODIFY COLUMN `max_retry` tinyint unsigned NOT NULL DEFAULT 1 COMMENT '最大重试次数',
+MODIFY COLUMN `max_parallel` tinyint unsigned NOT NULL DEFAULT 1 COMMENT '进程最大并行数',
+MODIFY COLUMN `stop_grace_period` int unsigned NOT NULL DEFAULT 10 COMMENT '停止宽限期,单位秒';
+
+ALTER TABLE `job_bundle_script`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id',
+MODIFY COLUMN `executor_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '执行器',
+MODIFY COLUMN `team_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '团队id';
+
+ALTER TABLE `executor`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id';
+
+ALTER TABLE `job_exec_history`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id';
+
+ALTER TABLE `job_running_status`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id',
+MODIFY COLUMN `restart_count` int unsigned NOT NULL DEFAULT 0 COMMENT '重启次数';
+
+ALTER TABLE `job_schedule_history`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id';
+
+ALTER TABLE `tag`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id';
+
+ALTER TABLE `tag_resource`
+MODIFY COLUMN `id` bigint unsigned NOT NULL AUTO_INCREMENT COMMENT '自增id',
+MODIFY COLUMN `tag_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '标签id',
+MODIFY COLUMN `resource_id` bigint unsigned NOT NULL DEFAULT 0 COMMENT '标签值id';
@@ -0,0 +1,3 @@
+ALTER TABLE artifact
+DROP KEY `idx_team_id`,
+DROP COLUMN `team_id`;
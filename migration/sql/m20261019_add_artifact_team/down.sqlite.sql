@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_artifact_team_id;
+ALTER TABLE artifact DROP COLUMN team_id;
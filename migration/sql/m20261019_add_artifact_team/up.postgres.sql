@@ -0,0 +1,3 @@
+ALTER TABLE artifact ADD COLUMN team_id BIGINT NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_artifact_team_id ON artifact (team_id);
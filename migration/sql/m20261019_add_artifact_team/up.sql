@@ -0,0 +1,3 @@
+ALTER TABLE artifact
+ADD COLUMN `team_id` bigint NOT NULL DEFAULT 0 COMMENT '所属团队' AFTER `version`,
+ADD KEY `idx_team_id` (`team_id`);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS calendar;
+DROP TABLE IF EXISTS blackout_window;
@@ -0,0 +1,35 @@
+CREATE TABLE calendar (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(500) NOT NULL DEFAULT '',
+    mode varchar(20) NOT NULL DEFAULT 'exclude',
+    dates JSONB DEFAULT NULL,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_calendar_name UNIQUE (name)
+);
+
+CREATE TRIGGER trg_calendar_updated_time BEFORE UPDATE ON calendar FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+CREATE TABLE blackout_window (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(500) NOT NULL DEFAULT '',
+    start_expr varchar(100) NOT NULL DEFAULT '',
+    duration_secs BIGINT NOT NULL DEFAULT 0,
+    timezone varchar(64) NOT NULL DEFAULT '',
+    namespace varchar(100) NOT NULL DEFAULT '',
+    instance_group_id BIGINT NOT NULL DEFAULT 0,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_blackout_window_namespace ON blackout_window (namespace);
+CREATE INDEX idx_blackout_window_instance_group_id ON blackout_window (instance_group_id);
+
+CREATE TRIGGER trg_blackout_window_updated_time BEFORE UPDATE ON blackout_window FOR EACH ROW EXECUTE FUNCTION set_updated_time();
@@ -0,0 +1,32 @@
+CREATE TABLE `calendar` (
+    `id` bigint NOT NULL AUTO_INCREMENT COMMENT '自增id',
+    `name` varchar(100) NOT NULL DEFAULT '' COMMENT '日历名称',
+    `info` varchar(500) NOT NULL DEFAULT '' COMMENT '描述',
+    `mode` varchar(20) NOT NULL DEFAULT 'exclude' COMMENT 'include只在这些日期运行, exclude跳过这些日期',
+    `dates` JSON DEFAULT NULL COMMENT '日期列表, YYYY-MM-DD',
+    `created_user` varchar(50) NOT NULL DEFAULT '' COMMENT '创建人',
+    `updated_user` varchar(50) NOT NULL DEFAULT '' COMMENT '更新人',
+    `created_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_name` (`name`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '业务日历';
+
+CREATE TABLE `blackout_window` (
+    `id` bigint NOT NULL AUTO_INCREMENT COMMENT '自增id',
+    `name` varchar(100) NOT NULL DEFAULT '' COMMENT '名称',
+    `info` varchar(500) NOT NULL DEFAULT '' COMMENT '描述',
+    `start_expr` varchar(100) NOT NULL DEFAULT '' COMMENT '窗口开始的cron表达式',
+    `duration_secs` bigint NOT NULL DEFAULT 0 COMMENT '窗口持续秒数',
+    `timezone` varchar(64) NOT NULL DEFAULT '' COMMENT '时区, 为空时使用节点本地时区',
+    `namespace` varchar(100) NOT NULL DEFAULT '' COMMENT '作用的命名空间',
+    `instance_group_id` bigint NOT NULL DEFAULT 0 COMMENT '作用的实例分组',
+    `enabled` boolean NOT NULL DEFAULT true COMMENT '是否启用',
+    `created_user` varchar(50) NOT NULL DEFAULT '' COMMENT '创建人',
+    `updated_user` varchar(50) NOT NULL DEFAULT '' COMMENT '更新人',
+    `created_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    KEY `idx_namespace` (`namespace`),
+    KEY `idx_instance_group_id` (`instance_group_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '禁止运行窗口';
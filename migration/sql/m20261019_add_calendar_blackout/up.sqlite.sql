@@ -0,0 +1,44 @@
+CREATE TABLE calendar (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(500) NOT NULL DEFAULT '',
+    mode varchar(20) NOT NULL DEFAULT 'exclude',
+    dates TEXT DEFAULT NULL,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_calendar_name ON calendar (name);
+
+CREATE TRIGGER trg_calendar_updated_time AFTER UPDATE ON calendar FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE calendar SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+CREATE TABLE blackout_window (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(500) NOT NULL DEFAULT '',
+    start_expr varchar(100) NOT NULL DEFAULT '',
+    duration_secs INTEGER NOT NULL DEFAULT 0,
+    timezone varchar(64) NOT NULL DEFAULT '',
+    namespace varchar(100) NOT NULL DEFAULT '',
+    instance_group_id INTEGER NOT NULL DEFAULT 0,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_blackout_window_namespace ON blackout_window (namespace);
+CREATE INDEX idx_blackout_window_instance_group_id ON blackout_window (instance_group_id);
+
+CREATE TRIGGER trg_blackout_window_updated_time AFTER UPDATE ON blackout_window FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE blackout_window SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
@@ -0,0 +1,8 @@
+DROP TABLE IF EXISTS script_module;
+DROP TABLE IF EXISTS script_module_version;
+DROP TABLE IF EXISTS job_template;
+
+DROP INDEX IF EXISTS idx_job_template_id;
+ALTER TABLE job
+DROP COLUMN template_id,
+DROP COLUMN template_version;
@@ -0,0 +1,8 @@
+DROP TABLE IF EXISTS `script_module`;
+DROP TABLE IF EXISTS `script_module_version`;
+DROP TABLE IF EXISTS `job_template`;
+
+ALTER TABLE job
+DROP KEY `idx_template_id`,
+DROP COLUMN `template_id`,
+DROP COLUMN `template_version`;
@@ -0,0 +1,56 @@
+CREATE TABLE script_module (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(500) NOT NULL DEFAULT '',
+    team_id BIGINT NOT NULL DEFAULT 0,
+    latest_version INTEGER NOT NULL DEFAULT 0,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_script_module_team_id_name UNIQUE (team_id, name)
+);
+
+CREATE TRIGGER trg_script_module_updated_time BEFORE UPDATE ON script_module FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+CREATE TABLE script_module_version (
+    id BIGSERIAL PRIMARY KEY,
+    module_id BIGINT NOT NULL DEFAULT 0,
+    version INTEGER NOT NULL DEFAULT 0,
+    code text NOT NULL,
+    changelog varchar(500) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_script_module_version_module_id_version UNIQUE (module_id, version)
+);
+
+CREATE TABLE job_template (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(500) NOT NULL DEFAULT '',
+    team_id BIGINT NOT NULL DEFAULT 0,
+    version INTEGER NOT NULL DEFAULT 1,
+    executor_id BIGINT NOT NULL DEFAULT 0,
+    work_dir varchar(500) NOT NULL DEFAULT '',
+    work_user varchar(50) NOT NULL DEFAULT '',
+    timeout BIGINT NOT NULL DEFAULT 60,
+    max_retry SMALLINT NOT NULL DEFAULT 1,
+    max_parallel SMALLINT NOT NULL DEFAULT 1,
+    stop_signal varchar(20) NOT NULL DEFAULT 'SIGTERM',
+    stop_grace_period INTEGER NOT NULL DEFAULT 10,
+    args JSONB DEFAULT NULL,
+    completed_callback JSONB DEFAULT NULL,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_job_template_team_id_name UNIQUE (team_id, name)
+);
+
+CREATE TRIGGER trg_job_template_updated_time BEFORE UPDATE ON job_template FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+ALTER TABLE job
+ADD COLUMN template_id BIGINT NOT NULL DEFAULT 0,
+ADD COLUMN template_version INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_job_template_id ON job (template_id);
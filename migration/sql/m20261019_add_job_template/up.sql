@@ -0,0 +1,54 @@
+CREATE TABLE `script_module` (
+    `id` bigint NOT NULL AUTO_INCREMENT COMMENT '自增id',
+    `name` varchar(100) NOT NULL DEFAULT '' COMMENT '模块名称,作业中通过 #@include 名称 引用',
+    `info` varchar(500) NOT NULL DEFAULT '' COMMENT '描述',
+    `team_id` bigint NOT NULL DEFAULT 0 COMMENT '所属团队, 0为所有团队共享',
+    `latest_version` int NOT NULL DEFAULT 0 COMMENT '最新版本',
+    `created_user` varchar(50) NOT NULL DEFAULT '' COMMENT '创建人',
+    `updated_user` varchar(50) NOT NULL DEFAULT '' COMMENT '更新人',
+    `created_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_team_id_name` (`team_id`, `name`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '脚本模块';
+
+CREATE TABLE `script_module_version` (
+    `id` bigint NOT NULL AUTO_INCREMENT COMMENT '自增id',
+    `module_id` bigint NOT NULL DEFAULT 0 COMMENT '脚本模块id',
+    `version` int NOT NULL DEFAULT 0 COMMENT '版本',
+    `code` text NOT NULL COMMENT '代码',
+    `changelog` varchar(500) NOT NULL DEFAULT '' COMMENT '变更说明',
+    `created_user` varchar(50) NOT NULL DEFAULT '' COMMENT '创建人',
+    `created_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_module_id_version` (`module_id`, `version`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '脚本模块版本';
+
+CREATE TABLE `job_template` (
+    `id` bigint NOT NULL AUTO_INCREMENT COMMENT '自增id',
+    `name` varchar(100) NOT NULL DEFAULT '' COMMENT '模板名称',
+    `info` varchar(500) NOT NULL DEFAULT '' COMMENT '描述',
+    `team_id` bigint NOT NULL DEFAULT 0 COMMENT '所属团队',
+    `version` int NOT NULL DEFAULT 1 COMMENT '版本,每次修改加一',
+    `executor_id` bigint NOT NULL DEFAULT 0 COMMENT '执行器',
+    `work_dir` varchar(500) NOT NULL DEFAULT '' COMMENT '工作目录',
+    `work_user` varchar(50) NOT NULL DEFAULT '' COMMENT '执行用户',
+    `timeout` bigint NOT NULL DEFAULT 60 COMMENT '执行超时,单位秒',
+    `max_retry` smallint NOT NULL DEFAULT 1 COMMENT '最大重试次数',
+    `max_parallel` smallint NOT NULL DEFAULT 1 COMMENT '进程最大并行数',
+    `stop_signal` varchar(20) NOT NULL DEFAULT 'SIGTERM' COMMENT '停止信号',
+    `stop_grace_period` int NOT NULL DEFAULT 10 COMMENT '停止宽限期,单位秒',
+    `args` json DEFAULT NULL COMMENT '作业参数',
+    `completed_callback` json DEFAULT NULL COMMENT '任务完成回调',
+    `created_user` varchar(50) NOT NULL DEFAULT '' COMMENT '创建人',
+    `updated_user` varchar(50) NOT NULL DEFAULT '' COMMENT '更新人',
+    `created_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_team_id_name` (`team_id`, `name`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '作业模板';
+
+ALTER TABLE job
+ADD COLUMN `template_id` bigint NOT NULL DEFAULT 0 COMMENT '继承的作业模板',
+ADD COLUMN `template_version` int NOT NULL DEFAULT 0 COMMENT '最后同步的模板版本',
+ADD KEY `idx_template_id` (`template_id`);
@@ -0,0 +1,66 @@
+CREATE TABLE script_module (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(500) NOT NULL DEFAULT '',
+    team_id INTEGER NOT NULL DEFAULT 0,
+    latest_version INTEGER NOT NULL DEFAULT 0,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_script_module_team_id_name ON script_module (team_id, name);
+
+CREATE TRIGGER trg_script_module_updated_time AFTER UPDATE ON script_module FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE script_module SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+CREATE TABLE script_module_version (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    module_id INTEGER NOT NULL DEFAULT 0,
+    version INTEGER NOT NULL DEFAULT 0,
+    code text NOT NULL,
+    changelog varchar(500) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_script_module_version_module_id_version ON script_module_version (module_id, version);
+
+CREATE TABLE job_template (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(500) NOT NULL DEFAULT '',
+    team_id INTEGER NOT NULL DEFAULT 0,
+    version INTEGER NOT NULL DEFAULT 1,
+    executor_id INTEGER NOT NULL DEFAULT 0,
+    work_dir varchar(500) NOT NULL DEFAULT '',
+    work_user varchar(50) NOT NULL DEFAULT '',
+    timeout INTEGER NOT NULL DEFAULT 60,
+    max_retry INTEGER NOT NULL DEFAULT 1,
+    max_parallel INTEGER NOT NULL DEFAULT 1,
+    stop_signal varchar(20) NOT NULL DEFAULT 'SIGTERM',
+    stop_grace_period INTEGER NOT NULL DEFAULT 10,
+    args TEXT DEFAULT NULL,
+    completed_callback TEXT DEFAULT NULL,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_job_template_team_id_name ON job_template (team_id, name);
+
+CREATE TRIGGER trg_job_template_updated_time AFTER UPDATE ON job_template FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE job_template SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+ALTER TABLE job ADD COLUMN template_id INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE job ADD COLUMN template_version INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_job_template_id ON job (template_id);
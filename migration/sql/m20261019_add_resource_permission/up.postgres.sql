@@ -0,0 +1,13 @@
+CREATE TABLE resource_permission (
+    id BIGSERIAL PRIMARY KEY,
+    resource_type varchar(20) NOT NULL DEFAULT '',
+    resource_id BIGINT NOT NULL DEFAULT 0,
+    grantee_type varchar(10) NOT NULL DEFAULT '',
+    grantee_id varchar(50) NOT NULL DEFAULT '',
+    action varchar(20) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_resource_permission_grant UNIQUE (resource_type, resource_id, grantee_type, grantee_id, action)
+);
+
+CREATE INDEX idx_resource_permission_grantee ON resource_permission (grantee_type, grantee_id);
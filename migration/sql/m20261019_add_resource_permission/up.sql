@@ -0,0 +1,13 @@
+CREATE TABLE `resource_permission` (
+    `id` bigint NOT NULL AUTO_INCREMENT COMMENT '自增id',
+    `resource_type` varchar(20) NOT NULL DEFAULT '' COMMENT '资源类型: job, timer, supervisor, instance_group',
+    `resource_id` bigint NOT NULL DEFAULT 0 COMMENT '资源id',
+    `grantee_type` varchar(10) NOT NULL DEFAULT '' COMMENT '授权对象类型: user, team',
+    `grantee_id` varchar(50) NOT NULL DEFAULT '' COMMENT '用户id或团队id',
+    `action` varchar(20) NOT NULL DEFAULT '' COMMENT '操作: view, edit, dispatch, kill, ssh',
+    `created_user` varchar(50) NOT NULL DEFAULT '' COMMENT '创建人',
+    `created_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_grant` (`resource_type`, `resource_id`, `grantee_type`, `grantee_id`, `action`),
+    KEY `idx_grantee` (`grantee_type`, `grantee_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '资源授权';
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS retention_policy;
+DROP TABLE IF EXISTS retention_prune_log;
@@ -0,0 +1,33 @@
+CREATE TABLE retention_policy (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(500) NOT NULL DEFAULT '',
+    team_id BIGINT NOT NULL DEFAULT 0,
+    eid varchar(100) NOT NULL DEFAULT '',
+    max_age_days INTEGER NOT NULL DEFAULT 0,
+    max_count INTEGER NOT NULL DEFAULT 0,
+    keep_failed INTEGER NOT NULL DEFAULT 0,
+    archive BOOLEAN NOT NULL DEFAULT false,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_retention_policy_team_id_eid UNIQUE (team_id, eid)
+);
+
+CREATE TRIGGER trg_retention_policy_updated_time BEFORE UPDATE ON retention_policy FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+CREATE TABLE retention_prune_log (
+    id BIGSERIAL PRIMARY KEY,
+    policy_id BIGINT NOT NULL DEFAULT 0,
+    eid varchar(100) NOT NULL DEFAULT '',
+    exec_history BIGINT NOT NULL DEFAULT 0,
+    schedule_history BIGINT NOT NULL DEFAULT 0,
+    running_status BIGINT NOT NULL DEFAULT 0,
+    archive_file varchar(500) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_retention_prune_log_policy_id ON retention_prune_log (policy_id);
+CREATE INDEX idx_retention_prune_log_eid ON retention_prune_log (eid);
@@ -0,0 +1,32 @@
+CREATE TABLE `retention_policy` (
+    `id` bigint NOT NULL AUTO_INCREMENT COMMENT '自增id',
+    `name` varchar(100) NOT NULL DEFAULT '' COMMENT '策略名称',
+    `info` varchar(500) NOT NULL DEFAULT '' COMMENT '描述',
+    `team_id` bigint NOT NULL DEFAULT 0 COMMENT '作用的团队, 为0且eid为空时作用于所有作业',
+    `eid` varchar(100) NOT NULL DEFAULT '' COMMENT '作用的作业, 为空时作用于团队下所有作业',
+    `max_age_days` int NOT NULL DEFAULT 0 COMMENT '保留天数, 0不限制',
+    `max_count` int NOT NULL DEFAULT 0 COMMENT '每个作业保留的执行记录数, 0不限制',
+    `keep_failed` int NOT NULL DEFAULT 0 COMMENT '总是保留最近的失败记录数',
+    `archive` boolean NOT NULL DEFAULT false COMMENT '删除前是否归档',
+    `enabled` boolean NOT NULL DEFAULT true COMMENT '是否启用',
+    `created_user` varchar(50) NOT NULL DEFAULT '' COMMENT '创建人',
+    `updated_user` varchar(50) NOT NULL DEFAULT '' COMMENT '更新人',
+    `created_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_team_id_eid` (`team_id`, `eid`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '历史记录保留策略';
+
+CREATE TABLE `retention_prune_log` (
+    `id` bigint NOT NULL AUTO_INCREMENT COMMENT '自增id',
+    `policy_id` bigint NOT NULL DEFAULT 0 COMMENT '保留策略id',
+    `eid` varchar(100) NOT NULL DEFAULT '' COMMENT '作业eid',
+    `exec_history` bigint NOT NULL DEFAULT 0 COMMENT '删除的执行记录数',
+    `schedule_history` bigint NOT NULL DEFAULT 0 COMMENT '删除的调度记录数',
+    `running_status` bigint NOT NULL DEFAULT 0 COMMENT '删除的运行状态数',
+    `archive_file` varchar(500) NOT NULL DEFAULT '' COMMENT '归档文件',
+    `created_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '清理时间',
+    PRIMARY KEY (`id`),
+    KEY `idx_policy_id` (`policy_id`),
+    KEY `idx_eid` (`eid`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '历史记录清理日志';
@@ -0,0 +1,38 @@
+CREATE TABLE retention_policy (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(500) NOT NULL DEFAULT '',
+    team_id INTEGER NOT NULL DEFAULT 0,
+    eid varchar(100) NOT NULL DEFAULT '',
+    max_age_days INTEGER NOT NULL DEFAULT 0,
+    max_count INTEGER NOT NULL DEFAULT 0,
+    keep_failed INTEGER NOT NULL DEFAULT 0,
+    archive BOOLEAN NOT NULL DEFAULT false,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_retention_policy_team_id_eid ON retention_policy (team_id, eid);
+
+CREATE TRIGGER trg_retention_policy_updated_time AFTER UPDATE ON retention_policy FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE retention_policy SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+CREATE TABLE retention_prune_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    policy_id INTEGER NOT NULL DEFAULT 0,
+    eid varchar(100) NOT NULL DEFAULT '',
+    exec_history INTEGER NOT NULL DEFAULT 0,
+    schedule_history INTEGER NOT NULL DEFAULT 0,
+    running_status INTEGER NOT NULL DEFAULT 0,
+    archive_file varchar(500) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_retention_prune_log_policy_id ON retention_prune_log (policy_id);
+CREATE INDEX idx_retention_prune_log_eid ON retention_prune_log (eid);
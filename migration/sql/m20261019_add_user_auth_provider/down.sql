@@ -0,0 +1,3 @@
+ALTER TABLE `user`
+DROP COLUMN `auth_provider`,
+DROP COLUMN `external_id`;
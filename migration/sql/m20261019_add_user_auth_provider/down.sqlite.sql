@@ -0,0 +1,2 @@
+ALTER TABLE "user" DROP COLUMN auth_provider;
+ALTER TABLE "user" DROP COLUMN external_id;
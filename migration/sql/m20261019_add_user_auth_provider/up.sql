@@ -0,0 +1,3 @@
+ALTER TABLE `user`
+ADD COLUMN `auth_provider` varchar(20) NOT NULL DEFAULT 'local' COMMENT '认证来源: local, oidc, ldap',
+ADD COLUMN `external_id` varchar(255) NOT NULL DEFAULT '' COMMENT '外部身份id, oidc的sub或ldap的dn';
@@ -0,0 +1,2 @@
+ALTER TABLE "user" ADD COLUMN auth_provider varchar(20) NOT NULL DEFAULT 'local';
+ALTER TABLE "user" ADD COLUMN external_id varchar(255) NOT NULL DEFAULT '';
@@ -0,0 +1,13 @@
+CREATE TABLE user_mfa (
+    id BIGSERIAL PRIMARY KEY,
+    user_id varchar(50) NOT NULL DEFAULT '',
+    secret varchar(512) NOT NULL DEFAULT '',
+    enabled BOOLEAN NOT NULL DEFAULT false,
+    recovery_codes TEXT NOT NULL DEFAULT '',
+    last_used_step BIGINT NOT NULL DEFAULT 0,
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_user_mfa_user_id UNIQUE (user_id)
+);
+
+CREATE TRIGGER trg_user_mfa_updated_time BEFORE UPDATE ON user_mfa FOR EACH ROW EXECUTE FUNCTION set_updated_time();
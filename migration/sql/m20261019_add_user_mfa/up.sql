@@ -0,0 +1,12 @@
+CREATE TABLE `user_mfa` (
+    `id` bigint NOT NULL AUTO_INCREMENT COMMENT '自增id',
+    `user_id` varchar(50) NOT NULL DEFAULT '' COMMENT '用户id',
+    `secret` varchar(512) NOT NULL DEFAULT '' COMMENT '加密后的totp密钥',
+    `enabled` boolean NOT NULL DEFAULT false COMMENT '是否已启用',
+    `recovery_codes` text NOT NULL COMMENT '恢复码摘要, json数组',
+    `last_used_step` bigint NOT NULL DEFAULT 0 COMMENT '最近一次使用的时间步, 防止重放',
+    `created_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+    `updated_time` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_user_id` (`user_id`)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COMMENT = '用户两步验证';
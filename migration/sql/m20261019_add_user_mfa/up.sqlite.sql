@@ -0,0 +1,18 @@
+CREATE TABLE user_mfa (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id varchar(50) NOT NULL DEFAULT '',
+    secret varchar(512) NOT NULL DEFAULT '',
+    enabled BOOLEAN NOT NULL DEFAULT false,
+    recovery_codes TEXT NOT NULL DEFAULT '',
+    last_used_step INTEGER NOT NULL DEFAULT 0,
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_user_mfa_user_id ON user_mfa (user_id);
+
+CREATE TRIGGER trg_user_mfa_updated_time AFTER UPDATE ON user_mfa FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE user_mfa SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
@@ -0,0 +1,31 @@
+DROP TABLE IF EXISTS "user";
+
+DROP TABLE IF EXISTS team;
+
+DROP TABLE IF EXISTS team_member;
+
+DROP TABLE IF EXISTS instance;
+
+DROP TABLE IF EXISTS instance_group;
+
+DROP TABLE IF EXISTS instance_role;
+
+DROP TABLE IF EXISTS role;
+
+DROP TABLE IF EXISTS user_server;
+
+DROP TABLE IF EXISTS job_timer;
+
+DROP TABLE IF EXISTS job_supervisor;
+
+DROP TABLE IF EXISTS job;
+
+DROP TABLE IF EXISTS job_bundle_script;
+
+DROP TABLE IF EXISTS executor;
+
+DROP TABLE IF EXISTS job_exec_history;
+
+DROP TABLE IF EXISTS job_running_status;
+
+DROP TABLE IF EXISTS job_schedule_history;
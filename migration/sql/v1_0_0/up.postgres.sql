@@ -0,0 +1,339 @@
+CREATE OR REPLACE FUNCTION set_updated_time() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_time = CURRENT_TIMESTAMP;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TABLE IF EXISTS "user";
+
+CREATE TABLE "user" (
+    id BIGSERIAL PRIMARY KEY,
+    user_id varchar(10) NOT NULL,
+    username varchar(50) NOT NULL DEFAULT '',
+    nickname varchar(50) NOT NULL DEFAULT '',
+    is_root BOOLEAN NOT NULL DEFAULT false,
+    role_id BIGINT NOT NULL DEFAULT 0,
+    salt varchar(50) NOT NULL DEFAULT '',
+    password varchar(100) NOT NULL DEFAULT '',
+    avatar varchar(100) NOT NULL DEFAULT '',
+    email varchar(200) NOT NULL DEFAULT '',
+    phone varchar(20) NOT NULL DEFAULT '',
+    gender varchar(10) NOT NULL DEFAULT 'male',
+    introduction varchar(2000) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uqe_user_username UNIQUE (username),
+    CONSTRAINT uqe_user_user_id UNIQUE (user_id)
+);
+
+CREATE TRIGGER trg_user_updated_time BEFORE UPDATE ON "user" FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+DROP TABLE IF EXISTS team;
+
+CREATE TABLE team (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(200) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_team_name UNIQUE (name, created_user)
+);
+
+CREATE TRIGGER trg_team_updated_time BEFORE UPDATE ON team FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+DROP TABLE IF EXISTS team_member;
+
+CREATE TABLE team_member (
+    id BIGSERIAL PRIMARY KEY,
+    team_id BIGINT NOT NULL DEFAULT 0,
+    user_id varchar(10) NOT NULL,
+    is_admin BOOLEAN NOT NULL DEFAULT false,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_team_member_user_id UNIQUE (user_id, team_id)
+);
+
+DROP TABLE IF EXISTS instance;
+
+CREATE TABLE instance (
+    id BIGSERIAL PRIMARY KEY,
+    instance_id varchar(40) NOT NULL DEFAULT '',
+    ip varchar(100) NOT NULL DEFAULT '',
+    namespace varchar(100) NOT NULL DEFAULT 'default',
+    mac_addr varchar(20) NOT NULL DEFAULT '',
+    instance_group_id BIGINT NOT NULL DEFAULT 0,
+    info varchar(500) NOT NULL DEFAULT '',
+    status SMALLINT NOT NULL DEFAULT 0,
+    sys_user varchar(20) NOT NULL DEFAULT 'root',
+    password varchar(1000) NOT NULL DEFAULT '',
+    ssh_port INTEGER NOT NULL DEFAULT 22,
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_instance_ip UNIQUE (mac_addr, ip)
+);
+
+CREATE TRIGGER trg_instance_updated_time BEFORE UPDATE ON instance FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+DROP TABLE IF EXISTS instance_group;
+
+CREATE TABLE instance_group (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(200) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_instance_group_name UNIQUE (name)
+);
+
+CREATE TRIGGER trg_instance_group_updated_time BEFORE UPDATE ON instance_group FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+DROP TABLE IF EXISTS instance_role;
+
+CREATE TABLE instance_role (
+    id BIGSERIAL PRIMARY KEY,
+    role_id BIGINT NOT NULL DEFAULT 0,
+    instance_id varchar(40) NOT NULL DEFAULT '',
+    instance_group_id BIGINT NOT NULL DEFAULT 0,
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_instance_role_instance UNIQUE (role_id, instance_id, instance_group_id)
+);
+
+DROP TABLE IF EXISTS role;
+
+CREATE TABLE role (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(200) NOT NULL DEFAULT '',
+    is_admin BOOLEAN NOT NULL DEFAULT false,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_role_name UNIQUE (name)
+);
+
+CREATE TRIGGER trg_role_updated_time BEFORE UPDATE ON role FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+DROP TABLE IF EXISTS user_server;
+
+CREATE TABLE user_server (
+    id BIGSERIAL PRIMARY KEY,
+    user_id varchar(10) NOT NULL,
+    instance_id varchar(40) NOT NULL DEFAULT '',
+    instance_group_id BIGINT NOT NULL DEFAULT 0,
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_user_server_instance UNIQUE (user_id, instance_id, instance_group_id)
+);
+
+DROP TABLE IF EXISTS job_timer;
+
+CREATE TABLE job_timer (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(100) NOT NULL DEFAULT '',
+    eid varchar(100) NOT NULL DEFAULT '',
+    timer_expr JSONB NULL,
+    job_type varchar(50) NOT NULL DEFAULT 'default',
+    info varchar(500) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_job_timer_name UNIQUE (name, eid)
+);
+
+CREATE INDEX idx_job_timer_eid ON job_timer (eid);
+
+CREATE TRIGGER trg_job_timer_updated_time BEFORE UPDATE ON job_timer FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+DROP TABLE IF EXISTS job_supervisor;
+
+CREATE TABLE job_supervisor (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(100) NOT NULL DEFAULT '',
+    eid varchar(100) NOT NULL DEFAULT '',
+    restart_interval BIGINT NOT NULL DEFAULT 1,
+    info varchar(500) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_job_supervisor_eid UNIQUE (eid),
+    CONSTRAINT uk_job_supervisor_name UNIQUE (name, eid)
+);
+
+CREATE TRIGGER trg_job_supervisor_updated_time BEFORE UPDATE ON job_supervisor FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+DROP TABLE IF EXISTS job;
+
+CREATE TABLE job (
+    id BIGSERIAL PRIMARY KEY,
+    eid varchar(100) NOT NULL DEFAULT '',
+    executor_id BIGINT NOT NULL DEFAULT 0,
+    job_type varchar(50) NOT NULL DEFAULT 'default',
+    team_id BIGINT NOT NULL DEFAULT 0,
+    name varchar(100) NOT NULL DEFAULT '',
+    code text NOT NULL,
+    info varchar(500) NOT NULL DEFAULT '',
+    bundle_script JSONB DEFAULT NULL,
+    upload_file varchar(500) NOT NULL DEFAULT '',
+    work_dir varchar(500) NOT NULL DEFAULT '',
+    work_user varchar(50) NOT NULL DEFAULT '',
+    timeout BIGINT NOT NULL DEFAULT 60,
+    max_retry SMALLINT NOT NULL DEFAULT 1,
+    max_parallel SMALLINT NOT NULL DEFAULT 1,
+    is_public SMALLINT NOT NULL DEFAULT 0,
+    display_on_dashboard BOOLEAN NOT NULL DEFAULT false,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    args JSONB DEFAULT NULL,
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_job_name UNIQUE (name, team_id)
+);
+
+CREATE TRIGGER trg_job_updated_time BEFORE UPDATE ON job FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+DROP TABLE IF EXISTS job_bundle_script;
+
+CREATE TABLE job_bundle_script (
+    id BIGSERIAL PRIMARY KEY,
+    eid varchar(100) NOT NULL DEFAULT '',
+    executor_id BIGINT NOT NULL DEFAULT 0,
+    team_id BIGINT NOT NULL DEFAULT 0,
+    name varchar(100) NOT NULL DEFAULT '',
+    code text NOT NULL,
+    info varchar(500) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    args JSONB DEFAULT NULL,
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_job_bundle_script_name UNIQUE (name, team_id)
+);
+
+CREATE TRIGGER trg_job_bundle_script_updated_time BEFORE UPDATE ON job_bundle_script FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+DROP TABLE IF EXISTS executor;
+
+CREATE TABLE executor (
+    id BIGSERIAL PRIMARY KEY,
+    name varchar(100) NOT NULL DEFAULT '',
+    command varchar(100) NOT NULL DEFAULT '',
+    platform varchar(10) NOT NULL DEFAULT 'linux',
+    info varchar(500) NOT NULL DEFAULT '',
+    read_code_from_stdin SMALLINT NOT NULL DEFAULT 0,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_executor_name UNIQUE (name)
+);
+
+CREATE TRIGGER trg_executor_updated_time BEFORE UPDATE ON executor FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+INSERT INTO
+    executor (
+        name,
+        command,
+        platform,
+        info,
+        created_user,
+        updated_user
+    )
+VALUES
+    (
+        'bash',
+        'bash -c',
+        'linux',
+        'run linux bash sciript',
+        'system',
+        'system'
+    ),
+    (
+        'python',
+        'python -c',
+        'linux',
+        'run python script',
+        'system',
+        'system'
+    );
+
+DROP TABLE IF EXISTS job_exec_history;
+
+CREATE TABLE job_exec_history (
+    id BIGSERIAL PRIMARY KEY,
+    schedule_id varchar(40) NOT NULL DEFAULT '',
+    eid varchar(100) NOT NULL DEFAULT '',
+    job_type varchar(50) NOT NULL DEFAULT 'default',
+    instance_id varchar(40) NOT NULL DEFAULT '',
+    bundle_script_result JSONB DEFAULT NULL,
+    exit_status varchar(200) NOT NULL DEFAULT '',
+    exit_code INTEGER NOT NULL DEFAULT 0,
+    output text NOT NULL,
+    start_time TIMESTAMPTZ NULL DEFAULT NULL,
+    end_time TIMESTAMPTZ NULL DEFAULT NULL,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_job_exec_history_eid ON job_exec_history (eid);
+
+CREATE INDEX idx_job_exec_history_ip ON job_exec_history (instance_id);
+
+CREATE INDEX idx_job_exec_history_schedule_id ON job_exec_history (schedule_id);
+
+CREATE TRIGGER trg_job_exec_history_updated_time BEFORE UPDATE ON job_exec_history FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+DROP TABLE IF EXISTS job_running_status;
+
+CREATE TABLE job_running_status (
+    id BIGSERIAL PRIMARY KEY,
+    instance_id varchar(40) NOT NULL DEFAULT '',
+    schedule_type varchar(10) NOT NULL DEFAULT 'once',
+    job_type varchar(50) NOT NULL DEFAULT 'default',
+    eid varchar(100) NOT NULL DEFAULT '',
+    schedule_id varchar(40) NOT NULL DEFAULT '',
+    schedule_status varchar(40) NOT NULL DEFAULT '',
+    run_status varchar(40) NOT NULL DEFAULT '',
+    exit_status varchar(200) NOT NULL DEFAULT '',
+    exit_code INTEGER NOT NULL DEFAULT 0,
+    dispatch_result JSONB DEFAULT NULL,
+    start_time TIMESTAMPTZ NULL DEFAULT NULL,
+    end_time TIMESTAMPTZ NULL DEFAULT NULL,
+    next_time TIMESTAMPTZ NULL DEFAULT NULL,
+    prev_time TIMESTAMPTZ NULL DEFAULT NULL,
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_job_running_status_eid UNIQUE (eid, schedule_type, instance_id)
+);
+
+CREATE TRIGGER trg_job_running_status_updated_time BEFORE UPDATE ON job_running_status FOR EACH ROW EXECUTE FUNCTION set_updated_time();
+
+DROP TABLE IF EXISTS job_schedule_history;
+
+CREATE TABLE job_schedule_history (
+    id BIGSERIAL PRIMARY KEY,
+    schedule_id varchar(40) NOT NULL DEFAULT '',
+    name varchar(100) NOT NULL DEFAULT '',
+    job_type varchar(50) NOT NULL DEFAULT 'default',
+    eid varchar(100) NOT NULL DEFAULT '',
+    dispatch_result JSONB DEFAULT NULL,
+    schedule_type varchar(20) NOT NULL DEFAULT '',
+    action varchar(20) NOT NULL DEFAULT '',
+    dispatch_data JSONB DEFAULT NULL,
+    snapshot_data JSONB DEFAULT NULL,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_job_schedule_history_schedule_id ON job_schedule_history (schedule_id);
+
+CREATE INDEX idx_job_schedule_history_eid ON job_schedule_history (eid);
+
+CREATE TRIGGER trg_job_schedule_history_updated_time BEFORE UPDATE ON job_schedule_history FOR EACH ROW EXECUTE FUNCTION set_updated_time();
@@ -0,0 +1,400 @@
+DROP TABLE IF EXISTS "user";
+
+CREATE TABLE "user" (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id varchar(10) NOT NULL,
+    username varchar(50) NOT NULL DEFAULT '',
+    nickname varchar(50) NOT NULL DEFAULT '',
+    is_root BOOLEAN NOT NULL DEFAULT false,
+    role_id INTEGER NOT NULL DEFAULT 0,
+    salt varchar(50) NOT NULL DEFAULT '',
+    password varchar(100) NOT NULL DEFAULT '',
+    avatar varchar(100) NOT NULL DEFAULT '',
+    email varchar(200) NOT NULL DEFAULT '',
+    phone varchar(20) NOT NULL DEFAULT '',
+    gender varchar(10) NOT NULL DEFAULT 'male',
+    introduction varchar(2000) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uqe_user_username ON "user" (username);
+
+CREATE UNIQUE INDEX uqe_user_user_id ON "user" (user_id);
+
+CREATE TRIGGER trg_user_updated_time AFTER UPDATE ON "user" FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE "user" SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+DROP TABLE IF EXISTS team;
+
+CREATE TABLE team (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(200) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_team_name ON team (name, created_user);
+
+CREATE TRIGGER trg_team_updated_time AFTER UPDATE ON team FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE team SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+DROP TABLE IF EXISTS team_member;
+
+CREATE TABLE team_member (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    team_id INTEGER NOT NULL DEFAULT 0,
+    user_id varchar(10) NOT NULL,
+    is_admin BOOLEAN NOT NULL DEFAULT false,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_team_member_user_id ON team_member (user_id, team_id);
+
+DROP TABLE IF EXISTS instance;
+
+CREATE TABLE instance (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    instance_id varchar(40) NOT NULL DEFAULT '',
+    ip varchar(100) NOT NULL DEFAULT '',
+    namespace varchar(100) NOT NULL DEFAULT 'default',
+    mac_addr varchar(20) NOT NULL DEFAULT '',
+    instance_group_id INTEGER NOT NULL DEFAULT 0,
+    info varchar(500) NOT NULL DEFAULT '',
+    status INTEGER NOT NULL DEFAULT 0,
+    sys_user varchar(20) NOT NULL DEFAULT 'root',
+    password varchar(1000) NOT NULL DEFAULT '',
+    ssh_port INTEGER NOT NULL DEFAULT 22,
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_instance_ip ON instance (mac_addr, ip);
+
+CREATE TRIGGER trg_instance_updated_time AFTER UPDATE ON instance FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE instance SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+DROP TABLE IF EXISTS instance_group;
+
+CREATE TABLE instance_group (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(200) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_instance_group_name ON instance_group (name);
+
+CREATE TRIGGER trg_instance_group_updated_time AFTER UPDATE ON instance_group FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE instance_group SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+DROP TABLE IF EXISTS instance_role;
+
+CREATE TABLE instance_role (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    role_id INTEGER NOT NULL DEFAULT 0,
+    instance_id varchar(40) NOT NULL DEFAULT '',
+    instance_group_id INTEGER NOT NULL DEFAULT 0,
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_instance_role_instance ON instance_role (role_id, instance_id, instance_group_id);
+
+DROP TABLE IF EXISTS role;
+
+CREATE TABLE role (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(100) NOT NULL DEFAULT '',
+    info varchar(200) NOT NULL DEFAULT '',
+    is_admin BOOLEAN NOT NULL DEFAULT false,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_role_name ON role (name);
+
+CREATE TRIGGER trg_role_updated_time AFTER UPDATE ON role FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE role SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+DROP TABLE IF EXISTS user_server;
+
+CREATE TABLE user_server (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id varchar(10) NOT NULL,
+    instance_id varchar(40) NOT NULL DEFAULT '',
+    instance_group_id INTEGER NOT NULL DEFAULT 0,
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_user_server_instance ON user_server (user_id, instance_id, instance_group_id);
+
+DROP TABLE IF EXISTS job_timer;
+
+CREATE TABLE job_timer (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(100) NOT NULL DEFAULT '',
+    eid varchar(100) NOT NULL DEFAULT '',
+    timer_expr TEXT NULL,
+    job_type varchar(50) NOT NULL DEFAULT 'default',
+    info varchar(500) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_job_timer_name ON job_timer (name, eid);
+
+CREATE INDEX idx_job_timer_eid ON job_timer (eid);
+
+CREATE TRIGGER trg_job_timer_updated_time AFTER UPDATE ON job_timer FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE job_timer SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+DROP TABLE IF EXISTS job_supervisor;
+
+CREATE TABLE job_supervisor (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(100) NOT NULL DEFAULT '',
+    eid varchar(100) NOT NULL DEFAULT '',
+    restart_interval INTEGER NOT NULL DEFAULT 1,
+    info varchar(500) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_job_supervisor_eid ON job_supervisor (eid);
+
+CREATE UNIQUE INDEX uk_job_supervisor_name ON job_supervisor (name, eid);
+
+CREATE TRIGGER trg_job_supervisor_updated_time AFTER UPDATE ON job_supervisor FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE job_supervisor SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+DROP TABLE IF EXISTS job;
+
+CREATE TABLE job (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    eid varchar(100) NOT NULL DEFAULT '',
+    executor_id INTEGER NOT NULL DEFAULT 0,
+    job_type varchar(50) NOT NULL DEFAULT 'default',
+    team_id INTEGER NOT NULL DEFAULT 0,
+    name varchar(100) NOT NULL DEFAULT '',
+    code text NOT NULL,
+    info varchar(500) NOT NULL DEFAULT '',
+    bundle_script TEXT DEFAULT NULL,
+    upload_file varchar(500) NOT NULL DEFAULT '',
+    work_dir varchar(500) NOT NULL DEFAULT '',
+    work_user varchar(50) NOT NULL DEFAULT '',
+    timeout INTEGER NOT NULL DEFAULT 60,
+    max_retry INTEGER NOT NULL DEFAULT 1,
+    max_parallel INTEGER NOT NULL DEFAULT 1,
+    is_public INTEGER NOT NULL DEFAULT 0,
+    display_on_dashboard BOOLEAN NOT NULL DEFAULT false,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    args TEXT DEFAULT NULL,
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_job_name ON job (name, team_id);
+
+CREATE TRIGGER trg_job_updated_time AFTER UPDATE ON job FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE job SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+DROP TABLE IF EXISTS job_bundle_script;
+
+CREATE TABLE job_bundle_script (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    eid varchar(100) NOT NULL DEFAULT '',
+    executor_id INTEGER NOT NULL DEFAULT 0,
+    team_id INTEGER NOT NULL DEFAULT 0,
+    name varchar(100) NOT NULL DEFAULT '',
+    code text NOT NULL,
+    info varchar(500) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    args TEXT DEFAULT NULL,
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_job_bundle_script_name ON job_bundle_script (name, team_id);
+
+CREATE TRIGGER trg_job_bundle_script_updated_time AFTER UPDATE ON job_bundle_script FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE job_bundle_script SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+DROP TABLE IF EXISTS executor;
+
+CREATE TABLE executor (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name varchar(100) NOT NULL DEFAULT '',
+    command varchar(100) NOT NULL DEFAULT '',
+    platform varchar(10) NOT NULL DEFAULT 'linux',
+    info varchar(500) NOT NULL DEFAULT '',
+    read_code_from_stdin INTEGER NOT NULL DEFAULT 0,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_executor_name ON executor (name);
+
+CREATE TRIGGER trg_executor_updated_time AFTER UPDATE ON executor FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE executor SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+INSERT INTO
+    executor (
+        name,
+        command,
+        platform,
+        info,
+        created_user,
+        updated_user
+    )
+VALUES
+    (
+        'bash',
+        'bash -c',
+        'linux',
+        'run linux bash sciript',
+        'system',
+        'system'
+    ),
+    (
+        'python',
+        'python -c',
+        'linux',
+        'run python script',
+        'system',
+        'system'
+    );
+
+DROP TABLE IF EXISTS job_exec_history;
+
+CREATE TABLE job_exec_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    schedule_id varchar(40) NOT NULL DEFAULT '',
+    eid varchar(100) NOT NULL DEFAULT '',
+    job_type varchar(50) NOT NULL DEFAULT 'default',
+    instance_id varchar(40) NOT NULL DEFAULT '',
+    bundle_script_result TEXT DEFAULT NULL,
+    exit_status varchar(200) NOT NULL DEFAULT '',
+    exit_code INTEGER NOT NULL DEFAULT 0,
+    output text NOT NULL,
+    start_time DATETIME NULL DEFAULT NULL,
+    end_time DATETIME NULL DEFAULT NULL,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_job_exec_history_eid ON job_exec_history (eid);
+
+CREATE INDEX idx_job_exec_history_ip ON job_exec_history (instance_id);
+
+CREATE INDEX idx_job_exec_history_schedule_id ON job_exec_history (schedule_id);
+
+CREATE TRIGGER trg_job_exec_history_updated_time AFTER UPDATE ON job_exec_history FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE job_exec_history SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+DROP TABLE IF EXISTS job_running_status;
+
+CREATE TABLE job_running_status (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    instance_id varchar(40) NOT NULL DEFAULT '',
+    schedule_type varchar(10) NOT NULL DEFAULT 'once',
+    job_type varchar(50) NOT NULL DEFAULT 'default',
+    eid varchar(100) NOT NULL DEFAULT '',
+    schedule_id varchar(40) NOT NULL DEFAULT '',
+    schedule_status varchar(40) NOT NULL DEFAULT '',
+    run_status varchar(40) NOT NULL DEFAULT '',
+    exit_status varchar(200) NOT NULL DEFAULT '',
+    exit_code INTEGER NOT NULL DEFAULT 0,
+    dispatch_result TEXT DEFAULT NULL,
+    start_time DATETIME NULL DEFAULT NULL,
+    end_time DATETIME NULL DEFAULT NULL,
+    next_time DATETIME NULL DEFAULT NULL,
+    prev_time DATETIME NULL DEFAULT NULL,
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_job_running_status_eid ON job_running_status (eid, schedule_type, instance_id);
+
+CREATE TRIGGER trg_job_running_status_updated_time AFTER UPDATE ON job_running_status FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE job_running_status SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+DROP TABLE IF EXISTS job_schedule_history;
+
+CREATE TABLE job_schedule_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    schedule_id varchar(40) NOT NULL DEFAULT '',
+    name varchar(100) NOT NULL DEFAULT '',
+    job_type varchar(50) NOT NULL DEFAULT 'default',
+    eid varchar(100) NOT NULL DEFAULT '',
+    dispatch_result TEXT DEFAULT NULL,
+    schedule_type varchar(20) NOT NULL DEFAULT '',
+    action varchar(20) NOT NULL DEFAULT '',
+    dispatch_data TEXT DEFAULT NULL,
+    snapshot_data TEXT DEFAULT NULL,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    updated_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_job_schedule_history_schedule_id ON job_schedule_history (schedule_id);
+
+CREATE INDEX idx_job_schedule_history_eid ON job_schedule_history (eid);
+
+CREATE TRIGGER trg_job_schedule_history_updated_time AFTER UPDATE ON job_schedule_history FOR EACH ROW
+WHEN NEW.updated_time = OLD.updated_time
+BEGIN
+    UPDATE job_schedule_history SET updated_time = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
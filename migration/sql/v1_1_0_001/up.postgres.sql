@@ -0,0 +1,21 @@
+DROP TABLE IF EXISTS tag;
+
+CREATE TABLE tag (
+    id BIGSERIAL PRIMARY KEY,
+    tag_name varchar(40) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_tag_tag_name UNIQUE (tag_name)
+);
+
+DROP TABLE IF EXISTS tag_resource;
+
+CREATE TABLE tag_resource (
+    id BIGSERIAL PRIMARY KEY,
+    tag_id BIGINT NOT NULL DEFAULT 0,
+    resource_type varchar(40) NOT NULL DEFAULT '',
+    resource_id BIGINT NOT NULL DEFAULT 0,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uk_tag_resource_tag_id UNIQUE (resource_type, tag_id, resource_id)
+);
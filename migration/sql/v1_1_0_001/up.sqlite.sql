@@ -0,0 +1,23 @@
+DROP TABLE IF EXISTS tag;
+
+CREATE TABLE tag (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tag_name varchar(40) NOT NULL DEFAULT '',
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_tag_tag_name ON tag (tag_name);
+
+DROP TABLE IF EXISTS tag_resource;
+
+CREATE TABLE tag_resource (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tag_id INTEGER NOT NULL DEFAULT 0,
+    resource_type varchar(40) NOT NULL DEFAULT '',
+    resource_id INTEGER NOT NULL DEFAULT 0,
+    created_user varchar(50) NOT NULL DEFAULT '',
+    created_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX uk_tag_resource_tag_id ON tag_resource (resource_type, tag_id, resource_id);
@@ -0,0 +1,3 @@
+ALTER TABLE job DROP COLUMN completed_callback;
+
+ALTER TABLE job_exec_history DROP COLUMN run_id;
@@ -0,0 +1,3 @@
+ALTER TABLE job ADD COLUMN completed_callback TEXT DEFAULT NULL;
+
+ALTER TABLE job_exec_history ADD COLUMN run_id varchar(50) NOT NULL DEFAULT '';
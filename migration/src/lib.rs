@@ -1,7 +1,40 @@
 pub use sea_orm_migration::prelude::*;
 
+/// Picks the sql file written for the connected backend, `up.sql` is the mysql
+/// variant, `up.postgres.sql` and `up.sqlite.sql` the other two.
+macro_rules! include_sql {
+    ($manager:expr, $path:literal) => {
+        match $manager.get_database_backend() {
+            sea_orm_migration::sea_orm::DbBackend::MySql => {
+                include_str!(concat!("../sql/", $path, ".sql"))
+            }
+            sea_orm_migration::sea_orm::DbBackend::Postgres => {
+                include_str!(concat!("../sql/", $path, ".postgres.sql"))
+            }
+            sea_orm_migration::sea_orm::DbBackend::Sqlite => {
+                include_str!(concat!("../sql/", $path, ".sqlite.sql"))
+            }
+        }
+    };
+}
+
 mod m20250412_add_job_soft_deleted;
 mod m20250420_modify_job_index;
+mod m20261018_add_artifact;
+mod m20261018_add_executor_container;
+mod m20261018_add_job_stop_signal;
+mod m20261018_add_ssh_credential;
+mod m20261018_add_supervisor_health_check;
+mod m20261018_add_supervisor_restart_policy;
+mod m20261018_add_terminal_session;
+mod m20261018_signed_integer_columns;
+mod m20261019_add_artifact_team;
+mod m20261019_add_calendar_blackout;
+mod m20261019_add_job_template;
+mod m20261019_add_resource_permission;
+mod m20261019_add_retention_policy;
+mod m20261019_add_user_auth_provider;
+mod m20261019_add_user_mfa;
 mod v1_0_0_create_table;
 mod v1_1_0_001_create_table;
 mod v1_1_0_002_create_table;
@@ -16,6 +49,30 @@ impl MigratorTrait for Migrator {
             Box::new(v1_1_0_002_create_table::Migration),
             Box::new(m20250412_add_job_soft_deleted::Migration),
             Box::new(m20250420_modify_job_index::Migration),
+            Box::new(m20261018_add_supervisor_restart_policy::Migration),
+            Box::new(m20261018_add_supervisor_health_check::Migration),
+            Box::new(m20261018_add_job_stop_signal::Migration),
+            Box::new(m20261018_add_executor_container::Migration),
+            Box::new(m20261018_signed_integer_columns::Migration),
+            Box::new(m20261018_add_terminal_session::Migration),
+            Box::new(m20261018_add_ssh_credential::Migration),
+            Box::new(m20261018_add_artifact::Migration),
+            Box::new(m20261019_add_resource_permission::Migration),
+            Box::new(m20261019_add_user_auth_provider::Migration),
+            Box::new(m20261019_add_user_mfa::Migration),
+            Box::new(m20261019_add_calendar_blackout::Migration),
+            Box::new(m20261019_add_retention_policy::Migration),
+            Box::new(m20261019_add_job_template::Migration),
+            Box::new(m20261019_add_artifact_team::Migration),
         ]
     }
 }
+
+#[async_std::test]
+async fn test_sqlite_migrate() {
+    let db = sea_orm_migration::sea_orm::Database::connect("sqlite::memory:")
+        .await
+        .unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    Migrator::down(&db, None).await.unwrap();
+}
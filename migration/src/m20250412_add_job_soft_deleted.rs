@@ -7,14 +7,14 @@ pub struct Migration;
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         let db = manager.get_connection();
-        let sql = include_str!("../sql/m20250412_add_job_soft_deleted/up.sql");
+        let sql = include_sql!(manager, "m20250412_add_job_soft_deleted/up");
         db.execute_unprepared(sql).await?;
         Ok(())
     }
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         let db = manager.get_connection();
-        let sql = include_str!("../sql/m20250412_add_job_soft_deleted/down.sql");
+        let sql = include_sql!(manager, "m20250412_add_job_soft_deleted/down");
         db.execute_unprepared(sql).await?;
         Ok(())
     }
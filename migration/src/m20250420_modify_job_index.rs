@@ -7,14 +7,14 @@ pub struct Migration;
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         let db = manager.get_connection();
-        let sql = include_str!("../sql/m20250420_modify_job_index/up.sql");
+        let sql = include_sql!(manager, "m20250420_modify_job_index/up");
         db.execute_unprepared(sql).await?;
         Ok(())
     }
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         let db = manager.get_connection();
-        let sql = include_str!("../sql/m20250420_modify_job_index/down.sql");
+        let sql = include_sql!(manager, "m20250420_modify_job_index/down");
         db.execute_unprepared(sql).await?;
         Ok(())
     }
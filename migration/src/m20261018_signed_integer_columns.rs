@@ -0,0 +1,29 @@
+use sea_orm_migration::{prelude::*, sea_orm::DbBackend};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Postgres and sqlite have no unsigned integers, the entities use signed types
+/// everywhere so the mysql columns follow. The other backends are created signed.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DbBackend::MySql {
+            return Ok(());
+        }
+        let db = manager.get_connection();
+        let sql = include_str!("../sql/m20261018_signed_integer_columns/up.sql");
+        db.execute_unprepared(sql).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DbBackend::MySql {
+            return Ok(());
+        }
+        let db = manager.get_connection();
+        let sql = include_str!("../sql/m20261018_signed_integer_columns/down.sql");
+        db.execute_unprepared(sql).await?;
+        Ok(())
+    }
+}
@@ -7,14 +7,14 @@ pub struct Migration;
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         let db = manager.get_connection();
-        let sql = include_str!("../sql/v1_1_0_001/up.sql");
+        let sql = include_sql!(manager, "v1_1_0_001/up");
         db.execute_unprepared(sql).await?;
         Ok(())
     }
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         let db = manager.get_connection();
-        let sql = include_str!("../sql/v1_1_0_001/down.sql");
+        let sql = include_sql!(manager, "v1_1_0_001/down");
         db.execute_unprepared(sql).await?;
         Ok(())
     }
@@ -1,10 +1,17 @@
+pub mod calendar;
 pub mod executor;
 pub mod file;
 pub mod instance;
 pub mod job;
+pub mod job_template;
 pub mod manage;
+pub mod manifest;
+pub mod metrics;
 pub mod migration;
+pub mod permission;
+pub mod retention;
 pub mod role;
+pub mod script_module;
 pub mod tag;
 pub mod team;
 pub mod terminal;
@@ -43,6 +50,13 @@ pub enum Tag {
     Admin,
     Migration,
     Tag,
+    Terminal,
+    Permission,
+    Calendar,
+    Manifest,
+    Retention,
+    ScriptModule,
+    JobTemplate,
 }
 
 pub struct OneOfValidator(Vec<String>);
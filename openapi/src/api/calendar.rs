@@ -0,0 +1,417 @@
+use std::collections::BTreeSet;
+
+use chrono::NaiveDate;
+use poem::{session::Session, web::Data};
+use poem_openapi::{param::Query, payload::Json, OpenApi};
+use sea_orm::{ActiveValue::NotSet, Set};
+
+use crate::{
+    api_response,
+    entity::{blackout_window, calendar},
+    error::NoPermission,
+    local_time,
+    logic::{self, calendar::parse_ics},
+    response::std_into_error,
+    return_err, return_ok, AppState,
+};
+
+pub struct CalendarApi;
+
+mod types {
+    use poem_openapi::Object;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct SaveCalendarReq {
+        pub id: Option<i64>,
+        #[oai(validator(min_length = 1, max_length = 100))]
+        pub name: String,
+        #[oai(default)]
+        pub info: String,
+        /// include runs timers only on the dates, exclude skips them
+        #[oai(validator(
+            custom = "crate::api::OneOfValidator::new(vec![\"include\", \"exclude\"])"
+        ))]
+        pub mode: String,
+        /// YYYY-MM-DD
+        pub dates: Vec<String>,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct ImportCalendarReq {
+        /// adds to this calendar instead of creating one
+        pub id: Option<i64>,
+        #[oai(validator(min_length = 1, max_length = 100))]
+        pub name: String,
+        #[oai(default)]
+        pub info: String,
+        #[oai(validator(
+            custom = "crate::api::OneOfValidator::new(vec![\"include\", \"exclude\"])"
+        ))]
+        pub mode: String,
+        /// content of an iCalendar (.ics) file
+        pub content: String,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct SaveCalendarResp {
+        pub id: i64,
+        pub dates: u64,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct CalendarRecord {
+        pub id: i64,
+        pub name: String,
+        pub info: String,
+        pub mode: String,
+        pub dates: Vec<String>,
+        pub created_user: String,
+        pub updated_user: String,
+        pub created_time: String,
+        pub updated_time: String,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct QueryCalendarResp {
+        pub total: u64,
+        pub list: Vec<CalendarRecord>,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct SaveBlackoutWindowReq {
+        pub id: Option<i64>,
+        #[oai(validator(min_length = 1, max_length = 100))]
+        pub name: String,
+        #[oai(default)]
+        pub info: String,
+        /// cron expression of the window start, such as `0 22 * * FRI`
+        pub start_expr: String,
+        #[oai(validator(minimum(value = "1"), maximum(value = "604800")))]
+        pub duration_secs: u64,
+        /// IANA time zone, the agent's local one when empty
+        #[oai(default)]
+        pub timezone: String,
+        /// namespace the window applies to
+        #[oai(default)]
+        pub namespace: String,
+        /// instance group the window applies to
+        #[oai(default)]
+        pub instance_group_id: i64,
+        #[oai(default = "crate::api::calendar::default_enabled")]
+        pub enabled: bool,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct SaveBlackoutWindowResp {
+        pub id: i64,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct BlackoutWindowRecord {
+        pub id: i64,
+        pub name: String,
+        pub info: String,
+        pub start_expr: String,
+        pub duration_secs: i64,
+        pub timezone: String,
+        pub namespace: String,
+        pub instance_group_id: i64,
+        pub enabled: bool,
+        /// whether a window is in effect right now
+        pub active: bool,
+        pub created_user: String,
+        pub updated_user: String,
+        pub created_time: String,
+        pub updated_time: String,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct QueryBlackoutWindowResp {
+        pub total: u64,
+        pub list: Vec<BlackoutWindowRecord>,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct DeleteReq {
+        pub id: i64,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct DeleteResp {
+        pub result: u64,
+    }
+}
+
+pub fn default_enabled() -> bool {
+    true
+}
+
+fn parse_dates(dates: &[String]) -> anyhow::Result<BTreeSet<NaiveDate>> {
+    dates
+        .iter()
+        .map(|v| {
+            NaiveDate::parse_from_str(v, "%Y-%m-%d")
+                .map_err(|e| anyhow::anyhow!("invalid date {v} - {e}"))
+        })
+        .collect()
+}
+
+#[OpenApi(prefix_path = "/calendar", tag = super::Tag::Calendar)]
+impl CalendarApi {
+    /// Creates or updates a calendar. Running timers keep the dates they were
+    /// started with until they are started again from their job timer
+    #[oai(path = "/save", method = "post")]
+    pub async fn save_calendar(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::SaveCalendarReq>,
+    ) -> api_response!(types::SaveCalendarResp) {
+        if !state.can_manage_job(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+        let dates = parse_dates(&req.dates)?;
+        let total = dates.len() as u64;
+        let ret = state
+            .service()
+            .calendar
+            .save_calendar(calendar::ActiveModel {
+                id: req.id.filter(|&v| v != 0).map_or(NotSet, Set),
+                name: Set(req.name),
+                info: Set(req.info),
+                mode: Set(req.mode),
+                dates: Set(Some(serde_json::to_value(dates).map_err(std_into_error)?)),
+                created_user: req.id.map_or(Set(user_info.username.clone()), |_| NotSet),
+                updated_user: Set(user_info.username.clone()),
+                ..Default::default()
+            })
+            .await?;
+        return_ok!(types::SaveCalendarResp {
+            id: ret.id.as_ref().to_owned(),
+            dates: total,
+        })
+    }
+
+    /// Creates a calendar from the events of an iCalendar file, or adds them
+    /// to an existing one. Running timers keep the dates they were started
+    /// with until they are started again from their job timer
+    #[oai(path = "/import", method = "post")]
+    pub async fn import_calendar(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::ImportCalendarReq>,
+    ) -> api_response!(types::SaveCalendarResp) {
+        if !state.can_manage_job(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+        let svc = state.service();
+        let mut dates = parse_ics(&req.content)?;
+        if let Some(id) = req.id.filter(|&v| v != 0) {
+            let existing = svc.calendar.get_timer_calendars(&[id]).await?;
+            dates.extend(existing.into_iter().flat_map(|v| v.dates));
+        }
+        let total = dates.len() as u64;
+        let ret = svc
+            .calendar
+            .save_calendar(calendar::ActiveModel {
+                id: req.id.filter(|&v| v != 0).map_or(NotSet, Set),
+                name: Set(req.name),
+                info: Set(req.info),
+                mode: Set(req.mode),
+                dates: Set(Some(serde_json::to_value(dates).map_err(std_into_error)?)),
+                created_user: req.id.map_or(Set(user_info.username.clone()), |_| NotSet),
+                updated_user: Set(user_info.username.clone()),
+                ..Default::default()
+            })
+            .await?;
+        return_ok!(types::SaveCalendarResp {
+            id: ret.id.as_ref().to_owned(),
+            dates: total,
+        })
+    }
+
+    #[oai(path = "/list", method = "get")]
+    pub async fn query_calendar(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        Query(name): Query<Option<String>>,
+        #[oai(
+            default = "crate::api::default_page_size",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page_size): Query<u64>,
+        #[oai(
+            default = "crate::api::default_page",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page): Query<u64>,
+    ) -> api_response!(types::QueryCalendarResp) {
+        let ret = state
+            .service()
+            .calendar
+            .query_calendar(name.filter(|v| !v.is_empty()), page - 1, page_size)
+            .await?;
+        let list = ret
+            .0
+            .into_iter()
+            .map(|v| types::CalendarRecord {
+                id: v.id,
+                name: v.name,
+                info: v.info,
+                mode: v.mode,
+                dates: v
+                    .dates
+                    .and_then(|v| serde_json::from_value(v).ok())
+                    .unwrap_or_default(),
+                created_user: v.created_user,
+                updated_user: v.updated_user,
+                created_time: local_time!(v.created_time),
+                updated_time: local_time!(v.updated_time),
+            })
+            .collect();
+        return_ok!(types::QueryCalendarResp { total: ret.1, list })
+    }
+
+    /// Deletes a calendar, refused while a job timer or a running schedule
+    /// uses it
+    #[oai(path = "/delete", method = "post")]
+    pub async fn delete_calendar(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::DeleteReq>,
+    ) -> api_response!(types::DeleteResp) {
+        if !state.can_manage_job(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+        let result = state.service().calendar.delete_calendar(req.id).await?;
+        return_ok!(types::DeleteResp { result })
+    }
+
+    /// Creates or updates a blackout window. Running timers keep the windows
+    /// they were dispatched with until they are dispatched again
+    #[oai(path = "/blackout/save", method = "post")]
+    pub async fn save_blackout_window(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::SaveBlackoutWindowReq>,
+    ) -> api_response!(types::SaveBlackoutWindowResp) {
+        if !state.can_manage_instance(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+        if req.namespace.is_empty() && req.instance_group_id == 0 {
+            return_err!("a blackout window needs a namespace or an instance group");
+        }
+        automate::BlackoutWindow {
+            name: req.name.clone(),
+            start_expr: req.start_expr.clone(),
+            duration_secs: req.duration_secs,
+            timezone: Some(req.timezone.clone()).filter(|v| !v.is_empty()),
+        }
+        .validate()?;
+
+        let model = blackout_window::ActiveModel {
+            id: req.id.filter(|&v| v != 0).map_or(NotSet, Set),
+            name: Set(req.name),
+            info: Set(req.info),
+            start_expr: Set(req.start_expr),
+            duration_secs: Set(req.duration_secs as i64),
+            timezone: Set(req.timezone),
+            namespace: Set(req.namespace),
+            instance_group_id: Set(req.instance_group_id),
+            enabled: Set(req.enabled),
+            created_user: req.id.map_or(Set(user_info.username.clone()), |_| NotSet),
+            updated_user: Set(user_info.username.clone()),
+            ..Default::default()
+        };
+        let ret = state.service().calendar.save_blackout_window(model).await?;
+        return_ok!(types::SaveBlackoutWindowResp {
+            id: ret.id.as_ref().to_owned(),
+        })
+    }
+
+    #[oai(path = "/blackout/list", method = "get")]
+    pub async fn query_blackout_window(
+        &self,
+        state: Data<&AppState>,
+        Query(name): Query<Option<String>>,
+        Query(namespace): Query<Option<String>>,
+        Query(instance_group_id): Query<Option<i64>>,
+        #[oai(
+            default = "crate::api::default_page_size",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page_size): Query<u64>,
+        #[oai(
+            default = "crate::api::default_page",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page): Query<u64>,
+    ) -> api_response!(types::QueryBlackoutWindowResp) {
+        let ret = state
+            .service()
+            .calendar
+            .query_blackout_window(
+                name.filter(|v| !v.is_empty()),
+                namespace.filter(|v| !v.is_empty()),
+                instance_group_id.filter(|&v| v != 0),
+                page - 1,
+                page_size,
+            )
+            .await?;
+        let now = chrono::Utc::now();
+        let list = ret
+            .0
+            .into_iter()
+            .map(|v| types::BlackoutWindowRecord {
+                active: v.enabled
+                    && logic::calendar::to_blackout_window(&v)
+                        .is_active(now)
+                        .unwrap_or_default(),
+                id: v.id,
+                name: v.name,
+                info: v.info,
+                start_expr: v.start_expr,
+                duration_secs: v.duration_secs,
+                timezone: v.timezone,
+                namespace: v.namespace,
+                instance_group_id: v.instance_group_id,
+                enabled: v.enabled,
+                created_user: v.created_user,
+                updated_user: v.updated_user,
+                created_time: local_time!(v.created_time),
+                updated_time: local_time!(v.updated_time),
+            })
+            .collect();
+        return_ok!(types::QueryBlackoutWindowResp { total: ret.1, list })
+    }
+
+    /// Deletes a blackout window. Running timers keep skipping their ticks in
+    /// it until they are dispatched again
+    #[oai(path = "/blackout/delete", method = "post")]
+    pub async fn delete_blackout_window(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::DeleteReq>,
+    ) -> api_response!(types::DeleteResp) {
+        if !state.can_manage_instance(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+        let result = state
+            .service()
+            .calendar
+            .delete_blackout_window(req.id)
+            .await?;
+        return_ok!(types::DeleteResp { result })
+    }
+}
@@ -1,10 +1,18 @@
-use crate::{entity::executor, local_time, logic, response::ApiStdResponse, return_ok, AppState};
+use crate::{
+    entity::executor,
+    local_time, logic,
+    response::{std_into_error, ApiStdResponse},
+    return_ok, AppState,
+};
 use poem::{session::Session, web::Data, Result};
 use poem_openapi::{param::Query, payload::Json, OpenApi};
 use sea_orm::{ActiveValue::NotSet, Set};
 
 mod types {
-    use poem_openapi::Object;
+    use std::collections::HashMap;
+
+    use automate::scheduler::types;
+    use poem_openapi::{Enum, Object};
     use serde::Serialize;
 
     #[derive(Object, Serialize, Default)]
@@ -14,18 +22,88 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct SaveExecutorReq {
-        pub id: Option<u64>,
+        pub id: Option<i64>,
         #[oai(validator(min_length = 1))]
         pub name: String,
         pub command: String,
         pub platform: String,
         pub info: String,
         pub read_code_from_stdin: Option<bool>,
+        /// run jobs in a container instead of on the host
+        pub container: Option<ContainerOptions>,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct ContainerOptions {
+        /// docker or podman, default docker
+        pub runtime: Option<String>,
+        #[oai(validator(min_length = 1))]
+        pub image: String,
+        pub pull_policy: PullPolicy,
+        /// bind mounts like "/data:/data:ro"
+        pub mounts: Option<Vec<String>>,
+        pub env: Option<HashMap<String, String>>,
+        pub network: Option<String>,
+        /// cpu limit like "1.5"
+        pub cpus: Option<String>,
+        /// memory limit like "512m"
+        pub memory: Option<String>,
+    }
+
+    impl From<types::ContainerOptions> for ContainerOptions {
+        fn from(value: types::ContainerOptions) -> Self {
+            let pull_policy = match value.pull_policy {
+                types::PullPolicy::Always => PullPolicy::Always,
+                types::PullPolicy::Missing => PullPolicy::Missing,
+                types::PullPolicy::Never => PullPolicy::Never,
+            };
+            Self {
+                runtime: Some(value.runtime),
+                image: value.image,
+                pull_policy,
+                mounts: Some(value.mounts),
+                env: Some(value.env),
+                network: value.network,
+                cpus: value.cpus,
+                memory: value.memory,
+            }
+        }
+    }
+
+    impl From<ContainerOptions> for types::ContainerOptions {
+        fn from(value: ContainerOptions) -> Self {
+            let pull_policy = match value.pull_policy {
+                PullPolicy::Always => types::PullPolicy::Always,
+                PullPolicy::Missing => types::PullPolicy::Missing,
+                PullPolicy::Never => types::PullPolicy::Never,
+            };
+            Self {
+                runtime: value.runtime.unwrap_or_default(),
+                image: value.image,
+                pull_policy,
+                mounts: value.mounts.unwrap_or_default(),
+                env: value.env.unwrap_or_default(),
+                network: value.network,
+                cpus: value.cpus,
+                memory: value.memory,
+            }
+        }
+    }
+
+    #[derive(Enum, Serialize, Default)]
+    pub enum PullPolicy {
+        #[oai(rename = "always")]
+        Always,
+        #[default]
+        #[oai(rename = "missing")]
+        Missing,
+        #[oai(rename = "never")]
+        Never,
     }
 
     #[derive(Object, Serialize, Default)]
     pub struct SaveExecutorRes {
-        pub result: u64,
+        pub result: i64,
     }
 
     #[derive(Object, Serialize, Default)]
@@ -36,11 +114,12 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct ExecutorRecord {
-        pub id: u64,
+        pub id: i64,
         pub name: String,
         pub command: String,
         pub platform: String,
         pub info: String,
+        pub container: Option<ContainerOptions>,
         pub created_time: String,
         pub updated_time: String,
     }
@@ -71,6 +150,12 @@ impl ExecutorApi {
     ) -> Result<ApiStdResponse<types::SaveExecutorRes>> {
         let svc = state.service();
 
+        let container = req
+            .container
+            .map(|v| serde_json::to_value(automate::ContainerOptions::from(v)))
+            .transpose()
+            .map_err(std_into_error)?;
+
         let ret = svc
             .executor
             .save_executor(executor::ActiveModel {
@@ -83,6 +168,7 @@ impl ExecutorApi {
                     true => 1,
                     false => 0,
                 })),
+                container: Set(container),
                 created_user: Set(user_info.username.clone()),
                 updated_user: Set(user_info.username.clone()),
                 ..Default::default()
@@ -100,7 +186,7 @@ impl ExecutorApi {
         state: Data<&AppState>,
         _session: &Session,
 
-        Query(default_id): Query<Option<u64>>,
+        Query(default_id): Query<Option<i64>>,
 
         #[oai(
             default = "crate::api::default_page",
@@ -131,6 +217,12 @@ impl ExecutorApi {
                 command: v.command,
                 platform: v.platform,
                 info: v.info,
+                container: v
+                    .container
+                    .map(serde_json::from_value::<automate::ContainerOptions>)
+                    .transpose()
+                    .unwrap_or_default()
+                    .map(types::ContainerOptions::from),
                 created_time: local_time!(v.created_time),
                 updated_time: local_time!(v.updated_time),
             })
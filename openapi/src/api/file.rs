@@ -1,27 +1,33 @@
 use std::{
+    env,
+    io::SeekFrom,
     path::PathBuf,
     time::{Duration, UNIX_EPOCH},
 };
 
 use anyhow::anyhow;
 
+use automate::transfer;
 use chrono::{DateTime, Utc};
-use poem::{session::Session, web::Data, Result};
+use poem::{session::Session, web::Data, Body, Result};
 use poem_openapi::{
-    param::{Path, Query},
+    param::{Header, Path, Query},
     payload::{Attachment, AttachmentType, Json, PlainText},
+    types::multipart::Upload,
     OpenApi,
 };
 use tokio::{
-    fs::{self, create_dir_all, File},
-    io::AsyncWriteExt,
+    fs,
+    io::{self, AsyncSeekExt, AsyncWriteExt},
 };
 
 use crate::{
+    entity::artifact,
     error::NoPermission,
     local_time,
     logic::{
         self,
+        artifact::{ArtifactLogic, NewArtifact, SOURCE_UPLOAD},
         ssh::{ConnectParams, Session as SshSession},
     },
     response::{std_into_error, ApiStdResponse},
@@ -29,6 +35,7 @@ use crate::{
 };
 
 pub mod types {
+    use poem::Body;
     use poem_openapi::{
         payload::{Attachment, PlainText},
         types::multipart::Upload,
@@ -39,17 +46,56 @@ pub mod types {
     #[derive(Debug, Multipart)]
     pub struct UploadPayload {
         pub file: Upload,
+        /// artifact name, default is the file name
+        pub name: Option<String>,
     }
 
     #[derive(Object, Serialize, Default)]
     pub struct UploadFileRes {
+        /// artifact reference to be saved as the upload file of a job
         pub result: String,
+        pub version: i32,
+        pub sha256: String,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct QueryArtifactResp {
+        pub total: u64,
+        pub list: Vec<ArtifactRecord>,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct ArtifactRecord {
+        pub id: i64,
+        pub name: String,
+        pub version: i32,
+        pub team_id: i64,
+        pub reference: String,
+        pub sha256: String,
+        pub size: i64,
+        pub filename: String,
+        /// upload or job
+        pub source: String,
+        pub eid: String,
+        pub run_id: String,
+        pub created_user: String,
+        pub created_time: String,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct DeleteArtifactReq {
+        pub id: i64,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct DeleteArtifactResp {
+        pub result: u64,
     }
 
     #[derive(Debug, ApiResponse)]
     pub enum GetFileResponse {
         #[oai(status = 200)]
-        Ok(Attachment<Vec<u8>>),
+        Ok(Attachment<Body>),
         #[oai(status = 403)]
         NotAllow,
         /// File not found
@@ -82,6 +128,8 @@ pub mod types {
         pub file: Upload,
         pub namespace: String,
         pub file_path: String,
+        /// id to poll the progress of a tunnel upload, random if omitted
+        pub transfer_id: Option<String>,
     }
 
     #[derive(Object, Serialize, Default)]
@@ -89,6 +137,15 @@ pub mod types {
         pub result: String,
     }
 
+    #[derive(Object, Serialize, Default)]
+    pub struct TransferProgressResp {
+        pub transfer_id: String,
+        /// bytes received by comet so far
+        pub offset: u64,
+        pub total: u64,
+        pub completed: bool,
+    }
+
     #[derive(Object, Serialize, Default)]
     pub struct SftpRemovePayload {
         pub instance_id: String,
@@ -112,6 +169,46 @@ macro_rules! unwrap_or_response {
     };
 }
 
+/// Artifacts of a team are shared with its members, the others with their
+/// creator and job managers. Only team admins and the creator may delete.
+async fn can_access_artifact(
+    state: &AppState,
+    user_info: &logic::types::UserInfo,
+    record: &artifact::Model,
+    write: bool,
+) -> anyhow::Result<bool> {
+    if record.created_user == user_info.username {
+        return Ok(true);
+    }
+    let team = &state.service().team;
+    match (record.team_id, write) {
+        (0, _) => state.can_manage_job(&user_info.user_id).await,
+        (v, false) => team.can_read_team(Some(v), user_info.user_id.clone()).await,
+        (v, true) => {
+            team.can_write_team(Some(v), user_info.user_id.clone())
+                .await
+        }
+    }
+}
+
+/// Streams an upload into a temporary file rather than memory, the file is
+/// returned rewound and removed once its handle is dropped.
+async fn spool_upload(upload: Upload) -> anyhow::Result<fs::File> {
+    let dir = env::temp_dir().join("jiascheduler");
+    fs::create_dir_all(&dir).await?;
+    let tmp_path = dir.join(format!("{}.upload", transfer::new_transfer_id()));
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&tmp_path)
+        .await?;
+    let _ = fs::remove_file(&tmp_path).await;
+    io::copy(&mut upload.into_async_read(), &mut file).await?;
+    file.seek(SeekFrom::Start(0)).await?;
+    Ok(file)
+}
+
 pub struct FileApi;
 
 #[OpenApi(prefix_path = "/file", tag = super::Tag::File)]
@@ -121,32 +218,178 @@ impl FileApi {
         &self,
         state: Data<&AppState>,
         _session: &Session,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         upload: types::UploadPayload,
         user_info: Data<&logic::types::UserInfo>,
     ) -> Result<ApiStdResponse<types::UploadFileRes>> {
         if !state.can_upload_file(&user_info.user_id).await? {
             return Err(NoPermission().into());
         }
-        let filename = upload.file.file_name().map(ToString::to_string);
-        let data = upload.file.into_vec().await.map_err(std_into_error)?;
+        if !state
+            .service()
+            .team
+            .can_read_team(team_id, user_info.user_id.clone())
+            .await?
+        {
+            return Err(NoPermission().into());
+        }
+        let filename = upload
+            .file
+            .file_name()
+            .and_then(|v| {
+                PathBuf::from(v)
+                    .file_name()
+                    .map(|v| v.to_string_lossy().to_string())
+            })
+            .filter(|v| !v.is_empty())
+            .unwrap_or("upload".to_string());
+        let file = spool_upload(upload.file).await?;
+
+        let record = state
+            .service()
+            .artifact
+            .create_artifact(
+                NewArtifact {
+                    name: upload
+                        .name
+                        .filter(|v| !v.is_empty())
+                        .unwrap_or(filename.clone()),
+                    team_id: team_id.unwrap_or_default(),
+                    filename,
+                    source: SOURCE_UPLOAD,
+                    eid: String::new(),
+                    run_id: String::new(),
+                    created_user: user_info.username.clone(),
+                },
+                file,
+            )
+            .await?;
 
-        create_dir_all("/tmp/jiascheduler")
-            .await
-            .map_err(std_into_error)?;
+        return_ok!(types::UploadFileRes {
+            result: ArtifactLogic::reference(&record),
+            version: record.version,
+            sha256: record.sha256,
+        })
+    }
 
-        let target_file = format!(
-            "/tmp/jiascheduler/{}",
-            filename.map_or("upload".to_string(), |v| v)
-        );
+    #[oai(path = "/artifact/list", method = "get")]
+    async fn query_artifact(
+        &self,
+        state: Data<&AppState>,
+        /// artifacts of the team, those without a team when not given
+        #[oai(name = "X-Team-Id")]
+        Header(team_id): Header<Option<i64>>,
+        Query(name): Query<Option<String>>,
+        Query(eid): Query<Option<String>>,
+        #[oai(
+            default = "crate::api::default_page_size",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page_size): Query<u64>,
+        #[oai(
+            default = "crate::api::default_page",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page): Query<u64>,
+        user_info: Data<&logic::types::UserInfo>,
+    ) -> Result<ApiStdResponse<types::QueryArtifactResp>> {
+        if !state.can_upload_file(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+        let svc = state.service();
+        if !svc
+            .team
+            .can_read_team(team_id, user_info.user_id.clone())
+            .await?
+        {
+            return Err(NoPermission().into());
+        }
+        // job managers see all artifacts without a team, others their own
+        let created_user = match state.can_manage_job(&user_info.user_id).await? {
+            true => None,
+            false => Some(user_info.username.clone()),
+        };
 
-        let mut tmp_file = File::create(target_file.clone())
-            .await
-            .map_err(std_into_error)?;
+        let ret = svc
+            .artifact
+            .query_artifact(
+                team_id.unwrap_or_default(),
+                created_user,
+                name.filter(|v| !v.is_empty()),
+                eid.filter(|v| !v.is_empty()),
+                page - 1,
+                page_size,
+            )
+            .await?;
 
-        tmp_file.write_all(&data).await.map_err(std_into_error)?;
-        return_ok!(types::UploadFileRes {
-            result: target_file
-        })
+        let list = ret
+            .0
+            .into_iter()
+            .map(|v| types::ArtifactRecord {
+                reference: ArtifactLogic::reference(&v),
+                id: v.id,
+                name: v.name,
+                version: v.version,
+                team_id: v.team_id,
+                sha256: v.sha256,
+                size: v.size,
+                filename: v.filename,
+                source: v.source,
+                eid: v.eid,
+                run_id: v.run_id,
+                created_user: v.created_user,
+                created_time: local_time!(v.created_time),
+            })
+            .collect();
+        return_ok!(types::QueryArtifactResp { total: ret.1, list })
+    }
+
+    /// Downloads an artifact by reference, eg: "artifact://deploy.tar.gz@3"
+    #[oai(path = "/artifact/download", method = "get")]
+    async fn download_artifact(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+        Query(reference): Query<String>,
+    ) -> types::GetFileResponse {
+        if !unwrap_or_response!(state.can_upload_file(&user_info.user_id).await) {
+            return types::GetFileResponse::NotAllow;
+        }
+        let svc = state.service();
+        let record = match svc.artifact.resolve(&reference).await {
+            Ok(v) => v,
+            Err(_) => return types::GetFileResponse::NotFound,
+        };
+        if !unwrap_or_response!(can_access_artifact(&state, &user_info, &record, false).await) {
+            return types::GetFileResponse::NotAllow;
+        }
+        let reader = unwrap_or_response!(svc.artifact.open(&record).await);
+
+        let attachment = Attachment::new(Body::from_async_read(reader))
+            .attachment_type(AttachmentType::Attachment)
+            .filename(record.filename);
+        types::GetFileResponse::Ok(attachment)
+    }
+
+    #[oai(path = "/artifact/delete", method = "post")]
+    async fn delete_artifact(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::DeleteArtifactReq>,
+    ) -> Result<ApiStdResponse<types::DeleteArtifactResp>> {
+        if !state.can_upload_file(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+        let svc = state.service();
+        let Some(record) = svc.artifact.get_artifact(req.id).await? else {
+            return_err!("cannot found artifact");
+        };
+        if !can_access_artifact(&state, &user_info, &record, true).await? {
+            return Err(NoPermission().into());
+        }
+        let ret = svc.artifact.delete_artifact(&record).await?;
+        return_ok!(types::DeleteArtifactResp { result: ret })
     }
 
     #[oai(path = "/get/:filename", method = "get")]
@@ -174,7 +417,8 @@ impl FileApi {
 
         let data = unwrap_or_response!(data);
 
-        let mut attachment = Attachment::new(data).attachment_type(AttachmentType::Attachment);
+        let mut attachment =
+            Attachment::new(data.into()).attachment_type(AttachmentType::Attachment);
         attachment = attachment.filename(name);
         types::GetFileResponse::Ok(attachment)
     }
@@ -195,13 +439,12 @@ impl FileApi {
         )
         .map_or(Err(anyhow!("not found")), |v| Ok(v));
         let instance_record = unwrap_or_response!(instance_record);
-        let password =
-            unwrap_or_response!(state.decrypt(instance_record.password.unwrap_or_default()));
+        let login = unwrap_or_response!(svc.credential.get_ssh_login(&instance_record).await);
 
         let ssh_session = unwrap_or_response!(
             SshSession::connect(ConnectParams {
-                user: instance_record.sys_user.unwrap_or_default(),
-                password,
+                user: login.user,
+                auth: login.auth,
                 addrs: (instance_record.ip, 22),
             })
             .await
@@ -216,7 +459,8 @@ impl FileApi {
             .flatten()
             .map_or("download.tmp".to_string(), |v| v.to_owned());
 
-        let mut attachment = Attachment::new(data).attachment_type(AttachmentType::Attachment);
+        let mut attachment =
+            Attachment::new(data.into()).attachment_type(AttachmentType::Attachment);
         attachment = attachment.filename(name);
         types::GetFileResponse::Ok(attachment)
     }
@@ -235,10 +479,10 @@ impl FileApi {
             .get_one_user_server_with_permission(state.clone(), &user_info, instance_id.clone())
             .await?
             .map_or(Err(anyhow!("not found")), |v| Ok(v))?;
-        let password = state.decrypt(instance_record.password.unwrap_or_default())?;
+        let login = svc.credential.get_ssh_login(&instance_record).await?;
         let ssh_session = SshSession::connect(ConnectParams {
-            user: instance_record.sys_user.unwrap_or_default(),
-            password,
+            user: login.user,
+            auth: login.auth,
             addrs: (instance_record.ip, 22),
         })
         .await?;
@@ -308,10 +552,10 @@ impl FileApi {
             .get_one_user_server_with_permission(state.clone(), &user_info, req.instance_id)
             .await?
             .map_or(Err(anyhow!("not found")), |v| Ok(v))?;
-        let password = state.decrypt(instance_record.password.unwrap_or_default())?;
+        let login = svc.credential.get_ssh_login(&instance_record).await?;
         let ssh_session = SshSession::connect(ConnectParams {
-            user: instance_record.sys_user.unwrap_or_default(),
-            password,
+            user: login.user,
+            auth: login.auth,
             addrs: (instance_record.ip, 22),
         })
         .await?;
@@ -330,14 +574,15 @@ impl FileApi {
             }
         }
 
-        let data = req.file.into_vec().await.map_err(std_into_error)?;
-
         let mut file = sftp_session
             .create(req.file_path)
             .await
             .map_err(std_into_error)?;
 
-        file.write_all(&data).await.map_err(std_into_error)?;
+        io::copy(&mut req.file.into_async_read(), &mut file)
+            .await
+            .map_err(std_into_error)?;
+        file.shutdown().await.map_err(std_into_error)?;
 
         return_ok!(types::SftpUploadFileRes {
             result: "success".to_string()
@@ -357,10 +602,10 @@ impl FileApi {
             .get_one_user_server_with_permission(state.clone(), &user_info, req.instance_id)
             .await?
             .map_or(Err(anyhow!("not found")), |v| Ok(v))?;
-        let password = state.decrypt(instance_record.password.unwrap_or_default())?;
+        let login = svc.credential.get_ssh_login(&instance_record).await?;
         let ssh_session = SshSession::connect(ConnectParams {
-            user: instance_record.sys_user.unwrap_or_default(),
-            password,
+            user: login.user,
+            auth: login.auth,
             addrs: (instance_record.ip, 22),
         })
         .await?;
@@ -398,20 +643,12 @@ impl FileApi {
             .get_one_user_server_with_permission(state.clone(), &user_info, instance_id)
             .await?
             .ok_or(anyhow!("not found instance"))?;
-        let user = instance_record
-            .sys_user
-            .filter(|v| v != "")
-            .ok_or(anyhow!("no system user"))?;
-        let password = instance_record
-            .password
-            .filter(|v| v != "")
-            .ok_or(anyhow!("no password"))?;
+        let login = svc.credential.get_ssh_login(&instance_record).await?;
         let port = instance_record
             .ssh_port
+            .and_then(|v| u16::try_from(v).ok())
             .filter(|&v| v != 0)
             .ok_or(anyhow!("no ssh port"))?;
-
-        let password = state.decrypt(password)?;
         let ret = svc
             .ssh
             .sftp_read_dir(
@@ -420,8 +657,8 @@ impl FileApi {
                 instance_record.mac_addr,
                 port,
                 dir,
-                user,
-                password,
+                login.user,
+                login.auth,
             )
             .await?;
 
@@ -444,22 +681,14 @@ impl FileApi {
             .await?
             .ok_or(anyhow!("not found instance"))?;
 
-        let user = instance_record
-            .sys_user
-            .filter(|v| v != "")
-            .ok_or(anyhow!("no sys user"))?;
-        let password = instance_record
-            .password
-            .filter(|v| v != "")
-            .ok_or(anyhow!("no password"))?;
+        let login = svc.credential.get_ssh_login(&instance_record).await?;
         let port = instance_record
             .ssh_port
+            .and_then(|v| u16::try_from(v).ok())
             .filter(|&v| v != 0)
             .ok_or(anyhow!("no ssh port"))?;
 
-        let password = state.decrypt(password)?;
-
-        let data = req.file.into_vec().await.map_err(std_into_error)?;
+        let mut file = spool_upload(req.file).await?;
 
         let ret = svc
             .ssh
@@ -468,10 +697,11 @@ impl FileApi {
                 instance_record.ip,
                 instance_record.mac_addr,
                 port,
-                user,
-                password,
+                login.user,
+                login.auth,
                 req.file_path,
-                data,
+                &mut file,
+                req.transfer_id,
             )
             .await?;
 
@@ -496,21 +726,13 @@ impl FileApi {
             .get_one_user_server_with_permission(state.clone(), &user_info, req.instance_id)
             .await?
             .ok_or(anyhow!("not found instance"))?;
-        let user = instance_record
-            .sys_user
-            .filter(|v| v != "")
-            .ok_or(anyhow!("no sys user"))?;
-        let password = instance_record
-            .password
-            .filter(|v| v != "")
-            .ok_or(anyhow!("no password"))?;
+        let login = svc.credential.get_ssh_login(&instance_record).await?;
         let port = instance_record
             .ssh_port
+            .and_then(|v| u16::try_from(v).ok())
             .filter(|&v| v != 0)
             .ok_or(anyhow!("no ssh port"))?;
 
-        let password = state.decrypt(password)?;
-
         let ret = svc
             .ssh
             .sftp_remove(
@@ -518,8 +740,8 @@ impl FileApi {
                 instance_record.ip,
                 instance_record.mac_addr,
                 port,
-                user,
-                password,
+                login.user,
+                login.auth,
                 req.path,
                 req.remove_type,
             )
@@ -535,6 +757,8 @@ impl FileApi {
         user_info: Data<&logic::types::UserInfo>,
         Query(file_path): Query<String>,
         Query(instance_id): Query<String>,
+        /// id to poll the progress of the download, random if omitted
+        Query(transfer_id): Query<Option<String>>,
     ) -> types::GetFileResponse {
         let svc = state.service();
         let instance_record = unwrap_or_response!(
@@ -546,22 +770,13 @@ impl FileApi {
         let instance_record =
             unwrap_or_response!(instance_record.ok_or(anyhow!("not found instance")));
 
-        let user = unwrap_or_response!(instance_record
-            .sys_user
-            .filter(|v| v != "")
-            .ok_or(anyhow!("no sys user")));
-
-        let password = unwrap_or_response!(instance_record
-            .password
-            .filter(|v| v != "")
-            .ok_or(anyhow!("no password")));
+        let login = unwrap_or_response!(svc.credential.get_ssh_login(&instance_record).await);
         let port = unwrap_or_response!(instance_record
             .ssh_port
+            .and_then(|v| u16::try_from(v).ok())
             .filter(|&v| v != 0)
             .ok_or(anyhow!("no ssh port")));
 
-        let password = unwrap_or_response!(state.decrypt(password));
-
         let data = unwrap_or_response!(
             svc.ssh
                 .sftp_download(
@@ -569,9 +784,10 @@ impl FileApi {
                     instance_record.ip,
                     instance_record.mac_addr,
                     port,
-                    user,
-                    password,
-                    file_path.clone()
+                    login.user,
+                    login.auth,
+                    file_path.clone(),
+                    transfer_id,
                 )
                 .await
         );
@@ -587,4 +803,35 @@ impl FileApi {
 
         types::GetFileResponse::Ok(attachment)
     }
+
+    /// Progress of a tunnel upload or download, the file is moved through comet
+    /// in chunks so large files can be tracked while they are transferred.
+    #[oai(path = "/sftp/tunnel/progress", method = "get")]
+    async fn sftp_tunnel_progress(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+        Query(instance_id): Query<String>,
+        Query(transfer_id): Query<String>,
+    ) -> Result<ApiStdResponse<types::TransferProgressResp>> {
+        let svc = state.service();
+        let instance_record = svc
+            .instance
+            .get_one_user_server_with_permission(state.clone(), &user_info, instance_id)
+            .await?
+            .ok_or(anyhow!("not found instance"))?;
+
+        let progress = svc
+            .ssh
+            .sftp_transfer_progress(instance_record.ip, instance_record.mac_addr, transfer_id)
+            .await?
+            .ok_or(anyhow!("not found transfer"))?;
+
+        return_ok!(types::TransferProgressResp {
+            completed: progress.is_completed(),
+            transfer_id: progress.transfer_id,
+            offset: progress.offset,
+            total: progress.total,
+        })
+    }
 }
@@ -6,7 +6,7 @@ use crate::{
     entity::instance, error::NoPermission, local_time, logic, response::ApiStdResponse, return_ok,
     AppState,
 };
-use entity::instance_group;
+use entity::{instance_group, ssh_credential};
 use poem::{session::Session, web::Data, Result};
 use poem_openapi::param::Query;
 use poem_openapi::payload::Json;
@@ -23,17 +23,19 @@ pub mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct InstanceRecord {
-        pub id: u64,
+        pub id: i64,
         pub instance_id: String,
         pub ip: String,
         pub namespace: String,
         pub instance_group: String,
         pub sys_user: String,
+        pub auth_type: String,
+        pub credential_id: i64,
         pub info: String,
-        pub status: i8,
-        pub role_id: u64,
+        pub status: i16,
+        pub role_id: i64,
         pub role_name: String,
-        pub instance_group_id: u64,
+        pub instance_group_id: i64,
         pub created_time: String,
         pub updated_time: String,
     }
@@ -56,7 +58,7 @@ pub mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct Tag {
-        pub tag_id: u64,
+        pub tag_id: i64,
         pub tag_name: String,
     }
 
@@ -65,9 +67,9 @@ pub mod types {
         pub instance_id: String,
         pub ip: String,
         pub namespace: String,
-        pub instance_group_id: u64,
+        pub instance_group_id: i64,
         pub instance_group: String,
-        pub status: i8,
+        pub status: i16,
         pub info: String,
         pub tags: Option<Vec<Tag>>,
         pub created_time: String,
@@ -80,15 +82,21 @@ pub mod types {
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct SaveInstanceReq {
-        pub id: Option<u64>,
+        pub id: Option<i64>,
         pub ip: String,
         pub namespace: String,
-        pub instance_group_id: Option<u64>,
+        pub instance_group_id: Option<i64>,
         pub info: Option<String>,
-        pub status: i8,
+        pub status: i16,
         pub sys_user: Option<String>,
         pub password: Option<String>,
         pub ssh_port: Option<u16>,
+        /// password or private_key
+        pub auth_type: Option<String>,
+        pub private_key: Option<String>,
+        pub key_passphrase: Option<String>,
+        /// shared ssh credential, 0 uses the credential of the instance itself
+        pub credential_id: Option<i64>,
     }
 
     #[derive(Object, Serialize, Deserialize)]
@@ -98,10 +106,10 @@ pub mod types {
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct SaveInstanceGroupReq {
-        pub id: Option<u64>,
+        pub id: Option<i64>,
         pub name: String,
         pub info: String,
-        pub instance_ids: Option<Vec<u64>>,
+        pub instance_ids: Option<Vec<i64>>,
     }
 
     #[derive(Object, Serialize, Deserialize)]
@@ -117,7 +125,7 @@ pub mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct InstanceGroupRecord {
-        pub id: u64,
+        pub id: i64,
         pub name: String,
         pub info: String,
         pub created_time: String,
@@ -127,7 +135,7 @@ pub mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct DeleteInstanceGroupReq {
-        pub id: u64,
+        pub id: i64,
     }
 
     #[derive(Object, Serialize, Default)]
@@ -135,6 +143,52 @@ pub mod types {
         pub result: u64,
     }
 
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct SaveCredentialReq {
+        pub id: Option<i64>,
+        pub name: String,
+        pub info: Option<String>,
+        pub sys_user: Option<String>,
+        /// password or private_key
+        pub auth_type: String,
+        pub password: Option<String>,
+        pub private_key: Option<String>,
+        pub key_passphrase: Option<String>,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct SaveCredentialResp {
+        pub result: u32,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct QueryCredentialResp {
+        pub total: u64,
+        pub list: Vec<CredentialRecord>,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct CredentialRecord {
+        pub id: i64,
+        pub name: String,
+        pub info: String,
+        pub sys_user: String,
+        pub auth_type: String,
+        pub created_user: String,
+        pub created_time: String,
+        pub updated_time: String,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct DeleteCredentialReq {
+        pub id: i64,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct DeleteCredentialResp {
+        pub result: u64,
+    }
+
     #[derive(Object, Serialize, Default)]
     pub struct GetInstanceStatsResp {
         pub instance_online_num: u64,
@@ -144,7 +198,7 @@ pub mod types {
     #[derive(Object, Serialize, Deserialize)]
     pub struct SaveInstanceStatusReq {
         pub status: bool,
-        pub instance_ids: Vec<u64>,
+        pub instance_ids: Vec<i64>,
     }
 
     #[derive(Object, Serialize, Deserialize)]
@@ -165,8 +219,8 @@ impl InstanceApi {
 
         Query(ip): Query<Option<String>>,
         Query(status): Query<Option<u8>>,
-        Query(role_id): Query<Option<u64>>,
-        Query(ignore_role_id): Query<Option<u64>>,
+        Query(role_id): Query<Option<i64>>,
+        Query(ignore_role_id): Query<Option<i64>>,
         #[oai(
             default = "crate::api::default_page_size",
             validator(maximum(value = "10000"))
@@ -225,6 +279,8 @@ impl InstanceApi {
                 status: v.status,
                 updated_time: local_time!(v.updated_time),
                 sys_user: v.sys_user,
+                auth_type: v.auth_type,
+                credential_id: v.credential_id,
                 info: v.info,
                 created_time: local_time!(v.created_time),
             })
@@ -263,8 +319,8 @@ impl InstanceApi {
 
         Query(ip): Query<Option<String>>,
         Query(instance_id): Query<Option<String>>,
-        Query(instance_group_id): Query<Option<u64>>,
-        Query(tag_id): Query<Option<Vec<u64>>>,
+        Query(instance_group_id): Query<Option<i64>>,
+        Query(tag_id): Query<Option<Vec<i64>>>,
         Query(status): Query<Option<u8>>,
 
         #[oai(
@@ -378,6 +434,10 @@ impl InstanceApi {
             .transpose()?
             .map_or(NotSet, |v| Set(v));
 
+        let (auth_type, private_key, key_passphrase) =
+            svc.credential
+                .encrypt_auth(req.auth_type, req.private_key, req.key_passphrase)?;
+
         svc.instance
             .save_instance(instance::ActiveModel {
                 id: req.id.filter(|&v| v != 0).map_or(NotSet, |v| Set(v)),
@@ -391,7 +451,14 @@ impl InstanceApi {
                     .filter(|v| v.trim() != "")
                     .map_or(NotSet, |v| Set(v)),
                 password,
-                ssh_port: req.ssh_port.filter(|&v| v != 0).map_or(NotSet, |v| Set(v)),
+                ssh_port: req
+                    .ssh_port
+                    .filter(|&v| v != 0)
+                    .map_or(NotSet, |v| Set(v.into())),
+                auth_type,
+                private_key,
+                key_passphrase,
+                credential_id: req.credential_id.map_or(NotSet, Set),
                 ..Default::default()
             })
             .await?;
@@ -430,12 +497,13 @@ impl InstanceApi {
         if !state.can_manage_instance(&user_info.user_id).await? {
             return Err(NoPermission().into());
         }
+        let id = req.id.filter(|&v| v != 0);
         svc.instance
             .save_group(instance_group::ActiveModel {
-                id: req.id.filter(|&v| v != 0).map_or(NotSet, |v| Set(v)),
+                id: id.map_or(NotSet, Set),
                 name: Set(req.name),
                 info: Set(req.info),
-                created_user: Set(user_info.username.to_string()),
+                created_user: id.map_or(Set(user_info.username.to_string()), |_| NotSet),
                 ..Default::default()
             })
             .await?;
@@ -449,8 +517,8 @@ impl InstanceApi {
         _session: &Session,
 
         Query(name): Query<Option<String>>,
-        Query(role_id): Query<Option<u64>>,
-        Query(ignore_role_id): Query<Option<u64>>,
+        Query(role_id): Query<Option<i64>>,
+        Query(ignore_role_id): Query<Option<i64>>,
 
         #[oai(
             default = "crate::api::default_page_size",
@@ -524,6 +592,111 @@ impl InstanceApi {
         return_ok!(types::DeleteInstanceGroupResp { result: ret })
     }
 
+    #[oai(path = "/credential/save", method = "post")]
+    pub async fn save_credential(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::SaveCredentialReq>,
+    ) -> api_response!(types::SaveCredentialResp) {
+        let svc = state.service();
+        if !state.can_manage_instance(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+
+        let password = req
+            .password
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| state.encrypt(v))
+            .transpose()?
+            .map_or(NotSet, Set);
+
+        let (auth_type, private_key, key_passphrase) = svc.credential.encrypt_auth(
+            Some(req.auth_type),
+            req.private_key,
+            req.key_passphrase,
+        )?;
+
+        let id = req.id.filter(|&v| v != 0);
+        svc.credential
+            .save_credential(ssh_credential::ActiveModel {
+                id: id.map_or(NotSet, Set),
+                name: Set(req.name),
+                info: req.info.map_or(NotSet, Set),
+                sys_user: req.sys_user.map_or(NotSet, Set),
+                auth_type,
+                password,
+                private_key,
+                key_passphrase,
+                created_user: id.map_or(Set(user_info.username.to_string()), |_| NotSet),
+                ..Default::default()
+            })
+            .await?;
+        return_ok!(types::SaveCredentialResp { result: 0 })
+    }
+
+    #[oai(path = "/credential/list", method = "get")]
+    pub async fn query_credential(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        Query(name): Query<Option<String>>,
+        #[oai(
+            default = "crate::api::default_page_size",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page_size): Query<u64>,
+        #[oai(
+            default = "crate::api::default_page",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page): Query<u64>,
+        user_info: Data<&logic::types::UserInfo>,
+    ) -> api_response!(types::QueryCredentialResp) {
+        let svc = state.service();
+        if !state.can_manage_instance(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+
+        let ret = svc
+            .credential
+            .query_credential(name.filter(|v| !v.is_empty()), page - 1, page_size)
+            .await?;
+
+        let list = ret
+            .0
+            .into_iter()
+            .map(|v| types::CredentialRecord {
+                id: v.id,
+                name: v.name,
+                info: v.info,
+                sys_user: v.sys_user,
+                auth_type: v.auth_type,
+                created_user: v.created_user,
+                created_time: local_time!(v.created_time),
+                updated_time: local_time!(v.updated_time),
+            })
+            .collect();
+        return_ok!(types::QueryCredentialResp { total: ret.1, list })
+    }
+
+    #[oai(path = "/credential/delete", method = "post")]
+    pub async fn delete_credential(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::DeleteCredentialReq>,
+    ) -> api_response!(types::DeleteCredentialResp) {
+        let svc = state.service();
+        if !state.can_manage_instance(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+        let ret = svc.credential.delete_credential(req.id).await?;
+        return_ok!(types::DeleteCredentialResp { result: ret })
+    }
+
     #[oai(path = "/instance-stats", method = "post")]
     pub async fn get_instance_stats(
         &self,
@@ -5,7 +5,11 @@ use crate::{
     entity::{job, job_bundle_script, job_supervisor},
     error::NoPermission,
     local_time,
-    logic::{self, job::types::BundleScriptRecord},
+    logic::{
+        self,
+        job::types::BundleScriptRecord,
+        permission::{GrantAction, GrantResource},
+    },
     middleware,
     response::{std_into_error, ApiStdResponse},
     return_err, return_ok, AppState,
@@ -23,44 +27,55 @@ use poem_openapi::{
 use sea_orm::{ActiveValue::NotSet, Set};
 use serde_json::json;
 use types::CompletedCallbackOpts;
-mod types {
+pub mod types {
     use std::collections::HashMap;
 
     use automate::scheduler::types;
     use poem_openapi::{Enum, Object};
 
-    use serde::Serialize;
+    use serde::{Deserialize, Serialize};
     use serde_json::Value;
 
     use crate::logic;
 
     #[derive(Object, Serialize, Default)]
     pub struct SaveJobResp {
-        pub result: u64,
+        pub result: i64,
     }
 
     #[derive(Object, Serialize, Default)]
     #[oai(skip_serializing_if_is_none)]
     pub struct SaveJobReq {
-        pub id: Option<u64>,
+        pub id: Option<i64>,
         pub eid: Option<String>,
-        pub executor_id: u64,
+        pub executor_id: i64,
         #[oai(validator(min_length = 1, max_length = 50))]
         pub name: String,
         pub work_user: Option<String>,
         pub work_dir: Option<String>,
-        pub timeout: Option<u64>,
-        pub max_retry: Option<u8>,
-        pub max_parallel: Option<u8>,
+        pub timeout: Option<i64>,
+        pub max_retry: Option<i16>,
+        pub max_parallel: Option<i16>,
+        /// signal sent on timeout or kill, default SIGTERM
+        #[oai(validator(max_length = 20))]
+        pub stop_signal: Option<String>,
+        /// seconds to wait after stop_signal before SIGKILL, default 10
+        pub stop_grace_period: Option<i32>,
         pub code: Option<String>,
         pub info: Option<String>,
         pub bundle_script: Option<Vec<BundleScript>>,
+        /// a legacy file path or an artifact reference, eg: "artifact://deploy.tar.gz@3"
         pub upload_file: Option<String>,
+        /// output files published as artifacts after each run
+        pub publish_artifacts: Option<Vec<String>>,
         #[oai(default)]
         pub is_public: Option<bool>,
         pub display_on_dashboard: Option<bool>,
         pub args: Option<HashMap<String, String>>,
         pub completed_callback: Option<CompletedCallbackOpts>,
+        /// job template whose executor, defaults, parameters and callback
+        /// fill the fields not given, 0 detaches the job from its template
+        pub template_id: Option<i64>,
     }
 
     #[derive(Object, Serialize, Default)]
@@ -118,12 +133,211 @@ mod types {
         Error,
     }
 
+    #[derive(Object, Serialize, Default)]
+    pub struct RestartPolicy {
+        pub mode: RestartMode,
+        /// exit codes that are not treated as a crash, default [0]
+        pub expected_exit_codes: Option<Vec<i32>>,
+        /// upper bound of the exponential backoff in seconds, 0 keeps a flat restart interval
+        pub max_backoff: u64,
+        /// max restarts within restart_window before the job turns fatal, 0 means unlimited
+        pub max_restarts: u32,
+        /// window in seconds used to count restarts
+        pub restart_window: u64,
+    }
+
+    impl From<types::RestartPolicy> for RestartPolicy {
+        fn from(value: types::RestartPolicy) -> Self {
+            let mode = match value.mode {
+                types::RestartMode::Always => RestartMode::Always,
+                types::RestartMode::OnFailure => RestartMode::OnFailure,
+                types::RestartMode::Never => RestartMode::Never,
+            };
+            Self {
+                mode,
+                expected_exit_codes: Some(value.expected_exit_codes),
+                max_backoff: value.max_backoff,
+                max_restarts: value.max_restarts,
+                restart_window: value.restart_window,
+            }
+        }
+    }
+
+    impl From<RestartPolicy> for types::RestartPolicy {
+        fn from(value: RestartPolicy) -> Self {
+            let mode = match value.mode {
+                RestartMode::Always => types::RestartMode::Always,
+                RestartMode::OnFailure => types::RestartMode::OnFailure,
+                RestartMode::Never => types::RestartMode::Never,
+            };
+            let default = types::RestartPolicy::default();
+            Self {
+                mode,
+                expected_exit_codes: value
+                    .expected_exit_codes
+                    .unwrap_or(default.expected_exit_codes),
+                max_backoff: value.max_backoff,
+                max_restarts: value.max_restarts,
+                restart_window: if value.restart_window == 0 {
+                    default.restart_window
+                } else {
+                    value.restart_window
+                },
+            }
+        }
+    }
+
+    #[derive(Enum, Serialize, Default)]
+    pub enum RestartMode {
+        #[default]
+        #[oai(rename = "always")]
+        Always,
+        #[oai(rename = "on_failure")]
+        OnFailure,
+        #[oai(rename = "never")]
+        Never,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct HealthCheck {
+        /// restart the process after it failed
+        pub liveness: Option<Probe>,
+        /// only reported
+        pub readiness: Option<Probe>,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct Probe {
+        pub kind: ProbeKind,
+        /// command of an exec probe
+        pub command: Option<String>,
+        pub args: Option<Vec<String>>,
+        /// host of a tcp probe, default 127.0.0.1
+        pub host: Option<String>,
+        pub port: Option<u16>,
+        /// url of an http probe
+        pub url: Option<String>,
+        pub initial_delay: u64,
+        /// seconds between two checks, default 10
+        pub interval: u64,
+        /// default 1 second
+        pub timeout: u64,
+        /// default 3
+        pub failure_threshold: u32,
+    }
+
+    #[derive(Enum, Serialize, Default)]
+    pub enum ProbeKind {
+        #[default]
+        #[oai(rename = "exec")]
+        Exec,
+        #[oai(rename = "tcp")]
+        Tcp,
+        #[oai(rename = "http")]
+        Http,
+    }
+
+    impl From<types::Probe> for Probe {
+        fn from(value: types::Probe) -> Self {
+            let mut probe = Self {
+                initial_delay: value.initial_delay,
+                interval: value.interval,
+                timeout: value.timeout,
+                failure_threshold: value.failure_threshold,
+                ..Default::default()
+            };
+            match value.action {
+                types::ProbeAction::Exec { command, args } => {
+                    probe.kind = ProbeKind::Exec;
+                    probe.command = Some(command);
+                    probe.args = Some(args);
+                }
+                types::ProbeAction::Tcp { host, port } => {
+                    probe.kind = ProbeKind::Tcp;
+                    probe.host = Some(host);
+                    probe.port = Some(port);
+                }
+                types::ProbeAction::Http { url } => {
+                    probe.kind = ProbeKind::Http;
+                    probe.url = Some(url);
+                }
+            }
+            probe
+        }
+    }
+
+    impl TryFrom<Probe> for types::Probe {
+        type Error = anyhow::Error;
+
+        fn try_from(value: Probe) -> Result<Self, Self::Error> {
+            let action = match value.kind {
+                ProbeKind::Exec => types::ProbeAction::Exec {
+                    command: value
+                        .command
+                        .filter(|v| !v.is_empty())
+                        .ok_or(anyhow::anyhow!("command is required for exec probe"))?,
+                    args: value.args.unwrap_or_default(),
+                },
+                ProbeKind::Tcp => types::ProbeAction::Tcp {
+                    host: value
+                        .host
+                        .filter(|v| !v.is_empty())
+                        .unwrap_or("127.0.0.1".to_string()),
+                    port: value
+                        .port
+                        .ok_or(anyhow::anyhow!("port is required for tcp probe"))?,
+                },
+                ProbeKind::Http => types::ProbeAction::Http {
+                    url: value
+                        .url
+                        .filter(|v| !v.is_empty())
+                        .ok_or(anyhow::anyhow!("url is required for http probe"))?,
+                },
+            };
+            Ok(Self {
+                action,
+                initial_delay: value.initial_delay,
+                interval: if value.interval == 0 {
+                    10
+                } else {
+                    value.interval
+                },
+                timeout: if value.timeout == 0 { 1 } else { value.timeout },
+                failure_threshold: if value.failure_threshold == 0 {
+                    3
+                } else {
+                    value.failure_threshold
+                },
+            })
+        }
+    }
+
+    impl From<types::HealthCheck> for HealthCheck {
+        fn from(value: types::HealthCheck) -> Self {
+            Self {
+                liveness: value.liveness.map(Probe::from),
+                readiness: value.readiness.map(Probe::from),
+            }
+        }
+    }
+
+    impl TryFrom<HealthCheck> for types::HealthCheck {
+        type Error = anyhow::Error;
+
+        fn try_from(value: HealthCheck) -> Result<Self, Self::Error> {
+            Ok(Self {
+                liveness: value.liveness.map(types::Probe::try_from).transpose()?,
+                readiness: value.readiness.map(types::Probe::try_from).transpose()?,
+            })
+        }
+    }
+
     #[derive(Object, Serialize, Default)]
     pub struct BundleScript {
         pub eid: String,
         pub name: String,
         pub info: String,
-        pub executor_id: u64,
+        pub executor_id: i64,
         pub code: String,
         pub cond_expr: String,
     }
@@ -144,9 +358,9 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct JobRecord {
-        pub id: u64,
+        pub id: i64,
         pub eid: String,
-        pub executor_id: u64,
+        pub executor_id: i64,
         pub executor_name: String,
         pub executor_platform: String,
         pub name: String,
@@ -155,36 +369,42 @@ mod types {
         pub is_public: bool,
         pub job_type: String,
         pub team_name: Option<String>,
-        pub team_id: Option<u64>,
+        pub team_id: Option<i64>,
         pub bundle_script: Option<Value>,
         pub tags: Option<Vec<JobTag>>,
         pub display_on_dashboard: bool,
         pub work_dir: String,
         pub work_user: String,
-        pub timeout: u64,
-        pub max_retry: u8,
-        pub max_parallel: u8,
+        pub timeout: i64,
+        pub max_retry: i16,
+        pub max_parallel: i16,
+        pub stop_signal: String,
+        pub stop_grace_period: i32,
         pub created_user: String,
         pub updated_user: String,
         pub upload_file: String,
+        pub publish_artifacts: Option<Vec<String>>,
         pub args: Option<Value>,
         pub completed_callback: Option<CompletedCallbackOpts>,
+        /// job template the job derives from, 0 for none
+        pub template_id: i64,
+        pub template_version: i32,
         pub created_time: String,
         pub updated_time: String,
     }
 
     #[derive(Object, Serialize, Default)]
     pub struct JobTag {
-        pub id: u64,
+        pub id: i64,
         pub tag_name: String,
     }
 
     #[derive(Object, Serialize, Default)]
     pub struct RunRecord {
-        pub id: u64,
-        pub executor_id: u64,
+        pub id: i64,
+        pub executor_id: i64,
         pub executor_name: String,
-        pub team_id: Option<u64>,
+        pub team_id: Option<i64>,
         pub team_name: Option<String>,
         pub instance_id: String,
         pub is_online: bool,
@@ -200,6 +420,9 @@ mod types {
         pub run_status: String,
         pub exit_status: String,
         pub exit_code: i32,
+        pub restart_count: i32,
+        pub last_crash_reason: String,
+        pub probe_status: Option<serde_json::Value>,
         pub dispatch_result: Option<serde_json::Value>,
         pub dispatch_data: Option<serde_json::Value>,
         pub tags: Option<Vec<JobTag>>,
@@ -230,13 +453,32 @@ mod types {
         pub eid: String,
         pub timer_expr: Option<TimerExpr>,
         pub restart_interval: Option<u64>,
+        pub restart_policy: Option<RestartPolicy>,
+        pub health_check: Option<HealthCheck>,
         pub is_sync: bool,
         pub action: String,
     }
 
     #[derive(Object, Serialize, Default)]
+    #[oai(skip_serializing_if_is_none)]
     pub struct DispatchJobResp {
-        pub result: u64,
+        pub result: i64,
+        /// what would be sent to each target, only on a dry run
+        pub preview: Option<Vec<DispatchPreview>>,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct DispatchPreview {
+        pub namespace: String,
+        pub ip: String,
+        pub instance_id: String,
+        pub is_online: bool,
+        /// comet the agent is connected to
+        pub comet_addr: Option<String>,
+        /// the dispatch params sent to the agent, secrets masked
+        pub params: Option<Value>,
+        /// what would keep the job from running on the target
+        pub problems: Vec<String>,
     }
 
     pub type RedispatchJobResp = Vec<DispatchJobResult>;
@@ -267,9 +509,9 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct ScheduleRecord {
-        pub id: u64,
+        pub id: i64,
         pub schedule_id: String,
-        pub team_id: Option<u64>,
+        pub team_id: Option<i64>,
         pub team_name: Option<String>,
         pub name: String,
         pub eid: String,
@@ -294,17 +536,17 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct ExecRecord {
-        pub id: u64,
+        pub id: i64,
         pub job_name: String,
         pub schedule_id: String,
         pub bind_ip: String,
         pub is_online: bool,
         pub job_type: String,
-        pub team_id: Option<u64>,
+        pub team_id: Option<i64>,
         pub team_name: Option<String>,
         pub bundle_script_result: Option<serde_json::Value>,
         pub exit_status: String,
-        pub exit_code: i64,
+        pub exit_code: i32,
         pub start_time: Option<String>,
         pub end_time: Option<String>,
         pub tags: Option<Vec<JobTag>>,
@@ -373,9 +615,9 @@ mod types {
     #[derive(Object, Serialize, Default)]
     #[oai(skip_serializing_if_is_none)]
     pub struct SaveJobBundleScriptReq {
-        pub id: Option<u64>,
+        pub id: Option<i64>,
         pub eid: Option<String>,
-        pub executor_id: u64,
+        pub executor_id: i64,
         #[oai(validator(min_length = 1))]
         pub name: String,
         pub code: String,
@@ -385,7 +627,7 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct SaveJobBundleScriptResp {
-        pub result: u64,
+        pub result: i64,
     }
 
     #[derive(Object, Serialize, Default)]
@@ -395,11 +637,11 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct JobBundleScriptRecord {
-        pub id: u64,
+        pub id: i64,
         pub eid: String,
-        pub executor_id: u64,
+        pub executor_id: i64,
         pub executor_name: String,
-        pub team_id: Option<u64>,
+        pub team_id: Option<i64>,
         pub team_name: Option<String>,
         pub name: String,
         pub code: String,
@@ -419,17 +661,19 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct JobTimerRecord {
-        pub id: u64,
+        pub id: i64,
         pub eid: String,
         pub name: String,
-        pub team_id: Option<u64>,
+        pub team_id: Option<i64>,
         pub team_name: Option<String>,
         pub job_name: String,
         pub job_type: String,
-        pub executor_id: u64,
+        pub executor_id: i64,
         pub executor_name: String,
         pub executor_platform: String,
         pub timer_expr: serde_json::Value,
+        /// upcoming fire time as computed by the console
+        pub next_time: Option<String>,
         pub info: String,
         pub tags: Option<Vec<JobTag>>,
         pub created_user: String,
@@ -447,7 +691,7 @@ mod types {
     #[derive(Object, Serialize, Default)]
     #[oai(skip_serializing_if_is_none)]
     pub struct SaveJobTimerReq {
-        pub id: Option<u64>,
+        pub id: Option<i64>,
         pub eid: String,
         pub job_type: String,
         #[oai(validator(min_length = 1, max_length = 50))]
@@ -456,14 +700,38 @@ mod types {
         pub info: String,
     }
 
-    #[derive(Object, Serialize, Default)]
+    /// A timer as edited in the console. Cron timers are built from the
+    /// fields, interval timers fire every `interval_secs` and one-shot timers
+    /// at `run_at`.
+    #[derive(Object, Serialize, Deserialize, Default, Clone)]
+    #[serde(default)]
     pub struct TimerExpr {
+        /// cron, interval or at, cron when empty
+        #[oai(default)]
+        pub kind: String,
+        #[oai(default)]
         pub second: String,
+        #[oai(default)]
         pub minute: String,
+        #[oai(default)]
         pub hour: String,
+        #[oai(default)]
         pub day_of_month: String,
+        #[oai(default)]
         pub month: String,
+        #[oai(default)]
+        pub day_of_week: String,
+        /// deprecated, read as the day of week when that is empty
+        #[oai(default)]
         pub year: String,
+        /// IANA time zone, the agent's local one when empty
+        pub timezone: Option<String>,
+        pub interval_secs: Option<u64>,
+        /// rfc3339, or `%Y-%m-%d %H:%M:%S` in the timer's time zone
+        pub run_at: Option<String>,
+        /// calendars deciding the days the timer runs on
+        #[oai(default)]
+        pub calendar_ids: Vec<i64>,
     }
 
     impl From<String> for TimerExpr {
@@ -475,23 +743,121 @@ mod types {
                 hour: vec.get(2).map_or("1".to_string(), |&v| v.to_string()),
                 day_of_month: vec.get(3).map_or("1".to_string(), |&v| v.to_string()),
                 month: vec.get(4).map_or("1".to_string(), |&v| v.to_string()),
-                year: vec.get(5).map_or("1".to_string(), |&v| v.to_string()),
+                day_of_week: vec.get(5).map_or("*".to_string(), |&v| v.to_string()),
+                ..Default::default()
             }
         }
     }
 
     impl Into<String> for TimerExpr {
         fn into(self) -> String {
+            let day_of_week = [self.day_of_week, self.year]
+                .into_iter()
+                .find(|v| !v.is_empty())
+                .unwrap_or("*".to_string());
             format!(
                 "{} {} {} {} {} {}",
-                self.second, self.minute, self.hour, self.day_of_month, self.month, self.year
+                self.second, self.minute, self.hour, self.day_of_month, self.month, day_of_week
             )
         }
     }
 
+    impl TryFrom<TimerExpr> for automate::TimerSchedule {
+        type Error = anyhow::Error;
+
+        fn try_from(value: TimerExpr) -> Result<Self, Self::Error> {
+            let timezone = value.timezone.clone().filter(|v| !v.is_empty());
+            let schedule = match value.kind.as_str() {
+                "" | "cron" => Self::Cron {
+                    expr: value.into(),
+                    timezone,
+                },
+                "interval" => Self::Interval {
+                    every_secs: value
+                        .interval_secs
+                        .ok_or(anyhow::anyhow!("missing timer interval"))?,
+                },
+                "at" => {
+                    let run_at = value
+                        .run_at
+                        .ok_or(anyhow::anyhow!("missing timer run time"))?;
+                    Self::At {
+                        time: parse_run_at(&run_at, timezone.as_deref())?,
+                    }
+                }
+                v => anyhow::bail!("unknown timer kind {v}"),
+            };
+            schedule.validate()?;
+            Ok(schedule)
+        }
+    }
+
+    fn parse_run_at(
+        value: &str,
+        timezone: Option<&str>,
+    ) -> anyhow::Result<chrono::DateTime<chrono::Utc>> {
+        use chrono::{DateTime, NaiveDateTime, Utc};
+
+        if let Ok(v) = DateTime::parse_from_rfc3339(value) {
+            return Ok(v.with_timezone(&Utc));
+        }
+        let naive = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+            .map_err(|e| anyhow::anyhow!("invalid timer run time {value} - {e}"))?;
+        let time = match timezone {
+            Some(v) => naive
+                .and_local_timezone(
+                    v.parse::<chrono_tz::Tz>()
+                        .map_err(|_| anyhow::anyhow!("unknown time zone {v}"))?,
+                )
+                .earliest()
+                .map(|v| v.with_timezone(&Utc)),
+            None => naive
+                .and_local_timezone(chrono::Local)
+                .earliest()
+                .map(|v| v.with_timezone(&Utc)),
+        };
+        time.ok_or(anyhow::anyhow!("timer run time {value} does not exist"))
+    }
+
+    /// Next fire time of a saved timer computed on the console, so a timer
+    /// without a time zone is read in the console's one. None when it will
+    /// not fire again, calendars missing from the map are ignored.
+    pub fn next_fire_time(
+        timer_expr: &TimerExpr,
+        after: chrono::DateTime<chrono::Utc>,
+        calendars: &HashMap<i64, automate::TimerCalendar>,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        let bound: Vec<_> = timer_expr
+            .calendar_ids
+            .iter()
+            .filter_map(|v| calendars.get(v).cloned())
+            .collect();
+        let schedule = automate::TimerSchedule::try_from(timer_expr.clone()).ok()?;
+        schedule.next_allowed(after, &bound).ok().flatten()
+    }
+
+    pub fn default_preview_count() -> u64 {
+        5
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct PreviewTimerReq {
+        pub timer_expr: TimerExpr,
+        #[oai(
+            default = "default_preview_count",
+            validator(minimum(value = "1"), maximum(value = "100"))
+        )]
+        pub count: u64,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct PreviewTimerResp {
+        pub list: Vec<String>,
+    }
+
     #[derive(Object, Serialize, Default)]
     pub struct SaveJobTimerResp {
-        pub result: u64,
+        pub result: i64,
     }
 
     #[derive(Object, Serialize, Default)]
@@ -539,16 +905,20 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct JobSupervisorRecord {
-        pub id: u64,
+        pub id: i64,
         pub name: String,
         pub job_name: String,
         pub eid: String,
-        pub executor_id: u64,
+        pub executor_id: i64,
         pub executor_name: String,
         pub executor_platform: String,
-        pub team_id: Option<u64>,
+        pub team_id: Option<i64>,
         pub team_name: Option<String>,
-        pub restart_interval: u64,
+        pub restart_interval: i64,
+        pub restart_policy: Option<RestartPolicy>,
+        pub health_check: Option<HealthCheck>,
+        pub stop_signal: String,
+        pub stop_grace_period: Option<i32>,
         pub info: String,
         pub tags: Option<Vec<JobTag>>,
         pub created_user: String,
@@ -566,9 +936,16 @@ mod types {
     #[derive(Object, Serialize, Default)]
     #[oai(skip_serializing_if_is_none)]
     pub struct SaveJobSupervisorReq {
-        pub id: Option<u64>,
+        pub id: Option<i64>,
         pub eid: String,
-        pub restart_interval: u64,
+        pub restart_interval: i64,
+        pub restart_policy: Option<RestartPolicy>,
+        pub health_check: Option<HealthCheck>,
+        /// overrides the stop signal of the job when set
+        #[oai(validator(max_length = 20))]
+        pub stop_signal: Option<String>,
+        /// overrides the stop grace period of the job when set
+        pub stop_grace_period: Option<i32>,
         #[oai(validator(min_length = 1, max_length = 50))]
         pub name: String,
         #[oai(validator(min_length = 0, max_length = 500))]
@@ -577,7 +954,7 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct SaveJobSupervisorResp {
-        pub result: u64,
+        pub result: i64,
     }
 
     #[derive(Object, Serialize, Default)]
@@ -621,7 +998,7 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct DeleteJobSupervisorReq {
-        pub id: u64,
+        pub id: i64,
     }
 
     #[derive(Object, Serialize, Default)]
@@ -631,7 +1008,7 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct DeleteJobTimerReq {
-        pub id: u64,
+        pub id: i64,
     }
 
     #[derive(Object, Serialize, Default)]
@@ -644,6 +1021,15 @@ fn set_middleware(ep: impl Endpoint) -> impl Endpoint {
     ep.with(middleware::TeamPermissionMiddleware)
 }
 
+/// A timer of a manifest in the form `/save-timer` stores, checked the same way.
+pub(crate) fn normalize_timer_expr(value: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    let timer_expr: types::TimerExpr = serde_json::from_value(value)?;
+    automate::TimerSchedule::try_from(timer_expr.clone())?;
+    Ok(logic::manifest::without_nulls(serde_json::to_value(
+        timer_expr,
+    )?))
+}
+
 pub struct JobApi;
 
 #[OpenApi(prefix_path = "/job", tag = super::Tag::Job)]
@@ -653,9 +1039,9 @@ impl JobApi {
         &self,
         state: Data<&AppState>,
         _session: &Session,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         user_info: Data<&logic::types::UserInfo>,
-        Json(req): Json<types::SaveJobReq>,
+        Json(mut req): Json<types::SaveJobReq>,
     ) -> Result<ApiStdResponse<types::SaveJobResp>> {
         let ok = state.is_change_forbid(&user_info.user_id).await?;
         if ok {
@@ -671,15 +1057,59 @@ impl JobApi {
             return Err(NoPermission().into());
         }
 
+        let template = match req.template_id.filter(|&v| v != 0) {
+            Some(v) => {
+                let Some(template) = svc.job_template.get_template(v).await? else {
+                    return_err!("cannot found job template");
+                };
+                if template.team_id != 0
+                    && !svc
+                        .team
+                        .can_read_team(Some(template.team_id), user_info.user_id.clone())
+                        .await?
+                {
+                    return Err(NoPermission().into());
+                }
+                Some(template)
+            }
+            None => None,
+        };
+        let (template_id, template_version) = match (&template, req.template_id) {
+            (Some(v), _) => (Set(v.id), Set(v.version)),
+            (None, Some(_)) => (Set(0), Set(0)),
+            (None, None) => (NotSet, NotSet),
+        };
+        if let Some(ref v) = template {
+            if req.executor_id == 0 {
+                req.executor_id = v.executor_id;
+            }
+            req.work_dir.get_or_insert_with(|| v.work_dir.clone());
+            req.work_user.get_or_insert_with(|| v.work_user.clone());
+            req.timeout.get_or_insert(v.timeout);
+            req.max_retry.get_or_insert(v.max_retry);
+            req.max_parallel.get_or_insert(v.max_parallel);
+            req.stop_signal.get_or_insert_with(|| v.stop_signal.clone());
+            req.stop_grace_period.get_or_insert(v.stop_grace_period);
+        }
+
         let args = req
             .args
             .map(|v| serde_json::to_value(&v))
             .transpose()
+            .map_err(std_into_error)?
+            .or_else(|| template.as_ref().and_then(|v| v.args.clone()));
+
+        let publish_artifacts = req
+            .publish_artifacts
+            .map(|v| serde_json::to_value(&v))
+            .transpose()
             .map_err(std_into_error)?;
 
         let completed_callback = if let Some(v) = req.completed_callback {
             let data: logic::types::CompletedCallbackOpts = v.into();
             Set(Some(serde_json::to_value(data).map_err(std_into_error)?))
+        } else if let Some(v) = template.as_ref().and_then(|v| v.completed_callback.clone()) {
+            Set(Some(v))
         } else {
             NotSet
         };
@@ -729,9 +1159,15 @@ impl JobApi {
                 max_retry: Set(req.max_retry.unwrap_or(1)),
                 max_parallel: Set(req.max_parallel.unwrap_or(1)),
                 timeout: Set(req.timeout.unwrap_or(60)),
+                stop_signal: Set(req
+                    .stop_signal
+                    .filter(|v| !v.is_empty())
+                    .unwrap_or("SIGTERM".to_string())),
+                stop_grace_period: Set(req.stop_grace_period.unwrap_or(10)),
                 bundle_script,
                 job_type,
                 upload_file: Set(req.upload_file.unwrap_or_default()),
+                publish_artifacts: Set(publish_artifacts),
                 is_public: Set(req.is_public.map_or(0, |v| match v {
                     true => 1,
                     false => 0,
@@ -742,6 +1178,8 @@ impl JobApi {
                 args: Set(args),
                 team_id: team_id.map_or(NotSet, |v| Set(v)),
                 completed_callback,
+                template_id,
+                template_version,
                 ..Default::default()
             })
             .await?;
@@ -760,16 +1198,16 @@ impl JobApi {
 
         #[oai(default = "types::default_page", validator(maximum(value = "10000")))]
         Query(page): Query<u64>,
-        Query(default_id): Query<Option<u64>>,
+        Query(default_id): Query<Option<i64>>,
         Query(default_eid): Query<Option<String>>,
         Query(search_username): Query<Option<String>>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         /// Search based on time range
         #[oai(validator(max_items = 2, min_items = 2))]
         Query(updated_time_range): Query<Option<Vec<String>>>,
         #[oai(default)] Query(name): Query<Option<String>>,
         #[oai(default)] Query(job_type): Query<Option<String>>,
-        Query(tag_ids): Query<Option<Vec<u64>>>,
+        Query(tag_ids): Query<Option<Vec<i64>>>,
         #[oai(
             default = "types::default_page_size",
             validator(maximum(value = "10000"))
@@ -790,11 +1228,21 @@ impl JobApi {
         } else {
             team_id.map_or_else(|| Some(user_info.username.clone()), |_| search_username)
         };
+        // jobs granted to the user are listed alongside the user's own
+        let granted_ids = match search_username {
+            Some(ref v) if v == &user_info.username => {
+                svc.permission
+                    .granted_resource_ids(&user_info, GrantResource::Job)
+                    .await?
+            }
+            _ => vec![],
+        };
 
         let ret = svc
             .job
             .query_job(
                 search_username,
+                granted_ids,
                 job_type.filter(|v| v != ""),
                 name.filter(|v| v != ""),
                 updated_time_range,
@@ -859,7 +1307,16 @@ impl JobApi {
                 timeout: v.timeout,
                 max_retry: v.max_retry,
                 max_parallel: v.max_parallel,
+                stop_signal: v.stop_signal,
+                stop_grace_period: v.stop_grace_period,
                 upload_file: v.upload_file,
+                publish_artifacts: v
+                    .publish_artifacts
+                    .map(serde_json::from_value)
+                    .transpose()
+                    .unwrap_or_default(),
+                template_id: v.template_id,
+                template_version: v.template_version,
                 created_time: local_time!(v.created_time),
                 updated_time: local_time!(v.updated_time),
             })
@@ -875,7 +1332,7 @@ impl JobApi {
         &self,
         state: Data<&AppState>,
         user_info: Data<&logic::types::UserInfo>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Json(req): Json<types::DeleteJobReq>,
     ) -> api_response!(types::DeleteJobResp) {
         let svc = state.service();
@@ -895,7 +1352,11 @@ impl JobApi {
     pub async fn dispatch(
         &self,
         state: Data<&AppState>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
+        /// resolve and check everything without contacting comet, the
+        /// response previews what each target would get
+        #[oai(default)]
+        Query(dry_run): Query<bool>,
         Json(req): Json<types::DispatchJobReq>,
         user_info: Data<&logic::types::UserInfo>,
     ) -> Result<ApiStdResponse<types::DispatchJobResp>> {
@@ -903,31 +1364,98 @@ impl JobApi {
         let action = req.action.as_str().try_into()?;
         let schedule_type = req.schedule_type.as_str().try_into()?;
         let secret = state.conf.comet_secret.clone();
+        let grant_action = GrantAction::for_job_action(&action);
 
         if !svc
             .job
-            .can_dispatch_job(&user_info, team_id, None, &req.eid)
+            .can_dispatch_job(&user_info, team_id, None, &req.eid, grant_action)
             .await?
         {
             return Err(NoPermission().into());
         }
 
-        let ret = svc
-            .job
-            .dispatch_job(
-                secret,
-                req.endpoints.into_iter().map(|v| v.instance_id).collect(),
-                req.eid,
-                req.is_sync,
-                req.schedule_name,
-                schedule_type,
-                action,
-                req.timer_expr.map(|v| v.into()),
-                req.restart_interval.map(|v| Duration::from_secs(v)),
-                user_info.username.clone(),
-            )
-            .await?;
-        return_ok!(types::DispatchJobResp { result: ret })
+        // a dry run reports an invalid timer or health check with the other
+        // problems instead of failing
+        let mut problems = vec![];
+        let timer = req
+            .timer_expr
+            .as_ref()
+            .map(|v| v.clone().try_into())
+            .transpose()
+            .or_else(|e: anyhow::Error| {
+                if !dry_run {
+                    return Err(e);
+                }
+                problems.push(format!("invalid timer, {e}"));
+                Ok(None)
+            })?;
+        let health_check =
+            req.health_check
+                .map(|v| v.try_into())
+                .transpose()
+                .or_else(|e: anyhow::Error| {
+                    if !dry_run {
+                        return Err(e);
+                    }
+                    problems.push(format!("invalid health check, {e}"));
+                    Ok(None)
+                })?;
+        let dispatch_req = logic::job::types::DispatchRequest {
+            instance_ids: req
+                .endpoints
+                .iter()
+                .map(|v| v.instance_id.clone())
+                .collect(),
+            eid: req.eid,
+            is_sync: req.is_sync,
+            schedule_name: req.schedule_name,
+            schedule_type,
+            action,
+            timer,
+            calendar_ids: req.timer_expr.map(|v| v.calendar_ids).unwrap_or_default(),
+            restart_interval: req.restart_interval.map(Duration::from_secs),
+            restart_policy: req.restart_policy.map(|v| v.into()),
+            health_check,
+            created_user: user_info.username.clone(),
+        };
+
+        if dry_run {
+            let preview = svc
+                .job
+                .preview_dispatch(&user_info, grant_action, dispatch_req, problems)
+                .await?
+                .into_iter()
+                .map(|v| {
+                    Ok(types::DispatchPreview {
+                        namespace: v.namespace,
+                        ip: v.bind_ip,
+                        instance_id: v.instance_id,
+                        is_online: v.is_online,
+                        comet_addr: v.comet_addr,
+                        params: v.params.map(serde_json::to_value).transpose()?,
+                        problems: v.problems,
+                    })
+                })
+                .collect::<serde_json::Result<Vec<_>>>()
+                .map_err(std_into_error)?;
+            return_ok!(types::DispatchJobResp {
+                result: 0,
+                preview: Some(preview),
+            });
+        }
+        if !svc
+            .permission
+            .can_operate_instances(&user_info, &dispatch_req.instance_ids, grant_action)
+            .await?
+        {
+            return_err!("no permission to run the job on the selected instance group");
+        }
+
+        let ret = svc.job.dispatch_job(secret, dispatch_req).await?;
+        return_ok!(types::DispatchJobResp {
+            result: ret,
+            preview: None,
+        })
     }
 
     #[oai(path = "/redispatch", method = "post", transform = "set_middleware")]
@@ -935,7 +1463,7 @@ impl JobApi {
         &self,
         state: Data<&AppState>,
         user_info: Data<&logic::types::UserInfo>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Json(req): Json<types::RedispatchJobReq>,
     ) -> Result<ApiStdResponse<types::RedispatchJobResp>> {
         let svc = state.service();
@@ -957,6 +1485,7 @@ impl JobApi {
                 team_id,
                 Some(&schedule_record.created_user),
                 &schedule_record.eid,
+                GrantAction::for_job_action(&action),
             )
             .await?
         {
@@ -965,14 +1494,33 @@ impl JobApi {
             );
         }
 
+        let dispatch_data: logic::job::types::DispatchData = schedule_record
+            .dispatch_data
+            .ok_or(anyhow::anyhow!("cannot found job dispatch data"))?
+            .try_into()?;
+        let dispatch_req = logic::job::types::DispatchRequest::from_schedule(
+            schedule_record.name,
+            &schedule_record.schedule_type,
+            &dispatch_data,
+            action,
+            user_info.username.clone(),
+        )?;
+        // the instances may have been moved to a group the user has no grant on
+        if !svc
+            .permission
+            .can_operate_instances(
+                &user_info,
+                &dispatch_req.instance_ids,
+                GrantAction::for_job_action(&action),
+            )
+            .await?
+        {
+            return_err!("no permission to run the job on the selected instance group");
+        }
+
         let ret = svc
             .job
-            .redispatch_job(
-                &req.schedule_id,
-                action,
-                schedule_record,
-                user_info.username.clone(),
-            )
+            .redispatch_job(&req.schedule_id, dispatch_data, dispatch_req)
             .await?;
 
         let ret = ret
@@ -1012,8 +1560,8 @@ impl JobApi {
         Query(schedule_type): Query<String>,
         #[oai(validator(custom = "super::OneOfValidator::new(vec![\"bundle\", \"default\"])"))]
         Query(job_type): Query<String>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
-        Query(tag_ids): Query<Option<Vec<u64>>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
+        Query(tag_ids): Query<Option<Vec<i64>>>,
         /// Search based on time range
         #[oai(validator(max_items = 2, min_items = 2))]
         Query(updated_time_range): Query<Option<Vec<String>>>,
@@ -1101,6 +1649,9 @@ impl JobApi {
                 run_status: v.run_status,
                 exit_status: v.exit_status,
                 exit_code: v.exit_code,
+                restart_count: v.restart_count,
+                last_crash_reason: v.last_crash_reason,
+                probe_status: v.probe_status,
                 job_type: v.job_type,
                 dispatch_result: v.dispatch_result,
                 start_time: v.start_time.map_or("".to_string(), |t| local_time!(t)),
@@ -1132,8 +1683,8 @@ impl JobApi {
 
         #[oai(default)] Query(name): Query<Option<String>>,
         #[oai(default)] Query(job_type): Query<String>,
-        Query(tag_ids): Query<Option<Vec<u64>>>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        Query(tag_ids): Query<Option<Vec<i64>>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         #[oai(default = "types::default_page", validator(maximum(value = "10000")))]
         Query(page): Query<u64>,
         #[oai(
@@ -1225,8 +1776,8 @@ impl JobApi {
         #[oai(validator(custom = "super::OneOfValidator::new(vec![\"bundle\", \"default\"])"))]
         Query(job_type): Query<String>,
         #[oai(default)] Query(schedule_name): Query<Option<String>>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
-        Query(tag_ids): Query<Option<Vec<u64>>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
+        Query(tag_ids): Query<Option<Vec<i64>>>,
         #[oai(validator(
             custom = "super::OneOfValidator::new(vec![\"once\",\"timer\",\"flow\",\"daemon\"])"
         ))]
@@ -1333,7 +1884,7 @@ impl JobApi {
         &self,
         state: Data<&AppState>,
         user_info: Data<&logic::types::UserInfo>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Json(req): Json<types::DeleteRunStatusReq>,
         _session: &Session,
     ) -> api_response!(types::DeleteRunStatusResp) {
@@ -1374,7 +1925,7 @@ impl JobApi {
         &self,
         state: Data<&AppState>,
         user_info: Data<&logic::types::UserInfo>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Json(req): Json<types::DeleteScheduleHistoryReq>,
         _session: &Session,
     ) -> api_response!(types::DeleteScheduleHistoryResp) {
@@ -1404,7 +1955,7 @@ impl JobApi {
         &self,
         state: Data<&AppState>,
         user_info: Data<&logic::types::UserInfo>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Json(req): Json<types::DeleteExecHistoryReq>,
         _session: &Session,
     ) -> api_response!(types::DeleteExecHistoryResp) {
@@ -1448,7 +1999,7 @@ impl JobApi {
         state: Data<&AppState>,
         user_info: Data<&logic::types::UserInfo>,
         _session: &Session,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Json(req): Json<types::ActionReq>,
     ) -> Result<ApiStdResponse<types::ActionRes>> {
         let svc = state.service();
@@ -1477,7 +2028,7 @@ impl JobApi {
         state: Data<&AppState>,
         _session: &Session,
         user_info: Data<&logic::types::UserInfo>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Json(req): Json<types::SaveJobBundleScriptReq>,
     ) -> Result<ApiStdResponse<types::SaveJobBundleScriptResp>> {
         let args = match req.args {
@@ -1534,7 +2085,7 @@ impl JobApi {
         &self,
         state: Data<&AppState>,
         user_info: Data<&logic::types::UserInfo>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Json(req): Json<types::DeleteJobBundleScriptReq>,
     ) -> Result<ApiStdResponse<u64>> {
         let svc = state.service();
@@ -1566,7 +2117,7 @@ impl JobApi {
         /// Search based on time range
         #[oai(validator(max_items = 2, min_items = 2))]
         Query(updated_time_range): Query<Option<Vec<String>>>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         #[oai(default = "types::default_page", validator(maximum(value = "10000")))]
         Query(page): Query<u64>,
 
@@ -1641,9 +2192,9 @@ impl JobApi {
         user_info: Data<&logic::types::UserInfo>,
         #[oai(default)] Query(name): Query<Option<String>>,
         #[oai(default)] Query(job_type): Query<Option<String>>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Query(search_username): Query<Option<String>>,
-        Query(tag_ids): Query<Option<Vec<u64>>>,
+        Query(tag_ids): Query<Option<Vec<i64>>>,
         /// Search based on time range
         #[oai(validator(max_items = 2, min_items = 2))]
         Query(updated_time_range): Query<Option<Vec<String>>>,
@@ -1685,14 +2236,35 @@ impl JobApi {
             .get_all_tag_bind_by_job_ids(ret.0.iter().map(|v| v.job_id).collect())
             .await?;
 
+        let timer_exprs: Vec<Option<types::TimerExpr>> = ret
+            .0
+            .iter()
+            .map(|v| {
+                v.timer_expr
+                    .clone()
+                    .and_then(|v| serde_json::from_value(v).ok())
+            })
+            .collect();
+        let calendar_ids: Vec<i64> = timer_exprs
+            .iter()
+            .flatten()
+            .flat_map(|v| v.calendar_ids.clone())
+            .collect();
+        let calendars = svc.calendar.get_calendar_map(&calendar_ids).await?;
+
+        let now = chrono::Utc::now();
         let list: Vec<types::JobTimerRecord> = ret
             .0
             .into_iter()
-            .map(|v| types::JobTimerRecord {
+            .zip(timer_exprs)
+            .map(|(v, timer_expr)| types::JobTimerRecord {
                 id: v.id,
                 eid: v.eid,
                 name: v.name,
                 job_name: v.job_name,
+                next_time: timer_expr
+                    .and_then(|v| types::next_fire_time(&v, now, &calendars))
+                    .map(|v| local_time!(v)),
                 timer_expr: v.timer_expr.map_or(json!("null"), |v| v),
                 job_type: v.job_type,
                 info: v.info,
@@ -1734,7 +2306,7 @@ impl JobApi {
         state: Data<&AppState>,
         _session: &Session,
         user_info: Data<&logic::types::UserInfo>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Json(req): Json<types::SaveJobTimerReq>,
     ) -> Result<ApiStdResponse<types::SaveJobTimerResp>> {
         let svc = state.service();
@@ -1747,6 +2319,11 @@ impl JobApi {
             return Err(NoPermission().into());
         }
 
+        automate::TimerSchedule::try_from(req.timer_expr.clone())?;
+        svc.calendar
+            .get_timer_calendars(&req.timer_expr.calendar_ids)
+            .await?;
+
         let ret = svc
             .job
             .save_job_timer(crate::entity::job_timer::ActiveModel {
@@ -1769,12 +2346,32 @@ impl JobApi {
         });
     }
 
+    #[oai(path = "/timer/preview", method = "post", transform = "set_middleware")]
+    pub async fn preview_timer(
+        &self,
+        state: Data<&AppState>,
+        Json(req): Json<types::PreviewTimerReq>,
+    ) -> api_response!(types::PreviewTimerResp) {
+        let calendars = state
+            .service()
+            .calendar
+            .get_timer_calendars(&req.timer_expr.calendar_ids)
+            .await?;
+        let schedule = automate::TimerSchedule::try_from(req.timer_expr)?;
+        let list = schedule
+            .upcoming(chrono::Utc::now(), req.count as usize, &calendars)?
+            .into_iter()
+            .map(|v| local_time!(v))
+            .collect();
+        return_ok!(types::PreviewTimerResp { list })
+    }
+
     #[oai(path = "/delete-timer", method = "post", transform = "set_middleware")]
     pub async fn delete_timer(
         &self,
         state: Data<&AppState>,
         user_info: Data<&logic::types::UserInfo>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Json(req): Json<types::DeleteJobTimerReq>,
     ) -> api_response!(types::DeleteJobTimerResp) {
         let svc = state.service();
@@ -1854,9 +2451,9 @@ impl JobApi {
         /// Search based on time range
         #[oai(validator(max_items = 2, min_items = 2))]
         Query(updated_time_range): Query<Option<Vec<String>>>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Query(search_username): Query<Option<String>>,
-        Query(tag_ids): Query<Option<Vec<u64>>>,
+        Query(tag_ids): Query<Option<Vec<i64>>>,
         #[oai(default = "types::default_page", validator(maximum(value = "10000")))]
         Query(page): Query<u64>,
         #[oai(
@@ -1924,6 +2521,20 @@ impl JobApi {
                 updated_time: local_time!(v.updated_time),
                 executor_name: v.executor_name,
                 restart_interval: v.restart_interval,
+                restart_policy: v
+                    .restart_policy
+                    .map(serde_json::from_value::<automate::RestartPolicy>)
+                    .transpose()
+                    .unwrap_or_default()
+                    .map(types::RestartPolicy::from),
+                health_check: v
+                    .health_check
+                    .map(serde_json::from_value::<automate::HealthCheck>)
+                    .transpose()
+                    .unwrap_or_default()
+                    .map(types::HealthCheck::from),
+                stop_signal: v.stop_signal,
+                stop_grace_period: v.stop_grace_period,
                 executor_platform: v.executor_platform,
             })
             .collect();
@@ -1943,7 +2554,7 @@ impl JobApi {
         state: Data<&AppState>,
         _session: &Session,
         user_info: Data<&logic::types::UserInfo>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Json(req): Json<types::SaveJobSupervisorReq>,
     ) -> api_response!(types::SaveJobSupervisorResp) {
         let svc = state.service();
@@ -1956,6 +2567,20 @@ impl JobApi {
             return Err(NoPermission().into());
         }
 
+        let restart_policy = if let Some(v) = req.restart_policy {
+            let data: automate::RestartPolicy = v.into();
+            Set(Some(serde_json::to_value(data).map_err(std_into_error)?))
+        } else {
+            NotSet
+        };
+
+        let health_check = if let Some(v) = req.health_check {
+            let data: automate::HealthCheck = v.try_into()?;
+            Set(Some(serde_json::to_value(data).map_err(std_into_error)?))
+        } else {
+            NotSet
+        };
+
         let ret = svc
             .job
             .save_job_supervisor(job_supervisor::ActiveModel {
@@ -1969,6 +2594,10 @@ impl JobApi {
                         req.restart_interval
                     }
                 }),
+                restart_policy,
+                health_check,
+                stop_signal: Set(req.stop_signal.unwrap_or_default()),
+                stop_grace_period: Set(req.stop_grace_period),
                 info: Set(req.info),
                 created_user: req.id.map_or(Set(user_info.username.clone()), |_| NotSet),
                 updated_user: Set(user_info.username.clone()),
@@ -1990,7 +2619,7 @@ impl JobApi {
         &self,
         state: Data<&AppState>,
         user_info: Data<&logic::types::UserInfo>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
         Json(req): Json<types::DeleteJobSupervisorReq>,
     ) -> api_response!(types::DeleteJobSupervisorResp) {
         let svc = state.service();
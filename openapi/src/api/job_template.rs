@@ -0,0 +1,443 @@
+use poem::{session::Session, web::Data};
+use poem_openapi::{param::Query, payload::Json, OpenApi};
+use sea_orm::{ActiveValue::NotSet, Set};
+
+use crate::{
+    api_response,
+    entity::job_template,
+    error::NoPermission,
+    local_time,
+    logic::{self, types::UserInfo},
+    response::std_into_error,
+    return_err, return_ok, AppState,
+};
+
+use super::job::types::CompletedCallbackOpts;
+
+pub struct JobTemplateApi;
+
+mod types {
+    use std::collections::HashMap;
+
+    use poem_openapi::Object;
+    use serde::Serialize;
+    use serde_json::Value;
+
+    use crate::api::job::types::CompletedCallbackOpts;
+
+    #[derive(Object, Serialize)]
+    pub struct SaveJobTemplateReq {
+        pub id: Option<i64>,
+        #[oai(validator(min_length = 1, max_length = 100))]
+        pub name: String,
+        #[oai(default)]
+        pub info: String,
+        /// team the template belongs to, 0 shares it with all teams
+        #[oai(default)]
+        pub team_id: i64,
+        pub executor_id: i64,
+        #[oai(default)]
+        pub work_dir: String,
+        #[oai(default)]
+        pub work_user: String,
+        #[oai(default = "default_timeout")]
+        pub timeout: i64,
+        #[oai(default = "default_one")]
+        pub max_retry: i16,
+        #[oai(default = "default_one")]
+        pub max_parallel: i16,
+        #[oai(default = "default_stop_signal", validator(max_length = 20))]
+        pub stop_signal: String,
+        #[oai(default = "default_stop_grace_period")]
+        pub stop_grace_period: i32,
+        pub args: Option<HashMap<String, String>>,
+        pub completed_callback: Option<CompletedCallbackOpts>,
+        /// updates the derived jobs that did not customize what they
+        /// inherited, otherwise they are only flagged as outdated
+        #[oai(default = "crate::api::calendar::default_enabled")]
+        pub propagate: bool,
+    }
+
+    pub fn default_timeout() -> i64 {
+        60
+    }
+
+    pub fn default_one() -> i16 {
+        1
+    }
+
+    pub fn default_stop_signal() -> String {
+        "SIGTERM".to_string()
+    }
+
+    pub fn default_stop_grace_period() -> i32 {
+        10
+    }
+
+    #[derive(Object, Serialize)]
+    pub struct SaveJobTemplateResp {
+        pub id: i64,
+        pub version: i32,
+        /// jobs updated to the new version
+        pub updated: Vec<String>,
+        /// derived jobs left on an older version
+        pub outdated: Vec<String>,
+    }
+
+    #[derive(Object, Serialize)]
+    pub struct JobTemplateRecord {
+        pub id: i64,
+        pub name: String,
+        pub info: String,
+        pub team_id: i64,
+        pub version: i32,
+        pub executor_id: i64,
+        pub work_dir: String,
+        pub work_user: String,
+        pub timeout: i64,
+        pub max_retry: i16,
+        pub max_parallel: i16,
+        pub stop_signal: String,
+        pub stop_grace_period: i32,
+        pub args: Option<Value>,
+        pub completed_callback: Option<CompletedCallbackOpts>,
+        pub created_user: String,
+        pub updated_user: String,
+        pub created_time: String,
+        pub updated_time: String,
+    }
+
+    #[derive(Object, Serialize)]
+    pub struct QueryJobTemplateResp {
+        pub total: u64,
+        pub list: Vec<JobTemplateRecord>,
+    }
+
+    #[derive(Object, Serialize)]
+    pub struct DerivedJobRecord {
+        pub eid: String,
+        pub name: String,
+        pub template_version: i32,
+        /// the template changed since it was last applied to the job
+        pub outdated: bool,
+        /// inherited fields the job changed
+        pub customized: Vec<String>,
+    }
+
+    #[derive(Object, Serialize)]
+    pub struct QueryDerivedJobResp {
+        pub version: i32,
+        pub list: Vec<DerivedJobRecord>,
+    }
+
+    #[derive(Object, Serialize)]
+    pub struct SyncJobTemplateReq {
+        pub id: i64,
+        /// jobs to update, all derived jobs the user may edit when empty
+        #[oai(default)]
+        pub eids: Vec<String>,
+    }
+
+    #[derive(Object, Serialize)]
+    pub struct SyncJobTemplateResp {
+        pub result: u64,
+    }
+
+    #[derive(Object, Serialize)]
+    pub struct DeleteJobTemplateReq {
+        pub id: i64,
+    }
+
+    #[derive(Object, Serialize)]
+    pub struct DeleteJobTemplateResp {
+        pub result: u64,
+    }
+}
+
+/// Templates of team 0 are shared by all teams, only job managers may change
+/// them.
+async fn can_write_template(
+    state: &AppState,
+    user_info: &UserInfo,
+    team_id: i64,
+) -> anyhow::Result<bool> {
+    match team_id {
+        0 => state.can_manage_job(&user_info.user_id).await,
+        v => {
+            state
+                .service()
+                .team
+                .can_write_team(Some(v), user_info.user_id.clone())
+                .await
+        }
+    }
+}
+
+async fn can_read_template(
+    state: &AppState,
+    user_info: &UserInfo,
+    team_id: i64,
+) -> anyhow::Result<bool> {
+    match team_id {
+        0 => Ok(true),
+        v => {
+            state
+                .service()
+                .team
+                .can_read_team(Some(v), user_info.user_id.clone())
+                .await
+        }
+    }
+}
+
+#[OpenApi(prefix_path = "/job-template", tag = super::Tag::JobTemplate)]
+impl JobTemplateApi {
+    /// Saves a job template. New jobs saved with the template inherit its
+    /// executor, defaults, parameters, completed callback and timeouts, and
+    /// changing any of them bumps the version of the template
+    #[oai(path = "/save", method = "post")]
+    pub async fn save_template(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&UserInfo>,
+        Json(req): Json<types::SaveJobTemplateReq>,
+    ) -> api_response!(types::SaveJobTemplateResp) {
+        let svc = state.service();
+        if !can_write_template(&state, &user_info, req.team_id).await? {
+            return Err(NoPermission().into());
+        }
+        let id = req.id.filter(|&v| v != 0);
+        if let Some(id) = id {
+            let Some(old) = svc.job_template.get_template(id).await? else {
+                return_err!("cannot found job template");
+            };
+            if !can_write_template(&state, &user_info, old.team_id).await? {
+                return Err(NoPermission().into());
+            }
+        }
+
+        let args = req
+            .args
+            .map(|v| serde_json::to_value(&v))
+            .transpose()
+            .map_err(std_into_error)?;
+        let completed_callback = req
+            .completed_callback
+            .map(|v| serde_json::to_value(Into::<logic::types::CompletedCallbackOpts>::into(v)))
+            .transpose()
+            .map_err(std_into_error)?;
+
+        let ret = svc
+            .job_template
+            .save_template(
+                job_template::ActiveModel {
+                    id: id.map_or(NotSet, Set),
+                    name: Set(req.name),
+                    info: Set(req.info),
+                    team_id: Set(req.team_id),
+                    executor_id: Set(req.executor_id),
+                    work_dir: Set(req.work_dir),
+                    work_user: Set(req.work_user),
+                    timeout: Set(req.timeout),
+                    max_retry: Set(req.max_retry),
+                    max_parallel: Set(req.max_parallel),
+                    stop_signal: Set(Some(req.stop_signal)
+                        .filter(|v| !v.is_empty())
+                        .unwrap_or(types::default_stop_signal())),
+                    stop_grace_period: Set(req.stop_grace_period),
+                    args: Set(args),
+                    completed_callback: Set(completed_callback),
+                    created_user: id.map_or(Set(user_info.username.clone()), |_| NotSet),
+                    updated_user: Set(user_info.username.clone()),
+                    ..Default::default()
+                },
+                req.propagate,
+                &user_info.username,
+            )
+            .await?;
+        return_ok!(types::SaveJobTemplateResp {
+            id: ret.id,
+            version: ret.version,
+            updated: ret.updated,
+            outdated: ret.outdated,
+        })
+    }
+
+    #[oai(path = "/list", method = "get")]
+    pub async fn query_template(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&UserInfo>,
+        /// templates of the team, the shared ones when not given
+        Query(team_id): Query<Option<i64>>,
+        Query(name): Query<Option<String>>,
+        #[oai(
+            default = "crate::api::default_page_size",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page_size): Query<u64>,
+        #[oai(
+            default = "crate::api::default_page",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page): Query<u64>,
+    ) -> api_response!(types::QueryJobTemplateResp) {
+        let team_id = team_id.unwrap_or_default();
+        if !can_read_template(&state, &user_info, team_id).await? {
+            return Err(NoPermission().into());
+        }
+
+        let ret = state
+            .service()
+            .job_template
+            .query_template(
+                Some(team_id),
+                name.filter(|v| !v.is_empty()),
+                page - 1,
+                page_size,
+            )
+            .await?;
+        let list = ret
+            .0
+            .into_iter()
+            .map(|v| types::JobTemplateRecord {
+                id: v.id,
+                name: v.name,
+                info: v.info,
+                team_id: v.team_id,
+                version: v.version,
+                executor_id: v.executor_id,
+                work_dir: v.work_dir,
+                work_user: v.work_user,
+                timeout: v.timeout,
+                max_retry: v.max_retry,
+                max_parallel: v.max_parallel,
+                stop_signal: v.stop_signal,
+                stop_grace_period: v.stop_grace_period,
+                args: v.args,
+                completed_callback: v
+                    .completed_callback
+                    .map(serde_json::from_value::<logic::types::CompletedCallbackOpts>)
+                    .transpose()
+                    .unwrap_or_default()
+                    .map(CompletedCallbackOpts::from),
+                created_user: v.created_user,
+                updated_user: v.updated_user,
+                created_time: local_time!(v.created_time),
+                updated_time: local_time!(v.updated_time),
+            })
+            .collect();
+        return_ok!(types::QueryJobTemplateResp { total: ret.1, list })
+    }
+
+    /// Jobs derived from the template, with the version they are on and the
+    /// inherited fields they customized
+    #[oai(path = "/derived", method = "get")]
+    pub async fn derived_jobs(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&UserInfo>,
+        Query(id): Query<i64>,
+    ) -> api_response!(types::QueryDerivedJobResp) {
+        let svc = state.service();
+        let Some(template) = svc.job_template.get_template(id).await? else {
+            return_err!("cannot found job template");
+        };
+        if !can_read_template(&state, &user_info, template.team_id).await? {
+            return Err(NoPermission().into());
+        }
+
+        let list = svc
+            .job_template
+            .derived_jobs(&template)
+            .await?
+            .into_iter()
+            .map(|v| types::DerivedJobRecord {
+                eid: v.eid,
+                name: v.name,
+                template_version: v.template_version,
+                outdated: v.outdated,
+                customized: v.customized.into_iter().map(String::from).collect(),
+            })
+            .collect();
+        return_ok!(types::QueryDerivedJobResp {
+            version: template.version,
+            list,
+        })
+    }
+
+    /// Applies the template to its derived jobs the user may edit,
+    /// overwriting what they customized
+    #[oai(path = "/sync", method = "post")]
+    pub async fn sync_jobs(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&UserInfo>,
+        Json(req): Json<types::SyncJobTemplateReq>,
+    ) -> api_response!(types::SyncJobTemplateResp) {
+        let svc = state.service();
+        let Some(template) = svc.job_template.get_template(req.id).await? else {
+            return_err!("cannot found job template");
+        };
+        if !can_write_template(&state, &user_info, template.team_id).await? {
+            return Err(NoPermission().into());
+        }
+
+        // only jobs the user may edit are overwritten, all derived jobs means
+        // all of those the user may edit
+        let explicit = !req.eids.is_empty();
+        let eids = if !explicit {
+            svc.job_template
+                .derived_jobs(&template)
+                .await?
+                .into_iter()
+                .map(|v| v.eid)
+                .collect()
+        } else {
+            req.eids
+        };
+        let mut writable = vec![];
+        for eid in eids {
+            if svc
+                .job
+                .can_write_job(&user_info, None, Some(eid.clone()))
+                .await?
+            {
+                writable.push(eid);
+            } else if explicit {
+                return_err!(format!("no permission to edit job {eid}"));
+            }
+        }
+        if writable.is_empty() {
+            return_ok!(types::SyncJobTemplateResp { result: 0 })
+        }
+        let result = svc
+            .job_template
+            .sync_jobs(&template, writable, &user_info.username)
+            .await?;
+        return_ok!(types::SyncJobTemplateResp { result })
+    }
+
+    /// Deletes a template, its jobs keep what they inherited
+    #[oai(path = "/delete", method = "post")]
+    pub async fn delete_template(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&UserInfo>,
+        Json(req): Json<types::DeleteJobTemplateReq>,
+    ) -> api_response!(types::DeleteJobTemplateResp) {
+        let svc = state.service();
+        let Some(template) = svc.job_template.get_template(req.id).await? else {
+            return_err!("cannot found job template");
+        };
+        if !can_write_template(&state, &user_info, template.team_id).await? {
+            return Err(NoPermission().into());
+        }
+        let result = svc.job_template.delete_template(req.id).await?;
+        return_ok!(types::DeleteJobTemplateResp { result })
+    }
+}
@@ -27,7 +27,7 @@ pub mod types {
     #[derive(Object, Serialize, Deserialize)]
     pub struct SetRoleReq {
         pub user_id: String,
-        pub role_id: u64,
+        pub role_id: i64,
     }
 
     #[derive(Object, Serialize, Deserialize)]
@@ -40,7 +40,7 @@ pub mod types {
         pub introduction: String, // 简介
         pub phone: String,
         pub user_id: String,
-        pub role_id: Option<u64>,
+        pub role_id: Option<i64>,
     }
 
     #[derive(Object, Serialize, Deserialize)]
@@ -170,8 +170,8 @@ impl ManageApi {
 
         Query(ip): Query<Option<String>>,
         Query(instance_id): Query<Option<String>>,
-        Query(instance_group_id): Query<Option<u64>>,
-        Query(tag_id): Query<Option<Vec<u64>>>,
+        Query(instance_group_id): Query<Option<i64>>,
+        Query(tag_id): Query<Option<Vec<i64>>>,
         Query(status): Query<Option<u8>>,
 
         /// only for admin role
@@ -0,0 +1,158 @@
+use poem::{session::Session, web::Data};
+use poem_openapi::{param::Query, payload::Json, OpenApi};
+
+use crate::{
+    api::job::normalize_timer_expr,
+    api_response,
+    error::NoPermission,
+    logic::{
+        self,
+        manifest::{Manifest, ManifestFormat},
+    },
+    return_ok, AppState,
+};
+
+pub struct ManifestApi;
+
+pub fn default_format() -> String {
+    "yaml".to_string()
+}
+
+mod types {
+    use poem_openapi::Object;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct ExportManifestResp {
+        pub format: String,
+        pub content: String,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct ApplyManifestReq {
+        /// manifest as exported, the team is taken from it
+        pub content: String,
+        #[oai(
+            default = "crate::api::manifest::default_format",
+            validator(custom = "crate::api::OneOfValidator::new(vec![\"yaml\", \"toml\"])")
+        )]
+        pub format: String,
+        /// deletes the jobs, bundle scripts, timers and supervisors of the
+        /// team missing from the manifest
+        #[oai(default)]
+        pub prune: bool,
+        /// only reports the changes
+        #[oai(default)]
+        pub dry_run: bool,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct ManifestChangeRecord {
+        pub kind: String,
+        pub name: String,
+        /// create, update or delete
+        pub action: String,
+        /// fields that differ on an update
+        pub fields: Vec<String>,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct ApplyManifestResp {
+        pub dry_run: bool,
+        pub list: Vec<ManifestChangeRecord>,
+    }
+}
+
+#[OpenApi(prefix_path = "/manifest", tag = super::Tag::Manifest)]
+impl ManifestApi {
+    /// Jobs of a team with their bundle scripts, timers, supervisors and
+    /// executors as a manifest, jobs without a team when no team is given
+    #[oai(path = "/export", method = "get")]
+    pub async fn export_manifest(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&logic::types::UserInfo>,
+        #[oai(default)] Query(team_id): Query<i64>,
+        #[oai(
+            default = "default_format",
+            validator(custom = "crate::api::OneOfValidator::new(vec![\"yaml\", \"toml\"])")
+        )]
+        Query(format): Query<String>,
+    ) -> api_response!(types::ExportManifestResp) {
+        let svc = state.service();
+        let allowed = match team_id {
+            0 => state.can_manage_job(&user_info.user_id).await?,
+            v => {
+                svc.team
+                    .can_read_team(Some(v), user_info.user_id.clone())
+                    .await?
+            }
+        };
+        if !allowed {
+            return Err(NoPermission().into());
+        }
+
+        let manifest = svc.manifest.export(team_id).await?;
+        let content = manifest.render(ManifestFormat::try_from(format.as_str())?)?;
+        return_ok!(types::ExportManifestResp { format, content })
+    }
+
+    /// Creates, updates and with `prune` deletes resources so that the team
+    /// matches the manifest. Resources are matched by name, so applying the
+    /// same manifest again changes nothing
+    #[oai(path = "/apply", method = "post")]
+    pub async fn apply_manifest(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::ApplyManifestReq>,
+    ) -> api_response!(types::ApplyManifestResp) {
+        if state.is_change_forbid(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+        let svc = state.service();
+        let mut manifest =
+            Manifest::parse(&req.content, ManifestFormat::try_from(req.format.as_str())?)?;
+        for v in manifest.timers.iter_mut() {
+            v.timer_expr = normalize_timer_expr(v.timer_expr.take())?;
+        }
+
+        let can_manage_job = state.can_manage_job(&user_info.user_id).await?;
+        let allowed = match svc.manifest.resolve_team(&manifest).await? {
+            0 => can_manage_job,
+            v => {
+                svc.team
+                    .can_write_team(Some(v), user_info.user_id.clone())
+                    .await?
+            }
+        };
+        if !allowed {
+            return Err(NoPermission().into());
+        }
+
+        let changes = svc
+            .manifest
+            .apply(
+                &user_info,
+                &manifest,
+                req.prune,
+                can_manage_job,
+                req.dry_run,
+            )
+            .await?;
+        return_ok!(types::ApplyManifestResp {
+            dry_run: req.dry_run,
+            list: changes
+                .into_iter()
+                .map(|v| types::ManifestChangeRecord {
+                    kind: v.kind,
+                    name: v.name,
+                    action: v.action.to_string(),
+                    fields: v.fields,
+                })
+                .collect(),
+        })
+    }
+}
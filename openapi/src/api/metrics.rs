@@ -0,0 +1,29 @@
+use poem::{handler, web::Data, Response};
+use tracing::error;
+
+use crate::AppState;
+
+/// Prometheus metrics of the console, served outside `/api` so that scrapers
+/// need no session, only the metrics token when one is configured.
+#[handler]
+pub async fn metrics(state: Data<&AppState>) -> Response {
+    match state.service().instance.count_by_status().await {
+        Ok(counts) => {
+            let count = |status| {
+                counts
+                    .iter()
+                    .filter(|v| v.0 == status)
+                    .map(|v| v.1)
+                    .sum::<i64>()
+            };
+            service::metrics::INSTANCES
+                .with_label_values(&["online"])
+                .set(count(1));
+            service::metrics::INSTANCES
+                .with_label_values(&["offline"])
+                .set(count(0));
+        }
+        Err(e) => error!("failed count instances - {e}"),
+    }
+    automate::metrics::response()
+}
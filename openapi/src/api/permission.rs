@@ -0,0 +1,179 @@
+use poem::{session::Session, web::Data};
+use poem_openapi::{param::Query, payload::Json, OpenApi};
+
+use crate::{
+    api_response,
+    error::NoPermission,
+    logic::{
+        self,
+        permission::{GrantAction, GrantResource, Grantee},
+    },
+    return_ok, AppState,
+};
+
+pub struct PermissionApi;
+
+mod types {
+    use poem_openapi::Object;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct GrantReq {
+        /// job, timer, supervisor or instance_group
+        #[oai(validator(
+            custom = "crate::api::OneOfValidator::new(vec![\"job\", \"timer\", \"supervisor\", \"instance_group\"])"
+        ))]
+        pub resource_type: String,
+        pub resource_id: i64,
+        #[oai(validator(custom = "crate::api::OneOfValidator::new(vec![\"user\", \"team\"])"))]
+        pub grantee_type: String,
+        /// user id or team id
+        pub grantee_id: String,
+        /// view, edit, dispatch, kill or ssh
+        pub actions: Vec<String>,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct RevokeReq {
+        #[oai(validator(
+            custom = "crate::api::OneOfValidator::new(vec![\"job\", \"timer\", \"supervisor\", \"instance_group\"])"
+        ))]
+        pub resource_type: String,
+        pub resource_id: i64,
+        #[oai(validator(custom = "crate::api::OneOfValidator::new(vec![\"user\", \"team\"])"))]
+        pub grantee_type: String,
+        pub grantee_id: String,
+        /// revokes every action of the grantee when omitted
+        pub actions: Option<Vec<String>>,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct UpdateResult {
+        pub affected: u64,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct GrantRecord {
+        /// user, team or role
+        pub grantee_type: String,
+        pub grantee_id: String,
+        pub grantee_name: String,
+        pub actions: Vec<String>,
+        /// grant, owner, team or role, only grants can be revoked
+        pub source: String,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct QueryGrantResp {
+        pub list: Vec<GrantRecord>,
+    }
+}
+
+fn parse_actions(actions: Vec<String>) -> anyhow::Result<Vec<GrantAction>> {
+    actions
+        .iter()
+        .map(|v| GrantAction::try_from(v.as_str()))
+        .collect()
+}
+
+#[OpenApi(prefix_path = "/permission", tag = super::Tag::Permission)]
+impl PermissionApi {
+    #[oai(path = "/grant", method = "post")]
+    pub async fn grant(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::GrantReq>,
+    ) -> api_response!(types::UpdateResult) {
+        let svc = state.service();
+        let resource = GrantResource::try_from(req.resource_type.as_str())?;
+        if !svc
+            .permission
+            .can_grant(&user_info, resource, req.resource_id)
+            .await?
+        {
+            return Err(NoPermission().into());
+        }
+
+        let grantee = Grantee::new(&req.grantee_type, &req.grantee_id)?;
+        let affected = svc
+            .permission
+            .grant(
+                resource,
+                req.resource_id,
+                &grantee,
+                parse_actions(req.actions)?,
+                &user_info.username,
+            )
+            .await?;
+        return_ok!(types::UpdateResult { affected })
+    }
+
+    #[oai(path = "/revoke", method = "post")]
+    pub async fn revoke(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::RevokeReq>,
+    ) -> api_response!(types::UpdateResult) {
+        let svc = state.service();
+        let resource = GrantResource::try_from(req.resource_type.as_str())?;
+        if !svc
+            .permission
+            .can_grant(&user_info, resource, req.resource_id)
+            .await?
+        {
+            return Err(NoPermission().into());
+        }
+
+        let grantee = Grantee::new(&req.grantee_type, &req.grantee_id)?;
+        let affected = svc
+            .permission
+            .revoke(
+                resource,
+                req.resource_id,
+                &grantee,
+                parse_actions(req.actions.unwrap_or_default())?,
+            )
+            .await?;
+        return_ok!(types::UpdateResult { affected })
+    }
+
+    /// Who can do what on a resource
+    #[oai(path = "/list", method = "get")]
+    pub async fn query_grant(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&logic::types::UserInfo>,
+        Query(resource_type): Query<String>,
+        Query(resource_id): Query<i64>,
+    ) -> api_response!(types::QueryGrantResp) {
+        let svc = state.service();
+        let resource = GrantResource::try_from(resource_type.as_str())?;
+        if !svc
+            .permission
+            .can_grant(&user_info, resource, resource_id)
+            .await?
+        {
+            return Err(NoPermission().into());
+        }
+
+        let list = svc
+            .permission
+            .list_grants(resource, resource_id)
+            .await?
+            .into_iter()
+            .map(|v| types::GrantRecord {
+                grantee_type: v.grantee_type,
+                grantee_id: v.grantee_id,
+                grantee_name: v.grantee_name,
+                actions: v.actions,
+                source: v.source,
+            })
+            .collect();
+        return_ok!(types::QueryGrantResp { list })
+    }
+}
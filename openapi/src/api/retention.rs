@@ -0,0 +1,325 @@
+use poem::{session::Session, web::Data};
+use poem_openapi::{param::Query, payload::Json, OpenApi};
+use sea_orm::{ActiveValue::NotSet, Set};
+
+use crate::{
+    api_response, entity::retention_policy, error::NoPermission, local_time,
+    logic::types::UserInfo, return_err, return_ok, AppState,
+};
+
+pub struct RetentionApi;
+
+mod types {
+    use poem_openapi::Object;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct SaveRetentionPolicyReq {
+        pub id: Option<i64>,
+        #[oai(validator(min_length = 1, max_length = 100))]
+        pub name: String,
+        #[oai(default)]
+        pub info: String,
+        /// team whose jobs the policy applies to, with neither a team nor a
+        /// job it is the default of all jobs
+        #[oai(default)]
+        pub team_id: i64,
+        /// job the policy applies to, takes precedence over the team
+        #[oai(default)]
+        pub eid: String,
+        /// deletes history older than this, 0 keeps it regardless of age
+        #[oai(default, validator(maximum(value = "36500")))]
+        pub max_age_days: i32,
+        /// keeps at most this many executions and schedules per job, 0 for no limit
+        #[oai(default, validator(maximum(value = "1000000")))]
+        pub max_count: i32,
+        /// keeps the latest failed executions even when they expired
+        #[oai(default, validator(maximum(value = "10000")))]
+        pub keep_failed: i32,
+        /// writes expired rows to compressed files before deleting them
+        #[oai(default)]
+        pub archive: bool,
+        #[oai(default = "crate::api::calendar::default_enabled")]
+        pub enabled: bool,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct SaveRetentionPolicyResp {
+        pub id: i64,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct RetentionPolicyRecord {
+        pub id: i64,
+        pub name: String,
+        pub info: String,
+        pub team_id: i64,
+        pub eid: String,
+        pub max_age_days: i32,
+        pub max_count: i32,
+        pub keep_failed: i32,
+        pub archive: bool,
+        pub enabled: bool,
+        pub created_user: String,
+        pub updated_user: String,
+        pub created_time: String,
+        pub updated_time: String,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct QueryRetentionPolicyResp {
+        pub total: u64,
+        pub list: Vec<RetentionPolicyRecord>,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct PruneLogRecord {
+        pub id: i64,
+        pub policy_id: i64,
+        pub eid: String,
+        pub exec_history: i64,
+        pub schedule_history: i64,
+        pub running_status: i64,
+        /// where the deleted rows were archived, empty without archiving
+        pub archive_file: String,
+        pub created_time: String,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct QueryPruneLogResp {
+        pub total: u64,
+        pub list: Vec<PruneLogRecord>,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct DeleteRetentionPolicyReq {
+        pub id: i64,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct DeleteRetentionPolicyResp {
+        pub result: u64,
+    }
+}
+
+/// Team 0 holds the default policy and the jobs without a team, only job
+/// managers may change it.
+async fn can_write_policy(
+    state: &AppState,
+    user_info: &UserInfo,
+    team_id: i64,
+) -> anyhow::Result<bool> {
+    match team_id {
+        0 => state.can_manage_job(&user_info.user_id).await,
+        v => {
+            state
+                .service()
+                .team
+                .can_write_team(Some(v), user_info.user_id.clone())
+                .await
+        }
+    }
+}
+
+#[OpenApi(prefix_path = "/retention", tag = super::Tag::Retention)]
+impl RetentionApi {
+    /// Saves how long the execution history, schedule history and finished
+    /// running status of jobs are kept, the leader console prunes them
+    /// periodically
+    #[oai(path = "/save", method = "post")]
+    pub async fn save_policy(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&UserInfo>,
+        Json(mut req): Json<types::SaveRetentionPolicyReq>,
+    ) -> api_response!(types::SaveRetentionPolicyResp) {
+        if req.max_age_days == 0 && req.max_count == 0 {
+            return_err!("a retention policy needs a max age or a max count");
+        }
+        let svc = state.service();
+        if !req.eid.is_empty() {
+            let Some(job) = svc.job.get_job_by_eid(&req.eid).await? else {
+                return_err!("cannot found job");
+            };
+            req.team_id = job.team_id;
+        }
+        if !can_write_policy(&state, &user_info, req.team_id).await? {
+            return Err(NoPermission().into());
+        }
+        if let Some(id) = req.id.filter(|&v| v != 0) {
+            let Some(old) = svc.retention.get_policy(id).await? else {
+                return_err!("cannot found retention policy");
+            };
+            if !can_write_policy(&state, &user_info, old.team_id).await? {
+                return Err(NoPermission().into());
+            }
+        }
+
+        let ret = svc
+            .retention
+            .save_policy(retention_policy::ActiveModel {
+                id: req.id.filter(|&v| v != 0).map_or(NotSet, Set),
+                name: Set(req.name),
+                info: Set(req.info),
+                team_id: Set(req.team_id),
+                eid: Set(req.eid),
+                max_age_days: Set(req.max_age_days),
+                max_count: Set(req.max_count),
+                keep_failed: Set(req.keep_failed),
+                archive: Set(req.archive),
+                enabled: Set(req.enabled),
+                created_user: req.id.map_or(Set(user_info.username.clone()), |_| NotSet),
+                updated_user: Set(user_info.username.clone()),
+                ..Default::default()
+            })
+            .await?;
+        return_ok!(types::SaveRetentionPolicyResp {
+            id: ret.id.as_ref().to_owned(),
+        })
+    }
+
+    #[oai(path = "/list", method = "get")]
+    pub async fn query_policy(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&UserInfo>,
+        /// policies of the team, all of them for job managers when not given
+        Query(team_id): Query<Option<i64>>,
+        Query(name): Query<Option<String>>,
+        #[oai(
+            default = "crate::api::default_page_size",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page_size): Query<u64>,
+        #[oai(
+            default = "crate::api::default_page",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page): Query<u64>,
+    ) -> api_response!(types::QueryRetentionPolicyResp) {
+        let svc = state.service();
+        let allowed = match team_id {
+            Some(v) if v != 0 => {
+                svc.team
+                    .can_read_team(Some(v), user_info.user_id.clone())
+                    .await?
+            }
+            _ => state.can_manage_job(&user_info.user_id).await?,
+        };
+        if !allowed {
+            return Err(NoPermission().into());
+        }
+
+        let ret = svc
+            .retention
+            .query_policy(team_id, name.filter(|v| !v.is_empty()), page - 1, page_size)
+            .await?;
+        let list = ret
+            .0
+            .into_iter()
+            .map(|v| types::RetentionPolicyRecord {
+                id: v.id,
+                name: v.name,
+                info: v.info,
+                team_id: v.team_id,
+                eid: v.eid,
+                max_age_days: v.max_age_days,
+                max_count: v.max_count,
+                keep_failed: v.keep_failed,
+                archive: v.archive,
+                enabled: v.enabled,
+                created_user: v.created_user,
+                updated_user: v.updated_user,
+                created_time: local_time!(v.created_time),
+                updated_time: local_time!(v.updated_time),
+            })
+            .collect();
+        return_ok!(types::QueryRetentionPolicyResp { total: ret.1, list })
+    }
+
+    #[oai(path = "/delete", method = "post")]
+    pub async fn delete_policy(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&UserInfo>,
+        Json(req): Json<types::DeleteRetentionPolicyReq>,
+    ) -> api_response!(types::DeleteRetentionPolicyResp) {
+        let svc = state.service();
+        let Some(policy) = svc.retention.get_policy(req.id).await? else {
+            return_err!("cannot found retention policy");
+        };
+        if !can_write_policy(&state, &user_info, policy.team_id).await? {
+            return Err(NoPermission().into());
+        }
+        let result = svc.retention.delete_policy(req.id).await?;
+        return_ok!(types::DeleteRetentionPolicyResp { result })
+    }
+
+    /// What each run pruned per job
+    #[oai(path = "/prune-log", method = "get")]
+    pub async fn query_prune_log(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&UserInfo>,
+        /// runs of the policy, all of them for job managers when not given
+        Query(policy_id): Query<Option<i64>>,
+        Query(eid): Query<Option<String>>,
+        #[oai(
+            default = "crate::api::default_page_size",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page_size): Query<u64>,
+        #[oai(
+            default = "crate::api::default_page",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page): Query<u64>,
+    ) -> api_response!(types::QueryPruneLogResp) {
+        let svc = state.service();
+        let team_id = match policy_id {
+            Some(id) => match svc.retention.get_policy(id).await? {
+                Some(v) => v.team_id,
+                None => return_err!("cannot found retention policy"),
+            },
+            None => 0,
+        };
+        let allowed = match team_id {
+            0 => state.can_manage_job(&user_info.user_id).await?,
+            v => {
+                svc.team
+                    .can_read_team(Some(v), user_info.user_id.clone())
+                    .await?
+            }
+        };
+        if !allowed {
+            return Err(NoPermission().into());
+        }
+
+        let ret = svc
+            .retention
+            .query_prune_log(
+                policy_id,
+                eid.filter(|v| !v.is_empty()),
+                page - 1,
+                page_size,
+            )
+            .await?;
+        let list = ret
+            .0
+            .into_iter()
+            .map(|v| types::PruneLogRecord {
+                id: v.id,
+                policy_id: v.policy_id,
+                eid: v.eid,
+                exec_history: v.exec_history,
+                schedule_history: v.schedule_history,
+                running_status: v.running_status,
+                archive_file: v.archive_file,
+                created_time: local_time!(v.created_time),
+            })
+            .collect();
+        return_ok!(types::QueryPruneLogResp { total: ret.1, list })
+    }
+}
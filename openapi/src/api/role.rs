@@ -20,7 +20,7 @@ mod types {
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct SaveRoleReq {
-        pub id: Option<u64>,
+        pub id: Option<i64>,
         pub name: String,
         pub info: String,
         pub user_ids: Option<Vec<String>>,
@@ -29,7 +29,7 @@ mod types {
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct SaveRoleResult {
-        pub role_id: u64,
+        pub role_id: i64,
     }
 
     #[derive(Object, Serialize, Default)]
@@ -40,7 +40,7 @@ mod types {
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct RoleRecord {
-        pub id: u64,
+        pub id: i64,
         pub name: String,
         pub info: String,
         pub user_total: i64,
@@ -51,31 +51,31 @@ mod types {
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct SetUserReq {
-        pub role_id: u64,
+        pub role_id: i64,
         pub user_ids: Option<Vec<String>>,
     }
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct DeleteRoleReq {
-        pub role_id: u64,
+        pub role_id: i64,
     }
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct BindInsanceReq {
-        pub role_id: u64,
-        pub instance_group_ids: Option<Vec<u64>>,
+        pub role_id: i64,
+        pub instance_group_ids: Option<Vec<i64>>,
         pub instance_ids: Option<Vec<String>>,
     }
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct BindInsanceResp {
-        pub result: u64,
+        pub result: i64,
     }
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct UnbindInsanceReq {
-        pub role_id: u64,
-        pub instance_group_ids: Option<Vec<u64>>,
+        pub role_id: i64,
+        pub instance_group_ids: Option<Vec<i64>>,
         pub instance_ids: Option<Vec<String>>,
     }
 
@@ -172,8 +172,8 @@ impl RoleApi {
         state: Data<&AppState>,
         _session: &Session,
 
-        Query(id): Query<Option<u64>>,
-        Query(default_id): Query<Option<u64>>,
+        Query(id): Query<Option<i64>>,
+        Query(default_id): Query<Option<i64>>,
         Query(name): Query<Option<String>>,
         #[oai(
             default = "crate::api::default_page_size",
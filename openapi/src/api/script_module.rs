@@ -0,0 +1,320 @@
+use poem::{session::Session, web::Data};
+use poem_openapi::{param::Query, payload::Json, OpenApi};
+use sea_orm::{ActiveValue::NotSet, Set};
+
+use crate::{
+    api_response, entity::script_module, error::NoPermission, local_time, logic::types::UserInfo,
+    return_err, return_ok, AppState,
+};
+
+pub struct ScriptModuleApi;
+
+mod types {
+    use poem_openapi::Object;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct SaveScriptModuleReq {
+        pub id: Option<i64>,
+        /// included by job code as "#@include <name>" or "#@include <name>@<version>"
+        #[oai(validator(min_length = 1, max_length = 100))]
+        pub name: String,
+        #[oai(default)]
+        pub info: String,
+        /// team the module belongs to, 0 shares it with all teams
+        #[oai(default)]
+        pub team_id: i64,
+        pub code: String,
+        /// what changed in this version
+        #[oai(default)]
+        pub changelog: String,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct SaveScriptModuleResp {
+        pub id: i64,
+        pub latest_version: i32,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct ScriptModuleRecord {
+        pub id: i64,
+        pub name: String,
+        pub info: String,
+        pub team_id: i64,
+        pub latest_version: i32,
+        pub created_user: String,
+        pub updated_user: String,
+        pub created_time: String,
+        pub updated_time: String,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct QueryScriptModuleResp {
+        pub total: u64,
+        pub list: Vec<ScriptModuleRecord>,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct ScriptModuleVersionRecord {
+        pub id: i64,
+        pub module_id: i64,
+        pub version: i32,
+        pub code: String,
+        pub changelog: String,
+        pub created_user: String,
+        pub created_time: String,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct QueryScriptModuleVersionResp {
+        pub total: u64,
+        pub list: Vec<ScriptModuleVersionRecord>,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct DeleteScriptModuleReq {
+        pub id: i64,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct DeleteScriptModuleResp {
+        pub result: u64,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct ExpandScriptReq {
+        /// team whose modules are looked up before the shared ones
+        #[oai(default)]
+        pub team_id: i64,
+        pub code: String,
+    }
+
+    #[derive(Object, Serialize, Deserialize)]
+    pub struct ExpandScriptResp {
+        pub code: String,
+    }
+}
+
+/// Modules of team 0 are shared by all teams, only job managers may change
+/// them.
+async fn can_write_module(
+    state: &AppState,
+    user_info: &UserInfo,
+    team_id: i64,
+) -> anyhow::Result<bool> {
+    match team_id {
+        0 => state.can_manage_job(&user_info.user_id).await,
+        v => {
+            state
+                .service()
+                .team
+                .can_write_team(Some(v), user_info.user_id.clone())
+                .await
+        }
+    }
+}
+
+async fn can_read_module(
+    state: &AppState,
+    user_info: &UserInfo,
+    team_id: i64,
+) -> anyhow::Result<bool> {
+    match team_id {
+        0 => Ok(true),
+        v => {
+            state
+                .service()
+                .team
+                .can_read_team(Some(v), user_info.user_id.clone())
+                .await
+        }
+    }
+}
+
+#[OpenApi(prefix_path = "/script-module", tag = super::Tag::ScriptModule)]
+impl ScriptModuleApi {
+    /// Saves a script module, a changed code becomes a new version. Jobs
+    /// include the latest version unless they pin one, and the includes are
+    /// expanded by the console when the job is dispatched
+    #[oai(path = "/save", method = "post")]
+    pub async fn save_module(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&UserInfo>,
+        Json(req): Json<types::SaveScriptModuleReq>,
+    ) -> api_response!(types::SaveScriptModuleResp) {
+        let svc = state.service();
+        if !can_write_module(&state, &user_info, req.team_id).await? {
+            return Err(NoPermission().into());
+        }
+        let id = req.id.filter(|&v| v != 0);
+        if let Some(id) = id {
+            let Some(old) = svc.script_module.get_module(id).await? else {
+                return_err!("cannot found script module");
+            };
+            if !can_write_module(&state, &user_info, old.team_id).await? {
+                return Err(NoPermission().into());
+            }
+        }
+
+        let (id, latest_version) = svc
+            .script_module
+            .save_module(
+                script_module::ActiveModel {
+                    id: id.map_or(NotSet, Set),
+                    team_id: Set(req.team_id),
+                    name: Set(req.name),
+                    info: Set(req.info),
+                    created_user: id.map_or(Set(user_info.username.clone()), |_| NotSet),
+                    updated_user: Set(user_info.username.clone()),
+                    ..Default::default()
+                },
+                req.code,
+                req.changelog,
+            )
+            .await?;
+        return_ok!(types::SaveScriptModuleResp { id, latest_version })
+    }
+
+    #[oai(path = "/list", method = "get")]
+    pub async fn query_module(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&UserInfo>,
+        /// modules of the team, the shared ones when not given
+        Query(team_id): Query<Option<i64>>,
+        Query(name): Query<Option<String>>,
+        #[oai(
+            default = "crate::api::default_page_size",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page_size): Query<u64>,
+        #[oai(
+            default = "crate::api::default_page",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page): Query<u64>,
+    ) -> api_response!(types::QueryScriptModuleResp) {
+        let team_id = team_id.unwrap_or_default();
+        if !can_read_module(&state, &user_info, team_id).await? {
+            return Err(NoPermission().into());
+        }
+
+        let ret = state
+            .service()
+            .script_module
+            .query_module(
+                Some(team_id),
+                name.filter(|v| !v.is_empty()),
+                page - 1,
+                page_size,
+            )
+            .await?;
+        let list = ret
+            .0
+            .into_iter()
+            .map(|v| types::ScriptModuleRecord {
+                id: v.id,
+                name: v.name,
+                info: v.info,
+                team_id: v.team_id,
+                latest_version: v.latest_version,
+                created_user: v.created_user,
+                updated_user: v.updated_user,
+                created_time: local_time!(v.created_time),
+                updated_time: local_time!(v.updated_time),
+            })
+            .collect();
+        return_ok!(types::QueryScriptModuleResp { total: ret.1, list })
+    }
+
+    /// Versions of a module with their code, the latest first
+    #[oai(path = "/versions", method = "get")]
+    pub async fn query_version(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&UserInfo>,
+        Query(id): Query<i64>,
+        #[oai(
+            default = "crate::api::default_page_size",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page_size): Query<u64>,
+        #[oai(
+            default = "crate::api::default_page",
+            validator(maximum(value = "10000"))
+        )]
+        Query(page): Query<u64>,
+    ) -> api_response!(types::QueryScriptModuleVersionResp) {
+        let svc = state.service();
+        let Some(module) = svc.script_module.get_module(id).await? else {
+            return_err!("cannot found script module");
+        };
+        if !can_read_module(&state, &user_info, module.team_id).await? {
+            return Err(NoPermission().into());
+        }
+
+        let ret = svc
+            .script_module
+            .query_version(id, page - 1, page_size)
+            .await?;
+        let list = ret
+            .0
+            .into_iter()
+            .map(|v| types::ScriptModuleVersionRecord {
+                id: v.id,
+                module_id: v.module_id,
+                version: v.version,
+                code: v.code,
+                changelog: v.changelog,
+                created_user: v.created_user,
+                created_time: local_time!(v.created_time),
+            })
+            .collect();
+        return_ok!(types::QueryScriptModuleVersionResp { total: ret.1, list })
+    }
+
+    /// Deletes a module with all its versions, refused while jobs, bundle
+    /// scripts or other modules still include it
+    #[oai(path = "/delete", method = "post")]
+    pub async fn delete_module(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&UserInfo>,
+        Json(req): Json<types::DeleteScriptModuleReq>,
+    ) -> api_response!(types::DeleteScriptModuleResp) {
+        let svc = state.service();
+        let Some(module) = svc.script_module.get_module(req.id).await? else {
+            return_err!("cannot found script module");
+        };
+        if !can_write_module(&state, &user_info, module.team_id).await? {
+            return Err(NoPermission().into());
+        }
+        let result = svc.script_module.delete_module(&module).await?;
+        return_ok!(types::DeleteScriptModuleResp { result })
+    }
+
+    /// The code as it would be dispatched, with the includes expanded
+    #[oai(path = "/expand", method = "post")]
+    pub async fn expand(
+        &self,
+        state: Data<&AppState>,
+        _session: &Session,
+        user_info: Data<&UserInfo>,
+        Json(req): Json<types::ExpandScriptReq>,
+    ) -> api_response!(types::ExpandScriptResp) {
+        if !can_read_module(&state, &user_info, req.team_id).await? {
+            return Err(NoPermission().into());
+        }
+        let code = state
+            .service()
+            .script_module
+            .expand(req.team_id, &req.code)
+            .await?;
+        return_ok!(types::ExpandScriptResp { code })
+    }
+}
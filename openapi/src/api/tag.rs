@@ -19,7 +19,7 @@ pub mod types {
 
     #[derive(Object, Deserialize, Serialize)]
     pub struct BindTagReq {
-        pub resource_id: u64,
+        pub resource_id: i64,
         pub resource_type: ResourceType,
         pub tag_name: String,
     }
@@ -35,14 +35,14 @@ pub mod types {
 
     #[derive(Object, Deserialize, Serialize)]
     pub struct BindTagResp {
-        pub result: u64,
+        pub result: i64,
     }
 
     #[derive(Object, Deserialize, Serialize)]
     pub struct UnbindTagReq {
-        pub resource_id: u64,
+        pub resource_id: i64,
         pub resource_type: ResourceType,
-        pub tag_id: u64,
+        pub tag_id: i64,
     }
 
     #[derive(Object, Deserialize, Serialize)]
@@ -57,7 +57,7 @@ pub mod types {
 
     #[derive(Object, Deserialize, Serialize)]
     pub struct TagCount {
-        pub tag_id: u64,
+        pub tag_id: i64,
         pub tag_name: String,
         pub total: i64,
     }
@@ -117,7 +117,7 @@ impl TagApi {
         user_info: Data<&logic::types::UserInfo>,
         state: Data<&AppState>,
         Query(resource_type): Query<types::ResourceType>,
-        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<u64>>,
+        #[oai(name = "X-Team-Id")] Header(team_id): Header<Option<i64>>,
     ) -> api_response!(types::CountTagResp) {
         let svc = state.service();
         let resource_type = match resource_type {
@@ -17,14 +17,14 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct SaveTeamReq {
-        pub id: Option<u64>,
+        pub id: Option<i64>,
         pub name: String,
         pub info: Option<String>,
     }
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct SaveTeamResult {
-        pub affected: u64,
+        pub affected: i64,
     }
 
     #[derive(Object, Serialize, Default)]
@@ -35,7 +35,7 @@ mod types {
 
     #[derive(Object, Serialize, Default)]
     pub struct GetTeamDetailResp {
-        pub id: u64,
+        pub id: i64,
         pub name: String,
         pub info: String,
         pub created_user: String,
@@ -52,7 +52,7 @@ mod types {
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct TeamRecord {
-        pub id: u64,
+        pub id: i64,
         pub name: String,
         pub info: String,
         pub user_total: i64,
@@ -64,7 +64,7 @@ mod types {
     #[derive(Object, Serialize, Deserialize)]
     pub struct AddTeamMemberReq {
         #[oai(validator(minimum(value = "1")))]
-        pub team_id: u64,
+        pub team_id: i64,
         pub members: Vec<AddTeamMember>,
     }
 
@@ -78,7 +78,7 @@ mod types {
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct RemoveTeamMemberReq {
-        pub team_id: u64,
+        pub team_id: i64,
         #[oai(validator(min_length = 1))]
         pub user_ids: Vec<String>,
     }
@@ -127,8 +127,8 @@ impl TeamApi {
         &self,
         state: Data<&AppState>,
         _session: &Session,
-        Query(id): Query<Option<u64>>,
-        Query(default_id): Query<Option<u64>>,
+        Query(id): Query<Option<i64>>,
+        Query(default_id): Query<Option<i64>>,
         /// Team adminitor can query all team
         Query(username): Query<Option<String>>,
         Query(name): Query<Option<String>>,
@@ -181,7 +181,7 @@ impl TeamApi {
         &self,
         state: Data<&AppState>,
         _session: &Session,
-        Query(id): Query<u64>,
+        Query(id): Query<i64>,
         user_info: Data<&logic::types::UserInfo>,
     ) -> api_response!(types::GetTeamDetailResp) {
         let svc = state.service();
@@ -1,22 +1,33 @@
 use std::sync::Arc;
 
+use crate::logic::mfa::MfaLogic;
 use crate::logic::ssh::{ConnectParams, Session};
+use crate::logic::terminal::{Recorder, StartSessionParams};
 use crate::state::AppState;
-use crate::{logic, return_err_to_wsconn};
+use crate::{api_response, default_local_time, local_time, logic, return_err_to_wsconn, return_ok};
 
 use automate::Logic;
 use futures::{SinkExt, StreamExt};
 use poem::http::HeaderMap;
 use poem::session::Session as WebSession;
-use poem::web::websocket::WebSocket;
+use poem::web::websocket::{Message, WebSocket};
 use poem::web::{Data, Path, Query};
 use poem::{handler, FromRequest, IntoResponse, Request};
+use poem_openapi::param;
+use poem_openapi::payload::{Attachment, AttachmentType, PlainText};
+use poem_openapi::OpenApi;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::RwLock;
 use tokio_tungstenite::connect_async;
+use url::form_urlencoded;
 
 use tracing::{debug, error};
 
 pub mod types {
+    use poem_openapi::{
+        payload::{Attachment, PlainText},
+        ApiResponse, Object,
+    };
     use serde::{Deserialize, Serialize};
     use serde_repr::*;
 
@@ -44,41 +55,171 @@ pub mod types {
         pub cols: u32,
         pub rows: u32,
     }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct QueryTerminalSessionResp {
+        pub total: u64,
+        pub list: Vec<TerminalSessionRecord>,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct TerminalSessionRecord {
+        pub id: i64,
+        pub session_id: String,
+        pub instance_id: String,
+        pub ip: String,
+        pub sys_user: String,
+        pub user_id: String,
+        pub username: String,
+        pub cols: i32,
+        pub rows: i32,
+        /// whether the session is still running and can be watched
+        pub is_live: bool,
+        pub start_time: String,
+        pub end_time: String,
+    }
+
+    #[derive(Debug, ApiResponse)]
+    pub enum GetRecordResponse {
+        /// asciicast v2 file
+        #[oai(status = 200)]
+        Ok(Attachment<Vec<u8>>),
+        #[oai(status = 403)]
+        NotAllow,
+        #[oai(status = 404)]
+        NotFound,
+        #[oai(status = 500)]
+        InternalError(PlainText<String>),
+    }
+}
+
+macro_rules! unwrap_or_response {
+    ($ret:expr) => {
+        match $ret {
+            Ok(v) => v,
+            Err(e) => return types::GetRecordResponse::InternalError(PlainText(e.to_string())),
+        }
+    };
+}
+
+pub struct TerminalApi;
+
+#[OpenApi(prefix_path = "/terminal", tag = super::Tag::Terminal)]
+impl TerminalApi {
+    #[oai(path = "/session/list", method = "get")]
+    pub async fn query_session(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+        param::Query(instance_id): param::Query<Option<String>>,
+        param::Query(user_id): param::Query<Option<String>>,
+        #[oai(
+            default = "crate::api::default_page_size",
+            validator(maximum(value = "10000"))
+        )]
+        param::Query(page_size): param::Query<u64>,
+        #[oai(
+            default = "crate::api::default_page",
+            validator(maximum(value = "10000"))
+        )]
+        param::Query(page): param::Query<u64>,
+    ) -> api_response!(types::QueryTerminalSessionResp) {
+        let svc = state.service();
+        // everyone but instance managers only sees their own sessions
+        let user_id = if state.can_manage_instance(&user_info.user_id).await? {
+            user_id.filter(|v| !v.is_empty())
+        } else {
+            Some(user_info.user_id.clone())
+        };
+
+        let (list, total) = svc
+            .terminal
+            .query_session(
+                instance_id.filter(|v| !v.is_empty()),
+                user_id,
+                page - 1,
+                page_size,
+            )
+            .await?;
+        let live = state.live_terminals.read().await;
+
+        let list = list
+            .into_iter()
+            .map(|v| types::TerminalSessionRecord {
+                id: v.id,
+                is_live: live.contains_key(&v.session_id),
+                session_id: v.session_id,
+                instance_id: v.instance_id,
+                ip: v.ip,
+                sys_user: v.sys_user,
+                user_id: v.user_id,
+                username: v.username,
+                cols: v.cols,
+                rows: v.rows,
+                start_time: local_time!(v.start_time),
+                end_time: default_local_time!(v.end_time),
+            })
+            .collect();
+
+        return_ok!(types::QueryTerminalSessionResp { total, list })
+    }
+
+    #[oai(path = "/session/record", method = "get")]
+    pub async fn get_record(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+        param::Query(session_id): param::Query<String>,
+    ) -> types::GetRecordResponse {
+        let svc = state.service();
+        let Some(session) = unwrap_or_response!(svc.terminal.get_session(&session_id).await) else {
+            return types::GetRecordResponse::NotFound;
+        };
+
+        if session.user_id != user_info.user_id
+            && !unwrap_or_response!(state.can_manage_instance(&user_info.user_id).await)
+        {
+            return types::GetRecordResponse::NotAllow;
+        }
+
+        let data = unwrap_or_response!(svc.terminal.read_record(&session).await);
+        let attachment = Attachment::new(data)
+            .attachment_type(AttachmentType::Inline)
+            .filename(format!("{session_id}.cast"));
+        types::GetRecordResponse::Ok(attachment)
+    }
 }
 
 #[handler]
 pub async fn webssh(
     Path(instance_id): Path<String>,
     state: Data<&AppState>,
-    _session: &WebSession,
+    session: &WebSession,
     user_info: Data<&logic::types::UserInfo>,
     Query(types::WebSshQuery { rows, cols }): Query<types::WebSshQuery>,
     ws: WebSocket,
 ) -> impl IntoResponse {
     let state_clone = state.clone();
-    let user_id = user_info.user_id.clone();
+    let user_info = user_info.clone();
+    let mfa_verified_time = session.get::<i64>(MfaLogic::VERIFIED_SESS_KEY);
 
     ws.on_upgrade(move |socket| async move {
-        let (mut sink, mut stream) = socket.split();
+        let (mut sink, stream) = socket.split();
 
         let svc = state_clone.service();
 
-        let can_manage_instance = match state_clone.can_manage_instance(&user_id).await {
-            Ok(v) => v,
-            Err(e) => {
-                return_err_to_wsconn!(sink, format!("Notice: failed to valid permissions, {e}"));
-            }
-        };
+        if let Err(e) = svc
+            .mfa
+            .check_step_up(&user_info.user_id, mfa_verified_time)
+            .await
+        {
+            return_err_to_wsconn!(sink, format!("Notice: {e}"));
+        }
 
-        let instance_record = if can_manage_instance {
-            svc.instance
-                .get_one_admin_server(None, None, Some(instance_id))
-                .await
-        } else {
-            svc.instance
-                .get_one_user_server(None, None, Some(instance_id), user_id.clone())
-                .await
-        };
+        let instance_record = svc
+            .instance
+            .get_one_user_server_with_permission(state_clone.clone(), &user_info, instance_id)
+            .await;
 
         let instance_record = match instance_record {
             Ok(Some(v)) => v,
@@ -90,20 +231,21 @@ pub async fn webssh(
             }
         };
 
-        let password = match state_clone.decrypt(instance_record.password.unwrap_or_default()) {
+        let login = match svc.credential.get_ssh_login(&instance_record).await {
             Ok(v) => v,
             Err(e) => {
-                return_err_to_wsconn!(
-                    sink,
-                    format!("Notice: failed decrypt instance password, {e}")
-                );
+                return_err_to_wsconn!(sink, format!("Notice: failed get instance credential, {e}"));
             }
         };
 
-        let mut ssh = match Session::connect(ConnectParams {
-            user: instance_record.sys_user.unwrap_or_default(),
-            password,
-            addrs: (instance_record.ip, instance_record.ssh_port.unwrap_or(22)),
+        let sys_user = login.user;
+        let ssh = match Session::connect(ConnectParams {
+            user: sys_user.clone(),
+            auth: login.auth,
+            addrs: (
+                instance_record.ip.clone(),
+                instance_record.ssh_port.unwrap_or(22) as u16,
+            ),
         })
         .await
         {
@@ -116,7 +258,35 @@ pub async fn webssh(
             }
         };
 
-        let code = match ssh.call("bash", cols, rows, &mut sink, stream).await {
+        let recorder = match svc
+            .terminal
+            .start_session(
+                &user_info,
+                StartSessionParams {
+                    instance_id: instance_record.instance_id,
+                    ip: instance_record.ip,
+                    sys_user,
+                    cols,
+                    rows,
+                },
+            )
+            .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                return_err_to_wsconn!(sink, format!("Notice: failed start recording, {e}"));
+            }
+        };
+
+        let ret = ssh
+            .call("bash", cols, rows, &mut sink, stream, &recorder)
+            .await;
+
+        if let Err(e) = svc.terminal.finish_session(&recorder).await {
+            error!("failed finish terminal session - {e}");
+        }
+
+        let code = match ret {
             Ok(v) => v,
             Err(e) => {
                 return_err_to_wsconn!(sink, format!("Notice: connection closed, {e}"));
@@ -137,11 +307,13 @@ pub async fn proxy_webssh(
     headers: &HeaderMap,
     state: Data<&AppState>,
     Path(instance_id): Path<String>,
+    session: &WebSession,
     user_info: Data<&logic::types::UserInfo>,
     Query(types::WebSshQuery { rows, cols }): Query<types::WebSshQuery>,
 ) -> impl IntoResponse {
     let state_clone = state.clone();
-    let user_id = user_info.user_id.clone();
+    let user_info = user_info.clone();
+    let mfa_verified_time = session.get::<i64>(MfaLogic::VERIFIED_SESS_KEY);
 
     let ws = WebSocket::from_request_without_body(req)
         .await
@@ -155,25 +327,18 @@ pub async fn proxy_webssh(
 
         let svc = state_clone.service();
 
-        let can_manage_instance = match state_clone.can_manage_instance(&user_id).await {
-            Ok(v) => v,
-            Err(e) => {
-                return_err_to_wsconn!(
-                    clientsink,
-                    format!("Notice: failed to valid permissions, {e}")
-                );
-            }
-        };
+        if let Err(e) = svc
+            .mfa
+            .check_step_up(&user_info.user_id, mfa_verified_time)
+            .await
+        {
+            return_err_to_wsconn!(clientsink, format!("Notice: {e}"));
+        }
 
-        let instance_record = if can_manage_instance {
-            svc.instance
-                .get_one_admin_server(None, None, Some(instance_id))
-                .await
-        } else {
-            svc.instance
-                .get_one_user_server(None, None, Some(instance_id), user_id.clone())
-                .await
-        };
+        let instance_record = svc
+            .instance
+            .get_one_user_server_with_permission(state_clone.clone(), &user_info, instance_id)
+            .await;
 
         let instance_record = match instance_record {
             Ok(Some(v)) => v,
@@ -198,43 +363,43 @@ pub async fn proxy_webssh(
             }
         };
 
-        let Some(password_raw) = instance_record.password else {
-            return_err_to_wsconn!(
-                clientsink,
-                format!("Notice: please set the instance password first")
-            );
-        };
-
-        let password = match state_clone.decrypt(password_raw) {
+        let login = match svc.credential.get_ssh_login(&instance_record).await {
             Ok(v) => v,
             Err(e) => {
                 return_err_to_wsconn!(
                     clientsink,
-                    format!("Notice: failed decrypt instance password, {e}")
+                    format!("Notice: please set the instance credential first, {e}")
                 );
             }
         };
 
-        let Some(user) = instance_record.sys_user  else {
-            return_err_to_wsconn!(clientsink, "Notice: please set the system user first");
-        };
-
-        let Some(port) =  instance_record.ssh_port else {
+        let Some(port) = instance_record.ssh_port else {
             return_err_to_wsconn!(clientsink, "Notice: please set the ssh port first");
         };
 
-        let uri = format!(
-            "ws://{}/ssh/tunnel?cols={}&rows={}&user={}&password={}&ip={}&port={}&namespace={}&mac_addr={}",
-            pair.1.comet_addr,
-            cols,
-            rows,
-            user,
-            password,
-            instance_record.ip,
-            port,
-            instance_record.namespace,
-            instance_record.mac_addr,
-        );
+        let user = login.user;
+        let (password, private_key, passphrase) = login.auth.into_parts();
+        // the serializer is not Send, keep it out of the await points below
+        let uri = {
+            let mut query = form_urlencoded::Serializer::new(String::new());
+            query
+                .append_pair("cols", &cols.to_string())
+                .append_pair("rows", &rows.to_string())
+                .append_pair("user", &user)
+                .append_pair("password", &password)
+                .append_pair("ip", &instance_record.ip)
+                .append_pair("port", &port.to_string())
+                .append_pair("namespace", &instance_record.namespace)
+                .append_pair("mac_addr", &instance_record.mac_addr);
+            if let Some(ref private_key) = private_key {
+                query.append_pair("private_key", private_key);
+            }
+            if let Some(ref passphrase) = passphrase {
+                query.append_pair("passphrase", passphrase);
+            }
+
+            format!("ws://{}/ssh/tunnel?{}", pair.1.comet_addr, query.finish())
+        };
 
         let mut ws_request = http::Request::builder()
             .header(
@@ -257,9 +422,30 @@ pub async fn proxy_webssh(
                 );
             }
         };
+        let recorder = match svc
+            .terminal
+            .start_session(
+                &user_info,
+                StartSessionParams {
+                    instance_id: instance_record.instance_id,
+                    ip: instance_record.ip,
+                    sys_user: user,
+                    cols,
+                    rows,
+                },
+            )
+            .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                return_err_to_wsconn!(clientsink, format!("Notice: failed start recording, {e}"));
+            }
+        };
+
         let (mut serversink, mut serverstream) = serversocket.split();
         let client_live = Arc::new(RwLock::new(true));
         let server_live = client_live.clone();
+        let client_recorder = recorder.clone();
 
         // Relay client messages to the server we are proxying
         tokio::spawn(async move {
@@ -269,6 +455,9 @@ pub async fn proxy_webssh(
                         if let poem::web::websocket::Message::Close(_) = msg {
                             break;
                         }
+                        if let poem::web::websocket::Message::Text(ref text) = msg {
+                            record_input(&client_recorder, text).await;
+                        }
                         if let Err(_) = serversink.send(msg.into()).await {
                             break;
                         }
@@ -290,6 +479,9 @@ pub async fn proxy_webssh(
             while let Some(ret) = serverstream.next().await {
                 match ret {
                     Ok(msg) => {
+                        if let tokio_tungstenite::tungstenite::Message::Text(ref text) = msg {
+                            recorder.output(text).await;
+                        }
                         if let Err(_) = clientsink.send(msg.into()).await {
                             break;
                         };
@@ -303,6 +495,96 @@ pub async fn proxy_webssh(
             }
             *server_live.write().await = false;
             let _ = clientsink.close().await;
+
+            if let Err(e) = state_clone
+                .service()
+                .terminal
+                .finish_session(&recorder)
+                .await
+            {
+                error!("failed finish terminal session - {e}");
+            }
         });
     })
 }
+
+async fn record_input(recorder: &Recorder, text: &str) {
+    let Ok(msg) = serde_json::from_str::<types::Msg>(text) else {
+        return;
+    };
+    match msg.r#type {
+        types::MsgType::Data => recorder.input(&msg.msg).await,
+        types::MsgType::Resize => recorder.resize(msg.cols, msg.rows).await,
+        types::MsgType::Ping => {}
+    }
+}
+
+/// Streams a running web ssh session to an instance manager, read only.
+/// The first message is the asciicast v2 header, every following message
+/// is one asciicast event.
+#[handler]
+pub async fn watch_webssh(
+    Path(session_id): Path<String>,
+    state: Data<&AppState>,
+    user_info: Data<&logic::types::UserInfo>,
+    ws: WebSocket,
+) -> impl IntoResponse {
+    let state_clone = state.clone();
+    let user_id = user_info.user_id.clone();
+
+    ws.on_upgrade(move |socket| async move {
+        let (mut sink, mut stream) = socket.split();
+
+        match state_clone.can_manage_instance(&user_id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return_err_to_wsconn!(sink, "Notice: no permission to watch terminal sessions");
+            }
+            Err(e) => {
+                return_err_to_wsconn!(sink, format!("Notice: failed to valid permissions, {e}"));
+            }
+        }
+
+        let Some(recorder) = state_clone
+            .service()
+            .terminal
+            .get_live_session(&session_id)
+            .await
+        else {
+            return_err_to_wsconn!(sink, "Notice: session is not running");
+        };
+
+        let (header, mut rx) = match recorder.watch() {
+            Ok(v) => v,
+            Err(e) => {
+                return_err_to_wsconn!(sink, format!("Notice: failed watch session, {e}"));
+            }
+        };
+        // the receiver is closed once the session drops its recorder
+        drop(recorder);
+
+        if sink.send(Message::Text(header)).await.is_err() {
+            return;
+        }
+
+        loop {
+            tokio::select! {
+                ret = rx.recv() => match ret {
+                    Ok(event) => {
+                        if sink.send(Message::Text(event)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(n)) => debug!("terminal watcher lagged {n} events"),
+                    Err(RecvError::Closed) => break,
+                },
+                // anything the watcher types is dropped
+                msg = stream.next() => match msg {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    _ => {}
+                },
+            }
+        }
+        let _ = sink.close().await;
+    })
+}
@@ -2,30 +2,230 @@ use crate::{
     entity::user,
     error::NoPermission,
     local_time,
-    logic::{self, user::UserLogic},
+    logic::{
+        self,
+        auth::{AuthLogic, OidcState, PROVIDER_LDAP},
+        mfa::{MfaLogic, MfaPending},
+        session::SessionLogic,
+        user::UserLogic,
+    },
     response::ApiStdResponse,
-    return_ok, AppState,
+    return_err, return_ok,
+    utils::client_ip,
+    AppState,
 };
 
 pub struct UserApi;
 
-use poem::{session::Session, web::Data, Result};
+/// Logs the user in, or parks the login until the second factor is given.
+/// Returns the pending mfa step, empty when the session is ready.
+async fn start_login(
+    session: &Session,
+    state: &AppState,
+    req: &Request,
+    login_user: logic::types::UserRecord,
+) -> Result<String> {
+    session.remove(MfaLogic::VERIFIED_SESS_KEY);
+    let svc = state.service();
+    let step = if svc.mfa.is_enabled(&login_user.user_id).await? {
+        "verify"
+    } else if svc.mfa.is_required(&login_user.user_id).await? {
+        "enroll"
+    } else {
+        ""
+    };
+    if step.is_empty() {
+        set_user_session(session, state, req, login_user).await?;
+        return Ok(String::new());
+    }
+
+    session.remove(UserLogic::SESS_KEY);
+    session.set(
+        MfaLogic::PENDING_SESS_KEY,
+        MfaPending {
+            user_id: login_user.user_id,
+            username: login_user.username,
+            expired_time: chrono::Utc::now().timestamp() + MfaLogic::PENDING_SECS,
+        },
+    );
+    Ok(step.to_string())
+}
+
+/// The logged in user, or the user of a login waiting for the second factor.
+fn mfa_subject(session: &Session) -> Option<MfaPending> {
+    if let Some(v) = session.get::<logic::types::UserInfo>(UserLogic::SESS_KEY) {
+        return Some(MfaPending {
+            user_id: v.user_id,
+            username: v.username,
+            expired_time: 0,
+        });
+    }
+    session
+        .get::<MfaPending>(MfaLogic::PENDING_SESS_KEY)
+        .filter(|v| v.expired_time >= chrono::Utc::now().timestamp())
+}
+
+/// Marks the second factor as given, finishing a pending login.
+async fn finish_mfa(
+    session: &Session,
+    state: &AppState,
+    req: &Request,
+    subject: &MfaPending,
+) -> Result<()> {
+    if subject.expired_time > 0 {
+        let user = state
+            .service()
+            .user
+            .get_user(None, Some(&subject.user_id))
+            .await?
+            .ok_or(anyhow::anyhow!("cannot found user"))?;
+        session.remove(MfaLogic::PENDING_SESS_KEY);
+        set_user_session(session, state, req, user).await?;
+    }
+    session.set(MfaLogic::VERIFIED_SESS_KEY, chrono::Utc::now().timestamp());
+    Ok(())
+}
+
+async fn set_user_session(
+    session: &Session,
+    state: &AppState,
+    req: &Request,
+    login_user: logic::types::UserRecord,
+) -> Result<()> {
+    let permissions = state.get_permissions_for_user(&login_user.user_id).await?;
+    let sid = state
+        .service()
+        .session
+        .create(
+            &login_user.user_id,
+            &client_ip(req, state.conf.auth.lockout.trust_proxy_headers),
+            req.header("User-Agent").unwrap_or_default(),
+        )
+        .await?;
+    session.set(SessionLogic::SESS_KEY, sid);
+
+    session.set(
+        UserLogic::SESS_KEY,
+        logic::types::UserInfo {
+            username: login_user.username,
+            nickname: login_user.nickname,
+            avatar: login_user.avatar,
+            email: login_user.email,
+            role_id: login_user.role_id,
+            is_root: login_user.is_root,
+            introduction: login_user.introduction,
+            phone: login_user.phone,
+            created_time: local_time!(login_user.created_time),
+            updated_time: local_time!(login_user.updated_time),
+            user_id: login_user.user_id,
+            gender: login_user.gender,
+            permissions,
+            role: login_user.role.unwrap_or_default(),
+        },
+    );
+    Ok(())
+}
+
+use poem::{session::Session, web::Data, Request, Result};
+use poem_openapi::payload::PlainText;
 use poem_openapi::{param::Query, payload::Json, OpenApi};
 use sea_orm::{ActiveValue::NotSet, Set};
 
 pub mod types {
-    use poem_openapi::Object;
+    use poem_openapi::{payload::PlainText, ApiResponse, Object};
     use serde::{Deserialize, Serialize};
 
     #[derive(Object)]
     pub struct LoginReq {
         pub username: String,
         pub password: String,
+        /// local or ldap, defaults to local
+        pub provider: Option<String>,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct SsoProvidersResp {
+        pub local_login: bool,
+        pub local_register: bool,
+        pub oidc: bool,
+        pub ldap: bool,
+    }
+
+    #[derive(Debug, ApiResponse)]
+    pub enum SsoRedirectResponse {
+        #[oai(status = 302)]
+        Found(#[oai(header = "Location")] String),
+        #[oai(status = 400)]
+        BadRequest(PlainText<String>),
     }
 
     #[derive(Serialize, Object, Default)]
     pub struct Logined {
         pub token: String,
+        /// verify or enroll when a second factor is needed before the
+        /// login is complete, empty otherwise
+        pub mfa: String,
+    }
+
+    #[derive(Object)]
+    pub struct MfaCodeReq {
+        /// code of the authenticator app or a recovery code
+        #[oai(validator(min_length = 6, max_length = 20))]
+        pub code: String,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct MfaStatusResp {
+        pub enabled: bool,
+        pub required: bool,
+        pub recovery_codes_left: u64,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct MfaEnrollResp {
+        pub secret: String,
+        pub otpauth_url: String,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct MfaRecoveryCodesResp {
+        pub recovery_codes: Vec<String>,
+    }
+
+    #[derive(Object)]
+    pub struct MfaResetReq {
+        pub user_id: String,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct SessionRecord {
+        pub sid: String,
+        pub ip: String,
+        pub user_agent: String,
+        pub login_time: String,
+        pub last_active_time: String,
+        /// the session making this request
+        pub current: bool,
+    }
+
+    #[derive(Object)]
+    pub struct RevokeSessionReq {
+        pub sid: String,
+    }
+
+    #[derive(Object, Serialize, Default)]
+    pub struct RevokeSessionResp {
+        pub result: u64,
+    }
+
+    #[derive(Object)]
+    pub struct ForceLogoutReq {
+        pub user_id: String,
+    }
+
+    #[derive(Object)]
+    pub struct UnlockUserReq {
+        pub username: String,
     }
 
     #[derive(Object, Serialize, Deserialize, Default)]
@@ -40,7 +240,7 @@ pub mod types {
         pub phone: String,
         pub user_id: String,
         pub role: String,
-        pub role_id: u64,
+        pub role_id: i64,
         pub permissions: Vec<String>,
         pub updated_time: String,
         pub created_time: String,
@@ -56,12 +256,12 @@ pub mod types {
         pub avatar: Option<String>,
         pub password: String,
         pub introduction: Option<String>,
-        pub role_id: u64,
+        pub role_id: i64,
     }
 
     #[derive(Object, Serialize, Deserialize)]
     pub struct RegistryResponse {
-        pub result: u64,
+        pub result: i64,
     }
 
     #[derive(Object, Serialize, Deserialize)]
@@ -92,7 +292,7 @@ pub mod types {
         pub username: String,
         pub nickname: String,
         pub role: String,
-        pub role_id: u64,
+        pub role_id: i64,
         pub avatar: String,
         pub email: String,
         pub phone: String,
@@ -108,41 +308,54 @@ impl UserApi {
     #[oai(path = "/login", method = "post")]
     pub async fn login(
         &self,
+        req: &Request,
         session: &Session,
         state: Data<&AppState>,
         // #[oai(name = "TOKEN")] _token: Header<String>,
         Json(login_req): Json<types::LoginReq>,
     ) -> Result<ApiStdResponse<types::Logined>> {
         let svc = state.service();
-        let login_user = svc
-            .user
-            .valid_user(&login_req.username, &login_req.password)
+        let ip = client_ip(req, state.conf.auth.lockout.trust_proxy_headers);
+        svc.security
+            .check_login_allowed(&login_req.username, &ip)
             .await?;
 
-        let permissions = state.get_permissions_for_user(&login_user.user_id).await?;
-
-        session.set(
-            UserLogic::SESS_KEY,
-            logic::types::UserInfo {
-                username: login_req.username,
-                nickname: login_user.nickname,
-                avatar: login_user.avatar,
-                email: login_user.email,
-                role_id: login_user.role_id,
-                is_root: login_user.is_root,
-                introduction: login_user.introduction,
-                phone: login_user.phone,
-                created_time: local_time!(login_user.created_time),
-                updated_time: local_time!(login_user.updated_time),
-                user_id: login_user.user_id,
-                gender: login_user.gender,
-                permissions,
-                role: login_user.role.unwrap_or_default(),
-            },
-        );
+        let ret = match login_req.provider.as_deref() {
+            Some(PROVIDER_LDAP) => {
+                svc.auth
+                    .ldap_login(&login_req.username, &login_req.password)
+                    .await
+            }
+            _ => {
+                svc.user
+                    .valid_user(&login_req.username, &login_req.password)
+                    .await
+            }
+        };
+        let login_user = match ret {
+            Ok(v) => v,
+            Err(e) => {
+                svc.security
+                    .record_login_failure(&login_req.username, &ip)
+                    .await?;
+                return Err(e.into());
+            }
+        };
+        svc.security
+            .record_login_success(&login_req.username)
+            .await?;
+
+        if login_req.provider.as_deref() != Some(PROVIDER_LDAP)
+            && !svc.auth.can_login_locally(&login_user)
+        {
+            return_err!("local login is disabled for this user");
+        }
+
+        let mfa = start_login(session, &state, req, login_user).await?;
 
         return_ok!(types::Logined {
             token: "success".into(),
+            mfa,
         });
     }
 
@@ -150,12 +363,318 @@ impl UserApi {
     pub async fn logout(
         &self,
         sess: &Session,
+        state: Data<&AppState>,
         // #[oai(name = "TOKEN")] _token: Header<String>,
     ) -> Result<ApiStdResponse<bool>> {
+        if let (Some(user_info), Some(sid)) = (
+            sess.get::<logic::types::UserInfo>(UserLogic::SESS_KEY),
+            sess.get::<String>(SessionLogic::SESS_KEY),
+        ) {
+            state
+                .service()
+                .session
+                .revoke(&user_info.user_id, &sid)
+                .await?;
+        }
         sess.clear();
         return_ok!(true);
     }
 
+    #[oai(path = "/sso/providers", method = "get")]
+    pub async fn sso_providers(
+        &self,
+        state: Data<&AppState>,
+    ) -> Result<ApiStdResponse<types::SsoProvidersResp>> {
+        let opts = &state.conf.auth;
+        return_ok!(types::SsoProvidersResp {
+            local_login: opts.local_login,
+            local_register: opts.local_register,
+            oidc: opts.oidc.is_some(),
+            ldap: opts.ldap.is_some(),
+        })
+    }
+
+    #[oai(path = "/sso/oidc/login", method = "get")]
+    pub async fn oidc_login(
+        &self,
+        session: &Session,
+        state: Data<&AppState>,
+    ) -> types::SsoRedirectResponse {
+        let svc = state.service();
+        match svc.auth.oidc_authorize_url().await {
+            Ok((url, oidc_state)) => {
+                session.set(AuthLogic::OIDC_SESS_KEY, oidc_state);
+                types::SsoRedirectResponse::Found(url)
+            }
+            Err(e) => types::SsoRedirectResponse::BadRequest(PlainText(e.to_string())),
+        }
+    }
+
+    #[oai(path = "/sso/oidc/callback", method = "get")]
+    pub async fn oidc_callback(
+        &self,
+        req: &Request,
+        session: &Session,
+        state: Data<&AppState>,
+        #[oai(validator(min_length = 1))] code: Query<String>,
+        #[oai(name = "state")] oidc_state: Query<String>,
+    ) -> types::SsoRedirectResponse {
+        let Some(expected) = session.get::<OidcState>(AuthLogic::OIDC_SESS_KEY) else {
+            return types::SsoRedirectResponse::BadRequest(PlainText(
+                "oidc login was not started from this browser".to_string(),
+            ));
+        };
+        session.remove(AuthLogic::OIDC_SESS_KEY);
+
+        let svc = state.service();
+        let ret = match svc.auth.oidc_login(&code.0, &oidc_state.0, &expected).await {
+            Ok(user) => start_login(session, &state, req, user).await,
+            Err(e) => Err(e.into()),
+        };
+        let mfa = match ret {
+            Ok(v) => v,
+            Err(e) => return types::SsoRedirectResponse::BadRequest(PlainText(e.to_string())),
+        };
+
+        let home_url = state
+            .conf
+            .auth
+            .oidc
+            .as_ref()
+            .map(|v| v.home_url.clone())
+            .unwrap_or("/".to_string());
+        if mfa.is_empty() {
+            return types::SsoRedirectResponse::Found(home_url);
+        }
+        let sep = if home_url.contains('?') { '&' } else { '?' };
+        types::SsoRedirectResponse::Found(format!("{home_url}{sep}mfa={mfa}"))
+    }
+
+    #[oai(path = "/mfa/status", method = "get")]
+    pub async fn mfa_status(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+    ) -> Result<ApiStdResponse<types::MfaStatusResp>> {
+        let v = state.service().mfa.status(&user_info.user_id).await?;
+        return_ok!(types::MfaStatusResp {
+            enabled: v.enabled,
+            required: v.required,
+            recovery_codes_left: v.recovery_codes_left as u64,
+        })
+    }
+
+    #[oai(path = "/mfa/enroll", method = "post")]
+    pub async fn mfa_enroll(
+        &self,
+        session: &Session,
+        state: Data<&AppState>,
+    ) -> Result<ApiStdResponse<types::MfaEnrollResp>> {
+        let Some(subject) = mfa_subject(session) else {
+            return_err!("not login");
+        };
+        let (secret, otpauth_url) = state
+            .service()
+            .mfa
+            .enroll(&subject.user_id, &subject.username)
+            .await?;
+        return_ok!(types::MfaEnrollResp {
+            secret,
+            otpauth_url
+        })
+    }
+
+    #[oai(path = "/mfa/activate", method = "post")]
+    pub async fn mfa_activate(
+        &self,
+        req: &Request,
+        session: &Session,
+        state: Data<&AppState>,
+        Json(code_req): Json<types::MfaCodeReq>,
+    ) -> Result<ApiStdResponse<types::MfaRecoveryCodesResp>> {
+        let Some(subject) = mfa_subject(session) else {
+            return_err!("not login");
+        };
+        let recovery_codes = state
+            .service()
+            .mfa
+            .activate(&subject.user_id, &code_req.code)
+            .await?;
+        finish_mfa(session, &state, req, &subject).await?;
+        return_ok!(types::MfaRecoveryCodesResp { recovery_codes })
+    }
+
+    /// Second step of a login, also the step-up check before web ssh.
+    #[oai(path = "/mfa/verify", method = "post")]
+    pub async fn mfa_verify(
+        &self,
+        req: &Request,
+        session: &Session,
+        state: Data<&AppState>,
+        Json(code_req): Json<types::MfaCodeReq>,
+    ) -> Result<ApiStdResponse<bool>> {
+        let Some(subject) = mfa_subject(session) else {
+            return_err!("not login");
+        };
+        let svc = state.service();
+        let ip = client_ip(req, state.conf.auth.lockout.trust_proxy_headers);
+        // codes are short, guesses count against the same lockout as passwords
+        svc.security
+            .check_login_allowed(&subject.username, &ip)
+            .await?;
+        if let Err(e) = svc.mfa.verify(&subject.user_id, &code_req.code).await {
+            svc.security
+                .record_login_failure(&subject.username, &ip)
+                .await?;
+            return Err(e.into());
+        }
+        finish_mfa(session, &state, req, &subject).await?;
+        return_ok!(true)
+    }
+
+    #[oai(path = "/mfa/recovery-codes", method = "post")]
+    pub async fn mfa_recovery_codes(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::MfaCodeReq>,
+    ) -> Result<ApiStdResponse<types::MfaRecoveryCodesResp>> {
+        let recovery_codes = state
+            .service()
+            .mfa
+            .regenerate_recovery_codes(&user_info.user_id, &req.code)
+            .await?;
+        return_ok!(types::MfaRecoveryCodesResp { recovery_codes })
+    }
+
+    #[oai(path = "/mfa/disable", method = "post")]
+    pub async fn mfa_disable(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::MfaCodeReq>,
+    ) -> Result<ApiStdResponse<bool>> {
+        state
+            .service()
+            .mfa
+            .disable(&user_info.user_id, &req.code)
+            .await?;
+        return_ok!(true)
+    }
+
+    /// Lets a user who lost the device enroll again.
+    #[oai(path = "/mfa/reset", method = "post")]
+    pub async fn mfa_reset(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::MfaResetReq>,
+    ) -> Result<ApiStdResponse<bool>> {
+        if !state.can_manage_user(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+        state.service().mfa.reset(&req.user_id).await?;
+        return_ok!(true)
+    }
+
+    #[oai(path = "/sessions", method = "get")]
+    pub async fn list_sessions(
+        &self,
+        sess: &Session,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+    ) -> Result<ApiStdResponse<Vec<types::SessionRecord>>> {
+        let current = sess
+            .get::<String>(SessionLogic::SESS_KEY)
+            .unwrap_or_default();
+        let list = state
+            .service()
+            .session
+            .list(&user_info.user_id)
+            .await?
+            .into_iter()
+            .map(|v| types::SessionRecord {
+                current: v.sid == current,
+                sid: v.sid,
+                ip: v.ip,
+                user_agent: v.user_agent,
+                login_time: local_time!(
+                    chrono::DateTime::from_timestamp(v.login_time, 0).unwrap_or_default()
+                ),
+                last_active_time: local_time!(chrono::DateTime::from_timestamp(
+                    v.last_active_time,
+                    0
+                )
+                .unwrap_or_default()),
+            })
+            .collect();
+        return_ok!(list)
+    }
+
+    #[oai(path = "/sessions/revoke", method = "post")]
+    pub async fn revoke_session(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::RevokeSessionReq>,
+    ) -> Result<ApiStdResponse<types::RevokeSessionResp>> {
+        let ok = state
+            .service()
+            .session
+            .revoke(&user_info.user_id, &req.sid)
+            .await?;
+        return_ok!(types::RevokeSessionResp { result: ok as u64 })
+    }
+
+    #[oai(path = "/sessions/revoke-others", method = "post")]
+    pub async fn revoke_other_sessions(
+        &self,
+        sess: &Session,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+    ) -> Result<ApiStdResponse<types::RevokeSessionResp>> {
+        let current = sess.get::<String>(SessionLogic::SESS_KEY);
+        let result = state
+            .service()
+            .session
+            .revoke_all(&user_info.user_id, current.as_deref())
+            .await?;
+        return_ok!(types::RevokeSessionResp { result })
+    }
+
+    #[oai(path = "/force-logout", method = "post")]
+    pub async fn force_logout(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::ForceLogoutReq>,
+    ) -> Result<ApiStdResponse<types::RevokeSessionResp>> {
+        if !state.can_manage_user(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+        let result = state
+            .service()
+            .session
+            .revoke_all(&req.user_id, None)
+            .await?;
+        return_ok!(types::RevokeSessionResp { result })
+    }
+
+    /// Lifts the lockout of an account after too many failed logins.
+    #[oai(path = "/unlock", method = "post")]
+    pub async fn unlock_user(
+        &self,
+        state: Data<&AppState>,
+        user_info: Data<&logic::types::UserInfo>,
+        Json(req): Json<types::UnlockUserReq>,
+    ) -> Result<ApiStdResponse<bool>> {
+        if !state.can_manage_user(&user_info.user_id).await? {
+            return Err(NoPermission().into());
+        }
+        state.service().security.unlock_user(&req.username).await?;
+        return_ok!(true)
+    }
+
     #[oai(path = "/register", method = "post")]
     pub async fn register(
         &self,
@@ -172,6 +691,10 @@ impl UserApi {
             return Err(NoPermission().into());
         }
 
+        if !state.conf.auth.local_register {
+            return_err!("local users are managed by the identity provider");
+        }
+
         let v = svc
             .user
             .create_user(user::Model {
@@ -227,6 +750,7 @@ impl UserApi {
     ) -> Result<ApiStdResponse<types::UpdateInfoResp>> {
         let svc = state.service();
         let user_id = user_info.user_id.clone();
+        let password_changed = req.password.as_ref().is_some_and(|v| !v.is_empty());
 
         let affected = svc
             .user
@@ -246,6 +770,13 @@ impl UserApi {
             )
             .await?;
 
+        if password_changed {
+            let sid = sess.get::<String>(SessionLogic::SESS_KEY);
+            svc.session
+                .revoke_all(&user_info.user_id, sid.as_deref())
+                .await?;
+        }
+
         let permissions = state.get_permissions_for_user(&user_info.user_id).await?;
         match svc.user.get_user(Some(&user_info.username), None).await? {
             Some(record) => {
@@ -280,9 +811,9 @@ impl UserApi {
         &self,
         state: Data<&AppState>,
         _session: &Session,
-        Query(role_id): Query<Option<u64>>,
+        Query(role_id): Query<Option<i64>>,
         Query(user_id): Query<Option<Vec<String>>>,
-        Query(ignore_role_id): Query<Option<u64>>,
+        Query(ignore_role_id): Query<Option<i64>>,
         Query(keyword): Query<Option<String>>,
         #[oai(
             default = "crate::api::default_page_size",
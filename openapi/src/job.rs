@@ -3,7 +3,7 @@ use std::{sync::Arc, time::Duration};
 use anyhow::{Context, Result};
 use automate::{
     bridge::msg::{AgentOfflineParams, AgentOnlineParams, HeartbeatParams},
-    bus::{Bus, Msg},
+    bus::Msg,
 };
 
 use leader_election::LeaderElection;
@@ -76,12 +76,13 @@ async fn agent_offline(state: AppState, msg: AgentOfflineParams) -> Result<()> {
         .await?)
 }
 
-pub async fn instance_health_check(state: AppState) {
+/// Campaigns for leadership among the consoles, the flag tells whether this
+/// one is the leader right now.
+fn leader_election(state: AppState) -> Arc<RwLock<bool>> {
     let is_master = Arc::new(RwLock::new(false));
-    let state_clone = state.clone();
     let is_master_clone = is_master.clone();
     tokio::spawn(async move {
-        let mut l = LeaderElection::new(state_clone.redis(), "jiascheduler:leader_election", 10)
+        let mut l = LeaderElection::new(state.redis(), "jiascheduler:leader_election", 10)
             .expect("failed initialize leader election");
 
         l.run_election(|ok| {
@@ -96,7 +97,10 @@ pub async fn instance_health_check(state: AppState) {
         .await
         .expect("faild run leader election");
     });
+    is_master
+}
 
+pub async fn instance_health_check(state: AppState, is_master: Arc<RwLock<bool>>) {
     tokio::spawn(async move {
         let svc = state.service();
         loop {
@@ -118,15 +122,41 @@ pub async fn instance_health_check(state: AppState) {
     });
 }
 
+/// Prunes the history tables by the retention policies on the leader.
+pub async fn retention_prune(state: AppState, is_master: Arc<RwLock<bool>>) {
+    tokio::spawn(async move {
+        let svc = state.service();
+        let interval = Duration::from_secs(state.conf.retention.interval_secs.max(60));
+        loop {
+            if *is_master.read().await {
+                match svc.retention.prune().await {
+                    Ok(v) => info!("retention pruned history of {} jobs", v.len()),
+                    Err(e) => error!("failed prune history, {e:?}"),
+                }
+                sleep(interval).await;
+            } else {
+                sleep(Duration::from_secs(1)).await;
+            }
+        }
+    });
+}
+
 pub async fn start(state: AppState) -> Result<()> {
-    let bus = Bus::new(state.redis().clone());
+    let bus = state
+        .conf
+        .bus
+        .connect(state.redis())
+        .await
+        .context("failed connect to bus")?;
 
-    instance_health_check(state.clone()).await;
+    let is_master = leader_election(state.clone());
+    instance_health_check(state.clone(), is_master.clone()).await;
+    retention_prune(state.clone(), is_master).await;
 
     tokio::spawn(async move {
         loop {
             let ret = bus
-                .recv(|_key, msg| {
+                .recv(&mut |_key, msg| {
                     let state = state.clone();
                     Box::pin(async move {
                         match msg {
@@ -3,14 +3,18 @@ pub mod response;
 
 use anyhow::{anyhow, Context, Result};
 use api::{
-    executor::ExecutorApi, file::FileApi, instance::InstanceApi, job::JobApi, manage::ManageApi,
-    migration::MigrationApi, role::RoleApi, tag::TagApi, team::TeamApi, terminal, user::UserApi,
+    calendar::CalendarApi, executor::ExecutorApi, file::FileApi, instance::InstanceApi,
+    job::JobApi, job_template::JobTemplateApi, manage::ManageApi, manifest::ManifestApi, metrics,
+    migration::MigrationApi, permission::PermissionApi, retention::RetentionApi, role::RoleApi,
+    script_module::ScriptModuleApi, tag::TagApi, team::TeamApi, terminal, terminal::TerminalApi,
+    user::UserApi,
 };
+use automate::{bus::BusKind, comet::handler::middleware::bearer_auth};
 use casbin::{CoreApi, DefaultModel, Enforcer};
 
 use ::migration::{Migrator, MigratorTrait};
 
-use logic::user::UserLogic;
+use logic::{session::SessionLogic, user::UserLogic};
 use middleware::AuthMiddleware;
 use poem::{get, IntoEndpoint};
 use service::config::Conf;
@@ -44,7 +48,6 @@ mod error;
 mod job;
 pub use service::logic;
 pub mod middleware;
-mod migration;
 
 pub use service::state;
 pub mod utils;
@@ -54,6 +57,7 @@ pub struct WebapiOptions {
     pub database_url: Option<String>,
     pub redis_url: Option<String>,
     pub bind_addr: Option<String>,
+    pub bus: Option<BusKind>,
     pub config_file: String,
 }
 
@@ -74,6 +78,9 @@ impl WebapiOptions {
             .bind_addr
             .iter()
             .map(|v| conf.bind_addr = v.to_string());
+        if let Some(kind) = self.bus {
+            conf.bus.kind = kind;
+        }
 
         Ok(conf)
     }
@@ -213,10 +220,14 @@ pub async fn run(opts: WebapiOptions, signal: Option<Sender<Conf>>) -> Result<()
     auth_value.set_sensitive(true);
     headers.insert(header::AUTHORIZATION, auth_value);
 
+    let redis_manager = ConnectionManager::new(client.clone())
+        .await
+        .context("failed connect to redis")?;
     let ctx = AppContext::builder()
         .db(conn)
         .conf(conf.clone())
         .redis(client)
+        .redis_manager(redis_manager)
         .enforcer(e)
         .rate_limit(30)
         .http_client(
@@ -225,7 +236,7 @@ pub async fn run(opts: WebapiOptions, signal: Option<Sender<Conf>>) -> Result<()
                 .build()?,
         )
         .build()?;
-    let state = AppState::Inner(ctx);
+    let state = AppState::Inner(Box::new(ctx));
 
     let api_service = OpenApiService::new(
         (
@@ -239,6 +250,12 @@ pub async fn run(opts: WebapiOptions, signal: Option<Sender<Conf>>) -> Result<()
             MigrationApi,
             ManageApi,
             TagApi,
+            TerminalApi,
+            PermissionApi,
+            CalendarApi,
+            ManifestApi,
+            // tuples only implement OpenApi up to 16 elements
+            (RetentionApi, ScriptModuleApi, JobTemplateApi),
         ),
         "jiascheduler web api",
         "1.0",
@@ -272,15 +289,31 @@ pub async fn run(opts: WebapiOptions, signal: Option<Sender<Conf>>) -> Result<()
             "/terminal/tunnel/:instance_id",
             get(terminal::proxy_webssh).with(AuthMiddleware),
         )
+        .at(
+            "/terminal/watch/:session_id",
+            get(terminal::watch_webssh).with(AuthMiddleware),
+        )
         .nest("/api", api_service.with(AuthMiddleware))
-        .nest("/doc", ui)
+        .nest("/doc", ui);
+    let app = if conf.metrics.enable {
+        app.at(
+            "/metrics",
+            get(metrics::metrics).with_if(
+                !conf.metrics.token.is_empty(),
+                bearer_auth(&conf.metrics.token),
+            ),
+        )
+    } else {
+        app
+    };
+    let app = app
         .catch_all_error(custom_error)
         .with(ServerSession::new(
             CookieConfig::default()
                 .name("jiaschduler-sid")
-                .max_age(Some(Duration::from_secs(86400)))
+                .max_age(Some(Duration::from_secs(SessionLogic::TTL_SECS)))
                 .secure(false),
-            RedisStorage::new(ConnectionManager::new(state.redis()).await.unwrap()),
+            RedisStorage::new(state.redis_manager()),
         ))
         .data(state)
         .data(InstallState::new(
@@ -1,8 +1,22 @@
-use crate::logic::{types, user::UserLogic};
+use crate::logic::{session::SessionLogic, types, user::UserLogic};
+use crate::state::AppState;
 use poem::{
     session::Session, web::Json, Endpoint, IntoResponse, Middleware, Request, Response, Result,
 };
 
+/// Paths served without a login.
+const PUBLIC_PATHS: &[&str] = &[
+    "/user/login",
+    "/user/logout",
+    "/user/sso/providers",
+    "/user/sso/oidc/login",
+    "/user/sso/oidc/callback",
+    "/user/mfa/enroll",
+    "/user/mfa/activate",
+    "/user/mfa/verify",
+    "/migration/version/check",
+];
+
 pub struct AuthMiddleware;
 
 impl<E: Endpoint> Middleware<E> for AuthMiddleware {
@@ -32,13 +46,24 @@ where
         .into_response();
 
         let sess: &Session = req.extensions().get().expect("not init session");
+        let mut user_info = sess.get::<types::UserInfo>(UserLogic::SESS_KEY);
+
+        if let Some(ref v) = user_info {
+            let state: &AppState = req.extensions().get().expect("not init state");
+            let sid = sess
+                .get::<String>(SessionLogic::SESS_KEY)
+                .unwrap_or_default();
+            // revoked remotely, or logged in before sessions were tracked
+            if !state.service().session.touch(&v.user_id, &sid).await? {
+                sess.clear();
+                user_info = None;
+            }
+        }
 
-        if let Some(user_info) = sess.get::<types::UserInfo>(UserLogic::SESS_KEY) {
+        if let Some(user_info) = user_info {
             req.extensions_mut().insert(user_info);
         } else {
-            if vec!["/user/login", "/user/logout", "/migration/version/check"]
-                .contains(&req.uri().path())
-            {
+            if PUBLIC_PATHS.contains(&req.uri().path()) {
                 return self.ep.call(req).await.map(IntoResponse::into_response);
             }
             return Ok(login_resp);
@@ -32,7 +32,7 @@ where
         }))
         .into_response();
 
-        let team_id = match req.header("X-Team-Id").map(str::parse::<u64>).transpose() {
+        let team_id = match req.header("X-Team-Id").map(str::parse::<i64>).transpose() {
             Ok(v) => v,
             Err(e) => {
                 return Ok(Json(serde_json::json! ({
@@ -1,8 +1,29 @@
 use std::{future::Future, pin::Pin, sync::Arc};
 
 use anyhow::{anyhow, Result};
+use poem::Request;
 use tokio::sync::RwLock;
 
+/// Address of the client, proxy headers are only honored when trusted since
+/// anyone can send them.
+pub fn client_ip(req: &Request, trust_proxy_headers: bool) -> String {
+    if trust_proxy_headers {
+        let forwarded = req
+            .header("X-Forwarded-For")
+            .and_then(|v| v.split(',').next())
+            .or(req.header("X-Real-IP"))
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        if let Some(v) = forwarded {
+            return v.to_string();
+        }
+    }
+    req.remote_addr()
+        .as_socket_addr()
+        .map(|v| v.ip().to_string())
+        .unwrap_or_default()
+}
+
 pub async fn async_batch_do<I, T, F>(data: Vec<I>, handler: F) -> Vec<Result<T>>
 where
     F: 'static + Send + Sync + Clone + Fn(I) -> Pin<Box<dyn Future<Output = Result<T>> + Send>>,
@@ -1,11 +1,14 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
 
 use tracing::error;
 
-use automate::scheduler::{
-    Scheduler,
-    types::{AssignUserOption, SshConnectionOption},
+use automate::{
+    scheduler::{
+        Scheduler,
+        types::{AssignUserOption, SshConnectionOption},
+    },
+    telemetry,
 };
 
 #[derive(Parser, Debug)]
@@ -17,11 +20,18 @@ use automate::scheduler::{
 struct AgentArgs {
     #[arg(short, long, default_value_t = String::from("0.0.0.0:3001"))]
     bind: String,
+    /// Address serving Prometheus metrics on /metrics, eg: "127.0.0.1:3001",
+    /// metrics are disabled when not set
+    #[arg(long)]
+    metrics_bind: Option<String>,
     #[arg(long, default_values_t = vec![String::from("ws://127.0.0.1:3000")])]
     comet_addr: Vec<String>,
     /// Directory for saving job execution logs
     #[arg(long, default_value_t = String::from("./log"))]
     output_dir: String,
+    /// Directory where artifacts are cached by their sha256
+    #[arg(long, default_value_t = String::from("./artifacts"))]
+    artifact_dir: String,
     #[arg(long, default_value_t = String::from("rYzBYE+cXbtdMg=="))]
     comet_secret: String,
     #[arg(short, long, default_value_t = String::from("default"))]
@@ -32,6 +42,12 @@ struct AgentArgs {
     /// Set the login user's password of the instance for SSH remote connection
     #[arg(long)]
     ssh_password: Option<String>,
+    /// Set the private key file of the instance for SSH remote connection
+    #[arg(long)]
+    ssh_private_key: Option<String>,
+    /// Set the passphrase of the SSH private key
+    #[arg(long)]
+    ssh_key_passphrase: Option<String>,
     /// Set the port of this instance for SSH remote connection
     #[arg(long)]
     ssh_port: Option<u16>,
@@ -46,6 +62,9 @@ struct AgentArgs {
     /// Set log level, eg: "trace", "debug", "info", "warn", "error" etc.
     #[arg(long, default_value_t = String::from("error"))]
     log_level: String,
+    /// OTLP http endpoint that spans are exported to, eg: "http://127.0.0.1:4318"
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    otlp_endpoint: Option<String>,
 }
 
 #[tokio::main]
@@ -54,17 +73,34 @@ async fn main() -> Result<()> {
     unsafe {
         std::env::set_var("RUST_LOG", args.log_level);
     }
-    tracing_subscriber::fmt::init();
+    let _telemetry = telemetry::init("jiascheduler-agent", args.otlp_endpoint.as_deref())?;
+    let ssh_private_key = args
+        .ssh_private_key
+        .as_ref()
+        .map(std::fs::read_to_string)
+        .transpose()
+        .context("failed to read ssh private key")?;
 
     let mut scheduler = Scheduler::new(
         args.namespace,
         args.comet_addr,
         args.comet_secret,
         args.output_dir,
-        SshConnectionOption::build(args.ssh_user, args.ssh_password, args.ssh_port),
+        args.artifact_dir,
+        SshConnectionOption::build(
+            args.ssh_user,
+            args.ssh_password,
+            ssh_private_key,
+            args.ssh_key_passphrase,
+            args.ssh_port,
+        ),
         AssignUserOption::build(args.assign_username, args.assign_password),
     );
 
+    if let Some(bind) = args.metrics_bind {
+        scheduler.serve_metrics(bind);
+    }
+
     if let Err(e) = scheduler.connect_comet().await {
         error!("failed connect to comet - {e}");
     }
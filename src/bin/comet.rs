@@ -1,5 +1,9 @@
 use anyhow::Result;
-use automate::comet::{self, CometOptions};
+use automate::{
+    bus::{BusKind, BusOptions},
+    comet::{self, CometOptions},
+    telemetry,
+};
 use clap::Parser;
 
 #[derive(Parser, Debug)]
@@ -18,10 +22,29 @@ struct CometArgs {
     redis_url: String,
     #[arg(long, default_value_t = String::from("rYzBYE+cXbtdMg=="))]
     secret: String,
+    /// Event bus used to deliver agent events to the console,
+    /// must be the same as the one configured in the console
+    #[arg(long, value_enum, default_value_t = BusKind::Redis)]
+    bus: BusKind,
+    /// NATS server address when bus is nats, eg: "nats://127.0.0.1:4222"
+    #[arg(long, default_value_t = String::new())]
+    nats_url: String,
+    /// Directory where artifacts are cached by their sha256
+    #[arg(long, default_value_t = String::from("./artifacts"))]
+    artifact_dir: String,
+    /// Serve Prometheus metrics on /metrics of the bind address
+    #[arg(long)]
+    metrics: bool,
+    /// Bearer token scrapers have to send for /metrics
+    #[arg(long, env = "JIASCHEDULER_METRICS_TOKEN")]
+    metrics_token: Option<String>,
 
     /// Set log level, eg: "trace", "debug", "info", "warn", "error" etc.
     #[arg(long, default_value_t = String::from("error"))]
     log_level: String,
+    /// OTLP http endpoint that spans are exported to, eg: "http://127.0.0.1:4318"
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    otlp_endpoint: Option<String>,
 }
 
 #[tokio::main]
@@ -31,13 +54,20 @@ async fn main() -> Result<()> {
         std::env::set_var("RUST_LOG", args.log_level);
     }
 
-    tracing_subscriber::fmt::init();
+    let _telemetry = telemetry::init("jiascheduler-comet", args.otlp_endpoint.as_deref())?;
 
     comet::run(
         CometOptions {
             redis_url: args.redis_url,
             bind_addr: args.bind,
             secret: args.secret,
+            bus: BusOptions {
+                kind: args.bus,
+                nats_url: args.nats_url,
+            },
+            artifact_dir: args.artifact_dir,
+            metrics: args.metrics,
+            metrics_token: args.metrics_token,
         },
         None,
     )
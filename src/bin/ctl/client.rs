@@ -0,0 +1,149 @@
+use std::{io::Write, path::Path};
+
+use anyhow::{Context, Result, anyhow};
+use openapi::response::StdResponse;
+use poem_openapi::types::{ParseFromJSON, ToJSON};
+use reqwest::{Method, header};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Name of the cookie holding the console session.
+pub const SESSION_COOKIE: &str = "jiaschduler-sid";
+
+const CODE_OK: i32 = 20000;
+
+/// The console and the session saved by login.
+#[derive(Serialize, Deserialize, Default)]
+pub struct CtlConfig {
+    pub server: String,
+    pub token: String,
+}
+
+impl CtlConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        let path = shellexpand::full(path)?.to_string();
+        match std::fs::read_to_string(&path) {
+            Ok(v) => toml::from_str(&v).with_context(|| format!("failed to parse {path}")),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("failed to read {path}")),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let path = shellexpand::full(path)?.to_string();
+        if let Some(dir) = Path::new(&path).parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let content = toml::to_string(self)?;
+        // the token is as good as a password, never let the file be readable
+        // by others, not even for a moment
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            // the mode is only applied to a new file
+            if Path::new(&path).exists() {
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+            }
+        }
+        options
+            .open(&path)
+            .and_then(|mut f| f.write_all(content.as_bytes()))
+            .with_context(|| format!("failed to write {path}"))?;
+        Ok(())
+    }
+}
+
+/// Calls the console api with the request and response types of the
+/// `openapi` crate.
+pub struct Client {
+    http: reqwest::Client,
+    server: String,
+    token: String,
+    team_id: Option<i64>,
+}
+
+impl Client {
+    pub fn new(server: &str, token: &str, team_id: Option<i64>) -> Result<Self> {
+        if server.is_empty() {
+            anyhow::bail!("no console address, pass --server or log in first");
+        }
+        Ok(Self {
+            http: reqwest::Client::builder().build()?,
+            server: server.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            team_id,
+        })
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub async fn get<T: ParseFromJSON + ToJSON>(
+        &mut self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T> {
+        self.send(Method::GET, path, query, None).await
+    }
+
+    pub async fn post<T: ParseFromJSON + ToJSON, B: ToJSON>(
+        &mut self,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        self.send(Method::POST, path, &[], body.to_json()).await
+    }
+
+    async fn send<T: ParseFromJSON + ToJSON>(
+        &mut self,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<Value>,
+    ) -> Result<T> {
+        let mut req = self
+            .http
+            .request(method, format!("{}/api{path}", self.server))
+            .query(query);
+        if !self.token.is_empty() {
+            req = req.header(header::COOKIE, format!("{SESSION_COOKIE}={}", self.token));
+        }
+        if let Some(team_id) = self.team_id {
+            req = req.header("X-Team-Id", team_id.to_string());
+        }
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+
+        let resp = req
+            .send()
+            .await
+            .with_context(|| format!("failed to request {path}"))?;
+        // a login hands out a new session
+        if let Some(sid) = resp
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .find_map(|v| v.strip_prefix(&format!("{SESSION_COOKIE}=")))
+        {
+            self.token = sid.split(';').next().unwrap_or_default().to_string();
+        }
+
+        let status = resp.status();
+        let body: Value = resp
+            .json()
+            .await
+            .with_context(|| format!("unexpected response of {path}, status {status}"))?;
+        let resp = StdResponse::<T>::parse_from_json(Some(body))
+            .map_err(|e| anyhow!("unexpected response of {path}, {}", e.into_message()))?;
+        if resp.code != CODE_OK {
+            anyhow::bail!("{}, code {}", resp.msg, resp.code);
+        }
+        resp.data.ok_or(anyhow!("empty response of {path}"))
+    }
+}
@@ -0,0 +1,692 @@
+mod client;
+mod output;
+
+use std::{
+    collections::BTreeSet,
+    io::{BufRead, Write},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, anyhow};
+use clap::{CommandFactory, Parser, Subcommand};
+use client::{Client, CtlConfig};
+use openapi::api::{
+    instance::types::QueryInstanceResp,
+    job::types::{
+        DispatchJobReq, DispatchJobResp, Endpoint, ExecRecord, JobRecord, QueryExecResp,
+        QueryJobResp, QueryJobSupervisorResp, QueryJobTimerResp, QueryScheduleResp,
+        RedispatchJobReq, RedispatchJobResp, SaveJobReq, SaveJobResp, ScheduleRecord, TimerExpr,
+    },
+    user::types::{LoginReq, Logined, MfaCodeReq},
+};
+use output::{Output, Table};
+use poem_openapi::types::ParseFromJSON;
+use serde_json::Value;
+
+/// How often a followed run or history is polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Command-line client of the jiascheduler console
+#[derive(Parser)]
+#[command(name = "jiascheduler-ctl", version)]
+struct CtlArgs {
+    /// Console address, eg: "http://127.0.0.1:9090", defaults to the one saved by login
+    #[arg(long, global = true, env = "JIASCHEDULER_SERVER")]
+    server: Option<String>,
+    /// Session token, defaults to the one saved by login
+    #[arg(
+        long,
+        global = true,
+        env = "JIASCHEDULER_TOKEN",
+        hide_env_values = true
+    )]
+    token: Option<String>,
+    /// Team the requests are made for
+    #[arg(long, global = true)]
+    team_id: Option<i64>,
+    /// Output format
+    #[arg(short, long, global = true, value_enum, default_value_t = Output::Table)]
+    output: Output,
+    /// Where login saves the console address and the session token
+    #[arg(long, global = true, value_name = "FILE", default_value_t = String::from("~/.jiascheduler/ctl.toml"))]
+    config: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Log in and save the session token
+    Login {
+        #[arg(short, long)]
+        username: String,
+        /// Read from stdin when not given
+        #[arg(short, long, env = "JIASCHEDULER_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+        /// Log in against the LDAP directory
+        #[arg(long)]
+        ldap: bool,
+    },
+    /// End the session and forget the saved token
+    Logout,
+    /// List, show, save and dispatch jobs
+    #[command(subcommand)]
+    Job(JobCommand),
+    /// Show the execution history
+    History {
+        /// Job eid
+        #[arg(long)]
+        eid: Option<String>,
+        #[arg(long)]
+        schedule_id: Option<String>,
+        /// default or bundle
+        #[arg(long, default_value_t = String::from("default"))]
+        job_type: String,
+        #[arg(long, default_value_t = 20)]
+        limit: u64,
+        /// Keep printing new executions with their output
+        #[arg(short, long)]
+        follow: bool,
+    },
+    /// List, start and stop timers
+    #[command(subcommand)]
+    Timer(TimerCommand),
+    /// List, start and stop supervisors
+    #[command(subcommand)]
+    Supervisor(SupervisorCommand),
+    /// List instances
+    #[command(subcommand)]
+    Instance(InstanceCommand),
+    /// Print the completion script of a shell
+    Completion { shell: clap_complete::Shell },
+}
+
+#[derive(Subcommand)]
+enum JobCommand {
+    List {
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long, default_value_t = 1)]
+        page: u64,
+        #[arg(long, default_value_t = 20)]
+        page_size: u64,
+    },
+    /// Show a job by name or eid
+    Show { job: String },
+    /// Create or update a job from a YAML or JSON file shaped like a /job/save request
+    Save {
+        #[arg(short, long)]
+        file: String,
+    },
+    /// Run a job once on instances
+    Dispatch {
+        /// Job name or eid
+        job: String,
+        /// Instance id, repeat for more instances
+        #[arg(short, long = "instance", required = true)]
+        instances: Vec<String>,
+        /// Wait for the runs and print their output
+        #[arg(short, long)]
+        follow: bool,
+        /// Only show what each instance would get and what keeps the job
+        /// from running there, exits non-zero on any problem
+        #[arg(long, conflicts_with = "follow")]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TimerCommand {
+    List {
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Start a saved timer on instances
+    Start {
+        /// Timer name
+        timer: String,
+        /// Instance id, repeat for more instances
+        #[arg(short, long = "instance", required = true)]
+        instances: Vec<String>,
+    },
+    /// Stop a started timer by the schedule id printed on start
+    Stop { schedule_id: String },
+}
+
+#[derive(Subcommand)]
+enum SupervisorCommand {
+    List {
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Start supervising a job on instances
+    Start {
+        /// Supervisor name
+        supervisor: String,
+        /// Instance id, repeat for more instances
+        #[arg(short, long = "instance", required = true)]
+        instances: Vec<String>,
+    },
+    /// Stop supervising by the schedule id printed on start
+    Stop { schedule_id: String },
+}
+
+#[derive(Subcommand)]
+enum InstanceCommand {
+    List {
+        #[arg(long)]
+        ip: Option<String>,
+        #[arg(long, default_value_t = 1)]
+        page: u64,
+        #[arg(long, default_value_t = 20)]
+        page_size: u64,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = CtlArgs::parse();
+    let output = args.output;
+    let mut config = CtlConfig::load(&args.config)?;
+
+    if let Command::Completion { shell } = args.command {
+        clap_complete::generate(
+            shell,
+            &mut CtlArgs::command(),
+            "jiascheduler-ctl",
+            &mut std::io::stdout(),
+        );
+        return Ok(());
+    }
+
+    let server = args.server.unwrap_or(config.server.clone());
+    let token = args.token.unwrap_or(config.token.clone());
+    let mut client = Client::new(&server, &token, args.team_id)?;
+
+    match args.command {
+        Command::Login {
+            username,
+            password,
+            ldap,
+        } => {
+            let password = match password {
+                Some(v) => v,
+                None => prompt("password: ")?,
+            };
+            let logined: Logined = client
+                .post(
+                    "/user/login",
+                    &LoginReq {
+                        username,
+                        password,
+                        provider: ldap.then(|| "ldap".to_string()),
+                    },
+                )
+                .await?;
+            match logined.mfa.as_str() {
+                "" => {}
+                "verify" => {
+                    let code = prompt("verification code: ")?;
+                    client
+                        .post::<bool, _>("/user/mfa/verify", &MfaCodeReq { code })
+                        .await?;
+                }
+                _ => anyhow::bail!("set up two-factor authentication in the console first"),
+            }
+            config.server = server;
+            config.token = client.token().to_string();
+            config.save(&args.config)?;
+            println!("logged in to {}", config.server);
+        }
+        Command::Logout => {
+            client.post::<bool, _>("/user/logout", &Value::Null).await?;
+            config.token.clear();
+            config.save(&args.config)?;
+        }
+        Command::Job(cmd) => job_command(&mut client, output, cmd).await?,
+        Command::History {
+            eid,
+            schedule_id,
+            job_type,
+            limit,
+            follow,
+        } => {
+            let mut query = vec![("job_type", job_type), ("page_size", limit.to_string())];
+            query.extend(eid.map(|v| ("eid", v)));
+            query.extend(schedule_id.map(|v| ("schedule_id", v)));
+            if follow {
+                follow_history(&mut client, output, &query, None).await?;
+            } else {
+                let resp: QueryExecResp = client.get("/job/exec-list", &query).await?;
+                output.print(&resp, |v| exec_table(&v.list))?;
+            }
+        }
+        Command::Timer(cmd) => timer_command(&mut client, output, cmd).await?,
+        Command::Supervisor(cmd) => supervisor_command(&mut client, output, cmd).await?,
+        Command::Instance(InstanceCommand::List {
+            ip,
+            page,
+            page_size,
+        }) => {
+            let mut query = vec![
+                ("page", page.to_string()),
+                ("page_size", page_size.to_string()),
+            ];
+            query.extend(ip.map(|v| ("ip", v)));
+            let resp: QueryInstanceResp = client.get("/instance/list", &query).await?;
+            output.print(&resp, |v| {
+                Table::new(vec![
+                    "INSTANCE ID",
+                    "IP",
+                    "NAMESPACE",
+                    "GROUP",
+                    "STATUS",
+                    "INFO",
+                ])
+                .rows(v.list.iter().map(|v| {
+                    vec![
+                        v.instance_id.clone(),
+                        v.ip.clone(),
+                        v.namespace.clone(),
+                        v.instance_group.clone(),
+                        match v.status {
+                            1 => "online".to_string(),
+                            _ => "offline".to_string(),
+                        },
+                        v.info.clone(),
+                    ]
+                }))
+            })?;
+        }
+        Command::Completion { .. } => unreachable!(),
+    }
+    Ok(())
+}
+
+async fn job_command(client: &mut Client, output: Output, cmd: JobCommand) -> Result<()> {
+    match cmd {
+        JobCommand::List {
+            name,
+            page,
+            page_size,
+        } => {
+            let mut query = vec![
+                ("page", page.to_string()),
+                ("page_size", page_size.to_string()),
+            ];
+            query.extend(name.map(|v| ("name", v)));
+            let resp: QueryJobResp = client.get("/job/list", &query).await?;
+            output.print(&resp, |v| {
+                Table::new(vec!["EID", "NAME", "TYPE", "EXECUTOR", "TEAM", "UPDATED"]).rows(
+                    v.list.iter().map(|v| {
+                        vec![
+                            v.eid.clone(),
+                            v.name.clone(),
+                            v.job_type.clone(),
+                            v.executor_name.clone(),
+                            v.team_name.clone().unwrap_or_default(),
+                            v.updated_time.clone(),
+                        ]
+                    }),
+                )
+            })?;
+        }
+        JobCommand::Show { job } => {
+            let record = find_job(client, &job).await?;
+            match output {
+                Output::Json => output.print(&record, |_| unreachable!())?,
+                Output::Table => {
+                    let Value::Object(fields) = serde_json::to_value(&record)? else {
+                        unreachable!()
+                    };
+                    for (k, v) in fields.iter().filter(|(k, _)| k.as_str() != "code") {
+                        match v {
+                            Value::String(v) => println!("{k}: {v}"),
+                            Value::Null => println!("{k}:"),
+                            v => println!("{k}: {v}"),
+                        }
+                    }
+                    println!("code:\n{}", record.code);
+                }
+            }
+        }
+        JobCommand::Save { file } => {
+            let content =
+                std::fs::read_to_string(&file).with_context(|| format!("failed to read {file}"))?;
+            let value: Value = match file.ends_with(".json") {
+                true => serde_json::from_str(&content)?,
+                false => serde_yaml::from_str(&content)?,
+            };
+            let req = SaveJobReq::parse_from_json(Some(value))
+                .map_err(|e| anyhow!("invalid job in {file}, {}", e.into_message()))?;
+            let resp: SaveJobResp = client.post("/job/save", &req).await?;
+            println!("saved job {}", resp.result);
+        }
+        JobCommand::Dispatch {
+            job,
+            instances,
+            follow,
+            dry_run,
+        } => {
+            let record = find_job(client, &job).await?;
+            // a name of its own to find the schedule again
+            let schedule_name = format!("{}-{}", record.name, nanoid::nanoid!(8));
+            let req = DispatchJobReq {
+                schedule_name: schedule_name.clone(),
+                schedule_type: "once".to_string(),
+                endpoints: endpoints(&instances),
+                eid: record.eid.clone(),
+                action: "exec".to_string(),
+                ..Default::default()
+            };
+            if dry_run {
+                let resp: DispatchJobResp = client.post("/job/dispatch?dry_run=true", &req).await?;
+                let preview = resp.preview.unwrap_or_default();
+                output.print(&preview, |v| {
+                    Table::new(vec!["INSTANCE", "IP", "ONLINE", "COMET", "PROBLEMS"]).rows(
+                        v.iter().map(|v| {
+                            vec![
+                                v.instance_id.clone(),
+                                v.ip.clone(),
+                                v.is_online.to_string(),
+                                v.comet_addr.clone().unwrap_or_default(),
+                                v.problems.join("; "),
+                            ]
+                        }),
+                    )
+                })?;
+                let failed = preview.iter().filter(|v| !v.problems.is_empty()).count();
+                if failed > 0 {
+                    anyhow::bail!("{failed} of {} instances have problems", preview.len());
+                }
+                return Ok(());
+            }
+            let resp: DispatchJobResp = client.post("/job/dispatch", &req).await?;
+            let schedule = find_schedule(client, &schedule_name, resp.result).await?;
+            println!("dispatched, schedule id {}", schedule.schedule_id);
+            if follow {
+                let query = vec![
+                    ("job_type", record.job_type.clone()),
+                    ("schedule_id", schedule.schedule_id),
+                    ("page_size", "100".to_string()),
+                ];
+                // retries and a margin for the agents to report back
+                let wait = Duration::from_secs(
+                    (record.timeout.max(1) as u64) * (record.max_retry.max(1) as u64) + 60,
+                );
+                follow_history(client, output, &query, Some((instances.len(), wait))).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn timer_command(client: &mut Client, output: Output, cmd: TimerCommand) -> Result<()> {
+    match cmd {
+        TimerCommand::List { name } => {
+            let resp: QueryJobTimerResp = client.get("/job/timer-list", &name_query(name)).await?;
+            output.print(&resp, |v| {
+                Table::new(vec!["ID", "NAME", "JOB", "NEXT TIME", "INFO"]).rows(v.list.iter().map(
+                    |v| {
+                        vec![
+                            v.id.to_string(),
+                            v.name.clone(),
+                            v.job_name.clone(),
+                            v.next_time.clone().unwrap_or_default(),
+                            v.info.clone(),
+                        ]
+                    },
+                ))
+            })?;
+        }
+        TimerCommand::Start { timer, instances } => {
+            let resp: QueryJobTimerResp = client
+                .get("/job/timer-list", &name_query(Some(timer.clone())))
+                .await?;
+            let record = resp
+                .list
+                .into_iter()
+                .find(|v| v.name == timer)
+                .ok_or(anyhow!("cannot found timer {timer}"))?;
+            let timer_expr = TimerExpr::parse_from_json(Some(record.timer_expr))
+                .map_err(|e| anyhow!("invalid timer {timer}, {}", e.into_message()))?;
+            let resp: DispatchJobResp = client
+                .post(
+                    "/job/dispatch",
+                    &DispatchJobReq {
+                        schedule_name: record.name.clone(),
+                        schedule_type: "timer".to_string(),
+                        endpoints: endpoints(&instances),
+                        eid: record.eid,
+                        timer_expr: Some(timer_expr),
+                        action: "start_timer".to_string(),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            let schedule = find_schedule(client, &record.name, resp.result).await?;
+            println!("started, schedule id {}", schedule.schedule_id);
+        }
+        TimerCommand::Stop { schedule_id } => {
+            redispatch(client, output, schedule_id, "stop_timer").await?
+        }
+    }
+    Ok(())
+}
+
+async fn supervisor_command(
+    client: &mut Client,
+    output: Output,
+    cmd: SupervisorCommand,
+) -> Result<()> {
+    match cmd {
+        SupervisorCommand::List { name } => {
+            let resp: QueryJobSupervisorResp = client
+                .get("/job/supervisor-list", &name_query(name))
+                .await?;
+            output.print(&resp, |v| {
+                Table::new(vec!["ID", "NAME", "JOB", "RESTART INTERVAL", "INFO"]).rows(
+                    v.list.iter().map(|v| {
+                        vec![
+                            v.id.to_string(),
+                            v.name.clone(),
+                            v.job_name.clone(),
+                            v.restart_interval.to_string(),
+                            v.info.clone(),
+                        ]
+                    }),
+                )
+            })?;
+        }
+        SupervisorCommand::Start {
+            supervisor,
+            instances,
+        } => {
+            let resp: QueryJobSupervisorResp = client
+                .get(
+                    "/job/supervisor-list",
+                    &name_query(Some(supervisor.clone())),
+                )
+                .await?;
+            let record = resp
+                .list
+                .into_iter()
+                .find(|v| v.name == supervisor)
+                .ok_or(anyhow!("cannot found supervisor {supervisor}"))?;
+            let resp: DispatchJobResp = client
+                .post(
+                    "/job/dispatch",
+                    &DispatchJobReq {
+                        schedule_name: record.name.clone(),
+                        schedule_type: "daemon".to_string(),
+                        endpoints: endpoints(&instances),
+                        eid: record.eid,
+                        restart_interval: Some(record.restart_interval.max(1) as u64),
+                        restart_policy: record.restart_policy,
+                        health_check: record.health_check,
+                        action: "start_supervising".to_string(),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            let schedule = find_schedule(client, &record.name, resp.result).await?;
+            println!("started, schedule id {}", schedule.schedule_id);
+        }
+        SupervisorCommand::Stop { schedule_id } => {
+            redispatch(client, output, schedule_id, "stop_supervising").await?
+        }
+    }
+    Ok(())
+}
+
+fn prompt(message: &str) -> Result<String> {
+    eprint!("{message}");
+    std::io::stderr().flush()?;
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+fn name_query(name: Option<String>) -> Vec<(&'static str, String)> {
+    name.map(|v| ("name", v)).into_iter().collect()
+}
+
+fn endpoints(instances: &[String]) -> Vec<Endpoint> {
+    instances
+        .iter()
+        .map(|v| Endpoint {
+            instance_id: v.clone(),
+        })
+        .collect()
+}
+
+async fn find_job(client: &mut Client, job: &str) -> Result<JobRecord> {
+    for query in [("name", job), ("default_eid", job)] {
+        let resp: QueryJobResp = client
+            .get("/job/list", &[(query.0, query.1.to_string())])
+            .await?;
+        if let Some(v) = resp
+            .list
+            .into_iter()
+            .find(|v| v.name == job || v.eid == job)
+        {
+            return Ok(v);
+        }
+    }
+    anyhow::bail!("cannot found job {job}")
+}
+
+/// The schedule a dispatch created, dispatch only answers with its id.
+async fn find_schedule(client: &mut Client, name: &str, id: i64) -> Result<ScheduleRecord> {
+    let resp: QueryScheduleResp = client
+        .get(
+            "/job/schedule-list",
+            &[("name", name.to_string()), ("page_size", "100".to_string())],
+        )
+        .await?;
+    resp.list
+        .into_iter()
+        .find(|v| v.id == id)
+        .ok_or(anyhow!("cannot found schedule {id}"))
+}
+
+async fn redispatch(
+    client: &mut Client,
+    output: Output,
+    schedule_id: String,
+    action: &str,
+) -> Result<()> {
+    let resp: RedispatchJobResp = client
+        .post(
+            "/job/redispatch",
+            &RedispatchJobReq {
+                schedule_id,
+                action: action.to_string(),
+            },
+        )
+        .await?;
+    output.print(&resp, |v| {
+        Table::new(vec!["NAMESPACE", "IP", "RESULT"]).rows(v.iter().map(|v| {
+            vec![
+                v.namespace.clone(),
+                v.ip.clone(),
+                match (&v.call_err, v.has_err) {
+                    (Some(e), _) => e.clone(),
+                    (None, true) => v.response.to_string(),
+                    (None, false) => "ok".to_string(),
+                },
+            ]
+        }))
+    })
+}
+
+fn exec_table(list: &[ExecRecord]) -> Table {
+    Table::new(vec![
+        "ID", "JOB", "SCHEDULE", "IP", "STATUS", "EXIT", "START", "END",
+    ])
+    .rows(list.iter().map(|v| {
+        vec![
+            v.id.to_string(),
+            v.job_name.clone(),
+            v.schedule_name.clone(),
+            v.bind_ip.clone(),
+            v.exit_status.clone(),
+            v.exit_code.to_string(),
+            v.start_time.clone().unwrap_or_default(),
+            v.end_time.clone().unwrap_or_default(),
+        ]
+    }))
+}
+
+/// Prints executions as they are reported. With `until`, stops once that many
+/// have been seen and fails when one of them did or the wait runs out.
+async fn follow_history(
+    client: &mut Client,
+    output: Output,
+    query: &[(&str, String)],
+    until: Option<(usize, Duration)>,
+) -> Result<()> {
+    let started = Instant::now();
+    let mut seen = BTreeSet::new();
+    let mut failed = 0;
+    loop {
+        let resp: QueryExecResp = client.get("/job/exec-list", query).await?;
+        // the list is newest first
+        for v in resp.list.iter().rev() {
+            if !seen.insert(v.id) {
+                continue;
+            }
+            match output {
+                Output::Json => println!("{}", serde_json::to_string(v)?),
+                Output::Table => {
+                    println!(
+                        "==> {} {} on {}, {} exit code {}",
+                        v.end_time.clone().unwrap_or_default(),
+                        v.job_name,
+                        v.bind_ip,
+                        v.exit_status,
+                        v.exit_code
+                    );
+                    println!("{}", v.output.trim_end());
+                }
+            }
+            if v.exit_code != 0 {
+                failed += 1;
+            }
+        }
+
+        if let Some((count, wait)) = until {
+            if seen.len() >= count {
+                if failed > 0 {
+                    anyhow::bail!("{failed} of {} runs failed", seen.len());
+                }
+                return Ok(());
+            }
+            if started.elapsed() > wait {
+                anyhow::bail!("{} of {count} runs reported in time", seen.len());
+            }
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// Longest cell printed in a table, longer ones are cut.
+const MAX_CELL_WIDTH: usize = 60;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Output {
+    Table,
+    Json,
+}
+
+impl Output {
+    /// Prints `data` as json, or the table built from it.
+    pub fn print<T: Serialize>(&self, data: &T, table: impl FnOnce(&T) -> Table) -> Result<()> {
+        match self {
+            Output::Json => println!("{}", serde_json::to_string_pretty(data)?),
+            Output::Table => table(data).print(),
+        }
+        Ok(())
+    }
+}
+
+pub struct Table {
+    header: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(header: Vec<&'static str>) -> Self {
+        Self {
+            header,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn row(mut self, row: Vec<String>) -> Self {
+        self.rows.push(
+            row.into_iter()
+                .map(|v| {
+                    let v = v.replace(['\n', '\t'], " ");
+                    match v.chars().count() > MAX_CELL_WIDTH {
+                        true => v.chars().take(MAX_CELL_WIDTH - 3).collect::<String>() + "...",
+                        false => v,
+                    }
+                })
+                .collect(),
+        );
+        self
+    }
+
+    pub fn rows(self, rows: impl IntoIterator<Item = Vec<String>>) -> Self {
+        rows.into_iter().fold(self, |table, row| table.row(row))
+    }
+
+    pub fn print(&self) {
+        let mut widths: Vec<usize> = self.header.iter().map(|v| v.len()).collect();
+        for row in self.rows.iter() {
+            for (i, cell) in row.iter().enumerate() {
+                if let Some(width) = widths.get_mut(i) {
+                    *width = (*width).max(cell.chars().count());
+                }
+            }
+        }
+        let line = |cells: Vec<&str>| {
+            let line: Vec<String> = cells
+                .iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect();
+            println!("{}", line.join("  ").trim_end());
+        };
+        line(self.header.clone());
+        for row in self.rows.iter() {
+            line(row.iter().map(|v| v.as_str()).collect());
+        }
+    }
+}
@@ -1,12 +1,14 @@
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use automate::{
+    bus::BusKind,
     comet::{self, CometOptions},
     scheduler::{
         Scheduler,
         types::{AssignUserOption, SshConnectionOption},
     },
+    telemetry,
 };
 use clap::Parser;
 use openapi::WebapiOptions;
@@ -32,6 +34,9 @@ struct WebapiArgs {
     /// Set log level, eg: "info", "debug", "warn", "error" etc.
     #[arg(long, default_value_t = String::from("error"))]
     log_level: String,
+    /// OTLP http endpoint that spans are exported to, eg: "http://127.0.0.1:4318"
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    otlp_endpoint: Option<String>,
 
     /// Comet server listen address, eg: "0.0.0.0:3000"
     #[arg(short, long, default_value_t = String::from("0.0.0.0:3000"))]
@@ -42,12 +47,21 @@ struct WebapiArgs {
     /// Directory for saving job execution logs
     #[arg(long, default_value_t = String::from("./log"))]
     output_dir: String,
+    /// Directory where comet and agent cache artifacts by their sha256
+    #[arg(long, default_value_t = String::from("./artifacts"))]
+    artifact_dir: String,
     /// Set the login user of the instance for SSH remote connection
     #[arg(long)]
     ssh_user: Option<String>,
     /// Set the login user's password of the instance for SSH remote connection
     #[arg(long)]
     ssh_password: Option<String>,
+    /// Set the private key file of the instance for SSH remote connection
+    #[arg(long)]
+    ssh_private_key: Option<String>,
+    /// Set the passphrase of the SSH private key
+    #[arg(long)]
+    ssh_key_passphrase: Option<String>,
     /// Set the port of this instance for SSH remote connection
     #[arg(long)]
     ssh_port: Option<u16>,
@@ -59,6 +73,17 @@ struct WebapiArgs {
     #[arg(long)]
     assign_password: Option<String>,
 
+    /// Event bus between comet and console, use "memory" to run without redis streams,
+    /// can be used to override configuration items in the configuration file
+    #[arg(long, value_enum)]
+    bus: Option<BusKind>,
+
+    /// Database connect address, mysql, postgres or sqlite,
+    /// eg: "sqlite://jiascheduler.db?mode=rwc" keeps everything in a single local file,
+    /// can be used to override configuration items in the configuration file
+    #[arg(long)]
+    database_url: Option<String>,
+
     /// where to read config file,
     /// you can temporarily overwrite the configuration file using command-line parameters
     #[arg(long, value_name = "FILE", default_value_t = String::from("~/.jiascheduler/console.toml"))]
@@ -75,7 +100,13 @@ async fn main() -> Result<()> {
         }
     }
 
-    tracing_subscriber::fmt::init();
+    let _telemetry = telemetry::init("jiascheduler", args.otlp_endpoint.as_deref())?;
+    let ssh_private_key = args
+        .ssh_private_key
+        .as_ref()
+        .map(std::fs::read_to_string)
+        .transpose()
+        .context("failed to read ssh private key")?;
 
     let (console_tx, console_rx) = channel::<Conf>();
     let (comet_tx, comet_rx) = channel::<()>();
@@ -83,6 +114,7 @@ async fn main() -> Result<()> {
     let console_conf: Arc<Mutex<Option<Conf>>> = Arc::new(Mutex::new(None));
     let console_conf_clone = console_conf.clone();
     let comet_bind_addr = args.comet_bind_addr.clone();
+    let comet_artifact_dir = args.artifact_dir.clone();
 
     tokio::spawn(async move {
         let conf = console_rx.await.unwrap();
@@ -93,6 +125,10 @@ async fn main() -> Result<()> {
                 redis_url: conf.redis_url,
                 bind_addr: comet_bind_addr.clone(),
                 secret: conf.comet_secret,
+                bus: conf.bus,
+                artifact_dir: comet_artifact_dir,
+                metrics: conf.metrics.enable,
+                metrics_token: Some(conf.metrics.token),
             },
             Some(comet_tx),
         )
@@ -111,7 +147,14 @@ async fn main() -> Result<()> {
             vec![format!("ws://{}", args.comet_bind_addr)],
             conf.comet_secret.to_string(),
             args.output_dir,
-            SshConnectionOption::build(args.ssh_user, args.ssh_password, args.ssh_port),
+            args.artifact_dir,
+            SshConnectionOption::build(
+                args.ssh_user,
+                args.ssh_password,
+                ssh_private_key,
+                args.ssh_key_passphrase,
+                args.ssh_port,
+            ),
             AssignUserOption::build(args.assign_username, args.assign_password),
         );
         info!("starting agent");
@@ -124,10 +167,11 @@ async fn main() -> Result<()> {
 
     openapi::run(
         WebapiOptions {
-            database_url: None,
+            database_url: args.database_url,
             redis_url: None,
             config_file: args.config,
             bind_addr: args.console_bind_addr,
+            bus: args.bus,
         },
         Some(console_tx),
     )